@@ -16,9 +16,14 @@ from .codetracer_python_recorder import (
     RecorderError,
     TargetError,
     UsageError,
+    check_environment,
     configure_policy,
     configure_policy_from_env,
     policy_snapshot,
+    trace_call,
+    trace_mark,
+    trace_schema,
+    validate_trace,
 )
 
 configure_policy_from_env()
@@ -31,7 +36,12 @@ __all__ = (
     "EnvironmentError",
     "TargetError",
     "InternalError",
+    "check_environment",
     "configure_policy",
     "configure_policy_from_env",
     "policy_snapshot",
+    "trace_call",
+    "trace_mark",
+    "trace_schema",
+    "validate_trace",
 )
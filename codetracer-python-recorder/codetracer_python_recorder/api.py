@@ -8,17 +8,33 @@ from __future__ import annotations
 
 from typing import Iterable
 
-from .formats import DEFAULT_FORMAT, TRACE_BINARY, TRACE_JSON
-from .session import TraceSession, flush, is_tracing, start, stop, trace
+from .formats import DEFAULT_FORMAT, TRACE_BINARY, TRACE_JSON, TRACE_NDJSON
+from .session import (
+    TraceSession,
+    flush,
+    is_tracing,
+    pause,
+    resume,
+    set_disable_callback,
+    start,
+    stop,
+    trace,
+    trace_filter_summary,
+)
 
 __all__: Iterable[str] = (
     "TraceSession",
     "DEFAULT_FORMAT",
     "TRACE_BINARY",
     "TRACE_JSON",
+    "TRACE_NDJSON",
     "start",
     "stop",
     "is_tracing",
     "trace",
     "flush",
+    "pause",
+    "resume",
+    "set_disable_callback",
+    "trace_filter_summary",
 )
@@ -168,6 +168,15 @@ def _parse_args(argv: Sequence[str]) -> RecorderCLIConfig:
             "environment variable accepts the same syntax for env auto-start."
         ),
     )
+    parser.add_argument(
+        "--always-trace",
+        action="append",
+        metavar="MODULE",
+        help=(
+            "Trace MODULE and its submodules even when a trace filter would skip them. "
+            "Provide multiple times for several modules."
+        ),
+    )
     parser.add_argument(
         "--on-recorder-error",
         choices=["abort", "disable"],
@@ -202,11 +211,12 @@ def _parse_args(argv: Sequence[str]) -> RecorderCLIConfig:
     )
     parser.add_argument(
         "--io-capture",
-        choices=["off", "proxies", "proxies+fd"],
+        choices=["off", "proxies", "proxies+fd", "proxies+fd-stdout", "proxies+fd-stderr"],
         help=(
             "Control stdout/stderr capture. Without this flag, line-aware proxies stay enabled. "
             "'off' disables capture, 'proxies' forces proxies without FD mirroring, "
-            "'proxies+fd' also mirrors raw file-descriptor writes."
+            "'proxies+fd' also mirrors raw file-descriptor writes, and "
+            "'proxies+fd-stdout'/'proxies+fd-stderr' mirror only that stream."
         ),
     )
     parser.add_argument(
@@ -227,6 +237,286 @@ def _parse_args(argv: Sequence[str]) -> RecorderCLIConfig:
             "Use '--no-propagate-script-exit' to force a zero exit status."
         ),
     )
+    parser.add_argument(
+        "--calls-only",
+        action="store_true",
+        help=(
+            "Record only function calls and returns (with arguments and return values), "
+            "skipping per-line steps. Much cheaper than full line tracing."
+        ),
+    )
+    parser.add_argument(
+        "--sort-dict-keys",
+        action="store_true",
+        help=(
+            "Encode dict values with their entries sorted by key so traces of "
+            "the same program diff cleanly. Unorderable keys keep insertion order."
+        ),
+    )
+    parser.add_argument(
+        "--summarize-modules",
+        action="store_true",
+        help=(
+            "Record module values (e.g. an imported `math`) as their `__name__` and "
+            "`__file__` instead of the opaque module repr."
+        ),
+    )
+    parser.add_argument(
+        "--capture-slots",
+        action="store_true",
+        help="Record instances of classes with `__slots__` as their slot attributes.",
+    )
+    parser.add_argument(
+        "--capture-properties",
+        action="store_true",
+        help=(
+            "Also evaluate `@property` getters when recording instances. Getters run "
+            "user code, so only enable this for side-effect-free properties."
+        ),
+    )
+    parser.add_argument(
+        "--statement-start-lines",
+        action="store_true",
+        help=(
+            "Record steps inside a multi-line statement at the statement's first line "
+            "instead of the physical line currently executing."
+        ),
+    )
+    parser.add_argument(
+        "--capture-with-blocks",
+        action="store_true",
+        help="Record markers when a `with` block's context manager is entered and exited.",
+    )
+    parser.add_argument(
+        "--unrepr-fallback",
+        choices=["sentinel", "repr", "type-name"],
+        help=(
+            "What to record when a value's str() raises: the '<unrepr>' sentinel "
+            "(default), its repr(), or only its type name."
+        ),
+    )
+    parser.add_argument(
+        "--expand-varargs",
+        action="store_true",
+        help="Record *args as indexed arguments (args[0], args[1], ...) instead of one tuple.",
+    )
+    parser.add_argument(
+        "--line-coverage",
+        action="store_true",
+        help=(
+            "Count how often each line runs and write coverage.json next to the trace "
+            "instead of recording per-line steps."
+        ),
+    )
+    parser.add_argument(
+        "--call-timestamps",
+        action="store_true",
+        help=(
+            "Record a wall-clock timestamp after each call and return so call "
+            "durations (for example flamegraphs) can be computed from the trace."
+        ),
+    )
+    parser.add_argument(
+        "--args-and-returns-only",
+        action="store_true",
+        help=(
+            "Record call arguments, return values, and per-line steps, but skip the "
+            "per-line variable snapshots."
+        ),
+    )
+    parser.add_argument(
+        "--flag-type-changes",
+        action="store_true",
+        help=(
+            "Record a 'type_changed' trace-log event whenever a local variable's type "
+            "differs from its previous step in the same frame."
+        ),
+    )
+    parser.add_argument(
+        "--record-loop-iterations",
+        action="store_true",
+        help=(
+            "Subscribe to JUMP events and record a 'loop_iteration' trace-log event "
+            "for every backward jump, marking each loop iteration."
+        ),
+    )
+    parser.add_argument(
+        "--deterministic",
+        action="store_true",
+        help=(
+            "Normalise run-specific data (memory addresses in raw values, call "
+            "timestamps, dict and set ordering) so repeated runs record identical events."
+        ),
+    )
+    parser.add_argument(
+        "--max-locals-per-step",
+        type=int,
+        metavar="N",
+        help=(
+            "Record at most N locals per line snapshot; the number left out is recorded "
+            "under '<omitted-locals>'. 0 records all locals."
+        ),
+    )
+    parser.add_argument(
+        "--capture-contextvars",
+        action="store_true",
+        help=(
+            "Attach the current contextvars entries to each call record as a "
+            "'<contextvars>' argument. Values follow the trace filter's redaction rules."
+        ),
+    )
+    parser.add_argument(
+        "--log-filter-decisions",
+        action="store_true",
+        help=(
+            "Write filter_decisions.json next to the trace, naming the filter file and "
+            "rule index behind every skipped scope."
+        ),
+    )
+    parser.add_argument(
+        "--pretty-json",
+        action="store_true",
+        help=(
+            "Indent JSON events files so they can be read by hand. Only affects "
+            "sessions started with format='json'; the CLI itself always writes CTFS."
+        ),
+    )
+    parser.add_argument(
+        "--keep-dropped-return-type",
+        action="store_true",
+        help=(
+            "When a filter drops a return value, record the value's type alongside "
+            "the <dropped> sentinel instead of the generic Dropped type."
+        ),
+    )
+    parser.add_argument(
+        "--capture-annotations",
+        action="store_true",
+        help=(
+            "Record the declared parameter, return and local variable annotations "
+            "of each traced function in functions.json."
+        ),
+    )
+    parser.add_argument(
+        "--redact-argv",
+        action="append",
+        metavar="PATTERN",
+        help=(
+            "Redact command-line arguments containing PATTERN (case-insensitive) from "
+            "the recorded command line. Provide multiple times for several patterns."
+        ),
+    )
+    parser.add_argument(
+        "--capture-object-ids",
+        action="store_true",
+        help=(
+            "Record id() and the reference count of non-primitive locals at each step "
+            "so aliasing can be followed across the trace. Ignored with --deterministic."
+        ),
+    )
+    parser.add_argument(
+        "--opaque-module",
+        action="append",
+        dest="opaque_modules",
+        metavar="PREFIX",
+        help=(
+            "Record instances of types from this module (or its submodules) by type name only, "
+            "without calling str(). Repeat for several modules, e.g. --opaque-module pandas."
+        ),
+    )
+    parser.add_argument(
+        "--validate-trace",
+        action="store_true",
+        help=(
+            "After a JSON trace finishes, check call/return balance and variable and path "
+            "references, logging a warning for each problem found."
+        ),
+    )
+    parser.add_argument(
+        "--max-call-depth",
+        type=int,
+        metavar="N",
+        help=(
+            "Record only the outermost N levels of nested calls; deeper frames run "
+            "untraced and calls and returns stay balanced. 0 records every level."
+        ),
+    )
+    parser.add_argument(
+        "--mark-default-args",
+        action="store_true",
+        help=(
+            "Follow each call record with a from_default trace-log event naming the "
+            "arguments that were not passed and still hold their default value."
+        ),
+    )
+    parser.add_argument(
+        "--delta-locals",
+        action="store_true",
+        help=(
+            "Record a full snapshot on frame entry and afterwards only the locals "
+            "whose value changed since the previous step in the same frame."
+        ),
+    )
+    parser.add_argument(
+        "--capture-env-changes",
+        action="store_true",
+        help=(
+            "Compare os.environ at each call and return and record an env_changed "
+            "trace-log event naming the variables set or removed since the last one."
+        ),
+    )
+    parser.add_argument(
+        "--exclude-stdlib",
+        action="store_true",
+        help=(
+            "Skip code objects whose file lives in the standard library directories "
+            "reported by sysconfig. Third-party packages are still traced."
+        ),
+    )
+    parser.add_argument(
+        "--capture-c-call",
+        action="append",
+        dest="capture_c_calls",
+        metavar="GLOB",
+        help=(
+            "Record call sites of callables whose module.qualname matches this glob, "
+            "with their first argument. Repeat for several globs, e.g. "
+            "--capture-c-call 'requests.*'."
+        ),
+    )
+    parser.add_argument(
+        "--skip-comprehensions",
+        action="store_true",
+        help=(
+            "Do not trace the frames of comprehensions and generator expressions. "
+            "The enclosing function is still traced."
+        ),
+    )
+    parser.add_argument(
+        "--redact-values-over",
+        type=int,
+        metavar="BYTES",
+        help=(
+            "Replace any value whose encoding exceeds BYTES with a "
+            "'<large-value N bytes>' marker instead of recording it. 0 records every value."
+        ),
+    )
+    parser.add_argument(
+        "--capture-warnings",
+        action="store_true",
+        help=(
+            "Record warnings issued through the 'warnings' module as structured trace "
+            "events instead of printing them to stderr."
+        ),
+    )
+    parser.add_argument(
+        "--record-instruction-offsets",
+        action="store_true",
+        help=(
+            "Emit an instruction_offset trace-log event with the bytecode offset of each "
+            "call site, so several calls on one line can be replayed precisely."
+        ),
+    )
     # P6.2 (Column-Aware-Tracing-And-Deminification milestone):
     # recorder-side autoformat of minified Python sources via ``black``.
     # Defaults to ``True`` so the recorder matches the JS recorder's
@@ -393,12 +683,92 @@ def _parse_args(argv: Sequence[str]) -> RecorderCLIConfig:
             case "proxies+fd":
                 policy["io_capture_line_proxies"] = True
                 policy["io_capture_fd_fallback"] = True
+            case "proxies+fd-stdout":
+                policy["io_capture_line_proxies"] = True
+                policy["io_capture_fd_fallback"] = False
+                policy["io_capture_fd_stdout"] = True
+            case "proxies+fd-stderr":
+                policy["io_capture_line_proxies"] = True
+                policy["io_capture_fd_fallback"] = False
+                policy["io_capture_fd_stderr"] = True
             case other:  # pragma: no cover - argparse choices block this
                 parser.error(f"unsupported io-capture mode '{other}'")
     if known.module_name_from_globals is not None:
         policy["module_name_from_globals"] = known.module_name_from_globals
     if known.propagate_script_exit is not None:
         policy["propagate_script_exit"] = known.propagate_script_exit
+    if known.calls_only:
+        policy["calls_only"] = True
+    if known.sort_dict_keys:
+        policy["sort_dict_keys"] = True
+    if known.summarize_modules:
+        policy["summarize_modules"] = True
+    if known.capture_slots:
+        policy["capture_slots"] = True
+    if known.capture_properties:
+        policy["capture_properties"] = True
+    if known.statement_start_lines:
+        policy["statement_start_lines"] = True
+    if known.capture_with_blocks:
+        policy["capture_with_blocks"] = True
+    if known.unrepr_fallback:
+        policy["unrepr_fallback"] = known.unrepr_fallback
+    if known.expand_varargs:
+        policy["expand_varargs"] = True
+    if known.line_coverage:
+        policy["line_coverage"] = True
+    if known.call_timestamps:
+        policy["call_timestamps"] = True
+    if known.args_and_returns_only:
+        policy["args_and_returns_only"] = True
+    if known.always_trace:
+        policy["always_trace"] = list(known.always_trace)
+    if known.flag_type_changes:
+        policy["flag_type_changes"] = True
+    if known.record_loop_iterations:
+        policy["record_loop_iterations"] = True
+    if known.deterministic:
+        policy["deterministic"] = True
+    if known.max_locals_per_step is not None:
+        policy["max_locals_per_step"] = known.max_locals_per_step
+    if known.capture_contextvars:
+        policy["capture_contextvars"] = True
+    if known.log_filter_decisions:
+        policy["log_filter_decisions"] = True
+    if known.pretty_json:
+        policy["pretty_json"] = True
+    if known.keep_dropped_return_type:
+        policy["keep_dropped_return_type"] = True
+    if known.capture_annotations:
+        policy["capture_annotations"] = True
+    if known.redact_argv:
+        policy["redact_argv"] = list(known.redact_argv)
+    if known.capture_object_ids:
+        policy["capture_object_ids"] = True
+    if known.record_instruction_offsets:
+        policy["record_instruction_offsets"] = True
+    if known.opaque_modules:
+        policy["opaque_modules"] = list(known.opaque_modules)
+    if known.validate_trace:
+        policy["validate_trace"] = True
+    if known.max_call_depth is not None:
+        policy["max_call_depth"] = known.max_call_depth
+    if known.mark_default_args:
+        policy["mark_default_args"] = True
+    if known.delta_locals:
+        policy["delta_locals"] = True
+    if known.capture_env_changes:
+        policy["capture_env_changes"] = True
+    if known.exclude_stdlib:
+        policy["exclude_stdlib"] = True
+    if known.capture_c_calls:
+        policy["capture_c_calls"] = list(known.capture_c_calls)
+    if known.skip_comprehensions:
+        policy["skip_comprehensions"] = True
+    if known.redact_values_over is not None:
+        policy["redact_values_over"] = known.redact_values_over
+    if known.capture_warnings:
+        policy["capture_warnings"] = True
 
     # P0.2 (Performance + E2E Coverage): set the env-var bridge so the
     # managed-upload session (codetracer_python_recorder.session) and
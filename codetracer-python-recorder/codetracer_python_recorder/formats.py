@@ -5,16 +5,18 @@ from typing import Iterable
 
 TRACE_BINARY: str = "binary"
 TRACE_JSON: str = "json"
+TRACE_NDJSON: str = "ndjson"
 TRACE_CTFS: str = "ctfs"
 DEFAULT_FORMAT: str = TRACE_CTFS
-SUPPORTED_FORMATS: frozenset[str] = frozenset({TRACE_BINARY, TRACE_JSON, TRACE_CTFS})
+SUPPORTED_FORMATS: frozenset[str] = frozenset({TRACE_BINARY, TRACE_JSON, TRACE_NDJSON, TRACE_CTFS})
 
 
 def normalize_format(value: str | None) -> str:
     """Normalise user-provided strings to the format names recognised by the backend.
 
     The runtime currently accepts ``"ctfs"`` (the default), ``"binary"``
-    (plus legacy aliases handled on the Rust side), and ``"json"``.
+    (plus legacy aliases handled on the Rust side), ``"json"``, and
+    ``"ndjson"``.
     Unknown formats fall back to the lower-cased input so the backend can
     decide how to react; callers can choose to guard against unsupported
     values by checking ``SUPPORTED_FORMATS``.
@@ -34,6 +36,7 @@ __all__: Iterable[str] = (
     "TRACE_BINARY",
     "TRACE_CTFS",
     "TRACE_JSON",
+    "TRACE_NDJSON",
     "SUPPORTED_FORMATS",
     "is_supported",
     "normalize_format",
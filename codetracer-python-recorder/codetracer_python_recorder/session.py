@@ -5,11 +5,14 @@ and surface structured :class:`RecorderError` instances on failure.
 """
 from __future__ import annotations
 
+import atexit
 import contextlib
 import os
+import signal
+import threading
 from collections.abc import Sequence
 from pathlib import Path
-from typing import Iterator, Mapping, Optional
+from typing import Callable, Iterator, Mapping, Optional
 
 from .codetracer_python_recorder import (
     configure_policy as _configure_policy,
@@ -17,25 +20,35 @@ from .codetracer_python_recorder import (
     flush_tracing as _flush_backend,
     is_tracing as _is_tracing_backend,
     managed_upload_materialized_trace as _managed_upload_materialized_backend,
+    pause_tracing as _pause_backend,
+    resume_tracing as _resume_backend,
+    set_disable_callback as _set_disable_callback_backend,
     start_tracing as _start_backend,
     stop_tracing as _stop_backend,
+    trace_filter_summary as _trace_filter_summary_backend,
 )
 from .formats import DEFAULT_FORMAT, SUPPORTED_FORMATS, is_supported, normalize_format
 
+#: Prefix of ``start`` targets that stream the trace over TCP.
+SOCKET_SCHEME = "socket://"
+
 _active_session: Optional["TraceSession"] = None
+_exit_hook_registered = False
+_installed_sigterm_handler = False
 
 
 class TraceSession:
     """Handle representing a live tracing session.
 
-    The object keeps the resolved trace path and format. Use
-    :meth:`flush` and :meth:`stop` to interact with the global session.
+    The object keeps the resolved trace path (or ``socket://`` target) and
+    format. Use :meth:`flush` and :meth:`stop` to interact with the global
+    session.
     """
 
-    path: Path
+    path: Path | str
     format: str
 
-    def __init__(self, path: Path, format: str) -> None:
+    def __init__(self, path: Path | str, format: str) -> None:
         self.path = path
         self.format = format
 
@@ -72,15 +85,22 @@ def start(
     policy: Mapping[str, object] | None = None,
     apply_env_policy: bool = True,
     test_framework: str | None = None,
+    stop_on_exit: bool = False,
+    only: str | Sequence[str] | None = None,
+    exclude: str | Sequence[str] | None = None,
+    on_existing: str = "overwrite",
 ) -> TraceSession:
     """Start a new global trace session.
 
     Parameters
     ----------
     path:
-        Destination directory for generated trace artefacts.
+        Destination directory for generated trace artefacts, or a
+        ``socket://host:port`` target to stream the ``"binary"`` format to a
+        TCP listener instead.
     format:
-        Trace events serialisation format (``"ctfs"``, ``"binary"``, or ``"json"``).
+        Trace events serialisation format (``"ctfs"``, ``"binary"``, ``"json"``, or
+        ``"ndjson"``, which writes one JSON event per line and appends on :func:`flush`).
     start_on_enter:
         Optional path that delays trace activation until the interpreter enters
         the referenced file.
@@ -101,6 +121,24 @@ def start(
         Optional test framework name (``"pytest"`` or ``"unittest"``). When
         provided, the corresponding builtin framework filter is automatically
         applied to skip framework internals from the trace.
+    stop_on_exit:
+        When ``True``, stop the session from an :mod:`atexit` hook and on
+        ``SIGTERM`` so buffered events are written even if the program never
+        calls :func:`stop`. The ``SIGTERM`` handler is only installed when the
+        signal still has its default disposition, so user handlers are left
+        untouched.
+    only:
+        Optional ``module.qualname`` glob (or sequence of globs) such as
+        ``"myapp.*"``. When given, only matching functions are traced and
+        everything else is skipped, without writing a filter file.
+    exclude:
+        Optional ``module.qualname`` glob (or sequence of globs) whose
+        matches are never traced, even when they also match ``only``.
+    on_existing:
+        What to do with trace files an earlier session left in ``path``:
+        ``"overwrite"`` (default) deletes them, ``"fail"`` raises
+        :class:`UsageError`, and ``"append"`` keeps them and records into the
+        next numbered files (``trace.1.json``, ``trace.2.json``, ...).
 
     Returns
     -------
@@ -120,19 +158,32 @@ def start(
     if _is_tracing_backend():
         raise RuntimeError("tracing already active")
 
-    trace_path = _validate_trace_path(Path(path))
+    trace_path = path if _is_socket_target(path) else _validate_trace_path(Path(path))
     normalized_format = _coerce_format(format)
     activation_path = _normalize_activation_path(start_on_enter)
     filter_chain = _normalize_trace_filter(trace_filter)
+    only_globs = _normalize_qualname_globs(only, "only")
+    exclude_globs = _normalize_qualname_globs(exclude, "exclude")
 
     if apply_env_policy:
         _configure_policy_from_env()
     if policy:
         _configure_policy(**_coerce_policy_kwargs(policy))
 
-    _start_backend(str(trace_path), normalized_format, activation_path, filter_chain, test_framework)
+    _start_backend(
+        str(trace_path),
+        normalized_format,
+        activation_path,
+        filter_chain,
+        test_framework,
+        only_globs,
+        exclude_globs,
+        on_existing,
+    )
     session = TraceSession(path=trace_path, format=normalized_format)
     _active_session = session
+    if stop_on_exit:
+        _install_exit_hooks()
     return session
 
 
@@ -149,7 +200,7 @@ def stop(*, exit_code: int | None = None) -> None:
         return
     trace_path = _active_session.path if _active_session is not None else None
     _stop_backend(exit_code)
-    if trace_path is not None and _managed_upload_enabled():
+    if isinstance(trace_path, Path) and _managed_upload_enabled():
         _managed_upload_materialized_backend(str(trace_path))
     _active_session = None
 
@@ -165,6 +216,80 @@ def flush() -> None:
         _flush_backend()
 
 
+def pause() -> None:
+    """Stop recording events while keeping the session and writer open.
+
+    Monitoring callbacks stay registered; call :func:`resume` to continue
+    recording into the same trace. A no-op when no session is running.
+    """
+    if _is_tracing_backend():
+        _pause_backend()
+
+
+def resume() -> None:
+    """Resume recording after :func:`pause`."""
+    _resume_backend()
+
+
+def trace_filter_summary() -> dict[str, object] | None:
+    """Describe the trace filter chain loaded for the active session.
+
+    Returns ``{"filters": [...]}`` with one entry per filter source in
+    composition order (builtin defaults first). Each entry carries the
+    source ``path``, its ``sha256`` digest, the ``name`` and ``version``
+    from its ``[meta]`` table and the number of scope ``rules`` it
+    contributes. Returns ``None`` when no session is running.
+    """
+    return _trace_filter_summary_backend()
+
+
+def set_disable_callback(callback: Callable[[str, str], None] | None) -> None:
+    """Register a callable notified when tracing disables itself.
+
+    Under the ``disable`` recorder-error policy the tracer detaches after a
+    callback error instead of raising. ``callback(code, message)`` then runs
+    with the error's ``ERR_*`` code and message once the tracer is
+    uninstalled, so it may safely call back into the recorder. Pass ``None``
+    to clear the callback.
+    """
+    if callback is not None and not callable(callback):
+        raise TypeError("disable callback must be callable or None")
+    _set_disable_callback_backend(callback)
+
+
+def _install_exit_hooks() -> None:
+    global _exit_hook_registered, _installed_sigterm_handler
+    if not _exit_hook_registered:
+        atexit.register(_stop_at_exit)
+        _exit_hook_registered = True
+
+    # Signal handlers can only be installed from the main thread, and we never
+    # replace a handler the program (or another library) already set.
+    if _installed_sigterm_handler or threading.current_thread() is not threading.main_thread():
+        return
+    if signal.getsignal(signal.SIGTERM) is not signal.SIG_DFL:
+        return
+    signal.signal(signal.SIGTERM, _stop_on_sigterm)
+    _installed_sigterm_handler = True
+
+
+def _stop_at_exit() -> None:
+    try:
+        stop()
+    except Exception:  # pragma: no cover - interpreter is shutting down
+        pass
+
+
+def _stop_on_sigterm(signum: int, frame: object) -> None:
+    global _installed_sigterm_handler
+    _stop_at_exit()
+    # Restore the default disposition and re-deliver so the process still
+    # terminates with the conventional SIGTERM status.
+    signal.signal(signum, signal.SIG_DFL)
+    _installed_sigterm_handler = False
+    signal.raise_signal(signum)
+
+
 def _managed_upload_enabled() -> bool:
     return bool(os.getenv("CODETRACER_MANAGED_UPLOAD_URL"))
 
@@ -202,10 +327,17 @@ def _coerce_format(value: str) -> str:
     return normalized
 
 
+def _is_socket_target(path: str | Path) -> bool:
+    return isinstance(path, str) and path.startswith(SOCKET_SCHEME)
+
+
 def _validate_trace_path(path: Path) -> Path:
     path = path.expanduser()
     if path.exists() and not path.is_dir():
-        raise ValueError("trace path exists and is not a directory")
+        raise ValueError(
+            f"trace path '{path}' exists and is not a directory; pass a directory path, "
+            "which is created when missing and receives the trace files"
+        )
     return path
 
 
@@ -251,6 +383,21 @@ def _extract_filter_segments(
     raise TypeError("trace_filter must be a path, iterable of paths, or None")
 
 
+def _normalize_qualname_globs(value: str | Sequence[str] | None, name: str) -> list[str] | None:
+    if value is None:
+        return None
+    items = [value] if isinstance(value, str) else list(value)
+    globs: list[str] = []
+    for item in items:
+        if not isinstance(item, str):
+            raise TypeError(f"{name} entries must be str")
+        pattern = item.strip()
+        if not pattern:
+            raise ValueError(f"{name} globs must not be empty")
+        globs.append(pattern)
+    return globs or None
+
+
 def _split_filter_spec(value: str) -> list[str]:
     parts = [segment.strip() for segment in value.split("::")]
     return [segment for segment in parts if segment]
@@ -274,6 +421,10 @@ def _coerce_policy_kwargs(policy: Mapping[str, object]) -> dict[str, object]:
             normalized[key] = os.fspath(raw_value)
         elif key in {"on_recorder_error", "log_level"} and raw_value is not None:
             normalized[key] = str(raw_value)
+        elif key in {"always_trace", "redact_argv", "opaque_modules"} and isinstance(
+            raw_value, str
+        ):
+            normalized[key] = [raw_value]
         else:
             normalized[key] = raw_value
     return normalized
@@ -283,6 +434,8 @@ __all__ = (
     "TraceSession",
     "flush",
     "is_tracing",
+    "pause",
+    "resume",
     "start",
     "stop",
     "trace",
@@ -112,6 +112,8 @@ pub enum ErrorCode {
     TraceDirectoryConflict,
     /// Failed to create the trace directory due to IO errors.
     TraceDirectoryCreateFailed,
+    /// Trace directory exists but the recorder cannot write its output files.
+    OutputDirectoryUnwritable,
     /// User requested an unsupported trace format.
     UnsupportedFormat,
     /// Introspection of positional arguments failed.
@@ -150,6 +152,7 @@ impl ErrorCode {
             ErrorCode::AlreadyTracing => "ERR_ALREADY_TRACING",
             ErrorCode::TraceDirectoryConflict => "ERR_TRACE_DIR_CONFLICT",
             ErrorCode::TraceDirectoryCreateFailed => "ERR_TRACE_DIR_CREATE_FAILED",
+            ErrorCode::OutputDirectoryUnwritable => "ERR_OUTPUT_DIR",
             ErrorCode::UnsupportedFormat => "ERR_UNSUPPORTED_FORMAT",
             ErrorCode::MissingPositionalArgument => "ERR_MISSING_POSITIONAL_ARG",
             ErrorCode::MissingKeywordArgument => "ERR_MISSING_KEYWORD_ARG",
@@ -180,6 +183,7 @@ impl ErrorCode {
             "ERR_ALREADY_TRACING" => Some(ErrorCode::AlreadyTracing),
             "ERR_TRACE_DIR_CONFLICT" => Some(ErrorCode::TraceDirectoryConflict),
             "ERR_TRACE_DIR_CREATE_FAILED" => Some(ErrorCode::TraceDirectoryCreateFailed),
+            "ERR_OUTPUT_DIR" => Some(ErrorCode::OutputDirectoryUnwritable),
             "ERR_UNSUPPORTED_FORMAT" => Some(ErrorCode::UnsupportedFormat),
             "ERR_MISSING_POSITIONAL_ARG" => Some(ErrorCode::MissingPositionalArgument),
             "ERR_MISSING_KEYWORD_ARG" => Some(ErrorCode::MissingKeywordArgument),
@@ -648,6 +652,7 @@ mod tests {
             ErrorCode::AlreadyTracing,
             ErrorCode::TraceDirectoryConflict,
             ErrorCode::TraceDirectoryCreateFailed,
+            ErrorCode::OutputDirectoryUnwritable,
             ErrorCode::UnsupportedFormat,
             ErrorCode::MissingPositionalArgument,
             ErrorCode::MissingKeywordArgument,
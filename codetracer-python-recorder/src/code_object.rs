@@ -100,6 +100,29 @@ impl CodeObjectWrapper {
         Ok(value)
     }
 
+    /// Whether the code object belongs to a generator, coroutine, or async
+    /// generator (`CO_GENERATOR | CO_COROUTINE | CO_ASYNC_GENERATOR`).
+    pub fn is_generator_like(&self, py: Python<'_>) -> PyResult<bool> {
+        const CO_GENERATOR: u32 = 0x20;
+        const CO_COROUTINE: u32 = 0x80;
+        const CO_ASYNC_GENERATOR: u32 = 0x200;
+        let flags = self.flags(py)?;
+        Ok(flags & (CO_GENERATOR | CO_COROUTINE | CO_ASYNC_GENERATOR) != 0)
+    }
+
+    /// The comprehension form (`"genexpr"`, `"listcomp"`, ...) when this is
+    /// the synthetic code object the compiler builds for one. List, set and
+    /// dict comprehensions are inlined from Python 3.12 (PEP 709), so only
+    /// generator expressions still run in frames of their own there.
+    pub fn comprehension_form(&self, py: Python<'_>) -> PyResult<Option<&'static str>> {
+        const FORMS: [&str; 4] = ["genexpr", "listcomp", "setcomp", "dictcomp"];
+        let name = self.qualname(py)?.rsplit('.').next().unwrap_or_default();
+        Ok(name
+            .strip_prefix('<')
+            .and_then(|rest| rest.strip_suffix('>'))
+            .and_then(|form| FORMS.into_iter().find(|known| *known == form)))
+    }
+
     fn lines<'py>(&'py self, py: Python<'py>) -> PyResult<&'py [LineEntry]> {
         let vec = self
             .cache
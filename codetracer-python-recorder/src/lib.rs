@@ -14,8 +14,13 @@ mod policy;
 mod runtime;
 mod session;
 pub mod trace_filter;
+mod trace_schema;
+mod trace_validation;
 
 pub use crate::code_object::{CodeObjectRegistry, CodeObjectWrapper};
+// Embedders (and our own tests) can redirect the recorder's structured log
+// lines into their own sink instead of stderr / the policy log file.
+pub use crate::logging::{clear_log_writer, set_log_writer};
 pub use crate::monitoring as tracer;
 // P6.2: re-export the recorder-side autoformat surface so external
 // integrators (and the CLI follow-up that wires the record-cmd hook)
@@ -27,7 +32,13 @@ pub use crate::monitoring::{
     EventSet, Tracer,
 };
 pub use crate::runtime::autoformat;
-pub use crate::session::{flush_tracing, is_tracing, start_tracing, stop_tracing};
+pub use crate::session::{
+    check_environment, flush_tracing, flush_tracing_bytes, is_tracing, pause_tracing,
+    resume_tracing, set_disable_callback, start_tracing, stop_tracing, trace_call,
+    trace_filter_summary, trace_mark,
+};
+pub use crate::trace_schema::{trace_schema_json, EVENT_KINDS, VALUE_KINDS};
+pub use crate::trace_validation::{validate_events, validate_trace_dir};
 
 use pyo3::prelude::*;
 
@@ -167,8 +178,18 @@ fn codetracer_python_recorder(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyRes
     ffi::register_exceptions(m)?;
     m.add_function(wrap_pyfunction!(start_tracing, m)?)?;
     m.add_function(wrap_pyfunction!(stop_tracing, m)?)?;
+    m.add_function(wrap_pyfunction!(trace_call, m)?)?;
     m.add_function(wrap_pyfunction!(is_tracing, m)?)?;
+    m.add_function(wrap_pyfunction!(check_environment, m)?)?;
     m.add_function(wrap_pyfunction!(flush_tracing, m)?)?;
+    m.add_function(wrap_pyfunction!(flush_tracing_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(pause_tracing, m)?)?;
+    m.add_function(wrap_pyfunction!(resume_tracing, m)?)?;
+    m.add_function(wrap_pyfunction!(set_disable_callback, m)?)?;
+    m.add_function(wrap_pyfunction!(trace_filter_summary, m)?)?;
+    m.add_function(wrap_pyfunction!(trace_mark, m)?)?;
+    m.add_function(wrap_pyfunction!(trace_schema::py_trace_schema, m)?)?;
+    m.add_function(wrap_pyfunction!(trace_validation::py_validate_trace, m)?)?;
     m.add_function(wrap_pyfunction!(policy::configure_policy_py, m)?)?;
     m.add_function(wrap_pyfunction!(policy::py_configure_policy_from_env, m)?)?;
     m.add_function(wrap_pyfunction!(policy::py_policy_snapshot, m)?)?;
@@ -7,8 +7,8 @@ mod trailer;
 #[cfg(test)]
 pub(crate) use logger::snapshot_run_and_trace;
 pub use logger::{
-    init_rust_logging_with_default, log_recorder_error, set_active_trace_id, with_error_code,
-    with_error_code_opt,
+    clear_log_writer, init_rust_logging_with_default, log_recorder_error, set_active_trace_id,
+    set_log_writer, with_error_code, with_error_code_opt,
 };
 #[allow(unused_imports)]
 pub use metrics::{
@@ -48,6 +48,9 @@ mod tests {
     use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
 
+    /// Serialises tests that redirect or inspect the global logger output.
+    static LOG_OUTPUT_LOCK: Mutex<()> = Mutex::new(());
+
     fn ensure_logger() {
         init_rust_logging_with_default("codetracer_python_recorder=debug");
     }
@@ -80,6 +83,7 @@ mod tests {
 
     #[test]
     fn structured_log_records_run_and_error_code() {
+        let _lock = LOG_OUTPUT_LOCK.lock().expect("log output lock");
         ensure_logger();
         let tmp = tempdir().expect("tempdir");
         let log_path = tmp.path().join("recorder.log");
@@ -112,6 +116,37 @@ mod tests {
         apply_policy(&crate::policy::RecorderPolicy::default());
     }
 
+    #[test]
+    fn log_writer_override_captures_log_lines() {
+        let _lock = LOG_OUTPUT_LOCK.lock().expect("log output lock");
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        set_log_writer(
+            "codetracer_python_recorder=debug",
+            Box::new(VecWriter::new(buffer.clone())),
+        );
+
+        log::warn!(target: "codetracer_python_recorder::tests", "captured by override");
+        clear_log_writer();
+        log::warn!(target: "codetracer_python_recorder::tests", "after override cleared");
+
+        let data = buffer.lock().expect("buffer lock").clone();
+        let payload = String::from_utf8(data).expect("utf8");
+        let messages: Vec<String> = payload
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter_map(|json| {
+                json.get("message")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+            })
+            .collect();
+        assert!(
+            messages.iter().any(|m| m == "captured by override"),
+            "expected captured log line, got {messages:?}"
+        );
+        assert!(!messages.iter().any(|m| m == "after override cleared"));
+    }
+
     #[test]
     fn json_error_trailers_emit_payload() {
         ensure_logger();
@@ -23,6 +23,10 @@ thread_local! {
 static LOGGER_INSTANCE: OnceCell<&'static RecorderLogger> = OnceCell::new();
 static INIT_LOGGER: Once = Once::new();
 
+/// Install the recorder's structured logger. Only the first call has an
+/// effect; later calls (from other entry points or tests) are no-ops. If the
+/// host application already registered its own `log` backend we leave it in
+/// place rather than panicking.
 pub fn init_rust_logging_with_default(default_filter: &str) {
     INIT_LOGGER.call_once(|| {
         let default_spec = FilterSpec::parse(default_filter, LevelFilter::Warn)
@@ -35,12 +39,40 @@ pub fn init_rust_logging_with_default(default_filter: &str) {
 
         let logger = RecorderLogger::new(default_spec, initial_spec);
         let leaked: &'static RecorderLogger = Box::leak(Box::new(logger));
-        log::set_logger(leaked).expect("recorder logger already initialised");
+        if log::set_logger(leaked).is_err() {
+            return;
+        }
         log::set_max_level(leaked.filter.read().expect("filter lock").max_level());
         let _ = LOGGER_INSTANCE.set(leaked);
     });
 }
 
+/// Send log lines to `writer` instead of stderr or the policy's log file, so
+/// embedders and tests can intercept them. The override survives policy
+/// updates until [`clear_log_writer`] is called. Initialises the logger with
+/// `default_filter` if nothing has done so yet.
+pub fn set_log_writer(default_filter: &str, writer: Box<dyn Write + Send>) {
+    init_rust_logging_with_default(default_filter);
+    if let Some(logger) = LOGGER_INSTANCE.get() {
+        *logger.override_writer.lock().expect("override writer lock") = Some(writer);
+    }
+}
+
+/// Drop the writer installed by [`set_log_writer`] and resume writing to the
+/// policy destination.
+pub fn clear_log_writer() {
+    if let Some(logger) = LOGGER_INSTANCE.get() {
+        let previous = logger
+            .override_writer
+            .lock()
+            .expect("override writer lock")
+            .take();
+        if let Some(mut writer) = previous {
+            let _ = writer.flush();
+        }
+    }
+}
+
 pub(crate) fn apply_logger_policy(policy: &RecorderPolicy) {
     if let Some(logger) = LOGGER_INSTANCE.get() {
         logger.apply_policy(policy);
@@ -95,6 +127,7 @@ struct RecorderLogger {
     default_filter: FilterSpec,
     filter: RwLock<FilterSpec>,
     writer: Mutex<Destination>,
+    override_writer: Mutex<Option<Box<dyn Write + Send>>>,
 }
 
 impl RecorderLogger {
@@ -103,6 +136,7 @@ impl RecorderLogger {
             run_id: Uuid::new_v4().to_string(),
             trace_id: RwLock::new(None),
             writer: Mutex::new(Destination::Stderr),
+            override_writer: Mutex::new(None),
             filter: RwLock::new(initial),
             default_filter,
         }
@@ -165,6 +199,9 @@ impl RecorderLogger {
         match serde_json::to_vec(entry) {
             Ok(mut bytes) => {
                 bytes.push(b'\n');
+                if self.write_override(&bytes) {
+                    return;
+                }
                 if let Err(err) = self.writer.lock().expect("writer lock").write_all(&bytes) {
                     let mut stderr = io::stderr().lock();
                     let _ = stderr.write_all(&bytes);
@@ -186,6 +223,19 @@ impl RecorderLogger {
         }
     }
 
+    /// Write `bytes` to the [`set_log_writer`] override. Returns `false` when
+    /// no override is installed.
+    fn write_override(&self, bytes: &[u8]) -> bool {
+        let mut guard = self.override_writer.lock().expect("override writer lock");
+        match guard.as_mut() {
+            Some(writer) => {
+                let _ = writer.write_all(bytes).and_then(|()| writer.flush());
+                true
+            }
+            None => false,
+        }
+    }
+
     fn snapshot_trace_id(&self) -> Option<String> {
         self.trace_id.read().expect("trace id lock").clone()
     }
@@ -231,6 +281,14 @@ impl Log for RecorderLogger {
     }
 
     fn flush(&self) {
+        if let Some(writer) = self
+            .override_writer
+            .lock()
+            .expect("override writer lock")
+            .as_mut()
+        {
+            let _ = writer.flush();
+        }
         let _ = self.writer.lock().expect("writer lock").flush();
     }
 }
@@ -223,11 +223,34 @@ pub trait Tracer: Send + Any {
         Ok(CallbackOutcome::Continue)
     }
 
+    /// Record a user annotation at the current step. Default is a no-op.
+    fn mark(&mut self, _py: Python<'_>, _label: &str, _metadata: Option<&str>) -> PyResult<()> {
+        Ok(())
+    }
+
+    /// Record a Python warning intercepted from `warnings.showwarning`.
+    /// Default is a no-op.
+    fn warning(
+        &mut self,
+        _py: Python<'_>,
+        _category: &str,
+        _message: &str,
+        _path: &str,
+        _line: Option<i64>,
+    ) -> PyResult<()> {
+        Ok(())
+    }
+
     /// Flush any buffered state to storage. Default is a no-op.
     fn flush(&mut self, _py: Python<'_>) -> PyResult<()> {
         Ok(())
     }
 
+    /// Size in bytes of the trace written to storage so far. Default is 0.
+    fn trace_size_bytes(&self) -> u64 {
+        0
+    }
+
     /// Finish and close any underlying writers. Default is a no-op.
     fn finish(&mut self, _py: Python<'_>) -> PyResult<()> {
         Ok(())
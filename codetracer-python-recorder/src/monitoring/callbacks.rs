@@ -1,7 +1,8 @@
 //! sys.monitoring callback metadata and helpers.
 
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
 use crate::code_object::{CodeObjectRegistry, CodeObjectWrapper};
 use crate::ffi;
@@ -29,6 +30,87 @@ pub(super) struct Global {
 
 pub(super) static GLOBAL: Mutex<Option<Global>> = Mutex::new(None);
 
+// Why a callback reentrancy guard is needed:
+//
+// - Callback bodies hold `GLOBAL`, a non-reentrant mutex, while they run.
+// - Encoding values can execute user code (`__str__`, `__repr__`, property
+//   getters) which may itself raise monitoring events on the same thread.
+// - Dispatching those nested events would try to lock `GLOBAL` again and
+//   deadlock, so events raised while a callback is already running on this
+//   thread are ignored instead.
+thread_local! {
+    static IN_MONITORING_CALLBACK: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Set by `pause_tracing`: callbacks stay registered but return immediately,
+/// so the session and writer survive until `resume_tracing`.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Pause (`true`) or resume (`false`) event recording.
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::SeqCst);
+}
+
+/// Whether event recording is currently paused.
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::SeqCst)
+}
+
+/// Marks the current thread as inside a monitoring callback until dropped.
+pub(super) struct CallbackReentry;
+
+impl CallbackReentry {
+    /// Enter a callback body, or `None` when one is already running on this
+    /// thread or recording is paused.
+    pub(super) fn enter() -> Option<Self> {
+        if is_paused() {
+            return None;
+        }
+        IN_MONITORING_CALLBACK.with(|flag| {
+            if flag.get() {
+                None
+            } else {
+                flag.set(true);
+                Some(CallbackReentry)
+            }
+        })
+    }
+}
+
+impl Drop for CallbackReentry {
+    fn drop(&mut self) {
+        IN_MONITORING_CALLBACK.with(|flag| flag.set(false));
+    }
+}
+
+/// Python callable notified as `listener(code, message)` when the `Disable`
+/// policy detaches the tracer after a callback error.
+static DISABLE_LISTENER: Mutex<Option<Py<PyAny>>> = Mutex::new(None);
+
+/// Register (or clear, with `None`) the auto-disable listener.
+pub fn set_disable_listener(listener: Option<Py<PyAny>>) {
+    let mut slot = DISABLE_LISTENER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *slot = listener;
+}
+
+/// Invoke the auto-disable listener, if any. Must run with `GLOBAL` released:
+/// the listener may call back into the recorder (`is_tracing`, `start`).
+fn notify_disable_listener(py: Python<'_>, code: ErrorCode, message: &str) {
+    let listener = DISABLE_LISTENER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .map(|listener| listener.clone_ref(py));
+    let Some(listener) = listener else {
+        return;
+    };
+    if let Err(err) = listener.call1(py, (code.as_str(), message)) {
+        warn!("auto-disable listener raised: {}", err);
+    }
+}
+
 fn catch_callback<F>(label: &'static str, callback: F) -> CallbackResult
 where
     F: FnOnce() -> CallbackResult,
@@ -57,7 +139,7 @@ where
 
 fn handle_callback_result(
     py: Python<'_>,
-    guard: &mut Option<Global>,
+    guard: MutexGuard<'_, Option<Global>>,
     result: CallbackResult,
 ) -> PyResult<Py<PyAny>> {
     match result {
@@ -72,7 +154,7 @@ fn handle_callback_result(
 
 fn handle_callback_error(
     py: Python<'_>,
-    guard: &mut Option<Global>,
+    mut guard: MutexGuard<'_, Option<Global>>,
     err: PyErr,
 ) -> PyResult<Py<PyAny>> {
     let policy = policy::policy_snapshot();
@@ -98,7 +180,10 @@ fn handle_callback_error(
                     });
                 }
             }
-            super::install::uninstall_locked(py, guard)?;
+            let uninstalled = super::install::uninstall_locked(py, &mut guard);
+            drop(guard);
+            notify_disable_listener(py, code.unwrap_or(ErrorCode::Unknown), &message);
+            uninstalled?;
             Ok(py.None())
         }
     }
@@ -113,6 +198,9 @@ pub(super) fn callback_call(
     arg0: Option<Bound<'_, PyAny>>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_call", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -121,7 +209,7 @@ pub(super) fn callback_call(
             call_tracer_with_code(py, &mut guard, &code, "callback_call", |tracer, wrapper| {
                 tracer.on_call(py, wrapper, offset, &callable, arg0.as_ref())
             });
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -132,6 +220,9 @@ pub(super) fn callback_line(
     lineno: u32,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_line", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -140,7 +231,7 @@ pub(super) fn callback_line(
             call_tracer_with_code(py, &mut guard, &code, "callback_line", |tracer, wrapper| {
                 tracer.on_line(py, wrapper, lineno)
             });
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -151,6 +242,9 @@ pub(super) fn callback_instruction(
     instruction_offset: i32,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_instruction", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -162,7 +256,7 @@ pub(super) fn callback_instruction(
             "callback_instruction",
             |tracer, wrapper| tracer.on_instruction(py, wrapper, instruction_offset),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -174,6 +268,9 @@ pub(super) fn callback_jump(
     destination_offset: i32,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_jump", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -182,7 +279,7 @@ pub(super) fn callback_jump(
             call_tracer_with_code(py, &mut guard, &code, "callback_jump", |tracer, wrapper| {
                 tracer.on_jump(py, wrapper, instruction_offset, destination_offset)
             });
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -194,6 +291,9 @@ pub(super) fn callback_branch(
     destination_offset: i32,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_branch", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -205,7 +305,7 @@ pub(super) fn callback_branch(
             "callback_branch",
             |tracer, wrapper| tracer.on_branch(py, wrapper, instruction_offset, destination_offset),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -216,6 +316,9 @@ pub(super) fn callback_py_start(
     instruction_offset: i32,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_py_start", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -227,7 +330,7 @@ pub(super) fn callback_py_start(
             "callback_py_start",
             |tracer, wrapper| tracer.on_py_start(py, wrapper, instruction_offset),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -238,6 +341,9 @@ pub(super) fn callback_py_resume(
     instruction_offset: i32,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_py_resume", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -249,7 +355,7 @@ pub(super) fn callback_py_resume(
             "callback_py_resume",
             |tracer, wrapper| tracer.on_py_resume(py, wrapper, instruction_offset),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -261,6 +367,9 @@ pub(super) fn callback_py_return(
     retval: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_py_return", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -272,7 +381,7 @@ pub(super) fn callback_py_return(
             "callback_py_return",
             |tracer, wrapper| tracer.on_py_return(py, wrapper, instruction_offset, &retval),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -284,6 +393,9 @@ pub(super) fn callback_py_yield(
     retval: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_py_yield", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -295,7 +407,7 @@ pub(super) fn callback_py_yield(
             "callback_py_yield",
             |tracer, wrapper| tracer.on_py_yield(py, wrapper, instruction_offset, &retval),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -307,6 +419,9 @@ pub(super) fn callback_py_throw(
     exception: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_py_throw", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -318,7 +433,7 @@ pub(super) fn callback_py_throw(
             "callback_py_throw",
             |tracer, wrapper| tracer.on_py_throw(py, wrapper, instruction_offset, &exception),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -330,6 +445,9 @@ pub(super) fn callback_py_unwind(
     exception: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_py_unwind", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -341,7 +459,7 @@ pub(super) fn callback_py_unwind(
             "callback_py_unwind",
             |tracer, wrapper| tracer.on_py_unwind(py, wrapper, instruction_offset, &exception),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -353,6 +471,9 @@ pub(super) fn callback_raise(
     exception: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_raise", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -364,7 +485,7 @@ pub(super) fn callback_raise(
             "callback_raise",
             |tracer, wrapper| tracer.on_raise(py, wrapper, instruction_offset, &exception),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -376,6 +497,9 @@ pub(super) fn callback_reraise(
     exception: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_reraise", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -387,7 +511,7 @@ pub(super) fn callback_reraise(
             "callback_reraise",
             |tracer, wrapper| tracer.on_reraise(py, wrapper, instruction_offset, &exception),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -399,6 +523,9 @@ pub(super) fn callback_exception_handled(
     exception: Bound<'_, PyAny>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_exception_handled", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -412,7 +539,7 @@ pub(super) fn callback_exception_handled(
                 tracer.on_exception_handled(py, wrapper, instruction_offset, &exception)
             },
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -441,6 +568,9 @@ pub(super) fn callback_c_return(
     arg0: Option<Bound<'_, PyAny>>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_c_return", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -452,7 +582,7 @@ pub(super) fn callback_c_return(
             "callback_c_return",
             |tracer, wrapper| tracer.on_c_return(py, wrapper, offset, &callable, arg0.as_ref()),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -465,6 +595,9 @@ pub(super) fn callback_c_raise(
     arg0: Option<Bound<'_, PyAny>>,
 ) -> PyResult<Py<PyAny>> {
     ffi::wrap_pyfunction("callback_c_raise", || {
+        let Some(_reentry) = CallbackReentry::enter() else {
+            return Ok(py.None());
+        };
         let mut guard = GLOBAL.lock().expect("GLOBAL mutex poisoned");
         if guard.is_none() {
             return Ok(py.None());
@@ -476,7 +609,7 @@ pub(super) fn callback_c_raise(
             "callback_c_raise",
             |tracer, wrapper| tracer.on_c_raise(py, wrapper, offset, &callable, arg0.as_ref()),
         );
-        handle_callback_result(py, &mut guard, result)
+        handle_callback_result(py, guard, result)
     })
 }
 
@@ -692,3 +825,19 @@ fn wrap_callback_c_return<'py>(module: &Bound<'py, PyModule>) -> PyResult<Callba
 fn wrap_callback_c_raise<'py>(module: &Bound<'py, PyModule>) -> PyResult<CallbackFn<'py>> {
     wrap_pyfunction!(callback_c_raise, module)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn callback_reentry_rejects_nested_entry_until_released() {
+        let outer = CallbackReentry::enter().expect("first entry succeeds");
+        assert!(
+            CallbackReentry::enter().is_none(),
+            "nested entry is ignored"
+        );
+        drop(outer);
+        assert!(CallbackReentry::enter().is_some(), "guard released on drop");
+    }
+}
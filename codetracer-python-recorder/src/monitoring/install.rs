@@ -7,7 +7,7 @@ use pyo3::{prelude::*, types::PyModule};
 use recorder_errors::{usage, ErrorCode};
 
 use super::api::Tracer;
-use super::callbacks::{self, Global, GLOBAL};
+use super::callbacks::{self, CallbackReentry, Global, GLOBAL};
 use super::{acquire_tool_id, free_tool_id, monitoring_events, set_events, NO_EVENTS};
 
 pub(super) fn uninstall_locked(py: Python<'_>, guard: &mut Option<Global>) -> PyResult<()> {
@@ -83,6 +83,53 @@ pub fn flush_installed_tracer(py: Python<'_>) -> PyResult<()> {
     Ok(())
 }
 
+/// Flush the currently installed tracer and report the on-disk size of its
+/// trace, or 0 when no tracer is installed.
+pub fn flush_installed_tracer_bytes(py: Python<'_>) -> PyResult<u64> {
+    match GLOBAL.lock().expect("GLOBAL mutex poisoned").as_mut() {
+        Some(global) => {
+            global.tracer.flush(py)?;
+            Ok(global.tracer.trace_size_bytes())
+        }
+        None => Ok(0),
+    }
+}
+
+/// Pass a user annotation to the installed tracer if any. Ignored while
+/// recording is paused and when user code run from inside a callback calls
+/// it, since that callback already holds `GLOBAL`.
+pub fn mark_installed_tracer(py: Python<'_>, label: &str, metadata: Option<&str>) -> PyResult<()> {
+    let Some(_reentry) = CallbackReentry::enter() else {
+        return Ok(());
+    };
+    if let Some(global) = GLOBAL.lock().expect("GLOBAL mutex poisoned").as_mut() {
+        global.tracer.mark(py, label, metadata)?;
+    }
+    Ok(())
+}
+
+/// Pass a Python warning to the installed tracer. Returns `false` when no
+/// tracer took it (none installed, or raised from inside a callback) so the
+/// caller can fall back to the original `warnings.showwarning`.
+pub fn warn_installed_tracer(
+    py: Python<'_>,
+    category: &str,
+    message: &str,
+    path: &str,
+    line: Option<i64>,
+) -> PyResult<bool> {
+    let Some(_reentry) = CallbackReentry::enter() else {
+        return Ok(false);
+    };
+    match GLOBAL.lock().expect("GLOBAL mutex poisoned").as_mut() {
+        Some(global) => {
+            global.tracer.warning(py, category, message, path, line)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
 /// Provide the session exit status to the active tracer if one is installed.
 pub fn update_exit_status(py: Python<'_>, exit_code: Option<i32>) -> PyResult<()> {
     if let Some(global) = GLOBAL.lock().expect("GLOBAL mutex poisoned").as_mut() {
@@ -10,10 +10,20 @@ pub(crate) mod install;
 pub mod tracer;
 
 pub use api::Tracer;
-pub use install::{flush_installed_tracer, install_tracer, uninstall_tracer, update_exit_status};
+pub(crate) use callbacks::{set_disable_listener, set_paused};
+pub use install::{
+    flush_installed_tracer, flush_installed_tracer_bytes, install_tracer, mark_installed_tracer,
+    uninstall_tracer, update_exit_status, warn_installed_tracer,
+};
 
 const MONITORING_TOOL_NAME: &str = "codetracer";
 
+/// Tool id the recorder registers under with `sys.monitoring`.
+pub const RECORDER_TOOL_ID: u8 = 5;
+
+/// Highest tool id `sys.monitoring.use_tool_id` accepts.
+const MAX_TOOL_ID: u8 = 5;
+
 /// Identifier for a monitoring event bit mask.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(transparent)]
@@ -87,9 +97,25 @@ impl EventSet {
 /// Acquire a monitoring tool id for Codetracer.
 pub fn acquire_tool_id(py: Python<'_>) -> PyResult<ToolId> {
     let monitoring = py.import("sys")?.getattr("monitoring")?;
-    const FALLBACK_ID: u8 = 5;
-    monitoring.call_method1("use_tool_id", (FALLBACK_ID, MONITORING_TOOL_NAME))?;
-    Ok(ToolId { id: FALLBACK_ID })
+    monitoring.call_method1("use_tool_id", (RECORDER_TOOL_ID, MONITORING_TOOL_NAME))?;
+    Ok(ToolId {
+        id: RECORDER_TOOL_ID,
+    })
+}
+
+/// Name registered for every `sys.monitoring` tool id (`None` when free), or
+/// `None` when the interpreter has no `sys.monitoring`.
+pub fn tool_id_owners(py: Python<'_>) -> PyResult<Option<Vec<(u8, Option<String>)>>> {
+    let Ok(monitoring) = py.import("sys")?.getattr("monitoring") else {
+        return Ok(None);
+    };
+    let owners = (0..=MAX_TOOL_ID)
+        .map(|id| {
+            let owner = monitoring.call_method1("get_tool", (id,))?.extract()?;
+            Ok((id, owner))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(Some(owners))
 }
 
 /// Load monitoring event identifiers from CPython.
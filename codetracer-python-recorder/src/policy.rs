@@ -8,16 +8,27 @@ pub(crate) mod test_support;
 
 #[allow(unused_imports)]
 pub use env::{
-    configure_policy_from_env, ENV_CAPTURE_IO, ENV_JSON_ERRORS, ENV_KEEP_PARTIAL_TRACE,
-    ENV_LOG_FILE, ENV_LOG_LEVEL, ENV_MODULE_NAME_FROM_GLOBALS, ENV_ON_RECORDER_ERROR,
-    ENV_PROPAGATE_SCRIPT_EXIT, ENV_REQUIRE_TRACE,
+    configure_policy_from_env, ENV_ALWAYS_TRACE, ENV_ARGS_AND_RETURNS_ONLY, ENV_CALLS_ONLY,
+    ENV_CALL_TIMESTAMPS, ENV_CAPTURE_ANNOTATIONS, ENV_CAPTURE_CONTEXTVARS, ENV_CAPTURE_C_CALLS,
+    ENV_CAPTURE_ENV_CHANGES, ENV_CAPTURE_IO, ENV_CAPTURE_OBJECT_IDS, ENV_CAPTURE_PROPERTIES,
+    ENV_CAPTURE_SLOTS, ENV_CAPTURE_WARNINGS, ENV_CAPTURE_WITH_BLOCKS, ENV_DELTA_LOCALS,
+    ENV_DETERMINISTIC, ENV_EXCLUDE_STDLIB, ENV_EXPAND_VARARGS, ENV_FLAG_TYPE_CHANGES,
+    ENV_JSON_ERRORS, ENV_KEEP_DROPPED_RETURN_TYPE, ENV_KEEP_PARTIAL_TRACE, ENV_LINE_COVERAGE,
+    ENV_LOG_FILE, ENV_LOG_FILTER_DECISIONS, ENV_LOG_LEVEL, ENV_MARK_DEFAULT_ARGS,
+    ENV_MAX_CALL_DEPTH, ENV_MAX_LOCALS_PER_STEP, ENV_MODULE_NAME_FROM_GLOBALS,
+    ENV_ON_RECORDER_ERROR, ENV_OPAQUE_MODULES, ENV_PRETTY_JSON, ENV_PROPAGATE_SCRIPT_EXIT,
+    ENV_RECORD_INSTRUCTION_OFFSETS, ENV_RECORD_LOOP_ITERATIONS, ENV_REDACT_ARGV,
+    ENV_REDACT_VALUES_OVER, ENV_REQUIRE_TRACE, ENV_SKIP_COMPREHENSIONS, ENV_SORT_DICT_KEYS,
+    ENV_STATEMENT_START_LINES, ENV_SUMMARIZE_MODULES, ENV_UNREPR_FALLBACK, ENV_VALIDATE_TRACE,
 };
 #[allow(unused_imports)]
 pub use ffi::{configure_policy_py, py_configure_policy_from_env, py_policy_snapshot};
 #[allow(unused_imports)]
 pub use model::PolicyParseError;
 #[allow(unused_imports)]
-pub use model::{policy_snapshot, IoCapturePolicy, OnRecorderError, RecorderPolicy};
+pub use model::{
+    policy_snapshot, IoCapturePolicy, OnRecorderError, RecorderPolicy, UnreprFallback,
+};
 
 #[cfg(test)]
 mod tests {
@@ -43,9 +54,46 @@ mod tests {
         assert!(snap.log_level.is_none());
         assert!(snap.log_file.is_none());
         assert!(snap.io_capture.line_proxies);
-        assert!(!snap.io_capture.fd_fallback);
+        assert!(!snap.io_capture.fd_mirror_stdout);
+        assert!(!snap.io_capture.fd_mirror_stderr);
         assert!(snap.module_name_from_globals);
         assert!(!snap.propagate_script_exit);
+        assert!(!snap.calls_only);
+        assert!(!snap.sort_dict_keys);
+        assert!(!snap.summarize_modules);
+        assert!(!snap.capture_slots);
+        assert!(!snap.capture_properties);
+        assert!(!snap.statement_start_lines);
+        assert!(!snap.capture_with_blocks);
+        assert_eq!(snap.unrepr_fallback, UnreprFallback::Sentinel);
+        assert!(!snap.expand_varargs);
+        assert!(!snap.line_coverage);
+        assert!(!snap.call_timestamps);
+        assert!(!snap.args_and_returns_only);
+        assert!(snap.always_trace.is_empty());
+        assert!(!snap.flag_type_changes);
+        assert!(!snap.record_loop_iterations);
+        assert!(!snap.deterministic);
+        assert!(snap.max_locals_per_step.is_none());
+        assert!(!snap.capture_contextvars);
+        assert!(!snap.log_filter_decisions);
+        assert!(!snap.pretty_json);
+        assert!(!snap.keep_dropped_return_type);
+        assert!(!snap.capture_annotations);
+        assert!(snap.redact_argv.is_empty());
+        assert!(!snap.capture_object_ids);
+        assert!(!snap.record_instruction_offsets);
+        assert!(snap.opaque_modules.is_empty());
+        assert!(!snap.validate_trace);
+        assert!(snap.max_call_depth.is_none());
+        assert!(!snap.mark_default_args);
+        assert!(!snap.delta_locals);
+        assert!(!snap.capture_env_changes);
+        assert!(!snap.exclude_stdlib);
+        assert!(snap.capture_c_calls.is_empty());
+        assert!(!snap.skip_comprehensions);
+        assert!(snap.redact_values_over.is_none());
+        assert!(!snap.capture_warnings);
     }
 
     #[test]
@@ -73,7 +121,8 @@ mod tests {
         assert_eq!(snap.log_file.as_deref(), Some(Path::new("/tmp/log.txt")));
         assert!(snap.json_errors);
         assert!(snap.io_capture.line_proxies);
-        assert!(snap.io_capture.fd_fallback);
+        assert!(snap.io_capture.fd_mirror_stdout);
+        assert!(snap.io_capture.fd_mirror_stderr);
         assert!(snap.module_name_from_globals);
         assert!(snap.propagate_script_exit);
         reset_policy();
@@ -105,7 +154,8 @@ mod tests {
         assert_eq!(snap.log_file.as_deref(), Some(Path::new("/tmp/out.log")));
         assert!(snap.json_errors);
         assert!(snap.io_capture.line_proxies);
-        assert!(snap.io_capture.fd_fallback);
+        assert!(snap.io_capture.fd_mirror_stdout);
+        assert!(snap.io_capture.fd_mirror_stderr);
         assert!(snap.module_name_from_globals);
         assert!(snap.propagate_script_exit);
         reset_policy();
@@ -123,7 +173,31 @@ mod tests {
 
         let snap = policy_snapshot();
         assert!(snap.io_capture.line_proxies);
-        assert!(snap.io_capture.fd_fallback);
+        assert!(snap.io_capture.fd_mirror_stdout);
+        assert!(snap.io_capture.fd_mirror_stderr);
+        reset_policy();
+    }
+
+    #[test]
+    fn io_capture_fd_mirror_can_be_set_per_stream() {
+        reset_policy();
+        let mut update = PolicyUpdate::default();
+        update.io_capture_fd_fallback = Some(true);
+        update.io_capture_fd_stdout = Some(false);
+        apply_policy_update(update);
+
+        let snap = policy_snapshot();
+        assert!(!snap.io_capture.fd_mirror_stdout);
+        assert!(snap.io_capture.fd_mirror_stderr);
+
+        let mut update = PolicyUpdate::default();
+        update.io_capture_line_proxies = Some(false);
+        update.io_capture_fd_stderr = Some(true);
+        apply_policy_update(update);
+
+        let snap = policy_snapshot();
+        assert!(!snap.io_capture.fd_mirror_stdout);
+        assert!(!snap.io_capture.fd_mirror_stderr);
         reset_policy();
     }
 
@@ -153,45 +227,10 @@ mod tests {
         reset_policy();
     }
 
-    // Process-wide mutex serialising env-var-mutating tests.  Without
-    // it, cargo test's default parallel execution lets two tests race
-    // on the global env block (e.g. test A sets `CAPTURE_IO=proxies,fd`
-    // while test B sets `CAPTURE_IO=invalid-token`), so test A's
-    // `configure_policy_from_env` reads test B's value and asserts
-    // fail with `Some(0)` shapes that don't match the test's literal
-    // pin.  On Windows the symptom is especially loud because env
-    // var visibility flips per-thread without warning.
-    static ENV_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
-
-    fn env_lock() -> EnvGuard {
-        // Hold the mutex for the duration of the test (released in
-        // Drop AFTER the per-test env-var cleanup).
-        let guard = ENV_MUTEX.lock().unwrap_or_else(|p| p.into_inner());
-        EnvGuard {
-            _guard: Some(guard),
-        }
-    }
-
-    struct EnvGuard {
-        _guard: Option<std::sync::MutexGuard<'static, ()>>,
-    }
-
-    impl Drop for EnvGuard {
-        fn drop(&mut self) {
-            for key in [
-                ENV_ON_RECORDER_ERROR,
-                ENV_REQUIRE_TRACE,
-                ENV_KEEP_PARTIAL_TRACE,
-                ENV_LOG_LEVEL,
-                ENV_LOG_FILE,
-                ENV_JSON_ERRORS,
-                ENV_CAPTURE_IO,
-                ENV_MODULE_NAME_FROM_GLOBALS,
-                ENV_PROPAGATE_SCRIPT_EXIT,
-            ] {
-                std::env::remove_var(key);
-            }
-            // _guard drops automatically, releasing the mutex.
-        }
+    // Env-var-mutating tests in every policy test module share one
+    // mutex (see `test_support`); the guard clears every policy env
+    // var on Drop so a failing assertion doesn't leak into the next test.
+    fn env_lock() -> test_support::EnvGuard {
+        test_support::EnvGuard::new(env::ALL_ENV_VARS)
     }
 }
@@ -1,6 +1,8 @@
 //! Environment variable parsing for recorder policy overrides.
 
-use crate::policy::model::{apply_policy_update, OnRecorderError, PolicyPath, PolicyUpdate};
+use crate::policy::model::{
+    apply_policy_update, OnRecorderError, PolicyPath, PolicyUpdate, UnreprFallback,
+};
 use recorder_errors::{usage, ErrorCode, RecorderResult};
 use std::env;
 use std::str::FromStr;
@@ -23,6 +25,128 @@ pub const ENV_CAPTURE_IO: &str = "CODETRACER_CAPTURE_IO";
 pub const ENV_MODULE_NAME_FROM_GLOBALS: &str = "CODETRACER_MODULE_NAME_FROM_GLOBALS";
 /// Environment variable toggling whether the recorder mirrors script exit codes.
 pub const ENV_PROPAGATE_SCRIPT_EXIT: &str = "CODETRACER_PROPAGATE_SCRIPT_EXIT";
+/// Environment variable restricting the trace to call/return events.
+pub const ENV_CALLS_ONLY: &str = "CODETRACER_CALLS_ONLY";
+/// Environment variable enabling key-sorted dict encoding.
+pub const ENV_SORT_DICT_KEYS: &str = "CODETRACER_SORT_DICT_KEYS";
+/// Environment variable enabling module summaries in place of module reprs.
+pub const ENV_SUMMARIZE_MODULES: &str = "CODETRACER_SUMMARIZE_MODULES";
+/// Environment variable enabling `__slots__` capture for instances.
+pub const ENV_CAPTURE_SLOTS: &str = "CODETRACER_CAPTURE_SLOTS";
+/// Environment variable enabling `@property` evaluation for instances.
+pub const ENV_CAPTURE_PROPERTIES: &str = "CODETRACER_CAPTURE_PROPERTIES";
+/// Environment variable enabling statement-start line numbers for steps.
+pub const ENV_STATEMENT_START_LINES: &str = "CODETRACER_STATEMENT_START_LINES";
+/// Environment variable toggling `with` block enter/exit markers.
+pub const ENV_CAPTURE_WITH_BLOCKS: &str = "CODETRACER_CAPTURE_WITH_BLOCKS";
+/// Environment variable selecting the fallback for values whose `str()` raises.
+pub const ENV_UNREPR_FALLBACK: &str = "CODETRACER_UNREPR_FALLBACK";
+/// Environment variable expanding `*args` into indexed arguments.
+pub const ENV_EXPAND_VARARGS: &str = "CODETRACER_EXPAND_VARARGS";
+/// Environment variable switching `LINE` events to coverage hit counting.
+pub const ENV_LINE_COVERAGE: &str = "CODETRACER_LINE_COVERAGE";
+/// Environment variable recording call/return timestamps.
+pub const ENV_CALL_TIMESTAMPS: &str = "CODETRACER_CALL_TIMESTAMPS";
+/// Environment variable recording only call arguments and return values.
+pub const ENV_ARGS_AND_RETURNS_ONLY: &str = "CODETRACER_ARGS_AND_RETURNS_ONLY";
+/// Environment variable listing comma-separated modules that are always traced.
+pub const ENV_ALWAYS_TRACE: &str = "CODETRACER_ALWAYS_TRACE";
+/// Environment variable flagging locals whose type changes between steps.
+pub const ENV_FLAG_TYPE_CHANGES: &str = "CODETRACER_FLAG_TYPE_CHANGES";
+/// Enables loop-iteration markers from backward jumps.
+pub const ENV_RECORD_LOOP_ITERATIONS: &str = "CODETRACER_RECORD_LOOP_ITERATIONS";
+/// Environment variable enabling deterministic trace output.
+pub const ENV_DETERMINISTIC: &str = "CODETRACER_DETERMINISTIC";
+/// Environment variable capping how many locals each line snapshot records.
+pub const ENV_MAX_LOCALS_PER_STEP: &str = "CODETRACER_MAX_LOCALS_PER_STEP";
+/// Environment variable toggling capture of `contextvars` at function entry.
+pub const ENV_CAPTURE_CONTEXTVARS: &str = "CODETRACER_CAPTURE_CONTEXTVARS";
+/// Environment variable toggling the `filter_decisions.json` skip attribution log.
+pub const ENV_LOG_FILTER_DECISIONS: &str = "CODETRACER_LOG_FILTER_DECISIONS";
+/// Environment variable selecting an indented layout for the `Json` events file.
+pub const ENV_PRETTY_JSON: &str = "CODETRACER_PRETTY_JSON";
+/// Environment variable keeping the type of return values a filter drops.
+pub const ENV_KEEP_DROPPED_RETURN_TYPE: &str = "CODETRACER_KEEP_DROPPED_RETURN_TYPE";
+/// Environment variable toggling capture of declared annotations in `functions.json`.
+pub const ENV_CAPTURE_ANNOTATIONS: &str = "CODETRACER_CAPTURE_ANNOTATIONS";
+/// Comma-separated patterns whose matching command-line arguments are redacted.
+pub const ENV_REDACT_ARGV: &str = "CODETRACER_REDACT_ARGV";
+/// Environment variable toggling capture of object ids and reference counts.
+pub const ENV_CAPTURE_OBJECT_IDS: &str = "CODETRACER_CAPTURE_OBJECT_IDS";
+/// Environment variable toggling bytecode offset markers.
+pub const ENV_RECORD_INSTRUCTION_OFFSETS: &str = "CODETRACER_RECORD_INSTRUCTION_OFFSETS";
+/// Environment variable listing module prefixes encoded as opaque type names.
+pub const ENV_OPAQUE_MODULES: &str = "CODETRACER_OPAQUE_MODULES";
+/// Environment variable toggling the post-trace validation pass.
+pub const ENV_VALIDATE_TRACE: &str = "CODETRACER_VALIDATE_TRACE";
+/// Environment variable capping the recorded call depth.
+pub const ENV_MAX_CALL_DEPTH: &str = "CODETRACER_MAX_CALL_DEPTH";
+/// Environment variable toggling `from_default` markers for defaulted arguments.
+pub const ENV_MARK_DEFAULT_ARGS: &str = "CODETRACER_MARK_DEFAULT_ARGS";
+/// Environment variable toggling delta snapshots of step locals.
+pub const ENV_DELTA_LOCALS: &str = "CODETRACER_DELTA_LOCALS";
+/// Environment variable toggling `env_changed` markers for `os.environ` mutations.
+pub const ENV_CAPTURE_ENV_CHANGES: &str = "CODETRACER_CAPTURE_ENV_CHANGES";
+/// Environment variable toggling exclusion of standard library code.
+pub const ENV_EXCLUDE_STDLIB: &str = "CODETRACER_EXCLUDE_STDLIB";
+/// Environment variable listing callable name globs whose call sites are recorded.
+pub const ENV_CAPTURE_C_CALLS: &str = "CODETRACER_CAPTURE_C_CALLS";
+/// Environment variable toggling skipping of comprehension frames.
+pub const ENV_SKIP_COMPREHENSIONS: &str = "CODETRACER_SKIP_COMPREHENSIONS";
+/// Environment variable setting the encoded size above which values are redacted.
+pub const ENV_REDACT_VALUES_OVER: &str = "CODETRACER_REDACT_VALUES_OVER";
+/// Environment variable toggling structured capture of Python warnings.
+pub const ENV_CAPTURE_WARNINGS: &str = "CODETRACER_CAPTURE_WARNINGS";
+
+/// Every policy environment variable, for tests that must clear them all.
+#[cfg(test)]
+pub(crate) const ALL_ENV_VARS: &[&str] = &[
+    ENV_ON_RECORDER_ERROR,
+    ENV_REQUIRE_TRACE,
+    ENV_KEEP_PARTIAL_TRACE,
+    ENV_LOG_LEVEL,
+    ENV_LOG_FILE,
+    ENV_JSON_ERRORS,
+    ENV_CAPTURE_IO,
+    ENV_MODULE_NAME_FROM_GLOBALS,
+    ENV_PROPAGATE_SCRIPT_EXIT,
+    ENV_CALLS_ONLY,
+    ENV_SORT_DICT_KEYS,
+    ENV_SUMMARIZE_MODULES,
+    ENV_CAPTURE_SLOTS,
+    ENV_CAPTURE_PROPERTIES,
+    ENV_STATEMENT_START_LINES,
+    ENV_CAPTURE_WITH_BLOCKS,
+    ENV_UNREPR_FALLBACK,
+    ENV_EXPAND_VARARGS,
+    ENV_LINE_COVERAGE,
+    ENV_CALL_TIMESTAMPS,
+    ENV_ARGS_AND_RETURNS_ONLY,
+    ENV_ALWAYS_TRACE,
+    ENV_FLAG_TYPE_CHANGES,
+    ENV_RECORD_LOOP_ITERATIONS,
+    ENV_DETERMINISTIC,
+    ENV_MAX_LOCALS_PER_STEP,
+    ENV_CAPTURE_CONTEXTVARS,
+    ENV_LOG_FILTER_DECISIONS,
+    ENV_PRETTY_JSON,
+    ENV_KEEP_DROPPED_RETURN_TYPE,
+    ENV_CAPTURE_ANNOTATIONS,
+    ENV_REDACT_ARGV,
+    ENV_CAPTURE_OBJECT_IDS,
+    ENV_RECORD_INSTRUCTION_OFFSETS,
+    ENV_OPAQUE_MODULES,
+    ENV_VALIDATE_TRACE,
+    ENV_MAX_CALL_DEPTH,
+    ENV_MARK_DEFAULT_ARGS,
+    ENV_DELTA_LOCALS,
+    ENV_CAPTURE_ENV_CHANGES,
+    ENV_EXCLUDE_STDLIB,
+    ENV_CAPTURE_C_CALLS,
+    ENV_SKIP_COMPREHENSIONS,
+    ENV_REDACT_VALUES_OVER,
+    ENV_CAPTURE_WARNINGS,
+];
 
 /// Load policy overrides from environment variables.
 pub fn configure_policy_from_env() -> RecorderResult<()> {
@@ -59,9 +183,10 @@ pub fn configure_policy_from_env() -> RecorderResult<()> {
     }
 
     if let Ok(value) = env::var(ENV_CAPTURE_IO) {
-        let (line_proxies, fd_fallback) = parse_capture_io(&value)?;
+        let (line_proxies, fd_stdout, fd_stderr) = parse_capture_io(&value)?;
         update.io_capture_line_proxies = Some(line_proxies);
-        update.io_capture_fd_fallback = Some(fd_fallback);
+        update.io_capture_fd_stdout = Some(fd_stdout);
+        update.io_capture_fd_stderr = Some(fd_stderr);
     }
 
     if let Ok(value) = env::var(ENV_MODULE_NAME_FROM_GLOBALS) {
@@ -72,6 +197,151 @@ pub fn configure_policy_from_env() -> RecorderResult<()> {
         update.propagate_script_exit = Some(parse_bool(&value)?);
     }
 
+    if let Ok(value) = env::var(ENV_CALLS_ONLY) {
+        update.calls_only = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_SORT_DICT_KEYS) {
+        update.sort_dict_keys = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_SUMMARIZE_MODULES) {
+        update.summarize_modules = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_SLOTS) {
+        update.capture_slots = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_PROPERTIES) {
+        update.capture_properties = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_STATEMENT_START_LINES) {
+        update.statement_start_lines = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_WITH_BLOCKS) {
+        update.capture_with_blocks = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_UNREPR_FALLBACK) {
+        let fallback = UnreprFallback::from_str(&value).map_err(|err| err.0)?;
+        update.unrepr_fallback = Some(fallback);
+    }
+
+    if let Ok(value) = env::var(ENV_EXPAND_VARARGS) {
+        update.expand_varargs = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_LINE_COVERAGE) {
+        update.line_coverage = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CALL_TIMESTAMPS) {
+        update.call_timestamps = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_ARGS_AND_RETURNS_ONLY) {
+        update.args_and_returns_only = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_ALWAYS_TRACE) {
+        update.always_trace = Some(parse_module_list(&value));
+    }
+
+    if let Ok(value) = env::var(ENV_FLAG_TYPE_CHANGES) {
+        update.flag_type_changes = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_RECORD_LOOP_ITERATIONS) {
+        update.record_loop_iterations = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_DETERMINISTIC) {
+        update.deterministic = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_MAX_LOCALS_PER_STEP) {
+        update.max_locals_per_step = Some(parse_count(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_CONTEXTVARS) {
+        update.capture_contextvars = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_LOG_FILTER_DECISIONS) {
+        update.log_filter_decisions = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_PRETTY_JSON) {
+        update.pretty_json = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_KEEP_DROPPED_RETURN_TYPE) {
+        update.keep_dropped_return_type = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_ANNOTATIONS) {
+        update.capture_annotations = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_REDACT_ARGV) {
+        update.redact_argv = Some(parse_module_list(&value));
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_OBJECT_IDS) {
+        update.capture_object_ids = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_RECORD_INSTRUCTION_OFFSETS) {
+        update.record_instruction_offsets = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_OPAQUE_MODULES) {
+        update.opaque_modules = Some(parse_module_list(&value));
+    }
+
+    if let Ok(value) = env::var(ENV_VALIDATE_TRACE) {
+        update.validate_trace = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_MAX_CALL_DEPTH) {
+        update.max_call_depth = Some(parse_count(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_MARK_DEFAULT_ARGS) {
+        update.mark_default_args = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_DELTA_LOCALS) {
+        update.delta_locals = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_ENV_CHANGES) {
+        update.capture_env_changes = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_EXCLUDE_STDLIB) {
+        update.exclude_stdlib = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_C_CALLS) {
+        update.capture_c_calls = Some(parse_module_list(&value));
+    }
+
+    if let Ok(value) = env::var(ENV_SKIP_COMPREHENSIONS) {
+        update.skip_comprehensions = Some(parse_bool(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_REDACT_VALUES_OVER) {
+        update.redact_values_over = Some(parse_count(&value)?);
+    }
+
+    if let Ok(value) = env::var(ENV_CAPTURE_WARNINGS) {
+        update.capture_warnings = Some(parse_bool(&value)?);
+    }
+
     apply_policy_update(update);
     Ok(())
 }
@@ -88,11 +358,35 @@ fn parse_bool(value: &str) -> RecorderResult<bool> {
     }
 }
 
-fn parse_capture_io(value: &str) -> RecorderResult<(bool, bool)> {
+fn parse_count(value: &str) -> RecorderResult<usize> {
+    value.trim().parse::<usize>().map_err(|_| {
+        usage!(
+            ErrorCode::InvalidPolicyValue,
+            "invalid count '{}' (expected a non-negative integer)",
+            value.trim()
+        )
+    })
+}
+
+fn parse_module_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|module| !module.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns `(line_proxies, fd_mirror_stdout, fd_mirror_stderr)`.
+fn parse_capture_io(value: &str) -> RecorderResult<(bool, bool, bool)> {
     let trimmed = value.trim();
     if trimmed.is_empty() {
         let default = crate::policy::model::IoCapturePolicy::default();
-        return Ok((default.line_proxies, default.fd_fallback));
+        return Ok((
+            default.line_proxies,
+            default.fd_mirror_stdout,
+            default.fd_mirror_stderr,
+        ));
     }
 
     let lower = trimmed.to_ascii_lowercase();
@@ -100,21 +394,31 @@ fn parse_capture_io(value: &str) -> RecorderResult<(bool, bool)> {
         lower.as_str(),
         "0" | "off" | "false" | "disable" | "disabled" | "none"
     ) {
-        return Ok((false, false));
+        return Ok((false, false, false));
     }
     if matches!(lower.as_str(), "1" | "on" | "true" | "enable" | "enabled") {
-        return Ok((true, false));
+        return Ok((true, false, false));
     }
 
     let mut line_proxies = false;
-    let mut fd_fallback = false;
+    let mut fd_stdout = false;
+    let mut fd_stderr = false;
     for token in lower.split(|c| matches!(c, ',' | '+')) {
         match token.trim() {
             "" => {}
             "proxies" | "proxy" => line_proxies = true,
             "fd" | "mirror" | "fallback" => {
                 line_proxies = true;
-                fd_fallback = true;
+                fd_stdout = true;
+                fd_stderr = true;
+            }
+            "fd-stdout" => {
+                line_proxies = true;
+                fd_stdout = true;
+            }
+            "fd-stderr" => {
+                line_proxies = true;
+                fd_stderr = true;
             }
             other => {
                 return Err(usage!(
@@ -126,14 +430,14 @@ fn parse_capture_io(value: &str) -> RecorderResult<(bool, bool)> {
         }
     }
 
-    if !line_proxies && !fd_fallback {
+    if !line_proxies {
         return Err(usage!(
             ErrorCode::InvalidPolicyValue,
             "CODETRACER_CAPTURE_IO must enable at least 'proxies' or 'fd'"
         ));
     }
 
-    Ok((line_proxies, fd_fallback))
+    Ok((line_proxies, fd_stdout, fd_stderr))
 }
 
 #[cfg(test)]
@@ -155,6 +459,42 @@ mod tests {
         std::env::set_var(ENV_CAPTURE_IO, "proxies,fd");
         std::env::set_var(ENV_MODULE_NAME_FROM_GLOBALS, "true");
         std::env::set_var(ENV_PROPAGATE_SCRIPT_EXIT, "true");
+        std::env::set_var(ENV_CALLS_ONLY, "true");
+        std::env::set_var(ENV_SORT_DICT_KEYS, "true");
+        std::env::set_var(ENV_SUMMARIZE_MODULES, "true");
+        std::env::set_var(ENV_CAPTURE_SLOTS, "true");
+        std::env::set_var(ENV_CAPTURE_PROPERTIES, "true");
+        std::env::set_var(ENV_STATEMENT_START_LINES, "true");
+        std::env::set_var(ENV_CAPTURE_WITH_BLOCKS, "true");
+        std::env::set_var(ENV_UNREPR_FALLBACK, "repr");
+        std::env::set_var(ENV_EXPAND_VARARGS, "true");
+        std::env::set_var(ENV_LINE_COVERAGE, "true");
+        std::env::set_var(ENV_CALL_TIMESTAMPS, "true");
+        std::env::set_var(ENV_ARGS_AND_RETURNS_ONLY, "true");
+        std::env::set_var(ENV_ALWAYS_TRACE, "app.critical, ,tools");
+        std::env::set_var(ENV_FLAG_TYPE_CHANGES, "true");
+        std::env::set_var(ENV_RECORD_LOOP_ITERATIONS, "true");
+        std::env::set_var(ENV_DETERMINISTIC, "true");
+        std::env::set_var(ENV_MAX_LOCALS_PER_STEP, "50");
+        std::env::set_var(ENV_CAPTURE_CONTEXTVARS, "true");
+        std::env::set_var(ENV_LOG_FILTER_DECISIONS, "true");
+        std::env::set_var(ENV_PRETTY_JSON, "true");
+        std::env::set_var(ENV_KEEP_DROPPED_RETURN_TYPE, "true");
+        std::env::set_var(ENV_CAPTURE_ANNOTATIONS, "true");
+        std::env::set_var(ENV_REDACT_ARGV, "token, ,password");
+        std::env::set_var(ENV_CAPTURE_OBJECT_IDS, "true");
+        std::env::set_var(ENV_RECORD_INSTRUCTION_OFFSETS, "true");
+        std::env::set_var(ENV_OPAQUE_MODULES, "pandas, torch");
+        std::env::set_var(ENV_VALIDATE_TRACE, "true");
+        std::env::set_var(ENV_MAX_CALL_DEPTH, "5");
+        std::env::set_var(ENV_MARK_DEFAULT_ARGS, "true");
+        std::env::set_var(ENV_DELTA_LOCALS, "true");
+        std::env::set_var(ENV_CAPTURE_ENV_CHANGES, "true");
+        std::env::set_var(ENV_EXCLUDE_STDLIB, "true");
+        std::env::set_var(ENV_CAPTURE_C_CALLS, "json.loads, requests.*");
+        std::env::set_var(ENV_SKIP_COMPREHENSIONS, "true");
+        std::env::set_var(ENV_REDACT_VALUES_OVER, "1024");
+        std::env::set_var(ENV_CAPTURE_WARNINGS, "true");
 
         configure_policy_from_env().expect("configure from env");
         let snap = policy_snapshot();
@@ -168,9 +508,46 @@ mod tests {
         );
         assert!(snap.json_errors);
         assert!(snap.io_capture.line_proxies);
-        assert!(snap.io_capture.fd_fallback);
+        assert!(snap.io_capture.fd_mirror_stdout);
+        assert!(snap.io_capture.fd_mirror_stderr);
         assert!(snap.module_name_from_globals);
         assert!(snap.propagate_script_exit);
+        assert!(snap.calls_only);
+        assert!(snap.sort_dict_keys);
+        assert!(snap.summarize_modules);
+        assert!(snap.capture_slots);
+        assert!(snap.capture_properties);
+        assert!(snap.statement_start_lines);
+        assert!(snap.capture_with_blocks);
+        assert_eq!(snap.unrepr_fallback, UnreprFallback::Repr);
+        assert!(snap.expand_varargs);
+        assert!(snap.line_coverage);
+        assert!(snap.call_timestamps);
+        assert!(snap.args_and_returns_only);
+        assert_eq!(snap.always_trace, vec!["app.critical", "tools"]);
+        assert!(snap.flag_type_changes);
+        assert!(snap.record_loop_iterations);
+        assert!(snap.deterministic);
+        assert_eq!(snap.max_locals_per_step, Some(50));
+        assert!(snap.capture_contextvars);
+        assert!(snap.log_filter_decisions);
+        assert!(snap.pretty_json);
+        assert!(snap.keep_dropped_return_type);
+        assert!(snap.capture_annotations);
+        assert_eq!(snap.redact_argv, vec!["token", "password"]);
+        assert!(snap.capture_object_ids);
+        assert!(snap.record_instruction_offsets);
+        assert_eq!(snap.opaque_modules, vec!["pandas", "torch"]);
+        assert!(snap.validate_trace);
+        assert_eq!(snap.max_call_depth, Some(5));
+        assert!(snap.mark_default_args);
+        assert!(snap.delta_locals);
+        assert!(snap.capture_env_changes);
+        assert!(snap.exclude_stdlib);
+        assert_eq!(snap.capture_c_calls, vec!["json.loads", "requests.*"]);
+        assert!(snap.skip_comprehensions);
+        assert_eq!(snap.redact_values_over, Some(1024));
+        assert!(snap.capture_warnings);
     }
 
     #[test]
@@ -190,11 +567,19 @@ mod tests {
     fn parse_capture_io_handles_aliases() {
         assert_eq!(
             parse_capture_io("proxies+fd").expect("parse proxies+fd"),
-            (true, true)
+            (true, true, true)
         );
         assert_eq!(
             parse_capture_io("proxies").expect("parse proxies"),
-            (true, false)
+            (true, false, false)
+        );
+        assert_eq!(
+            parse_capture_io("proxies+fd-stderr").expect("parse proxies+fd-stderr"),
+            (true, false, true)
+        );
+        assert_eq!(
+            parse_capture_io("fd-stdout").expect("parse fd-stdout"),
+            (true, true, false)
         );
 
         assert!(parse_capture_io("invalid-token").is_err());
@@ -205,20 +590,26 @@ mod tests {
         assert!(parse_bool("sometimes").is_err());
     }
 
+    #[test]
+    fn parse_count_rejects_negative_and_text() {
+        assert_eq!(parse_count(" 50 ").expect("count"), 50);
+        assert!(parse_count("-1").is_err());
+        assert!(parse_count("many").is_err());
+    }
+
+    #[test]
+    fn all_env_vars_lists_every_policy_variable() {
+        let declared = include_str!("env.rs")
+            .lines()
+            .filter(|line| line.starts_with("pub const ENV_"))
+            .count();
+        assert_eq!(ALL_ENV_VARS.len(), declared);
+    }
+
     struct EnvGuard;
     impl EnvGuard {
         fn new() -> crate::policy::test_support::EnvGuard {
-            crate::policy::test_support::EnvGuard::new(&[
-                ENV_ON_RECORDER_ERROR,
-                ENV_REQUIRE_TRACE,
-                ENV_KEEP_PARTIAL_TRACE,
-                ENV_LOG_LEVEL,
-                ENV_LOG_FILE,
-                ENV_JSON_ERRORS,
-                ENV_CAPTURE_IO,
-                ENV_MODULE_NAME_FROM_GLOBALS,
-                ENV_PROPAGATE_SCRIPT_EXIT,
-            ])
+            crate::policy::test_support::EnvGuard::new(ALL_ENV_VARS)
         }
     }
 }
@@ -2,82 +2,130 @@
 
 use super::env::configure_policy_from_env;
 use super::model::{
-    apply_policy_update, policy_snapshot, OnRecorderError, PolicyPath, PolicyUpdate,
+    apply_policy_update, policy_snapshot, OnRecorderError, PolicyPath, PolicyUpdate, UnreprFallback,
 };
 use crate::ffi;
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+/// Update the recorder policy from Python.
+///
+/// Every argument is keyword-only and optional, so callers name just the
+/// fields they change: `configure_policy(require_trace=True)`. Omitted
+/// fields, and fields passed as `None`, keep their current value. Each
+/// keyword is named after the matching `PolicyUpdate` field; unknown
+/// keywords raise `TypeError`.
 #[pyfunction(name = "configure_policy")]
-#[pyo3(signature = (on_recorder_error=None, require_trace=None, keep_partial_trace=None, log_level=None, log_file=None, json_errors=None, io_capture_line_proxies=None, io_capture_fd_fallback=None, module_name_from_globals=None, propagate_script_exit=None))]
-pub fn configure_policy_py(
-    on_recorder_error: Option<&str>,
-    require_trace: Option<bool>,
-    keep_partial_trace: Option<bool>,
-    log_level: Option<&str>,
-    log_file: Option<&str>,
-    json_errors: Option<bool>,
-    io_capture_line_proxies: Option<bool>,
-    io_capture_fd_fallback: Option<bool>,
-    module_name_from_globals: Option<bool>,
-    propagate_script_exit: Option<bool>,
-) -> PyResult<()> {
+#[pyo3(signature = (**kwargs))]
+pub fn configure_policy_py(kwargs: Option<&Bound<'_, PyDict>>) -> PyResult<()> {
     let mut update = PolicyUpdate::default();
-
-    if let Some(value) = on_recorder_error {
-        match OnRecorderError::from_str(value) {
-            Ok(parsed) => update.on_recorder_error = Some(parsed),
-            Err(err) => return Err(ffi::map_recorder_error(err.0)),
+    if let Some(kwargs) = kwargs {
+        for (key, value) in kwargs.iter() {
+            let key: String = key.extract()?;
+            apply_policy_kwarg(&mut update, &key, &value)?;
         }
     }
+    apply_policy_update(update);
+    Ok(())
+}
 
-    if let Some(value) = require_trace {
-        update.require_trace = Some(value);
-    }
-
-    if let Some(value) = keep_partial_trace {
-        update.keep_partial_trace = Some(value);
-    }
-
-    if let Some(value) = log_level {
-        update.log_level = Some(value.to_string());
-    }
-
-    if let Some(value) = log_file {
-        let path = if value.trim().is_empty() {
-            PolicyPath::Clear
-        } else {
-            PolicyPath::Value(PathBuf::from(value))
-        };
-        update.log_file = Some(path);
-    }
-
-    if let Some(value) = json_errors {
-        update.json_errors = Some(value);
-    }
-
-    if let Some(value) = io_capture_line_proxies {
-        update.io_capture_line_proxies = Some(value);
-    }
-
-    if let Some(value) = io_capture_fd_fallback {
-        update.io_capture_fd_fallback = Some(value);
-    }
-
-    if let Some(value) = module_name_from_globals {
-        update.module_name_from_globals = Some(value);
-    }
-
-    if let Some(value) = propagate_script_exit {
-        update.propagate_script_exit = Some(value);
+/// Copy one `configure_policy` keyword onto the matching update field.
+fn apply_policy_kwarg(
+    update: &mut PolicyUpdate,
+    key: &str,
+    value: &Bound<'_, PyAny>,
+) -> PyResult<()> {
+    match key {
+        "on_recorder_error" => {
+            if let Some(value) = extract_kwarg::<Option<String>>(key, value)? {
+                let parsed = OnRecorderError::from_str(&value)
+                    .map_err(|err| ffi::map_recorder_error(err.0))?;
+                update.on_recorder_error = Some(parsed);
+            }
+        }
+        "log_level" => update.log_level = extract_kwarg(key, value)?,
+        "log_file" => {
+            if let Some(value) = extract_kwarg::<Option<String>>(key, value)? {
+                let path = if value.trim().is_empty() {
+                    PolicyPath::Clear
+                } else {
+                    PolicyPath::Value(PathBuf::from(value))
+                };
+                update.log_file = Some(path);
+            }
+        }
+        "unrepr_fallback" => {
+            if let Some(value) = extract_kwarg::<Option<String>>(key, value)? {
+                let parsed = UnreprFallback::from_str(&value)
+                    .map_err(|err| ffi::map_recorder_error(err.0))?;
+                update.unrepr_fallback = Some(parsed);
+            }
+        }
+        "require_trace" => update.require_trace = extract_kwarg(key, value)?,
+        "keep_partial_trace" => update.keep_partial_trace = extract_kwarg(key, value)?,
+        "json_errors" => update.json_errors = extract_kwarg(key, value)?,
+        "io_capture_line_proxies" => update.io_capture_line_proxies = extract_kwarg(key, value)?,
+        "io_capture_fd_fallback" => update.io_capture_fd_fallback = extract_kwarg(key, value)?,
+        "io_capture_fd_stdout" => update.io_capture_fd_stdout = extract_kwarg(key, value)?,
+        "io_capture_fd_stderr" => update.io_capture_fd_stderr = extract_kwarg(key, value)?,
+        "module_name_from_globals" => update.module_name_from_globals = extract_kwarg(key, value)?,
+        "propagate_script_exit" => update.propagate_script_exit = extract_kwarg(key, value)?,
+        "calls_only" => update.calls_only = extract_kwarg(key, value)?,
+        "sort_dict_keys" => update.sort_dict_keys = extract_kwarg(key, value)?,
+        "summarize_modules" => update.summarize_modules = extract_kwarg(key, value)?,
+        "capture_slots" => update.capture_slots = extract_kwarg(key, value)?,
+        "capture_properties" => update.capture_properties = extract_kwarg(key, value)?,
+        "statement_start_lines" => update.statement_start_lines = extract_kwarg(key, value)?,
+        "capture_with_blocks" => update.capture_with_blocks = extract_kwarg(key, value)?,
+        "expand_varargs" => update.expand_varargs = extract_kwarg(key, value)?,
+        "line_coverage" => update.line_coverage = extract_kwarg(key, value)?,
+        "call_timestamps" => update.call_timestamps = extract_kwarg(key, value)?,
+        "args_and_returns_only" => update.args_and_returns_only = extract_kwarg(key, value)?,
+        "always_trace" => update.always_trace = extract_kwarg(key, value)?,
+        "flag_type_changes" => update.flag_type_changes = extract_kwarg(key, value)?,
+        "record_loop_iterations" => update.record_loop_iterations = extract_kwarg(key, value)?,
+        "deterministic" => update.deterministic = extract_kwarg(key, value)?,
+        "max_locals_per_step" => update.max_locals_per_step = extract_kwarg(key, value)?,
+        "capture_contextvars" => update.capture_contextvars = extract_kwarg(key, value)?,
+        "log_filter_decisions" => update.log_filter_decisions = extract_kwarg(key, value)?,
+        "pretty_json" => update.pretty_json = extract_kwarg(key, value)?,
+        "keep_dropped_return_type" => update.keep_dropped_return_type = extract_kwarg(key, value)?,
+        "capture_annotations" => update.capture_annotations = extract_kwarg(key, value)?,
+        "redact_argv" => update.redact_argv = extract_kwarg(key, value)?,
+        "capture_object_ids" => update.capture_object_ids = extract_kwarg(key, value)?,
+        "record_instruction_offsets" => {
+            update.record_instruction_offsets = extract_kwarg(key, value)?
+        }
+        "opaque_modules" => update.opaque_modules = extract_kwarg(key, value)?,
+        "validate_trace" => update.validate_trace = extract_kwarg(key, value)?,
+        "max_call_depth" => update.max_call_depth = extract_kwarg(key, value)?,
+        "mark_default_args" => update.mark_default_args = extract_kwarg(key, value)?,
+        "delta_locals" => update.delta_locals = extract_kwarg(key, value)?,
+        "capture_env_changes" => update.capture_env_changes = extract_kwarg(key, value)?,
+        "exclude_stdlib" => update.exclude_stdlib = extract_kwarg(key, value)?,
+        "capture_c_calls" => update.capture_c_calls = extract_kwarg(key, value)?,
+        "skip_comprehensions" => update.skip_comprehensions = extract_kwarg(key, value)?,
+        "redact_values_over" => update.redact_values_over = extract_kwarg(key, value)?,
+        "capture_warnings" => update.capture_warnings = extract_kwarg(key, value)?,
+        _ => {
+            return Err(PyTypeError::new_err(format!(
+                "configure_policy() got an unexpected keyword argument '{key}'"
+            )))
+        }
     }
-
-    apply_policy_update(update);
     Ok(())
 }
 
+/// Extract a keyword value, naming the keyword when its type is wrong.
+fn extract_kwarg<'py, T: FromPyObject<'py>>(key: &str, value: &Bound<'py, PyAny>) -> PyResult<T> {
+    value
+        .extract()
+        .map_err(|err| PyTypeError::new_err(format!("configure_policy() argument '{key}': {err}")))
+}
+
 #[pyfunction(name = "configure_policy_from_env")]
 pub fn py_configure_policy_from_env() -> PyResult<()> {
     configure_policy_from_env().map_err(ffi::map_recorder_error)
@@ -112,10 +160,53 @@ pub fn py_policy_snapshot(py: Python<'_>) -> PyResult<PyObject> {
         snapshot.module_name_from_globals,
     )?;
     dict.set_item("propagate_script_exit", snapshot.propagate_script_exit)?;
+    dict.set_item("calls_only", snapshot.calls_only)?;
+    dict.set_item("sort_dict_keys", snapshot.sort_dict_keys)?;
+    dict.set_item("summarize_modules", snapshot.summarize_modules)?;
+    dict.set_item("capture_slots", snapshot.capture_slots)?;
+    dict.set_item("capture_properties", snapshot.capture_properties)?;
+    dict.set_item("statement_start_lines", snapshot.statement_start_lines)?;
+    dict.set_item("capture_with_blocks", snapshot.capture_with_blocks)?;
+    dict.set_item("unrepr_fallback", snapshot.unrepr_fallback.as_str())?;
+    dict.set_item("expand_varargs", snapshot.expand_varargs)?;
+    dict.set_item("line_coverage", snapshot.line_coverage)?;
+    dict.set_item("call_timestamps", snapshot.call_timestamps)?;
+    dict.set_item("args_and_returns_only", snapshot.args_and_returns_only)?;
+    dict.set_item("always_trace", snapshot.always_trace.clone())?;
+    dict.set_item("flag_type_changes", snapshot.flag_type_changes)?;
+    dict.set_item("record_loop_iterations", snapshot.record_loop_iterations)?;
+    dict.set_item("deterministic", snapshot.deterministic)?;
+    dict.set_item("max_locals_per_step", snapshot.max_locals_per_step)?;
+    dict.set_item("capture_contextvars", snapshot.capture_contextvars)?;
+    dict.set_item("log_filter_decisions", snapshot.log_filter_decisions)?;
+    dict.set_item("pretty_json", snapshot.pretty_json)?;
+    dict.set_item(
+        "keep_dropped_return_type",
+        snapshot.keep_dropped_return_type,
+    )?;
+    dict.set_item("capture_annotations", snapshot.capture_annotations)?;
+    dict.set_item("redact_argv", snapshot.redact_argv.clone())?;
+    dict.set_item("capture_object_ids", snapshot.capture_object_ids)?;
+    dict.set_item(
+        "record_instruction_offsets",
+        snapshot.record_instruction_offsets,
+    )?;
+    dict.set_item("opaque_modules", snapshot.opaque_modules.clone())?;
+    dict.set_item("validate_trace", snapshot.validate_trace)?;
+    dict.set_item("max_call_depth", snapshot.max_call_depth)?;
+    dict.set_item("mark_default_args", snapshot.mark_default_args)?;
+    dict.set_item("delta_locals", snapshot.delta_locals)?;
+    dict.set_item("capture_env_changes", snapshot.capture_env_changes)?;
+    dict.set_item("exclude_stdlib", snapshot.exclude_stdlib)?;
+    dict.set_item("capture_c_calls", snapshot.capture_c_calls.clone())?;
+    dict.set_item("skip_comprehensions", snapshot.skip_comprehensions)?;
+    dict.set_item("redact_values_over", snapshot.redact_values_over)?;
+    dict.set_item("capture_warnings", snapshot.capture_warnings)?;
 
     let io_dict = PyDict::new(py);
     io_dict.set_item("line_proxies", snapshot.io_capture.line_proxies)?;
-    io_dict.set_item("fd_fallback", snapshot.io_capture.fd_fallback)?;
+    io_dict.set_item("fd_mirror_stdout", snapshot.io_capture.fd_mirror_stdout)?;
+    io_dict.set_item("fd_mirror_stderr", snapshot.io_capture.fd_mirror_stderr)?;
     dict.set_item("io_capture", io_dict)?;
     Ok(dict.into())
 }
@@ -124,24 +215,36 @@ pub fn py_policy_snapshot(py: Python<'_>) -> PyResult<PyObject> {
 mod tests {
     use super::*;
     use crate::policy::model::{policy_snapshot, reset_policy_for_tests};
+    use crate::policy::test_support::configure_policy_with;
     use pyo3::Python;
 
     #[test]
     fn configure_policy_py_updates_policy() {
         reset_policy_for_tests();
-        configure_policy_py(
-            Some("disable"),
-            Some(true),
-            Some(true),
-            Some("debug"),
-            Some("/tmp/log.txt"),
-            Some(true),
-            Some(true),
-            Some(true),
-            Some(true),
-            Some(true),
-        )
-        .expect("configure policy via PyO3 facade");
+        Python::with_gil(|py| {
+            configure_policy_with(
+                py,
+                "on_recorder_error='disable', require_trace=True, keep_partial_trace=True, \
+                 log_level='debug', log_file='/tmp/log.txt', json_errors=True, \
+                 io_capture_line_proxies=True, io_capture_fd_fallback=True, \
+                 module_name_from_globals=True, propagate_script_exit=True, calls_only=True, \
+                 sort_dict_keys=True, summarize_modules=True, capture_slots=True, \
+                 capture_properties=True, statement_start_lines=True, \
+                 capture_with_blocks=True, unrepr_fallback='type_name', expand_varargs=True, \
+                 line_coverage=True, call_timestamps=True, args_and_returns_only=True, \
+                 always_trace=['app.critical'], flag_type_changes=True, \
+                 record_loop_iterations=True, deterministic=True, max_locals_per_step=50, \
+                 capture_contextvars=True, log_filter_decisions=True, pretty_json=True, \
+                 keep_dropped_return_type=True, capture_annotations=True, \
+                 redact_argv=['token'], capture_object_ids=True, \
+                 record_instruction_offsets=True, opaque_modules=['pandas'], \
+                 validate_trace=True, max_call_depth=5, mark_default_args=True, \
+                 delta_locals=True, capture_env_changes=True, exclude_stdlib=True, \
+                 capture_c_calls=['json.loads'], skip_comprehensions=True, \
+                 redact_values_over=1024, capture_warnings=True, io_capture_fd_stderr=False",
+            )
+            .expect("configure policy via PyO3 facade");
+        });
 
         let snap = policy_snapshot();
         assert_eq!(snap.on_recorder_error, OnRecorderError::Disable);
@@ -157,28 +260,55 @@ mod tests {
         );
         assert!(snap.json_errors);
         assert!(snap.io_capture.line_proxies);
-        assert!(snap.io_capture.fd_fallback);
+        // `io_capture_fd_fallback` turned both on; the stderr override wins.
+        assert!(snap.io_capture.fd_mirror_stdout);
+        assert!(!snap.io_capture.fd_mirror_stderr);
         assert!(snap.module_name_from_globals);
         assert!(snap.propagate_script_exit);
+        assert!(snap.calls_only);
+        assert!(snap.sort_dict_keys);
+        assert!(snap.summarize_modules);
+        assert!(snap.capture_slots);
+        assert!(snap.capture_properties);
+        assert!(snap.statement_start_lines);
+        assert!(snap.capture_with_blocks);
+        assert_eq!(snap.unrepr_fallback, UnreprFallback::TypeName);
+        assert!(snap.expand_varargs);
+        assert!(snap.line_coverage);
+        assert!(snap.call_timestamps);
+        assert!(snap.args_and_returns_only);
+        assert_eq!(snap.always_trace, vec!["app.critical"]);
+        assert!(snap.flag_type_changes);
+        assert!(snap.record_loop_iterations);
+        assert!(snap.deterministic);
+        assert_eq!(snap.max_locals_per_step, Some(50));
+        assert!(snap.capture_contextvars);
+        assert!(snap.log_filter_decisions);
+        assert!(snap.pretty_json);
+        assert!(snap.keep_dropped_return_type);
+        assert!(snap.capture_annotations);
+        assert_eq!(snap.redact_argv, vec!["token"]);
+        assert!(snap.capture_object_ids);
+        assert!(snap.record_instruction_offsets);
+        assert_eq!(snap.opaque_modules, vec!["pandas"]);
+        assert!(snap.validate_trace);
+        assert_eq!(snap.max_call_depth, Some(5));
+        assert!(snap.mark_default_args);
+        assert!(snap.delta_locals);
+        assert!(snap.capture_env_changes);
+        assert!(snap.exclude_stdlib);
+        assert_eq!(snap.capture_c_calls, vec!["json.loads"]);
+        assert!(snap.skip_comprehensions);
+        assert_eq!(snap.redact_values_over, Some(1024));
+        assert!(snap.capture_warnings);
         reset_policy_for_tests();
     }
 
     #[test]
     fn configure_policy_py_rejects_invalid_on_recorder_error() {
         reset_policy_for_tests();
-        let err = configure_policy_py(
-            Some("unknown"),
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-        )
-        .expect_err("invalid variant should error");
+        let err = Python::with_gil(|py| configure_policy_with(py, "on_recorder_error='unknown'"))
+            .expect_err("invalid variant should error");
         // Ensure the error maps through map_recorder_error by checking the display text.
         let message = Python::with_gil(|py| err.value(py).to_string());
         assert!(
@@ -188,6 +318,61 @@ mod tests {
         reset_policy_for_tests();
     }
 
+    #[test]
+    fn configure_policy_accepts_individual_keywords() {
+        reset_policy_for_tests();
+        Python::with_gil(|py| {
+            let configure =
+                wrap_pyfunction!(configure_policy_py, py).expect("wrap configure_policy");
+            let kwargs = PyDict::new(py);
+            kwargs
+                .set_item("require_trace", true)
+                .expect("set require_trace");
+            configure
+                .call((), Some(&kwargs))
+                .expect("configure a single field by keyword");
+
+            let snap = policy_snapshot();
+            assert!(snap.require_trace);
+            assert_eq!(snap.on_recorder_error, OnRecorderError::Abort);
+            assert!(!snap.keep_partial_trace);
+
+            let err = configure
+                .call1(("disable",))
+                .expect_err("positional arguments are rejected");
+            assert!(err.is_instance_of::<pyo3::exceptions::PyTypeError>(py));
+        });
+        reset_policy_for_tests();
+    }
+
+    #[test]
+    fn configure_policy_rejects_unknown_and_mistyped_keywords() {
+        reset_policy_for_tests();
+        Python::with_gil(|py| {
+            let err = configure_policy_with(py, "require_trace=True, no_such_field=True")
+                .expect_err("unknown keyword should error");
+            assert!(err.is_instance_of::<pyo3::exceptions::PyTypeError>(py));
+            assert!(
+                err.value(py).to_string().contains("no_such_field"),
+                "error names the keyword: {err}"
+            );
+
+            let err = configure_policy_with(py, "always_trace='app.critical'")
+                .expect_err("a string is not a module list");
+            assert!(err.is_instance_of::<pyo3::exceptions::PyTypeError>(py));
+            assert!(
+                err.value(py).to_string().contains("always_trace"),
+                "error names the keyword: {err}"
+            );
+
+            configure_policy_with(py, "require_trace=None").expect("None keeps the field");
+        });
+        let snap = policy_snapshot();
+        assert!(!snap.require_trace, "a rejected call applies nothing");
+        assert!(snap.always_trace.is_empty());
+        reset_policy_for_tests();
+    }
+
     #[test]
     fn py_configure_policy_from_env_propagates_error() {
         reset_policy_for_tests();
@@ -207,19 +392,13 @@ mod tests {
     #[test]
     fn py_policy_snapshot_matches_model() {
         reset_policy_for_tests();
-        configure_policy_py(
-            Some("disable"),
-            Some(true),
-            Some(true),
-            Some("info"),
-            Some(""),
-            Some(true),
-            Some(false),
-            Some(false),
-            Some(false),
-            Some(true),
-        )
-        .expect("configure policy");
+        Python::with_gil(|py| {
+            configure_policy_with(
+                py,
+                "on_recorder_error='disable', propagate_script_exit=True",
+            )
+            .expect("configure policy");
+        });
 
         Python::with_gil(|py| {
             let obj = py_policy_snapshot(py).expect("snapshot dict");
@@ -246,17 +425,7 @@ mod tests {
     struct EnvGuard;
     impl EnvGuard {
         fn new() -> crate::policy::test_support::EnvGuard {
-            crate::policy::test_support::EnvGuard::new(&[
-                super::super::env::ENV_ON_RECORDER_ERROR,
-                super::super::env::ENV_REQUIRE_TRACE,
-                super::super::env::ENV_KEEP_PARTIAL_TRACE,
-                super::super::env::ENV_LOG_LEVEL,
-                super::super::env::ENV_LOG_FILE,
-                super::super::env::ENV_JSON_ERRORS,
-                super::super::env::ENV_CAPTURE_IO,
-                super::super::env::ENV_MODULE_NAME_FROM_GLOBALS,
-                super::super::env::ENV_PROPAGATE_SCRIPT_EXIT,
-            ])
+            crate::policy::test_support::EnvGuard::new(super::super::env::ALL_ENV_VARS)
         }
     }
 }
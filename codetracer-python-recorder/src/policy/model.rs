@@ -46,17 +46,60 @@ impl FromStr for OnRecorderError {
     }
 }
 
+/// How a value whose `str()` raises is recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnreprFallback {
+    /// Record the `<unrepr>` error sentinel.
+    #[default]
+    Sentinel,
+    /// Try `repr()` before falling back to the sentinel.
+    Repr,
+    /// Record only the value's class `__qualname__`.
+    TypeName,
+}
+
+impl UnreprFallback {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnreprFallback::Sentinel => "sentinel",
+            UnreprFallback::Repr => "repr",
+            UnreprFallback::TypeName => "type-name",
+        }
+    }
+}
+
+impl FromStr for UnreprFallback {
+    type Err = PolicyParseError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "sentinel" => Ok(UnreprFallback::Sentinel),
+            "repr" => Ok(UnreprFallback::Repr),
+            "type-name" => Ok(UnreprFallback::TypeName),
+            other => Err(PolicyParseError(usage!(
+                ErrorCode::InvalidPolicyValue,
+                "invalid unrepr_fallback value '{}' (expected 'sentinel', 'repr' or 'type-name')",
+                other
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IoCapturePolicy {
     pub line_proxies: bool,
-    pub fd_fallback: bool,
+    /// Mirror writes to file descriptor 1 that bypass the stdout proxy.
+    pub fd_mirror_stdout: bool,
+    /// Mirror writes to file descriptor 2 that bypass the stderr proxy.
+    pub fd_mirror_stderr: bool,
 }
 
 impl Default for IoCapturePolicy {
     fn default() -> Self {
         Self {
             line_proxies: true,
-            fd_fallback: false,
+            fd_mirror_stdout: false,
+            fd_mirror_stderr: false,
         }
     }
 }
@@ -73,6 +116,79 @@ pub struct RecorderPolicy {
     pub io_capture: IoCapturePolicy,
     pub module_name_from_globals: bool,
     pub propagate_script_exit: bool,
+    /// Record only call/return edges and skip per-line steps entirely.
+    pub calls_only: bool,
+    /// Encode dict entries sorted by key instead of insertion order.
+    pub sort_dict_keys: bool,
+    /// Encode module values as a `__name__`/`__file__` summary instead of their repr.
+    pub summarize_modules: bool,
+    /// Encode instances with their `__slots__` attributes instead of their repr.
+    pub capture_slots: bool,
+    /// Evaluate `@property` getters when encoding instances (may run user code).
+    pub capture_properties: bool,
+    /// Report multi-line statements at their first line instead of the physical line executing.
+    pub statement_start_lines: bool,
+    /// Emit markers when a `with` block's context manager is entered and exited.
+    pub capture_with_blocks: bool,
+    /// How values whose `str()` raises are recorded.
+    pub unrepr_fallback: UnreprFallback,
+    /// Record `*args` as one indexed argument per element instead of a single tuple.
+    pub expand_varargs: bool,
+    /// Accumulate per-line hit counts into `coverage.json` instead of recording steps.
+    pub line_coverage: bool,
+    /// Record wall-clock timestamps next to each call and return record.
+    pub call_timestamps: bool,
+    /// Record call arguments and return values but skip per-line variable snapshots.
+    pub args_and_returns_only: bool,
+    /// Modules (with their submodules) traced even when a filter would skip them.
+    pub always_trace: Vec<String>,
+    /// Emit a `type_changed` marker when a local's type differs from its previous snapshot.
+    pub flag_type_changes: bool,
+    /// Emit a `loop_iteration` trace-log marker for every backward jump.
+    pub record_loop_iterations: bool,
+    /// Normalise run-specific data (raw-value addresses, timestamps, dict and
+    /// set ordering) so repeated runs record identical events.
+    pub deterministic: bool,
+    /// Record at most this many locals per line snapshot; `None` records all.
+    pub max_locals_per_step: Option<usize>,
+    /// Record the current `contextvars` context as a structured record at function entry.
+    pub capture_contextvars: bool,
+    /// Attribute each filter skip to its filter file and rule in `filter_decisions.json`.
+    pub log_filter_decisions: bool,
+    /// Lay the `Json` events file out indented for human inspection instead of compact.
+    pub pretty_json: bool,
+    /// Tag the `<dropped>` return sentinel with the dropped value's type instead of `Dropped`.
+    pub keep_dropped_return_type: bool,
+    /// Record declared parameter, return and local annotations of each function in `functions.json`.
+    pub capture_annotations: bool,
+    /// Patterns whose matching `sys.argv` entries are redacted from the recorded command line.
+    pub redact_argv: Vec<String>,
+    /// Record `id()` and the reference count of non-primitive locals at each step.
+    pub capture_object_ids: bool,
+    /// Record the bytecode offset of every call site as a trace-log event.
+    pub record_instruction_offsets: bool,
+    /// Module prefixes whose instances are recorded by type name only, without calling `str()`.
+    pub opaque_modules: Vec<String>,
+    /// Check the finished JSON trace for structural problems and log each one.
+    pub validate_trace: bool,
+    /// Deepest call nesting recorded; deeper frames run untraced. `None` disables the limit.
+    pub max_call_depth: Option<usize>,
+    /// Emit a `from_default` marker naming the arguments that still hold their default.
+    pub mark_default_args: bool,
+    /// Record only the locals whose value changed since the previous step in the same frame.
+    pub delta_locals: bool,
+    /// Diff `os.environ` at each call and return and record the variables set or removed.
+    pub capture_env_changes: bool,
+    /// Skip code objects whose file lives in the standard library directories.
+    pub exclude_stdlib: bool,
+    /// Callable name globs (`module.qualname`) whose call sites are recorded with their first argument.
+    pub capture_c_calls: Vec<String>,
+    /// Skip the frames of comprehensions and generator expressions entirely.
+    pub skip_comprehensions: bool,
+    /// Values whose encoding exceeds this many bytes become a `<large-value N bytes>` marker.
+    pub redact_values_over: Option<usize>,
+    /// Record `warnings.showwarning` calls as structured warning events instead of stderr text.
+    pub capture_warnings: bool,
 }
 
 impl Default for RecorderPolicy {
@@ -87,6 +203,42 @@ impl Default for RecorderPolicy {
             io_capture: IoCapturePolicy::default(),
             module_name_from_globals: true,
             propagate_script_exit: false,
+            calls_only: false,
+            sort_dict_keys: false,
+            summarize_modules: false,
+            capture_slots: false,
+            capture_properties: false,
+            statement_start_lines: false,
+            capture_with_blocks: false,
+            unrepr_fallback: UnreprFallback::Sentinel,
+            expand_varargs: false,
+            line_coverage: false,
+            call_timestamps: false,
+            args_and_returns_only: false,
+            always_trace: Vec::new(),
+            flag_type_changes: false,
+            record_loop_iterations: false,
+            deterministic: false,
+            max_locals_per_step: None,
+            capture_contextvars: false,
+            log_filter_decisions: false,
+            pretty_json: false,
+            keep_dropped_return_type: false,
+            capture_annotations: false,
+            redact_argv: Vec::new(),
+            capture_object_ids: false,
+            record_instruction_offsets: false,
+            opaque_modules: Vec::new(),
+            validate_trace: false,
+            max_call_depth: None,
+            mark_default_args: false,
+            delta_locals: false,
+            capture_env_changes: false,
+            exclude_stdlib: false,
+            capture_c_calls: Vec::new(),
+            skip_comprehensions: false,
+            redact_values_over: None,
+            capture_warnings: false,
         }
     }
 }
@@ -120,12 +272,21 @@ impl RecorderPolicy {
         if let Some(line_proxies) = update.io_capture_line_proxies {
             self.io_capture.line_proxies = line_proxies;
             if !self.io_capture.line_proxies {
-                self.io_capture.fd_fallback = false;
+                self.io_capture.fd_mirror_stdout = false;
+                self.io_capture.fd_mirror_stderr = false;
             }
         }
+        // The fd mirror requires proxies to be on. `fd_fallback` sets both
+        // streams; the per-stream settings override it.
         if let Some(fd_fallback) = update.io_capture_fd_fallback {
-            // fd fallback requires proxies to be on.
-            self.io_capture.fd_fallback = fd_fallback && self.io_capture.line_proxies;
+            self.io_capture.fd_mirror_stdout = fd_fallback && self.io_capture.line_proxies;
+            self.io_capture.fd_mirror_stderr = fd_fallback && self.io_capture.line_proxies;
+        }
+        if let Some(fd_stdout) = update.io_capture_fd_stdout {
+            self.io_capture.fd_mirror_stdout = fd_stdout && self.io_capture.line_proxies;
+        }
+        if let Some(fd_stderr) = update.io_capture_fd_stderr {
+            self.io_capture.fd_mirror_stderr = fd_stderr && self.io_capture.line_proxies;
         }
         if let Some(module_name_from_globals) = update.module_name_from_globals {
             self.module_name_from_globals = module_name_from_globals;
@@ -133,6 +294,117 @@ impl RecorderPolicy {
         if let Some(propagate_script_exit) = update.propagate_script_exit {
             self.propagate_script_exit = propagate_script_exit;
         }
+        if let Some(calls_only) = update.calls_only {
+            self.calls_only = calls_only;
+        }
+        if let Some(sort_dict_keys) = update.sort_dict_keys {
+            self.sort_dict_keys = sort_dict_keys;
+        }
+        if let Some(summarize_modules) = update.summarize_modules {
+            self.summarize_modules = summarize_modules;
+        }
+        if let Some(capture_slots) = update.capture_slots {
+            self.capture_slots = capture_slots;
+        }
+        if let Some(capture_properties) = update.capture_properties {
+            self.capture_properties = capture_properties;
+        }
+        if let Some(statement_start_lines) = update.statement_start_lines {
+            self.statement_start_lines = statement_start_lines;
+        }
+        if let Some(capture_with_blocks) = update.capture_with_blocks {
+            self.capture_with_blocks = capture_with_blocks;
+        }
+        if let Some(unrepr_fallback) = update.unrepr_fallback {
+            self.unrepr_fallback = unrepr_fallback;
+        }
+        if let Some(expand_varargs) = update.expand_varargs {
+            self.expand_varargs = expand_varargs;
+        }
+        if let Some(line_coverage) = update.line_coverage {
+            self.line_coverage = line_coverage;
+        }
+        if let Some(call_timestamps) = update.call_timestamps {
+            self.call_timestamps = call_timestamps;
+        }
+        if let Some(args_and_returns_only) = update.args_and_returns_only {
+            self.args_and_returns_only = args_and_returns_only;
+        }
+        if let Some(always_trace) = update.always_trace {
+            self.always_trace = always_trace;
+        }
+        if let Some(flag_type_changes) = update.flag_type_changes {
+            self.flag_type_changes = flag_type_changes;
+        }
+        if let Some(record_loop_iterations) = update.record_loop_iterations {
+            self.record_loop_iterations = record_loop_iterations;
+        }
+        if let Some(deterministic) = update.deterministic {
+            self.deterministic = deterministic;
+        }
+        if let Some(max_locals) = update.max_locals_per_step {
+            // 0 lifts the cap.
+            self.max_locals_per_step = (max_locals > 0).then_some(max_locals);
+        }
+        if let Some(capture_contextvars) = update.capture_contextvars {
+            self.capture_contextvars = capture_contextvars;
+        }
+        if let Some(log_filter_decisions) = update.log_filter_decisions {
+            self.log_filter_decisions = log_filter_decisions;
+        }
+        if let Some(pretty_json) = update.pretty_json {
+            self.pretty_json = pretty_json;
+        }
+        if let Some(keep_dropped_return_type) = update.keep_dropped_return_type {
+            self.keep_dropped_return_type = keep_dropped_return_type;
+        }
+        if let Some(capture_annotations) = update.capture_annotations {
+            self.capture_annotations = capture_annotations;
+        }
+        if let Some(redact_argv) = update.redact_argv {
+            self.redact_argv = redact_argv;
+        }
+        if let Some(capture_object_ids) = update.capture_object_ids {
+            self.capture_object_ids = capture_object_ids;
+        }
+        if let Some(record_instruction_offsets) = update.record_instruction_offsets {
+            self.record_instruction_offsets = record_instruction_offsets;
+        }
+        if let Some(opaque_modules) = update.opaque_modules {
+            self.opaque_modules = opaque_modules;
+        }
+        if let Some(validate_trace) = update.validate_trace {
+            self.validate_trace = validate_trace;
+        }
+        if let Some(max_depth) = update.max_call_depth {
+            // 0 lifts the cap.
+            self.max_call_depth = (max_depth > 0).then_some(max_depth);
+        }
+        if let Some(mark_default_args) = update.mark_default_args {
+            self.mark_default_args = mark_default_args;
+        }
+        if let Some(delta_locals) = update.delta_locals {
+            self.delta_locals = delta_locals;
+        }
+        if let Some(capture_env_changes) = update.capture_env_changes {
+            self.capture_env_changes = capture_env_changes;
+        }
+        if let Some(exclude_stdlib) = update.exclude_stdlib {
+            self.exclude_stdlib = exclude_stdlib;
+        }
+        if let Some(capture_c_calls) = update.capture_c_calls {
+            self.capture_c_calls = capture_c_calls;
+        }
+        if let Some(skip_comprehensions) = update.skip_comprehensions {
+            self.skip_comprehensions = skip_comprehensions;
+        }
+        if let Some(limit) = update.redact_values_over {
+            // 0 lifts the limit.
+            self.redact_values_over = (limit > 0).then_some(limit);
+        }
+        if let Some(capture_warnings) = update.capture_warnings {
+            self.capture_warnings = capture_warnings;
+        }
     }
 }
 
@@ -154,8 +426,46 @@ pub(crate) struct PolicyUpdate {
     pub(crate) json_errors: Option<bool>,
     pub(crate) io_capture_line_proxies: Option<bool>,
     pub(crate) io_capture_fd_fallback: Option<bool>,
+    pub(crate) io_capture_fd_stdout: Option<bool>,
+    pub(crate) io_capture_fd_stderr: Option<bool>,
     pub(crate) module_name_from_globals: Option<bool>,
     pub(crate) propagate_script_exit: Option<bool>,
+    pub(crate) calls_only: Option<bool>,
+    pub(crate) sort_dict_keys: Option<bool>,
+    pub(crate) summarize_modules: Option<bool>,
+    pub(crate) capture_slots: Option<bool>,
+    pub(crate) capture_properties: Option<bool>,
+    pub(crate) statement_start_lines: Option<bool>,
+    pub(crate) capture_with_blocks: Option<bool>,
+    pub(crate) unrepr_fallback: Option<UnreprFallback>,
+    pub(crate) expand_varargs: Option<bool>,
+    pub(crate) line_coverage: Option<bool>,
+    pub(crate) call_timestamps: Option<bool>,
+    pub(crate) args_and_returns_only: Option<bool>,
+    pub(crate) always_trace: Option<Vec<String>>,
+    pub(crate) flag_type_changes: Option<bool>,
+    pub(crate) record_loop_iterations: Option<bool>,
+    pub(crate) deterministic: Option<bool>,
+    pub(crate) max_locals_per_step: Option<usize>,
+    pub(crate) capture_contextvars: Option<bool>,
+    pub(crate) log_filter_decisions: Option<bool>,
+    pub(crate) pretty_json: Option<bool>,
+    pub(crate) keep_dropped_return_type: Option<bool>,
+    pub(crate) capture_annotations: Option<bool>,
+    pub(crate) redact_argv: Option<Vec<String>>,
+    pub(crate) capture_object_ids: Option<bool>,
+    pub(crate) record_instruction_offsets: Option<bool>,
+    pub(crate) opaque_modules: Option<Vec<String>>,
+    pub(crate) validate_trace: Option<bool>,
+    pub(crate) max_call_depth: Option<usize>,
+    pub(crate) mark_default_args: Option<bool>,
+    pub(crate) delta_locals: Option<bool>,
+    pub(crate) capture_env_changes: Option<bool>,
+    pub(crate) exclude_stdlib: Option<bool>,
+    pub(crate) capture_c_calls: Option<Vec<String>>,
+    pub(crate) skip_comprehensions: Option<bool>,
+    pub(crate) redact_values_over: Option<usize>,
+    pub(crate) capture_warnings: Option<bool>,
 }
 
 /// Snapshot the current policy.
@@ -15,6 +15,9 @@
 //! On Windows the symptom is especially loud because env-var
 //! visibility flips per-thread without warning.
 
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CString;
 use std::sync::{Mutex, MutexGuard};
 
 pub(crate) static ENV_MUTEX: Mutex<()> = Mutex::new(());
@@ -49,3 +52,12 @@ impl Drop for EnvGuard {
         // _guard drops automatically afterwards, releasing the mutex.
     }
 }
+
+/// Call `configure_policy` with keyword arguments written in Python
+/// syntax, e.g. `configure_policy_with(py, "require_trace=True")`, so a
+/// test names only the fields it cares about.
+pub(crate) fn configure_policy_with(py: Python<'_>, kwargs: &str) -> PyResult<()> {
+    let code = CString::new(format!("dict({kwargs})"))?;
+    let kwargs = py.eval(&code, None, None)?.downcast_into::<PyDict>()?;
+    super::configure_policy_py(Some(&kwargs))
+}
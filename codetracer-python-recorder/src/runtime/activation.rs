@@ -1,6 +1,9 @@
 //! Activation gating for the runtime tracer.
 
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::thread::{self, ThreadId};
 
 use pyo3::Python;
 
@@ -8,13 +11,31 @@ use crate::code_object::CodeObjectWrapper;
 
 /// Tracks activation gating for the runtime tracer. When configured with an
 /// activation path, tracing remains paused until code from that file starts
-/// executing. Once the activation window completes, tracing is disabled for the
-/// remainder of the session.
+/// executing. Once the activation window completes, tracing is disabled again.
+///
+/// Windows are tracked per thread: a thread entering or leaving the
+/// activation file only toggles tracing for itself. A thread's state is
+/// dropped when its window closes, so the map only holds open windows and a
+/// thread that later gets the same id starts inactive.
 #[derive(Debug)]
 pub struct ActivationController {
     activation_path: Option<PathBuf>,
+    /// Symlink-free form of `activation_path`, when the file exists.
+    canonical_activation_path: Option<PathBuf>,
+    /// Code filenames already resolved and found not to be the activation
+    /// file, so each one costs filesystem calls at most once.
+    non_matching_filenames: HashSet<String>,
+    /// Open activation window of each thread inside the activation file.
+    threads: HashMap<ThreadId, ThreadActivation>,
+}
+
+/// Activation window of a single thread.
+#[derive(Debug, Default)]
+struct ThreadActivation {
     activation_code_id: Option<usize>,
-    activation_done: bool,
+    /// Live frames of the activation code object, so a recursive activation
+    /// function only deactivates when its outermost frame returns.
+    activation_depth: usize,
     started: bool,
     suspended: bool,
 }
@@ -29,18 +50,24 @@ impl ActivationController {
     pub fn new(activation_path: Option<&Path>) -> Self {
         let activation_path = activation_path
             .map(|p| std::path::absolute(p).expect("activation_path should resolve"));
-        let started = activation_path.is_none();
+        let canonical_activation_path = activation_path
+            .as_deref()
+            .and_then(|path| fs::canonicalize(path).ok());
         Self {
             activation_path,
-            activation_code_id: None,
-            activation_done: false,
-            started,
-            suspended: false,
+            canonical_activation_path,
+            non_matching_filenames: HashSet::new(),
+            threads: HashMap::new(),
         }
     }
 
+    /// Whether tracing is active for the calling thread.
     pub fn is_active(&self) -> bool {
-        self.started
+        self.activation_path.is_none()
+            || self
+                .threads
+                .get(&thread::current().id())
+                .is_some_and(|state| state.started)
     }
 
     /// Ensure activation state reflects the current event and report whether
@@ -51,60 +78,130 @@ impl ActivationController {
         self.is_active()
     }
 
+    /// Whether an activation path was configured but does not exist on disk,
+    /// in which case tracing would never activate.
+    pub fn activation_path_missing(&self) -> bool {
+        self.activation_path
+            .as_deref()
+            .is_some_and(|path| !path.exists())
+    }
+
     /// Return the canonical start path for writer initialisation.
     pub fn start_path<'a>(&'a self, fallback: &'a Path) -> &'a Path {
         self.activation_path.as_deref().unwrap_or(fallback)
     }
 
-    /// Attempt to transition into the active state. When the code object
-    /// corresponds to the activation path, tracing becomes active and remembers
-    /// the triggering code id so it can stop on return.
+    /// Attempt to transition the calling thread into the active state. When
+    /// the code object corresponds to the activation path, tracing becomes
+    /// active for this thread and remembers the triggering code id so it can
+    /// stop on return.
     pub fn ensure_started(&mut self, py: Python<'_>, code: &CodeObjectWrapper) {
-        if self.started || self.activation_done {
+        if self.activation_path.is_none() {
+            return;
+        }
+        let thread_id = thread::current().id();
+        if self
+            .threads
+            .get(&thread_id)
+            .is_some_and(|state| state.started)
+        {
             return;
         }
-        if let Some(activation) = &self.activation_path {
-            if let Ok(filename) = code.filename(py) {
-                let file = Path::new(filename);
-                // `CodeObjectWrapper::filename` is expected to return an absolute
-                // path. If this assumption turns out to be wrong we will revisit
-                // the comparison logic. Canonicalisation is deliberately avoided
-                // here to limit syscalls on hot paths.
-                if file == activation {
-                    self.started = true;
-                    self.activation_code_id = Some(code.id());
-                    log::debug!(
-                        "[RuntimeTracer] activated on enter: {}",
-                        activation.display()
-                    );
-                }
+        let Ok(filename) = code.filename(py) else {
+            return;
+        };
+        if self.matches_activation_path(filename) {
+            let state = self.threads.entry(thread_id).or_default();
+            state.started = true;
+            state.activation_code_id = Some(code.id());
+            if let Some(activation) = &self.activation_path {
+                log::debug!(
+                    "[RuntimeTracer] activated on enter: {} ({:?})",
+                    activation.display(),
+                    thread_id
+                );
+            }
+        }
+    }
+
+    /// Compare a code filename with the activation path. Absolute filenames
+    /// usually match verbatim; relative ones (scripts started as
+    /// `python rel/app.py`) and symlinked paths are resolved against the
+    /// filesystem once and remembered when they do not match.
+    fn matches_activation_path(&mut self, filename: &str) -> bool {
+        let Some(activation) = &self.activation_path else {
+            return false;
+        };
+        let file = Path::new(filename);
+        if file == activation {
+            return true;
+        }
+        // Pseudo filenames such as `<string>` or `<frozen importlib._bootstrap>`
+        // never name a file on disk.
+        if filename.starts_with('<') || self.non_matching_filenames.contains(filename) {
+            return false;
+        }
+        let absolute_matches = std::path::absolute(file)
+            .map(|absolute| &absolute == activation)
+            .unwrap_or(false);
+        if absolute_matches {
+            return true;
+        }
+        if let Some(canonical) = &self.canonical_activation_path {
+            if fs::canonicalize(file).is_ok_and(|resolved| &resolved == canonical) {
+                return true;
+            }
+        }
+        self.non_matching_filenames.insert(filename.to_string());
+        false
+    }
+
+    fn current_thread_mut(&mut self) -> Option<&mut ThreadActivation> {
+        self.threads.get_mut(&thread::current().id())
+    }
+
+    /// Count a new frame of the activation code object (a `PY_START`, not a
+    /// generator resumption).
+    pub fn handle_entry(&mut self, code_id: usize) {
+        if let Some(state) = self.current_thread_mut() {
+            if state.started && state.activation_code_id == Some(code_id) {
+                state.activation_depth += 1;
             }
         }
     }
 
     /// Handle activation exits, marking suspension or completion as appropriate.
-    /// Returns `true` when tracing was deactivated by this call.
+    /// Returns `true` when tracing was deactivated for the calling thread.
     pub fn handle_exit(&mut self, code_id: usize, exit: ActivationExitKind) -> bool {
-        if self.activation_code_id != Some(code_id) {
+        let Some(state) = self.current_thread_mut() else {
+            return false;
+        };
+        if state.activation_code_id != Some(code_id) {
             return false;
         }
         match exit {
             ActivationExitKind::Suspended => {
-                self.suspended = true;
+                state.suspended = true;
+                false
+            }
+            // A recursive call of the activation code returned; the frame
+            // that activated tracing is still running.
+            ActivationExitKind::Completed if state.activation_depth > 1 => {
+                state.activation_depth -= 1;
                 false
             }
             ActivationExitKind::Completed => {
-                self.started = false;
-                self.activation_done = true;
-                self.suspended = false;
+                self.threads.remove(&thread::current().id());
                 true
             }
         }
     }
 
     fn resume_if_needed(&mut self, code: &CodeObjectWrapper) {
-        if self.started && self.suspended && self.activation_code_id == Some(code.id()) {
-            self.suspended = false;
+        if let Some(state) = self.current_thread_mut() {
+            if state.started && state.suspended && state.activation_code_id == Some(code.id()) {
+                state.suspended = false;
+            }
         }
     }
 }
@@ -177,6 +274,47 @@ mod tests {
         });
     }
 
+    #[test]
+    fn activates_for_relative_code_filename() {
+        Python::with_gil(|py| {
+            let relative = Path::new("relative_target.py");
+            let code = build_code(py, "target", "relative_target.py");
+            let mut controller = ActivationController::new(Some(relative));
+            assert!(controller.should_process_event(py, &code));
+            assert!(controller.is_active());
+        });
+    }
+
+    #[test]
+    fn activates_when_code_filename_resolves_to_activation_file() {
+        Python::with_gil(|py| {
+            let dir = tempfile::tempdir().expect("tempdir");
+            let target = dir.path().join("target.py");
+            fs::write(&target, "def target():\n    return 42\n").expect("write target");
+            // A `..` detour names the same file without being equal to it.
+            let detour = dir.path().join("sub").join("..").join("target.py");
+            fs::create_dir(dir.path().join("sub")).expect("create sub dir");
+            let code = build_code(py, "target", detour.to_str().expect("path is utf-8"));
+            let mut controller = ActivationController::new(Some(&target));
+            assert!(controller.should_process_event(py, &code));
+            assert!(controller.is_active());
+        });
+    }
+
+    #[test]
+    fn remembers_non_matching_filenames() {
+        Python::with_gil(|py| {
+            let code = build_code(py, "other", "relative_other.py");
+            let mut controller = ActivationController::new(Some(&abs_path("relative_target.py")));
+            assert!(!controller.should_process_event(py, &code));
+            assert!(controller
+                .non_matching_filenames
+                .contains("relative_other.py"));
+            assert!(!controller.should_process_event(py, &code));
+            assert_eq!(controller.non_matching_filenames.len(), 1);
+        });
+    }
+
     #[test]
     fn deactivates_after_activation_return() {
         Python::with_gil(|py| {
@@ -187,7 +325,29 @@ mod tests {
             assert!(controller.is_active());
             assert!(controller.handle_exit(code.id(), ActivationExitKind::Completed));
             assert!(!controller.is_active());
-            assert!(!controller.should_process_event(py, &code));
+            // Entering the activation code again opens a new window.
+            assert!(controller.should_process_event(py, &code));
+        });
+    }
+
+    #[test]
+    fn closed_window_leaves_no_state_for_a_reused_thread_id() {
+        Python::with_gil(|py| {
+            let target = abs_path("target.py");
+            let code = build_code(py, "target", target.to_str().expect("path is utf-8"));
+            let other = build_code(
+                py,
+                "other",
+                abs_path("other.py").to_str().expect("path is utf-8"),
+            );
+            let mut controller = ActivationController::new(Some(&target));
+            assert!(controller.should_process_event(py, &code));
+            assert!(controller.handle_exit(code.id(), ActivationExitKind::Completed));
+
+            // A thread that is handed this id next finds nothing to inherit.
+            assert!(controller.threads.is_empty());
+            assert!(!controller.is_active());
+            assert!(!controller.should_process_event(py, &other));
         });
     }
 
@@ -208,6 +368,82 @@ mod tests {
         });
     }
 
+    #[test]
+    fn recursive_activation_deactivates_on_outermost_return() {
+        Python::with_gil(|py| {
+            let target = abs_path("target.py");
+            let code = build_code(py, "target", target.to_str().expect("path is utf-8"));
+            let mut controller = ActivationController::new(Some(&target));
+            for _ in 0..3 {
+                assert!(controller.should_process_event(py, &code));
+                controller.handle_entry(code.id());
+            }
+            assert!(!controller.handle_exit(code.id(), ActivationExitKind::Completed));
+            assert!(!controller.handle_exit(code.id(), ActivationExitKind::Completed));
+            assert!(controller.is_active());
+            assert!(controller.handle_exit(code.id(), ActivationExitKind::Completed));
+            assert!(!controller.is_active());
+        });
+    }
+
+    #[test]
+    fn threads_activate_and_deactivate_independently() {
+        use std::sync::{Barrier, Mutex};
+
+        let target = abs_path("threaded_target.py");
+        let (activation_code, other_code) = Python::with_gil(|py| {
+            (
+                build_code(py, "target", target.to_str().expect("path is utf-8")),
+                build_code(
+                    py,
+                    "other",
+                    abs_path("threaded_other.py")
+                        .to_str()
+                        .expect("path is utf-8"),
+                ),
+            )
+        });
+        let controller = Mutex::new(ActivationController::new(Some(&target)));
+        let step = Barrier::new(2);
+        let process = |code: &CodeObjectWrapper| {
+            Python::with_gil(|py| {
+                controller
+                    .lock()
+                    .expect("controller lock")
+                    .should_process_event(py, code)
+            })
+        };
+        let exit = |code: &CodeObjectWrapper| {
+            controller
+                .lock()
+                .expect("controller lock")
+                .handle_exit(code.id(), ActivationExitKind::Completed)
+        };
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                // Window A opens first.
+                assert!(process(&activation_code));
+                step.wait();
+                // B enters while A is still active.
+                step.wait();
+                assert!(exit(&activation_code), "A's own return closes A");
+                assert!(!process(&other_code), "A stays inactive after return");
+                step.wait();
+            });
+            scope.spawn(|| {
+                step.wait();
+                assert!(!process(&other_code), "A's activation must not switch B on");
+                assert!(process(&activation_code));
+                step.wait();
+                step.wait();
+                assert!(process(&other_code), "A's return must not switch B off");
+                assert!(exit(&activation_code));
+                assert!(!process(&other_code));
+            });
+        });
+    }
+
     #[test]
     fn start_path_prefers_activation_path() {
         let target = abs_path("target.py");
@@ -10,11 +10,40 @@ use recorder_errors::{enverr, ErrorCode};
 use crate::code_object::CodeObjectWrapper;
 use crate::ffi::map_recorder_error;
 
+#[cfg(not(feature = "limited-api-frames"))]
 extern "C" {
     fn PyFrame_GetLocals(frame: *mut ffi::PyFrameObject) -> *mut ffi::PyObject;
     fn PyFrame_GetGlobals(frame: *mut ffi::PyFrameObject) -> *mut ffi::PyObject;
 }
 
+/// How [`capture_frame`] reads locals and globals once the frame is located.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameAccess {
+    /// `PyFrame_FastToLocalsWithError` followed by `PyFrame_GetLocals` /
+    /// `PyFrame_GetGlobals`. Fast, but relies on symbols outside the limited
+    /// API whose behaviour has shifted between CPython releases.
+    #[cfg(not(feature = "limited-api-frames"))]
+    Ffi,
+    /// `frame.f_locals` / `frame.f_globals` attribute reads, the same view
+    /// `sys._getframe()` gives Python code. Used by builds with the
+    /// `limited-api-frames` feature, where the FFI symbols are not linked.
+    Attributes,
+}
+
+impl FrameAccess {
+    /// Access mode compiled into this build.
+    pub const fn for_build() -> Self {
+        #[cfg(feature = "limited-api-frames")]
+        {
+            FrameAccess::Attributes
+        }
+        #[cfg(not(feature = "limited-api-frames"))]
+        {
+            FrameAccess::Ffi
+        }
+    }
+}
+
 /// Snapshot of the current frame including materialised locals and globals.
 #[derive(Debug)]
 pub struct FrameSnapshot<'py> {
@@ -66,6 +95,58 @@ pub fn capture_frame<'py>(
     py: Python<'py>,
     code: &CodeObjectWrapper,
 ) -> PyResult<FrameSnapshot<'py>> {
+    capture_frame_with(py, code, FrameAccess::for_build())
+}
+
+/// [`capture_frame`] with an explicit [`FrameAccess`] mode.
+pub fn capture_frame_with<'py>(
+    py: Python<'py>,
+    code: &CodeObjectWrapper,
+    access: FrameAccess,
+) -> PyResult<FrameSnapshot<'py>> {
+    let frame_ptr = locate_frame(py, code)?;
+
+    let mappings = match access {
+        #[cfg(not(feature = "limited-api-frames"))]
+        FrameAccess::Ffi => frame_mappings_ffi(py, frame_ptr),
+        FrameAccess::Attributes => frame_mappings_attributes(py, frame_ptr),
+    };
+    let materialised =
+        mappings.and_then(|(locals, globals)| materialise_mappings(py, &locals, &globals));
+    let (locals_dict, globals_dict, locals_is_globals) = match materialised {
+        Ok(parts) => parts,
+        Err(err) => {
+            unsafe {
+                ffi::Py_DECREF(frame_ptr.cast());
+            }
+            return Err(err);
+        }
+    };
+
+    Ok(FrameSnapshot {
+        frame_ptr,
+        locals: locals_dict,
+        globals: globals_dict,
+        locals_is_globals,
+    })
+}
+
+/// Address of the frame executing *code*, without materialising its locals.
+///
+/// A generator or coroutine keeps the same frame object across suspensions,
+/// so the address identifies it until the frame finishes.
+pub fn frame_identity(py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<u64> {
+    let frame_ptr = locate_frame(py, code)?;
+    let identity = frame_ptr as usize as u64;
+    unsafe {
+        ffi::Py_DECREF(frame_ptr.cast());
+    }
+    Ok(identity)
+}
+
+/// Walk the current thread's frames to the one executing *code* and return it
+/// with a new reference.
+fn locate_frame(py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<*mut ffi::PyFrameObject> {
     let mut frame_ptr = unsafe { ffi::PyEval_GetFrame() };
     if frame_ptr.is_null() {
         return Err(map_recorder_error(enverr!(
@@ -111,44 +192,79 @@ pub fn capture_frame<'py>(
             "Failed to locate frame for code object"
         )));
     }
+    Ok(frame_ptr)
+}
 
+#[cfg(not(feature = "limited-api-frames"))]
+fn frame_mappings_ffi<'py>(
+    py: Python<'py>,
+    frame_ptr: *mut ffi::PyFrameObject,
+) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyAny>)> {
     unsafe {
         if ffi::PyFrame_FastToLocalsWithError(frame_ptr) < 0 {
-            ffi::Py_DECREF(frame_ptr.cast());
-            let err = PyErr::fetch(py);
-            return Err(err);
+            return Err(PyErr::fetch(py));
         }
     }
 
     let locals_raw = unsafe { PyFrame_GetLocals(frame_ptr) };
     if locals_raw.is_null() {
-        unsafe {
-            ffi::Py_DECREF(frame_ptr.cast());
-        }
         return Err(map_recorder_error(enverr!(
             ErrorCode::FrameIntrospectionFailed,
             "PyFrame_GetLocals returned null"
         )));
     }
-    let locals_any = unsafe { Bound::<PyAny>::from_owned_ptr(py, locals_raw.cast()) };
-    let locals_mapping = locals_any.downcast::<PyMapping>().map_err(|_| {
-        map_recorder_error(enverr!(
-            ErrorCode::FrameIntrospectionFailed,
-            "Frame locals was not a mapping"
-        ))
-    })?;
+    let locals = unsafe { Bound::<PyAny>::from_owned_ptr(py, locals_raw.cast()) };
 
     let globals_raw = unsafe { PyFrame_GetGlobals(frame_ptr) };
     if globals_raw.is_null() {
-        unsafe {
-            ffi::Py_DECREF(frame_ptr.cast());
-        }
         return Err(map_recorder_error(enverr!(
             ErrorCode::GlobalsIntrospectionFailed,
             "PyFrame_GetGlobals returned null"
         )));
     }
-    let globals_any = unsafe { Bound::<PyAny>::from_owned_ptr(py, globals_raw.cast()) };
+    let globals = unsafe { Bound::<PyAny>::from_owned_ptr(py, globals_raw.cast()) };
+    Ok((locals, globals))
+}
+
+fn frame_mappings_attributes<'py>(
+    py: Python<'py>,
+    frame_ptr: *mut ffi::PyFrameObject,
+) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyAny>)> {
+    let frame = unsafe { Bound::<PyAny>::from_borrowed_ptr(py, frame_ptr.cast()) };
+    let locals = frame.getattr("f_locals").map_err(|err| {
+        map_recorder_error(
+            enverr!(
+                ErrorCode::FrameIntrospectionFailed,
+                "Failed to read frame.f_locals"
+            )
+            .with_context("details", err.to_string()),
+        )
+    })?;
+    let globals = frame.getattr("f_globals").map_err(|err| {
+        map_recorder_error(
+            enverr!(
+                ErrorCode::GlobalsIntrospectionFailed,
+                "Failed to read frame.f_globals"
+            )
+            .with_context("details", err.to_string()),
+        )
+    })?;
+    Ok((locals, globals))
+}
+
+/// Copy the frame mappings into plain dicts so later mutation of the frame
+/// does not change what gets recorded.
+fn materialise_mappings<'py>(
+    py: Python<'py>,
+    locals_any: &Bound<'py, PyAny>,
+    globals_any: &Bound<'py, PyAny>,
+) -> PyResult<(Bound<'py, PyDict>, Option<Bound<'py, PyDict>>, bool)> {
+    let locals_mapping = locals_any.downcast::<PyMapping>().map_err(|_| {
+        map_recorder_error(enverr!(
+            ErrorCode::FrameIntrospectionFailed,
+            "Frame locals was not a mapping"
+        ))
+    })?;
     let globals_mapping = globals_any.downcast::<PyMapping>().map_err(|_| {
         map_recorder_error(enverr!(
             ErrorCode::GlobalsIntrospectionFailed,
@@ -156,10 +272,10 @@ pub fn capture_frame<'py>(
         ))
     })?;
 
-    let locals_is_globals = locals_raw == globals_raw;
+    let locals_is_globals = locals_any.as_ptr() == globals_any.as_ptr();
 
     let locals_dict = PyDict::new(py);
-    locals_dict.update(&locals_mapping).map_err(|err| {
+    locals_dict.update(locals_mapping).map_err(|err| {
         map_recorder_error(
             enverr!(
                 ErrorCode::FrameIntrospectionFailed,
@@ -173,7 +289,7 @@ pub fn capture_frame<'py>(
         None
     } else {
         let dict = PyDict::new(py);
-        dict.update(&globals_mapping).map_err(|err| {
+        dict.update(globals_mapping).map_err(|err| {
             map_recorder_error(
                 enverr!(
                     ErrorCode::GlobalsIntrospectionFailed,
@@ -185,10 +301,84 @@ pub fn capture_frame<'py>(
         Some(dict)
     };
 
-    Ok(FrameSnapshot {
-        frame_ptr,
-        locals: locals_dict,
-        globals: globals_dict,
-        locals_is_globals,
-    })
+    Ok((locals_dict, globals_dict, locals_is_globals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyCode;
+    use pyo3::wrap_pyfunction;
+    use std::ffi::CString;
+
+    #[pyfunction]
+    fn capture_locals_via_attributes(
+        py: Python<'_>,
+        code: Bound<'_, PyCode>,
+    ) -> PyResult<Py<PyDict>> {
+        let wrapper = CodeObjectWrapper::new(py, &code);
+        let snapshot = capture_frame_with(py, &wrapper, FrameAccess::Attributes)?;
+        Ok(snapshot.locals().copy()?.unbind())
+    }
+
+    #[pyfunction]
+    fn capture_locals_for_build(py: Python<'_>, code: Bound<'_, PyCode>) -> PyResult<Py<PyDict>> {
+        let wrapper = CodeObjectWrapper::new(py, &code);
+        let snapshot = capture_frame(py, &wrapper)?;
+        Ok(snapshot.locals().copy()?.unbind())
+    }
+
+    #[test]
+    fn attribute_fallback_captures_function_locals() {
+        Python::with_gil(|py| {
+            let globals = PyDict::new(py);
+            globals
+                .set_item(
+                    "via_attributes",
+                    wrap_pyfunction!(capture_locals_via_attributes, py).expect("wrap fallback"),
+                )
+                .expect("install fallback");
+            globals
+                .set_item(
+                    "for_build",
+                    wrap_pyfunction!(capture_locals_for_build, py).expect("wrap default"),
+                )
+                .expect("install default");
+            let script = CString::new(
+                "def probe(n):\n    count = n + 1\n    label = 'frame'\n    return via_attributes(probe.__code__), for_build(probe.__code__)\nfallback, default = probe(41)\n",
+            )
+            .expect("script cstring");
+            py.run(script.as_c_str(), Some(&globals), None)
+                .expect("run probe script");
+
+            let fallback = globals
+                .get_item("fallback")
+                .expect("lookup fallback")
+                .expect("fallback locals");
+            let fallback = fallback.downcast::<PyDict>().expect("fallback dict");
+            let count: i64 = fallback
+                .get_item("count")
+                .expect("lookup count")
+                .expect("count captured")
+                .extract()
+                .expect("count int");
+            assert_eq!(count, 42);
+            let label: String = fallback
+                .get_item("label")
+                .expect("lookup label")
+                .expect("label captured")
+                .extract()
+                .expect("label str");
+            assert_eq!(label, "frame");
+
+            let default = globals
+                .get_item("default")
+                .expect("lookup default")
+                .expect("default locals");
+            assert!(
+                default.eq(fallback).expect("compare locals"),
+                "fallback locals should match the build default"
+            );
+        });
+    }
 }
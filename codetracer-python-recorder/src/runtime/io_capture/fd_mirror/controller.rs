@@ -2,18 +2,18 @@ use super::ledger::MirrorLedgers;
 use crate::runtime::io_capture::sink::IoChunkConsumer;
 use std::sync::Arc;
 
-#[cfg(not(unix))]
-use super::stub as unix;
-#[cfg(unix)]
-use super::unix;
+#[cfg(any(unix, windows))]
+use super::native;
+#[cfg(not(any(unix, windows)))]
+use super::stub as native;
 
-#[cfg(not(unix))]
+#[cfg(any(unix, windows))]
+pub use super::native::FdMirrorError;
+#[cfg(not(any(unix, windows)))]
 pub use super::stub::FdMirrorError;
-#[cfg(unix)]
-pub use super::unix::FdMirrorError;
 
 pub struct FdMirrorController {
-    inner: Option<unix::FdMirrorController>,
+    inner: Option<native::FdMirrorController>,
 }
 
 impl FdMirrorController {
@@ -22,7 +22,7 @@ impl FdMirrorController {
         consumer: Arc<dyn IoChunkConsumer>,
     ) -> Result<Self, FdMirrorError> {
         let inner = if let Some(set) = ledgers.inner() {
-            Some(unix::FdMirrorController::new(set, consumer)?)
+            Some(native::FdMirrorController::new(set, consumer)?)
         } else {
             None
         };
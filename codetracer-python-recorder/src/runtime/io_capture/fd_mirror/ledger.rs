@@ -1,21 +1,27 @@
 use crate::runtime::io_capture::events::IoStream;
 use std::sync::Arc;
 
-#[cfg(not(unix))]
+#[cfg(any(unix, windows))]
+use super::native::MirrorLedgerSet;
+#[cfg(not(any(unix, windows)))]
 use super::stub::MirrorLedgerSet;
-#[cfg(unix)]
-use super::unix::MirrorLedgerSet;
 
 #[derive(Clone, Default)]
 pub struct MirrorLedgers(Option<Arc<MirrorLedgerSet>>);
 
 impl MirrorLedgers {
-    pub fn new_enabled() -> Self {
-        #[cfg(unix)]
+    /// Create ledgers for the streams that should be mirrored. Returns a
+    /// disabled set when neither stream is requested or the platform has no
+    /// mirror support.
+    pub fn new_enabled(stdout: bool, stderr: bool) -> Self {
+        if !stdout && !stderr {
+            return Self(None);
+        }
+        #[cfg(any(unix, windows))]
         {
-            Self(Some(Arc::new(MirrorLedgerSet::new())))
+            Self(Some(Arc::new(MirrorLedgerSet::new(stdout, stderr))))
         }
-        #[cfg(not(unix))]
+        #[cfg(not(any(unix, windows)))]
         {
             Self(None)
         }
@@ -36,7 +42,7 @@ impl MirrorLedgers {
     }
 }
 
-#[cfg(not(unix))]
+#[cfg(any(unix, windows))]
+pub use super::native::LedgerTicket;
+#[cfg(not(any(unix, windows)))]
 pub use super::stub::LedgerTicket;
-#[cfg(unix)]
-pub use super::unix::LedgerTicket;
@@ -1,9 +1,11 @@
 mod controller;
 mod ledger;
-#[cfg(not(unix))]
+#[cfg(any(unix, windows))]
+mod native;
+#[cfg(not(any(unix, windows)))]
 mod stub;
-#[cfg(unix)]
-mod unix;
+#[cfg(any(unix, windows))]
+mod sys;
 
 pub use controller::FdMirrorController;
 pub use ledger::{LedgerTicket, MirrorLedgers};
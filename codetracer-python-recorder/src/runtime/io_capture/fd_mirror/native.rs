@@ -1,9 +1,9 @@
+use super::sys::{self, Fd, RawFd};
 use crate::runtime::io_capture::events::IoStream;
 use crate::runtime::io_capture::sink::{IoChunk, IoChunkConsumer, IoChunkFlags};
 use log::warn;
 use std::collections::VecDeque;
 use std::io;
-use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -144,32 +144,31 @@ impl Ledger {
     }
 }
 
+/// Ledgers for the mirrored streams; a stream without a ledger is left
+/// untouched by the mirror.
 #[derive(Clone)]
 pub struct MirrorLedgerSet {
-    stdout: Arc<Ledger>,
-    stderr: Arc<Ledger>,
+    stdout: Option<Arc<Ledger>>,
+    stderr: Option<Arc<Ledger>>,
 }
 
 impl MirrorLedgerSet {
-    pub fn new() -> Self {
+    pub fn new(stdout: bool, stderr: bool) -> Self {
         Self {
-            stdout: Arc::new(Ledger::new()),
-            stderr: Arc::new(Ledger::new()),
+            stdout: stdout.then(|| Arc::new(Ledger::new())),
+            stderr: stderr.then(|| Arc::new(Ledger::new())),
         }
     }
 
     pub fn begin_proxy_write(&self, stream: IoStream, payload: &[u8]) -> Option<LedgerTicket> {
-        match stream {
-            IoStream::Stdout => Some(self.stdout.begin_entry(payload)),
-            IoStream::Stderr => Some(self.stderr.begin_entry(payload)),
-            IoStream::Stdin => None,
-        }
+        self.ledger_for(stream)
+            .map(|ledger| ledger.begin_entry(payload))
     }
 
     fn ledger_for(&self, stream: IoStream) -> Option<Arc<Ledger>> {
         match stream {
-            IoStream::Stdout => Some(Arc::clone(&self.stdout)),
-            IoStream::Stderr => Some(Arc::clone(&self.stderr)),
+            IoStream::Stdout => self.stdout.clone(),
+            IoStream::Stderr => self.stderr.clone(),
             IoStream::Stdin => None,
         }
     }
@@ -205,7 +204,7 @@ impl Drop for LedgerTicket {
 
 struct StreamMirror {
     target_fd: RawFd,
-    preserved_fd: OwnedFd,
+    preserved_fd: Fd,
     ledger: Arc<Ledger>,
     join: Option<thread::JoinHandle<()>>,
     shutdown_trigger: Arc<ShutdownSignal>,
@@ -221,42 +220,28 @@ impl StreamMirror {
         consumer: Arc<dyn IoChunkConsumer>,
     ) -> Result<Self, FdMirrorError> {
         let target_fd = match stream {
-            IoStream::Stdout => libc::STDOUT_FILENO,
-            IoStream::Stderr => libc::STDERR_FILENO,
+            IoStream::Stdout => sys::STDOUT_FD,
+            IoStream::Stderr => sys::STDERR_FD,
             IoStream::Stdin => {
                 return Err(FdMirrorError::new("stdin mirroring not supported"));
             }
         };
 
-        let preserved = unsafe { libc::dup(target_fd) };
-        if preserved < 0 {
-            return Err(FdMirrorError::new("dup failed for target fd"));
-        }
-        let preserved_fd = unsafe { OwnedFd::from_raw_fd(preserved) };
+        let preserved_fd = sys::dup(target_fd)
+            .map_err(|err| FdMirrorError::new(format!("dup failed for target fd: {err}")))?;
 
-        let mut pipe_fds = [0; 2];
-        if unsafe { libc::pipe(pipe_fds.as_mut_ptr()) } != 0 {
-            return Err(FdMirrorError::new("pipe setup failed"));
-        }
-        let read_fd = unsafe { OwnedFd::from_raw_fd(pipe_fds[0]) };
-        let write_fd = pipe_fds[1];
+        let (read_fd, write_fd) =
+            sys::pipe().map_err(|err| FdMirrorError::new(format!("pipe setup failed: {err}")))?;
 
-        if unsafe { libc::dup2(write_fd, target_fd) } < 0 {
-            unsafe {
-                libc::close(write_fd);
-            }
-            return Err(FdMirrorError::new("dup2 failed while installing mirror"));
-        }
+        // The target now refers to the pipe; our own write end is dropped so
+        // the reader sees EOF once the target is restored.
+        sys::dup2(write_fd.raw(), target_fd).map_err(|err| {
+            FdMirrorError::new(format!("dup2 failed while installing mirror: {err}"))
+        })?;
+        drop(write_fd);
 
-        unsafe {
-            libc::close(write_fd);
-        }
-
-        let forward_fd = unsafe { libc::dup(preserved_fd.as_raw_fd()) };
-        if forward_fd < 0 {
-            return Err(FdMirrorError::new("dup failed for forward fd"));
-        }
-        let forward_owned = unsafe { OwnedFd::from_raw_fd(forward_fd) };
+        let forward_owned = sys::dup(preserved_fd.raw())
+            .map_err(|err| FdMirrorError::new(format!("dup failed for forward fd: {err}")))?;
 
         let shutdown = Arc::new(ShutdownSignal::default());
         let thread_shutdown = shutdown.clone();
@@ -288,7 +273,7 @@ impl StreamMirror {
 
     fn shutdown(&mut self) {
         self.shutdown_trigger.request_shutdown();
-        if unsafe { libc::dup2(self.preserved_fd.as_raw_fd(), self.target_fd) } < 0 {
+        if sys::dup2(self.preserved_fd.raw(), self.target_fd).is_err() {
             warn!("failed to restore fd {} after mirroring", self.target_fd);
         }
         if let Some(join) = self.join.take() {
@@ -438,38 +423,31 @@ fn mirror_loop(
     stream: IoStream,
     ledger: Arc<Ledger>,
     consumer: Arc<dyn IoChunkConsumer>,
-    read_fd: OwnedFd,
-    forward_fd: OwnedFd,
+    read_fd: Fd,
+    forward_fd: Fd,
     shutdown: Arc<ShutdownSignal>,
 ) {
     let mut buffer = vec![0u8; 8192];
     while !shutdown.should_exit() {
-        let read = unsafe {
-            libc::read(
-                read_fd.as_raw_fd(),
-                buffer.as_mut_ptr() as *mut libc::c_void,
-                buffer.len(),
-            )
-        };
-        if read < 0 {
-            let err = io::Error::last_os_error();
-            if err.kind() == io::ErrorKind::Interrupted {
-                continue;
+        let read = match sys::read(read_fd.raw(), &mut buffer) {
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                warn!("fd mirror read error on {stream}: {err}");
+                break;
             }
-            warn!("fd mirror read error on {stream}: {err}");
-            break;
-        }
+        };
         if read == 0 {
             break;
         }
 
-        let payload = &buffer[..read as usize];
+        let payload = &buffer[..read];
         let leftover = ledger.subtract_from_chunk(payload);
         if leftover.is_empty() {
             continue;
         }
 
-        if let Err(err) = write_all(forward_fd.as_raw_fd(), &leftover) {
+        if let Err(err) = write_all(forward_fd.raw(), &leftover) {
             warn!("fd mirror write back error on {stream}: {err}");
             break;
         }
@@ -491,27 +469,18 @@ fn mirror_loop(
 
 fn write_all(fd: RawFd, mut data: &[u8]) -> io::Result<()> {
     while !data.is_empty() {
-        let written = unsafe {
-            libc::write(
-                fd,
-                data.as_ptr() as *const libc::c_void,
-                data.len().min(isize::MAX as usize),
-            )
+        let written = match sys::write(fd, data) {
+            Ok(written) => written,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
         };
-        if written < 0 {
-            let err = io::Error::last_os_error();
-            if err.kind() == io::ErrorKind::Interrupted {
-                continue;
-            }
-            return Err(err);
-        }
         if written == 0 {
             return Err(io::Error::new(
                 io::ErrorKind::WriteZero,
                 "failed to write to preserved fd",
             ));
         }
-        data = &data[written as usize..];
+        data = &data[written..];
     }
     Ok(())
 }
@@ -31,7 +31,7 @@ pub struct LedgerTicket;
 pub struct FdMirrorController;
 
 impl MirrorLedgerSet {
-    pub fn new() -> Self {
+    pub fn new(_: bool, _: bool) -> Self {
         Self
     }
 
@@ -0,0 +1,145 @@
+//! Descriptor primitives the mirror needs, over POSIX or the Windows CRT.
+//!
+//! On Windows the mirror works on CRT file descriptors (`_dup`, `_dup2`,
+//! `_pipe`) rather than raw `HANDLE`s: Python's `os.write` and C-level
+//! `printf` both go through the CRT, and `_dup2` onto descriptors 0-2 also
+//! updates the process standard handles (`SetStdHandle`), so native code
+//! calling `GetStdHandle` after the mirror is installed writes into the pipe
+//! as well.
+
+use std::io;
+
+pub type RawFd = libc::c_int;
+
+pub const STDOUT_FD: RawFd = 1;
+pub const STDERR_FD: RawFd = 2;
+
+/// Owned descriptor, closed on drop.
+#[derive(Debug)]
+pub struct Fd(RawFd);
+
+impl Fd {
+    pub fn raw(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl Drop for Fd {
+    fn drop(&mut self) {
+        unsafe {
+            imp::close(self.0);
+        }
+    }
+}
+
+/// Duplicate `fd` into a new descriptor.
+pub fn dup(fd: RawFd) -> io::Result<Fd> {
+    let duplicated = unsafe { imp::dup(fd) };
+    if duplicated < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Fd(duplicated))
+}
+
+/// Point `target` at the file `source` refers to.
+pub fn dup2(source: RawFd, target: RawFd) -> io::Result<()> {
+    if unsafe { imp::dup2(source, target) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Create an anonymous pipe, returning `(read, write)` ends.
+pub fn pipe() -> io::Result<(Fd, Fd)> {
+    let mut fds: [RawFd; 2] = [0; 2];
+    if unsafe { imp::pipe(&mut fds) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((Fd(fds[0]), Fd(fds[1])))
+}
+
+pub fn read(fd: RawFd, buffer: &mut [u8]) -> io::Result<usize> {
+    let read = unsafe { imp::read(fd, buffer) };
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(read as usize)
+}
+
+pub fn write(fd: RawFd, data: &[u8]) -> io::Result<usize> {
+    let written = unsafe { imp::write(fd, data) };
+    if written < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(written as usize)
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::RawFd;
+
+    pub unsafe fn close(fd: RawFd) {
+        libc::close(fd);
+    }
+
+    pub unsafe fn dup(fd: RawFd) -> RawFd {
+        libc::dup(fd)
+    }
+
+    pub unsafe fn dup2(source: RawFd, target: RawFd) -> RawFd {
+        libc::dup2(source, target)
+    }
+
+    pub unsafe fn pipe(fds: &mut [RawFd; 2]) -> libc::c_int {
+        libc::pipe(fds.as_mut_ptr())
+    }
+
+    pub unsafe fn read(fd: RawFd, buffer: &mut [u8]) -> isize {
+        libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len())
+    }
+
+    pub unsafe fn write(fd: RawFd, data: &[u8]) -> isize {
+        libc::write(
+            fd,
+            data.as_ptr() as *const libc::c_void,
+            data.len().min(isize::MAX as usize),
+        )
+    }
+}
+
+#[cfg(windows)]
+mod imp {
+    use super::RawFd;
+
+    // <fcntl.h>: binary mode so `\n` is not translated, and keep the pipe
+    // out of child processes so they cannot hold the reader open.
+    const O_BINARY: libc::c_int = 0x8000;
+    const O_NOINHERIT: libc::c_int = 0x0080;
+    const PIPE_BUFFER: libc::c_uint = 64 * 1024;
+
+    pub unsafe fn close(fd: RawFd) {
+        libc::close(fd);
+    }
+
+    pub unsafe fn dup(fd: RawFd) -> RawFd {
+        libc::dup(fd)
+    }
+
+    pub unsafe fn dup2(source: RawFd, target: RawFd) -> RawFd {
+        libc::dup2(source, target)
+    }
+
+    pub unsafe fn pipe(fds: &mut [RawFd; 2]) -> libc::c_int {
+        libc::pipe(fds.as_mut_ptr(), PIPE_BUFFER, O_BINARY | O_NOINHERIT)
+    }
+
+    pub unsafe fn read(fd: RawFd, buffer: &mut [u8]) -> isize {
+        let len = buffer.len().min(libc::c_uint::MAX as usize) as libc::c_uint;
+        libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, len) as isize
+    }
+
+    pub unsafe fn write(fd: RawFd, data: &[u8]) -> isize {
+        let len = data.len().min(libc::c_int::MAX as usize) as libc::c_uint;
+        libc::write(fd, data.as_ptr() as *const libc::c_void, len) as isize
+    }
+}
@@ -13,7 +13,17 @@ use std::thread::ThreadId;
 #[derive(Clone, Copy, Debug, Default)]
 pub struct IoCaptureSettings {
     pub line_proxies: bool,
-    pub fd_mirror: bool,
+    /// Mirror writes that bypass `sys.stdout` by redirecting fd 1.
+    pub fd_mirror_stdout: bool,
+    /// Mirror writes that bypass `sys.stderr` by redirecting fd 2.
+    pub fd_mirror_stderr: bool,
+}
+
+impl IoCaptureSettings {
+    /// Whether any stream requested the FD mirror.
+    pub fn fd_mirror(&self) -> bool {
+        self.fd_mirror_stdout || self.fd_mirror_stderr
+    }
 }
 
 pub struct IoCapturePipeline {
@@ -37,8 +47,9 @@ impl IoCapturePipeline {
         let buffer = Arc::new(IoChunkBuffer::new());
         let consumer: Arc<dyn IoChunkConsumer> = buffer.clone();
 
-        let mirror_ledgers = if settings.fd_mirror {
-            let ledgers = MirrorLedgers::new_enabled();
+        let mirror_ledgers = if settings.fd_mirror() {
+            let ledgers =
+                MirrorLedgers::new_enabled(settings.fd_mirror_stdout, settings.fd_mirror_stderr);
             if !ledgers.is_enabled() {
                 log::warn!("fd_fallback requested but not supported on this platform");
                 None
@@ -100,6 +100,22 @@ impl IoChunkBatcher {
                     &*self.consumer,
                 );
             }
+            IoOperation::Flush if buffer.payload.is_empty() => {
+                // `print(..., flush=True)` flushes after writing its `end`,
+                // so the content already left as a newline chunk. Record the
+                // flush as an empty marker instead of dropping it.
+                self.consumer.consume(IoChunk {
+                    stream: event.stream,
+                    payload: Vec::new(),
+                    thread_id: event.thread_id,
+                    timestamp: event.timestamp,
+                    frame_id: event.frame_id,
+                    path_id: event.path_id,
+                    line: event.line,
+                    path: event.path.take(),
+                    flags: IoChunkFlags::EXPLICIT_FLUSH,
+                });
+            }
             IoOperation::Flush => {
                 buffer.emit(
                     event.thread_id,
@@ -11,9 +11,11 @@ pub mod io_capture;
 mod line_snapshots;
 mod logging;
 mod output_paths;
+mod statement_lines;
 pub mod tracer;
 mod value_capture;
 mod value_encoder;
+mod with_blocks;
 
 pub use output_paths::TraceOutputPaths;
 pub use tracer::RuntimeTracer;
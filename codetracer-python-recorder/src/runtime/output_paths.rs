@@ -1,11 +1,16 @@
 //! File-system helpers for trace output management.
 
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 
-use codetracer_trace_types::{Line, PathId};
+use codetracer_trace_types::{Line, PathId, TraceLowLevelEvent};
 use codetracer_trace_writer_nim::trace_writer::TraceWriter;
 use codetracer_trace_writer_nim::TraceEventsFileFormat;
 use recorder_errors::{enverr, ErrorCode};
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
 
 use crate::errors::Result;
 use crate::runtime::autoformat::{self, AutoformatOutcome, SkipReason};
@@ -20,28 +25,91 @@ use crate::runtime::autoformat::{self, AutoformatOutcome, SkipReason};
 #[derive(Debug, Clone)]
 pub struct TraceOutputPaths {
     events: PathBuf,
+    coverage: PathBuf,
+    functions: PathBuf,
+    filter_decisions: PathBuf,
     format: TraceEventsFileFormat,
+    ndjson: bool,
 }
 
 impl TraceOutputPaths {
     /// Build output paths for a given directory. The directory is expected to
     /// exist before initialisation; callers should ensure it is created.
     pub fn new(root: &Path, format: TraceEventsFileFormat) -> Self {
-        let events_name = match format {
-            TraceEventsFileFormat::Json => "trace.json",
-            TraceEventsFileFormat::Ctfs => "trace.ct",
-            _ => "trace.bin",
+        Self::with_suffix(root, format, "")
+    }
+
+    /// Paths for the `index`-th session appended to `root`, with every file
+    /// name carrying the index (`trace.1.json`, `functions.1.json`, ...).
+    pub fn segment(root: &Path, format: TraceEventsFileFormat, index: usize) -> Self {
+        Self::with_suffix(root, format, &format!(".{index}"))
+    }
+
+    fn with_suffix(root: &Path, format: TraceEventsFileFormat, suffix: &str) -> Self {
+        let events_ext = match format {
+            TraceEventsFileFormat::Json => "json",
+            TraceEventsFileFormat::Ctfs => "ct",
+            _ => "bin",
         };
         Self {
-            events: root.join(events_name),
+            events: root.join(format!("trace{suffix}.{events_ext}")),
+            coverage: root.join(format!("coverage{suffix}.json")),
+            functions: root.join(format!("functions{suffix}.json")),
+            filter_decisions: root.join(format!("filter_decisions{suffix}.json")),
             format,
+            ndjson: false,
         }
     }
 
+    /// Lay the `Json` events out as NDJSON in `trace.ndjson`, one event per
+    /// line, instead of a single array in `trace.json`.
+    pub fn into_ndjson(mut self) -> Self {
+        if matches!(self.format, TraceEventsFileFormat::Json) {
+            self.events.set_extension("ndjson");
+            self.ndjson = true;
+        }
+        self
+    }
+
+    /// Whether the events file is written as NDJSON.
+    pub fn is_ndjson(&self) -> bool {
+        self.ndjson
+    }
+
+    /// The trace files of this layout that are already on disk.
+    pub fn existing_files(&self) -> Vec<&Path> {
+        [
+            &self.events,
+            &self.coverage,
+            &self.functions,
+            &self.filter_decisions,
+        ]
+        .into_iter()
+        .map(PathBuf::as_path)
+        .filter(|path| path.exists())
+        .collect()
+    }
+
     pub fn events(&self) -> &Path {
         &self.events
     }
 
+    /// Line hit report written when the coverage policy is on.
+    pub fn coverage(&self) -> &Path {
+        &self.coverage
+    }
+
+    /// `FunctionId` → name/file/first-line table written when tracing finishes.
+    pub fn functions(&self) -> &Path {
+        &self.functions
+    }
+
+    /// Filter source and rule behind each skipped scope, written when
+    /// filter decision logging is on.
+    pub fn filter_decisions(&self) -> &Path {
+        &self.filter_decisions
+    }
+
     pub fn format(&self) -> TraceEventsFileFormat {
         self.format
     }
@@ -74,9 +142,12 @@ impl TraceOutputPaths {
         start_line: u32,
     ) -> Result<()> {
         TraceWriter::begin_writing_trace_events(writer, self.events()).map_err(|err| {
-            enverr!(ErrorCode::Io, "failed to begin trace events")
-                .with_context("path", self.events().display().to_string())
-                .with_context("source", err.to_string())
+            enverr!(
+                ErrorCode::OutputDirectoryUnwritable,
+                "failed to open trace output"
+            )
+            .with_context("path", self.events().display().to_string())
+            .with_context("source", err.to_string())
         })?;
         if matches!(self.format, TraceEventsFileFormat::Ctfs) {
             // P1.1: opt the CTFS writer into column-aware step encoding.
@@ -120,6 +191,151 @@ impl TraceOutputPaths {
         TraceWriter::start(writer, start_path, Line(start_line as i64));
         Ok(())
     }
+
+    /// Lay the finished `Json` events file out indented (`pretty`) or on a
+    /// single line. The writer backend serialises the events itself, so the
+    /// layout is applied once the file has been finalised. Other formats are
+    /// left untouched.
+    pub fn apply_json_layout(&self, pretty: bool) -> Result<()> {
+        if !matches!(self.format, TraceEventsFileFormat::Json) || !self.events.exists() {
+            return Ok(());
+        }
+        let layout = if pretty {
+            EventsLayout::Pretty
+        } else {
+            EventsLayout::Compact
+        };
+        self.relayout_events(layout, "failed to lay out JSON trace events")
+    }
+
+    /// Append `events` to the NDJSON events file, one compact JSON object
+    /// per line. Used to flush a running session incrementally.
+    pub fn append_ndjson_events(&self, events: &[TraceLowLevelEvent]) -> Result<()> {
+        let io_error = |err: String| {
+            enverr!(ErrorCode::Io, "failed to append NDJSON trace events")
+                .with_context("path", self.events.display().to_string())
+                .with_context("source", err)
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.events)
+            .map_err(|err| io_error(err.to_string()))?;
+        let mut out = BufWriter::new(file);
+        for event in events {
+            serde_json::to_writer(&mut out, event).map_err(|err| io_error(err.to_string()))?;
+            out.write_all(b"\n")
+                .map_err(|err| io_error(err.to_string()))?;
+        }
+        out.flush().map_err(|err| io_error(err.to_string()))
+    }
+
+    /// Rewrite the array the `Json` writer leaves in the events file on
+    /// finish as NDJSON. Other layouts are left untouched.
+    pub fn apply_ndjson_layout(&self) -> Result<()> {
+        if !self.ndjson || !self.events.exists() {
+            return Ok(());
+        }
+        self.relayout_events(
+            EventsLayout::Ndjson,
+            "failed to lay out NDJSON trace events",
+        )
+    }
+
+    /// Stream the events array through `layout` into a sibling temp file,
+    /// one event at a time, and rename it over the events file. Memory stays
+    /// bounded by the largest event, and a failure leaves the original file
+    /// in place.
+    fn relayout_events(&self, layout: EventsLayout, message: &'static str) -> Result<()> {
+        let mut staging = self.events.clone().into_os_string();
+        staging.push(".tmp");
+        let staging = PathBuf::from(staging);
+
+        let result = write_relayout(&self.events, &staging, layout);
+        if let Err(err) = result.and_then(|()| std::fs::rename(&staging, &self.events)) {
+            let _ = std::fs::remove_file(&staging);
+            return Err(enverr!(ErrorCode::Io, "{}", message)
+                .with_context("path", self.events.display().to_string())
+                .with_context("source", err.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Write the events array in `source` to `staging` in `layout`.
+fn write_relayout(source: &Path, staging: &Path, layout: EventsLayout) -> std::io::Result<()> {
+    let input = BufReader::new(File::open(source)?);
+    let mut out = BufWriter::new(File::create(staging)?);
+    let mut events = serde_json::Deserializer::from_reader(input);
+    events.deserialize_seq(RelayoutVisitor {
+        out: &mut out,
+        layout,
+    })?;
+    events.end()?;
+    out.flush()?;
+    out.into_inner().map_err(|err| err.into_error())?.sync_all()
+}
+
+/// How [`TraceOutputPaths::relayout_events`] writes the events back out.
+#[derive(Debug, Clone, Copy)]
+enum EventsLayout {
+    Compact,
+    Pretty,
+    Ndjson,
+}
+
+/// Copies a JSON array of events to `out` as it is parsed, so the whole
+/// trace is never held in memory.
+struct RelayoutVisitor<'a, W> {
+    out: &'a mut W,
+    layout: EventsLayout,
+}
+
+impl<'de, W: Write> Visitor<'de> for RelayoutVisitor<'_, W> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array of trace events")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, events: A) -> std::result::Result<(), A::Error> {
+        match self.layout {
+            EventsLayout::Compact => {
+                copy_events(&mut serde_json::Serializer::new(self.out), events)
+            }
+            EventsLayout::Pretty => {
+                copy_events(&mut serde_json::Serializer::pretty(self.out), events)
+            }
+            EventsLayout::Ndjson => {
+                let mut events = events;
+                while let Some(event) = events.next_element::<serde_json::Value>()? {
+                    serde_json::to_writer(&mut *self.out, &event)
+                        .map_err(<A::Error as de::Error>::custom)?;
+                    self.out
+                        .write_all(b"\n")
+                        .map_err(<A::Error as de::Error>::custom)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Re-serialise every event of `events` as one array through `serializer`.
+fn copy_events<'de, S: Serializer, A: SeqAccess<'de>>(
+    serializer: S,
+    mut events: A,
+) -> std::result::Result<(), A::Error> {
+    let mut array = serializer
+        .serialize_seq(None)
+        .map_err(<A::Error as de::Error>::custom)?;
+    while let Some(event) = events.next_element::<serde_json::Value>()? {
+        array
+            .serialize_element(&event)
+            .map_err(<A::Error as de::Error>::custom)?;
+    }
+    array.end().map_err(<A::Error as de::Error>::custom)?;
+    Ok(())
 }
 
 /// P6.2: run the recorder-side autoformat pass on `source_path` and,
@@ -288,4 +504,73 @@ mod tests {
             .any(|event| matches!(event, TraceLowLevelEvent::Call(_)));
         assert!(has_call, "expected toplevel call event");
     }
+
+    const EVENTS_FIXTURE: &str = r#"[{"Path":"program.py"},{"Step":{"path_id":0,"line":1}},{"Call":{"function_id":0,"args":[]}}]"#;
+
+    #[test]
+    fn apply_json_layout_pretty_indents_events() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        std::fs::write(paths.events(), EVENTS_FIXTURE).expect("write events");
+
+        paths.apply_json_layout(true).expect("pretty layout");
+
+        let text = std::fs::read_to_string(paths.events()).expect("read events");
+        assert!(text.contains('\n'), "pretty output should span lines");
+        assert!(text.contains("\n  "), "pretty output should be indented");
+    }
+
+    #[test]
+    fn ndjson_layout_writes_one_event_per_line() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json).into_ndjson();
+        assert_eq!(paths.events(), tmp.path().join("trace.ndjson").as_path());
+        std::fs::write(paths.events(), EVENTS_FIXTURE).expect("write events");
+
+        paths.apply_ndjson_layout().expect("ndjson layout");
+
+        let text = std::fs::read_to_string(paths.events()).expect("read events");
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3, "{text}");
+        for line in lines {
+            let event: serde_json::Value = serde_json::from_str(line).expect("line is JSON");
+            assert!(event.is_object());
+        }
+    }
+
+    #[test]
+    fn failed_layout_leaves_events_file_untouched() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json).into_ndjson();
+        let truncated = &EVENTS_FIXTURE[..EVENTS_FIXTURE.len() - 10];
+        std::fs::write(paths.events(), truncated).expect("write events");
+
+        paths
+            .apply_ndjson_layout()
+            .expect_err("truncated array should fail");
+
+        let text = std::fs::read_to_string(paths.events()).expect("read events");
+        assert_eq!(text, truncated);
+        let leftovers: Vec<_> = std::fs::read_dir(tmp.path())
+            .expect("list dir")
+            .map(|entry| entry.expect("entry").file_name())
+            .collect();
+        assert_eq!(leftovers, vec![std::ffi::OsString::from("trace.ndjson")]);
+    }
+
+    #[test]
+    fn apply_json_layout_compact_keeps_events_on_one_line() {
+        let tmp = tempdir().expect("tempdir");
+        let paths = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        std::fs::write(paths.events(), EVENTS_FIXTURE).expect("write events");
+        paths.apply_json_layout(true).expect("pretty layout");
+        paths.apply_json_layout(false).expect("compact layout");
+
+        let text = std::fs::read_to_string(paths.events()).expect("read events");
+        assert!(!text.contains('\n'), "compact output should not span lines");
+        assert!(
+            !text.contains("  "),
+            "compact output should not be indented"
+        );
+    }
 }
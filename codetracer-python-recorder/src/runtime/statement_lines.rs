@@ -0,0 +1,142 @@
+//! Maps physical line numbers onto the first line of their enclosing statement.
+//!
+//! `sys.monitoring` reports the line of the instruction about to execute, so a
+//! statement spread over several physical lines can surface Step events on its
+//! continuation lines. When the policy asks for statement-start lines we remap
+//! those continuation lines onto the statement's first line.
+//!
+//! ## Why the AST and not `co_positions`
+//!
+//! `co_positions()` gives each instruction a `(lineno, end_lineno)` span, which
+//! is enough to spot instructions that straddle several lines. It cannot tell a
+//! continuation line apart from the body of a compound statement though: the
+//! `BEFORE_WITH`, `FOR_ITER` and exception-matching instructions span the whole
+//! block they guard. The statement boundaries therefore come from parsing the
+//! source with `ast`, once per file. Only the header lines of compound
+//! statements (`if (a and\n b):`) are folded; their bodies keep their own lines.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyString};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Continuation line -> first line of the statement it belongs to.
+#[derive(Debug, Clone, Default)]
+pub struct StatementLineMap {
+    starts: HashMap<u32, u32>,
+}
+
+impl StatementLineMap {
+    /// First line of the statement covering `line`, or `line` itself when it
+    /// already starts a statement (or the source was unavailable).
+    pub fn statement_start(&self, line: u32) -> u32 {
+        self.starts.get(&line).copied().unwrap_or(line)
+    }
+}
+
+/// Cache of per-file statement maps keyed by the code object's filename.
+#[derive(Default)]
+pub struct StatementLines {
+    cache: HashMap<String, Arc<StatementLineMap>>,
+}
+
+impl StatementLines {
+    /// Look up or build the statement map for `filename`.
+    ///
+    /// Files whose source cannot be read or parsed yield an empty map so the
+    /// physical line is reported unchanged.
+    pub fn map_for(&mut self, py: Python<'_>, filename: &str) -> Arc<StatementLineMap> {
+        if let Some(existing) = self.cache.get(filename) {
+            return Arc::clone(existing);
+        }
+        let map = match build_map(py, filename) {
+            Ok(map) => map,
+            Err(err) => {
+                log::debug!(
+                    "[RuntimeTracer] statement line map unavailable for {}: {}",
+                    filename,
+                    err
+                );
+                StatementLineMap::default()
+            }
+        };
+        let map = Arc::new(map);
+        self.cache.insert(filename.to_string(), Arc::clone(&map));
+        map
+    }
+}
+
+/// Parse `filename` via `linecache` + `ast.parse` and record, for every line
+/// after the first of a statement (or of a compound statement's header), the
+/// line the statement starts on.
+fn build_map(py: Python<'_>, filename: &str) -> PyResult<StatementLineMap> {
+    let Some(tree) = parse_source(py, filename)? else {
+        return Ok(StatementLineMap::default());
+    };
+    let ast = py.import("ast")?;
+    let stmt_type = ast.getattr("stmt")?;
+
+    let mut starts = HashMap::new();
+    for node in ast.call_method1("walk", (tree,))?.try_iter()? {
+        let node = node?;
+        if !node.is_instance(&stmt_type)? {
+            continue;
+        }
+        let first: u32 = node.getattr("lineno")?.extract()?;
+        let last = match first_nested_line(&node)? {
+            // Compound statement: only its header belongs to `first`.
+            Some(body_line) => body_line.saturating_sub(1),
+            None => node
+                .getattr("end_lineno")?
+                .extract::<Option<u32>>()?
+                .unwrap_or(first),
+        };
+        for line in first.saturating_add(1)..=last {
+            starts.insert(line, first);
+        }
+    }
+    Ok(StatementLineMap { starts })
+}
+
+/// Parse the source of `filename` (via `linecache`) into an `ast.Module`.
+/// Returns `None` when no source is available.
+pub(crate) fn parse_source<'py>(
+    py: Python<'py>,
+    filename: &str,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let linecache = py.import("linecache")?;
+    let lines = linecache.call_method1("getlines", (filename,))?;
+    let lines = lines.downcast::<PyList>()?;
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    let source: String = PyString::new(py, "")
+        .call_method1("join", (lines,))?
+        .extract()?;
+    let tree = py
+        .import("ast")?
+        .call_method1("parse", (source, filename))?;
+    Ok(Some(tree))
+}
+
+/// Line of the first nested statement (or `match` case pattern) of a compound
+/// statement, `None` for simple statements.
+fn first_nested_line(node: &Bound<'_, PyAny>) -> PyResult<Option<u32>> {
+    if let Ok(body) = node.getattr("body") {
+        if let Ok(body) = body.downcast::<PyList>() {
+            if let Ok(first) = body.get_item(0) {
+                return Ok(Some(first.getattr("lineno")?.extract()?));
+            }
+        }
+    }
+    if let Ok(cases) = node.getattr("cases") {
+        if let Ok(cases) = cases.downcast::<PyList>() {
+            if let Ok(first) = cases.get_item(0) {
+                return Ok(Some(
+                    first.getattr("pattern")?.getattr("lineno")?.extract()?,
+                ));
+            }
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,68 @@
+//! Callable name globs selecting which call sites are recorded.
+
+use crate::runtime::value_encoder::callable_name;
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+
+/// Globs from the `capture_c_calls` policy, matched against a callable's
+/// `module.qualname`. `*` matches any run of characters, dots included, so
+/// `requests.*` covers every function in the package.
+#[derive(Debug, Default)]
+pub(crate) struct CCallPatterns {
+    patterns: Vec<String>,
+}
+
+impl CCallPatterns {
+    pub(crate) fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: patterns.to_vec(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// `module.qualname` of `callable` when it matches one of the globs.
+    pub(crate) fn matching_name(&self, callable: &Bound<'_, PyAny>) -> Option<String> {
+        if self.patterns.is_empty() {
+            return None;
+        }
+        let name = callable_name(callable).or_else(|| class_name(callable))?;
+        self.patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern, &name))
+            .then_some(name)
+    }
+}
+
+/// Classes are callables too; name them like functions.
+fn class_name(callable: &Bound<'_, PyAny>) -> Option<String> {
+    let class = callable.downcast::<PyType>().ok()?;
+    let module = class.module().ok()?;
+    let qualname = class.qualname().ok()?;
+    Some(format!("{module}.{qualname}"))
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it currently absorbs up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, absorbed)) = backtrack {
+            backtrack = Some((star, absorbed + 1));
+            p = star + 1;
+            t = absorbed + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
@@ -0,0 +1,54 @@
+//! Per-thread nesting depth of traced frames, used to enforce `max_call_depth`.
+
+use std::collections::HashMap;
+use std::thread::{self, ThreadId};
+
+#[derive(Debug, Default)]
+pub(crate) struct CallDepth {
+    limit: Option<usize>,
+    depths: HashMap<ThreadId, usize>,
+}
+
+impl CallDepth {
+    pub(crate) fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
+
+    /// Enter a frame on the calling thread. Returns `false` when the frame
+    /// sits beyond the limit and must run unrecorded.
+    pub(crate) fn enter(&mut self) -> bool {
+        if self.limit.is_none() {
+            return true;
+        }
+        let depth = self.depths.entry(thread::current().id()).or_insert(0);
+        *depth += 1;
+        !self.beyond_limit()
+    }
+
+    /// Leave the current frame. Returns `false` when its entry was skipped,
+    /// so the matching return is skipped too and call records stay balanced.
+    pub(crate) fn exit(&mut self) -> bool {
+        if self.limit.is_none() {
+            return true;
+        }
+        let recorded = !self.beyond_limit();
+        if let Some(depth) = self.depths.get_mut(&thread::current().id()) {
+            *depth = depth.saturating_sub(1);
+        }
+        recorded
+    }
+
+    /// Whether the frame running on the calling thread is beyond the limit.
+    pub(crate) fn beyond_limit(&self) -> bool {
+        let Some(limit) = self.limit else {
+            return false;
+        };
+        self.depths
+            .get(&thread::current().id())
+            .is_some_and(|depth| *depth > limit)
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.depths.clear();
+    }
+}
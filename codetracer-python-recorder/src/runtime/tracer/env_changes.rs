@@ -0,0 +1,66 @@
+//! Last observed `os.environ`, diffed at call boundaries to record mutations.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Default)]
+pub(crate) struct EnvChanges {
+    /// Copy of the raw `os.environ._data` mapping at the previous
+    /// observation. Comparing it is a single dict comparison in C, so an
+    /// unchanged environment is detected without decoding any entries.
+    last_raw: Option<Py<PyDict>>,
+    last: Option<HashMap<String, String>>,
+}
+
+/// Variables set or changed and variables removed between two observations.
+#[derive(Debug)]
+pub(crate) struct EnvDiff {
+    pub(crate) set: BTreeMap<String, String>,
+    pub(crate) removed: Vec<String>,
+}
+
+impl EnvChanges {
+    /// Read `os.environ` and return what changed since the previous
+    /// observation. The first observation only records the baseline.
+    pub(crate) fn observe(&mut self, py: Python<'_>) -> PyResult<Option<EnvDiff>> {
+        let environ = py.import("os")?.getattr("environ")?;
+        let raw = environ
+            .getattr("_data")
+            .ok()
+            .and_then(|data| data.downcast_into::<PyDict>().ok());
+        if let (Some(raw), Some(last_raw)) = (raw.as_ref(), self.last_raw.as_ref()) {
+            if raw.eq(last_raw.bind(py))? {
+                return Ok(None);
+            }
+        }
+        let current: HashMap<String, String> = environ.call_method0("copy")?.extract()?;
+        let diff = self
+            .last
+            .as_ref()
+            .and_then(|previous| diff(previous, &current));
+        self.last_raw = raw.map(|raw| raw.copy()).transpose()?.map(Bound::unbind);
+        self.last = Some(current);
+        Ok(diff)
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.last_raw = None;
+        self.last = None;
+    }
+}
+
+fn diff(previous: &HashMap<String, String>, current: &HashMap<String, String>) -> Option<EnvDiff> {
+    let set: BTreeMap<String, String> = current
+        .iter()
+        .filter(|(name, value)| previous.get(*name) != Some(*value))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    let mut removed: Vec<String> = previous
+        .keys()
+        .filter(|name| !current.contains_key(*name))
+        .cloned()
+        .collect();
+    removed.sort();
+    (!set.is_empty() || !removed.is_empty()).then_some(EnvDiff { set, removed })
+}
@@ -16,14 +16,15 @@ use crate::runtime::io_capture::ScopedMuteIoCapture;
 use crate::runtime::line_snapshots::FrameId;
 use crate::runtime::logging::log_event;
 use crate::runtime::value_capture::{
-    capture_call_arguments, encode_named_argument, record_return_value_streaming,
-    record_visible_scope_streaming,
+    capture_call_arguments, capture_context_vars, encode_named_argument,
+    record_generator_return_streaming, record_return_value_streaming,
+    record_visible_scope_streaming, GENERATOR_RETURN_LABEL,
 };
 use crate::trace_filter::config::ValueAction;
 use crate::trace_filter::engine::{ValueKind, ValuePolicy};
 use codetracer_trace_types::{
-    AssignmentRecord, BindVariableRecord, CallKey, FullValueRecord, Line, PassBy, PathId, Place,
-    RValue, TraceLowLevelEvent, VariableId,
+    AssignmentRecord, BindVariableRecord, CallKey, FullValueRecord, FunctionId, Line, PassBy,
+    PathId, Place, RValue, TraceLowLevelEvent, VariableId,
 };
 use codetracer_trace_writer_nim::trace_writer::TraceWriter;
 use codetracer_trace_writer_nim::TraceEventsFileFormat;
@@ -47,6 +48,7 @@ static FAILURE_TRIGGERED: AtomicBool = AtomicBool::new(false);
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum FailureStage {
     PyStart,
+    FunctionId,
     Line,
     Finish,
 }
@@ -55,6 +57,7 @@ impl FailureStage {
     fn as_str(self) -> &'static str {
         match self {
             FailureStage::PyStart => "py_start",
+            FailureStage::FunctionId => "function_id",
             FailureStage::Line => "line",
             FailureStage::Finish => "finish",
         }
@@ -81,6 +84,7 @@ fn configured_failure_mode() -> Option<FailureMode> {
         }
         raw.and_then(|raw| match raw.trim().to_ascii_lowercase().as_str() {
             "py_start" | "py-start" => Some(FailureMode::Stage(FailureStage::PyStart)),
+            "function_id" | "function-id" => Some(FailureMode::Stage(FailureStage::FunctionId)),
             "line" => Some(FailureMode::Stage(FailureStage::Line)),
             "finish" => Some(FailureMode::Stage(FailureStage::Finish)),
             "suppress-events" | "suppress_events" | "suppress" => Some(FailureMode::SuppressEvents),
@@ -362,15 +366,26 @@ fn maybe_register_autoformat_view(
 impl Tracer for RuntimeTracer {
     fn interest(&self, events: &MonitoringEvents) -> EventSet {
         // Balanced call stack requires tracking yields, resumes, throws, and unwinds
-        events_union(&[
+        let mut wanted = vec![
             events.PY_START,
             events.PY_RETURN,
             events.PY_YIELD,
             events.PY_UNWIND,
             events.PY_RESUME,
             events.PY_THROW,
-            events.LINE,
-        ])
+        ];
+        // Calls-only mode skips LINE entirely; it is by far the hottest
+        // event, so leaving it unsubscribed is what makes the mode cheap.
+        if !self.calls_only {
+            wanted.push(events.LINE);
+        }
+        if self.record_loop_iterations {
+            wanted.push(events.JUMP);
+        }
+        if self.record_instruction_offsets || !self.c_calls.is_empty() {
+            wanted.push(events.CALL);
+        }
+        events_union(&wanted)
     }
 
     fn on_py_start(
@@ -392,11 +407,21 @@ impl Tracer for RuntimeTracer {
             }
             Err(_) => None,
         };
-        self.filter.set_module_name_hint(code.id(), globals_name);
+        self.filter
+            .set_module_name_hint(code.id(), globals_name.clone());
 
         if let Some(outcome) = self.evaluate_gate(py, code, true) {
             return Ok(outcome);
         }
+        self.lifecycle.activation_mut().handle_entry(code.id());
+        if let (Some(name), Ok("<module>")) = (globals_name.as_deref(), code.qualname(py)) {
+            if self.lifecycle.record_entry_module(name) {
+                self.emit_entry_module();
+            }
+        }
+        if !self.call_depth.enter() {
+            return Ok(CallbackOutcome::Continue);
+        }
 
         if should_inject_failure(FailureStage::PyStart) {
             return Err(injected_failure_err(FailureStage::PyStart));
@@ -424,8 +449,42 @@ impl Tracer for RuntimeTracer {
             None
         };
         let telemetry = telemetry_holder.as_deref_mut();
-        match capture_call_arguments(py, &mut *self.writer, code, value_policy, telemetry) {
-            Ok(args) => self.register_call_record(py, code, args),
+        let options = self.streaming_encoder.options();
+        match capture_call_arguments(
+            py,
+            &mut *self.writer,
+            code,
+            value_policy,
+            telemetry,
+            options,
+        ) {
+            Ok(mut args) => {
+                if self.capture_contextvars {
+                    match capture_context_vars(
+                        py,
+                        &mut *self.writer,
+                        value_policy,
+                        telemetry_holder.as_deref_mut(),
+                        options,
+                    ) {
+                        Ok(Some(record)) => args.push(record),
+                        Ok(None) => {}
+                        Err(err) => {
+                            let _mute = ScopedMuteIoCapture::new();
+                            log::warn!("on_py_start: failed to capture contextvars: {err}");
+                        }
+                    }
+                }
+                let fid = self.register_call_record(py, code, args)?;
+                // Counted here only: resumptions reuse the call record path.
+                self.function_table.record_call(fid.0);
+                self.emit_generator_link(py, code, "start", false);
+                self.emit_comprehension_tag(py, code);
+                if self.mark_default_args {
+                    self.emit_default_args(py, code);
+                }
+                self.emit_env_changes(py);
+            }
             Err(err) => {
                 let details = err.to_string();
                 with_error_code(ErrorCode::FrameIntrospectionFailed, || {
@@ -455,15 +514,28 @@ impl Tracer for RuntimeTracer {
             return Ok(outcome);
         }
 
+        if !self.call_depth.enter() {
+            return Ok(CallbackOutcome::Continue);
+        }
+
         log_event(py, code, "on_py_resume", None);
-        self.register_call_record(py, code, Vec::new());
+        self.register_call_record(py, code, Vec::new())?;
+        self.emit_generator_link(py, code, "resume", false);
+        self.emit_comprehension_tag(py, code);
         Ok(CallbackOutcome::Continue)
     }
 
     fn on_line(&mut self, py: Python<'_>, code: &CodeObjectWrapper, lineno: u32) -> CallbackResult {
+        if self.calls_only {
+            return Ok(CallbackOutcome::Continue);
+        }
+
         if let Some(outcome) = self.evaluate_gate(py, code, false) {
             return Ok(outcome);
         }
+        if self.call_depth.beyond_limit() {
+            return Ok(CallbackOutcome::Continue);
+        }
 
         if should_inject_failure(FailureStage::Line) {
             return Err(injected_failure_err(FailureStage::Line));
@@ -476,8 +548,19 @@ impl Tracer for RuntimeTracer {
             }
         }
 
+        let physical_line = lineno;
+        let lineno = self.reported_line(py, code, lineno);
+
         log_event(py, code, "on_line", Some(lineno));
 
+        if self.line_coverage {
+            if let Ok(filename) = code.filename(py) {
+                self.coverage.record(filename, lineno);
+                self.mark_event();
+            }
+            return Ok(CallbackOutcome::Continue);
+        }
+
         self.flush_io_before_step(thread::current().id());
 
         let scope_resolution = self.filter.cached_resolution(py, code);
@@ -521,7 +604,7 @@ impl Tracer for RuntimeTracer {
         let previous_line = self.last_line_per_frame.get(&frame_raw).copied();
         let first_to_emit = previous_line.map(|p| p + 1).unwrap_or(0);
         let last_to_emit = lineno.saturating_sub(1);
-        if first_to_emit <= last_to_emit {
+        if !self.args_and_returns_only && first_to_emit <= last_to_emit {
             if let Ok(table) = self.assignment_reconstructor.table_for(py, code) {
                 for line in first_to_emit..=last_to_emit {
                     let assignments = table.for_line(line);
@@ -642,6 +725,7 @@ impl Tracer for RuntimeTracer {
                 TraceWriter::register_step(&mut *self.writer, path, line_value);
             }
             self.mark_event();
+            self.path_stats.record(path);
             recorded_path = Some((path_id, line_value));
         }
 
@@ -651,10 +735,19 @@ impl Tracer for RuntimeTracer {
                 .record_snapshot(thread::current().id(), path_id, line, frame_id);
         }
 
+        self.track_with_blocks(py, code, frame_raw, physical_line);
+
         // Remember this line so the next on_line in the same frame can
         // emit Assignment events for it.
         self.last_line_per_frame.insert(frame_raw, lineno);
 
+        if self.args_and_returns_only {
+            return Ok(CallbackOutcome::Continue);
+        }
+
+        self.flag_local_type_changes(&snapshot, frame_raw, value_policy);
+        self.emit_object_ids(&snapshot, value_policy);
+
         let mut recorded: HashSet<String> = HashSet::new();
         let mut telemetry_holder = if wants_telemetry {
             Some(self.filter.values_mut())
@@ -662,6 +755,9 @@ impl Tracer for RuntimeTracer {
             None
         };
         let telemetry = telemetry_holder.as_deref_mut();
+        let last_values = self
+            .delta_locals
+            .then(|| self.local_deltas.frame_mut(frame_raw));
         record_visible_scope_streaming(
             py,
             &mut *self.writer,
@@ -670,26 +766,95 @@ impl Tracer for RuntimeTracer {
             &mut recorded,
             value_policy,
             telemetry,
+            self.max_locals_per_step,
+            last_values,
         );
 
         Ok(CallbackOutcome::Continue)
     }
 
+    fn on_jump(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        offset: i32,
+        destination_offset: i32,
+    ) -> CallbackResult {
+        // A jump's target is fixed per instruction, so a forward jump will
+        // never mark an iteration and its location can be disabled.
+        if !self.record_loop_iterations || destination_offset >= offset {
+            return Ok(CallbackOutcome::DisableLocation);
+        }
+        if let Some(outcome) = self.evaluate_gate(py, code, true) {
+            return Ok(outcome);
+        }
+        if self.call_depth.beyond_limit() {
+            return Ok(CallbackOutcome::Continue);
+        }
+        let line = u32::try_from(destination_offset)
+            .ok()
+            .and_then(|target| code.line_for_offset(py, target).ok().flatten());
+        if let Ok(filename) = code.filename(py) {
+            self.emit_loop_iteration(filename, line);
+        }
+        Ok(CallbackOutcome::Continue)
+    }
+
+    fn on_call(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        offset: i32,
+        callable: &Bound<'_, PyAny>,
+        arg0: Option<&Bound<'_, PyAny>>,
+    ) -> CallbackResult {
+        if !self.record_instruction_offsets && self.c_calls.is_empty() {
+            return Ok(CallbackOutcome::DisableLocation);
+        }
+        if let Some(outcome) = self.evaluate_gate(py, code, true) {
+            return Ok(outcome);
+        }
+        if self.call_depth.beyond_limit() {
+            return Ok(CallbackOutcome::Continue);
+        }
+        let line = u32::try_from(offset)
+            .ok()
+            .and_then(|offset| code.line_for_offset(py, offset).ok().flatten());
+        if let Ok(filename) = code.filename(py) {
+            if self.record_instruction_offsets {
+                self.emit_instruction_offset(filename, line, offset);
+            }
+            // The callable at a call site can change between executions, so
+            // unmatched sites stay enabled rather than being disabled.
+            if let Some(name) = self.c_calls.matching_name(callable) {
+                self.emit_c_call(py, filename, line, &name, arg0);
+            }
+        }
+        Ok(CallbackOutcome::Continue)
+    }
+
     fn on_py_return(
         &mut self,
         py: Python<'_>,
         code: &CodeObjectWrapper,
-        _offset: i32,
+        offset: i32,
         retval: &Bound<'_, PyAny>,
     ) -> CallbackResult {
+        // A generator's `return X` becomes `StopIteration.value`; label it so
+        // the final value is recorded apart from the `<yield>` records.
+        let capture_label = code
+            .is_generator_like(py)
+            .unwrap_or(false)
+            .then_some(GENERATOR_RETURN_LABEL);
         self.handle_return_edge(
             py,
             code,
             "on_py_return",
             retval,
-            None,
+            capture_label,
             Some(ActivationExitKind::Completed),
             true,
+            Some(offset),
         )
     }
 
@@ -708,6 +873,7 @@ impl Tracer for RuntimeTracer {
             Some("<yield>"),
             Some(ActivationExitKind::Suspended),
             false,
+            None,
         )
     }
 
@@ -722,6 +888,10 @@ impl Tracer for RuntimeTracer {
             return Ok(outcome);
         }
 
+        if !self.call_depth.enter() {
+            return Ok(CallbackOutcome::Continue);
+        }
+
         log_event(py, code, "on_py_throw", None);
 
         let scope_resolution = self.filter.cached_resolution(py, code);
@@ -743,10 +913,13 @@ impl Tracer for RuntimeTracer {
             "exception",
             value_policy,
             telemetry,
+            self.streaming_encoder.options(),
         ) {
             args.push(arg);
         }
-        self.register_call_record(py, code, args);
+        self.register_call_record(py, code, args)?;
+        self.emit_generator_link(py, code, "throw", false);
+        self.emit_comprehension_tag(py, code);
 
         Ok(CallbackOutcome::Continue)
     }
@@ -766,6 +939,7 @@ impl Tracer for RuntimeTracer {
             Some("<unwind>"),
             Some(ActivationExitKind::Completed),
             false,
+            None,
         )
     }
 
@@ -779,6 +953,24 @@ impl Tracer for RuntimeTracer {
         Ok(())
     }
 
+    fn mark(&mut self, py: Python<'_>, label: &str, metadata: Option<&str>) -> PyResult<()> {
+        let _trace_scope = self.lifecycle.trace_id_scope();
+        self.emit_mark(py, label, metadata)
+    }
+
+    fn warning(
+        &mut self,
+        _py: Python<'_>,
+        category: &str,
+        message: &str,
+        path: &str,
+        line: Option<i64>,
+    ) -> PyResult<()> {
+        let _trace_scope = self.lifecycle.trace_id_scope();
+        self.emit_warning(category, message, path, line);
+        Ok(())
+    }
+
     fn flush(&mut self, _py: Python<'_>) -> PyResult<()> {
         // Trace event entry
         let _mute = ScopedMuteIoCapture::new();
@@ -787,6 +979,12 @@ impl Tracer for RuntimeTracer {
         self.flush_pending_io();
         // For non-streaming formats we can update the events file.
         match self.format {
+            TraceEventsFileFormat::Json if self.writes_ndjson() => {
+                // NDJSON grows by appending; finishing the writer would
+                // replace the file with an array.
+                self.append_ndjson_events()
+                    .map_err(ffi::map_recorder_error)?;
+            }
             TraceEventsFileFormat::Json | TraceEventsFileFormat::BinaryV0 => {
                 TraceWriter::finish_writing_trace_events(&mut *self.writer).map_err(|err| {
                     ffi::map_recorder_error(
@@ -803,6 +1001,14 @@ impl Tracer for RuntimeTracer {
         Ok(())
     }
 
+    fn trace_size_bytes(&self) -> u64 {
+        self.lifecycle
+            .output_paths()
+            .and_then(|outputs| std::fs::metadata(outputs.events()).ok())
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    }
+
     fn finish(&mut self, py: Python<'_>) -> PyResult<()> {
         // Trace event entry
         let _mute_finish = ScopedMuteIoCapture::new();
@@ -815,13 +1021,31 @@ impl Tracer for RuntimeTracer {
         let _trace_scope = self.lifecycle.trace_id_scope();
         let policy = policy_snapshot();
 
+        // Tear IO capture down first so buffered output, including a final
+        // line without a newline, lands before the session exit record.
         if self.io.teardown(py, &mut *self.writer) {
             self.mark_event();
         }
+        self.warnings_hook.uninstall(py);
 
-        self.emit_session_exit(py);
+        if self.filter.everything_skipped() {
+            log::warn!(
+                "trace filter skipped every scope it evaluated; the trace will be empty \
+                 (check `default_exec` and the filter's trace rules)"
+            );
+        }
+
+        self.emit_path_summary();
+        self.emit_filter_summary();
 
         let exit_summary = self.exit_summary();
+        // Like call timestamps, the duration differs on every run.
+        if !policy.deterministic {
+            self.emit_session_duration(&exit_summary);
+        }
+        // The session exit record closes the trace, so the summaries above
+        // are written ahead of it.
+        self.emit_session_exit(py);
 
         if self.lifecycle.encountered_failure() {
             if policy.keep_partial_trace {
@@ -849,10 +1073,7 @@ impl Tracer for RuntimeTracer {
                     .cleanup_partial_outputs()
                     .map_err(ffi::map_recorder_error)?;
             }
-            self.function_ids.clear();
-            self.io.clear_snapshots();
-            self.filter.reset();
-            self.lifecycle.reset_event_state();
+            self.reset_session_state();
             return Ok(());
         }
 
@@ -862,10 +1083,18 @@ impl Tracer for RuntimeTracer {
         self.lifecycle
             .finalise(&mut *self.writer, &self.filter, &exit_summary)
             .map_err(ffi::map_recorder_error)?;
-        self.function_ids.clear();
-        self.filter.reset();
-        self.io.clear_snapshots();
-        self.lifecycle.reset_event_state();
+        self.write_json_layout().map_err(ffi::map_recorder_error)?;
+        self.validate_written_trace()
+            .map_err(ffi::map_recorder_error)?;
+        self.write_function_table()
+            .map_err(ffi::map_recorder_error)?;
+        self.write_filter_decisions()
+            .map_err(ffi::map_recorder_error)?;
+        if self.line_coverage {
+            self.write_line_coverage()
+                .map_err(ffi::map_recorder_error)?;
+        }
+        self.reset_session_state();
         Ok(())
     }
 }
@@ -992,17 +1221,44 @@ impl RuntimeTracer {
         py: Python<'_>,
         code: &CodeObjectWrapper,
         args: Vec<FullValueRecord>,
-    ) {
-        if let Ok(fid) = self.ensure_function_id(py, code) {
-            TraceWriter::register_call(&mut *self.writer, fid, args);
-            // M15: the writer's CallRecord index advances by exactly one per
-            // register_call call. Track that so we can stamp the next
-            // observed `result = foo()` assignment with the matching CallKey.
-            self.last_call_key += 1;
-            self.mark_event();
+    ) -> PyResult<FunctionId> {
+        // A call we cannot name must not be dropped silently: its return edge
+        // would still be recorded and unbalance the trace. Surface the error
+        // so the recorder-error policy either aborts or detaches the tracer.
+        if should_inject_failure(FailureStage::FunctionId) {
+            return Err(injected_failure_err(FailureStage::FunctionId));
         }
+        let fid = match self.ensure_function_id(py, code) {
+            Ok(fid) => fid,
+            Err(err) => {
+                let details = err.to_string();
+                with_error_code(ErrorCode::FrameIntrospectionFailed, || {
+                    let _mute = ScopedMuteIoCapture::new();
+                    log::error!("failed to register function for call record: {details}");
+                });
+                return Err(ffi::map_recorder_error(
+                    enverr!(
+                        ErrorCode::FrameIntrospectionFailed,
+                        "failed to register function for call record"
+                    )
+                    .with_context("details", details),
+                ));
+            }
+        };
+        TraceWriter::register_call(&mut *self.writer, fid, args);
+        self.emit_call_timestamp("call");
+        // M15: the writer's CallRecord index advances by exactly one per
+        // register_call call. Track that so we can stamp the next
+        // observed `result = foo()` assignment with the matching CallKey.
+        self.last_call_key += 1;
+        self.mark_event();
+        if let Ok(filename) = code.filename(py) {
+            self.path_stats.record(Path::new(filename));
+        }
+        Ok(fid)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn handle_return_edge(
         &mut self,
         py: Python<'_>,
@@ -1012,15 +1268,25 @@ impl RuntimeTracer {
         capture_label: Option<&'static str>,
         exit_kind: Option<ActivationExitKind>,
         allow_disable: bool,
+        return_offset: Option<i32>,
     ) -> CallbackResult {
         if let Some(outcome) = self.evaluate_gate(py, code, allow_disable) {
             return Ok(outcome);
         }
+        if !self.call_depth.exit() {
+            self.handle_activation_exit(code, exit_kind);
+            return Ok(CallbackOutcome::Continue);
+        }
 
         log_event(py, code, label, None);
 
         self.flush_pending_io();
 
+        if exit_kind == Some(ActivationExitKind::Completed) {
+            self.close_with_blocks(py, code);
+            self.forget_frame_locals(py, code);
+        }
+
         let scope_resolution = self.filter.cached_resolution(py, code);
         let value_policy = scope_resolution.as_ref().map(|res| res.value_policy());
         let wants_telemetry = value_policy.is_some();
@@ -1035,24 +1301,53 @@ impl RuntimeTracer {
 
         let candidate_name = capture_label.map(|label| label as &str).or(object_name);
 
-        record_return_value_streaming(
-            py,
-            &mut *self.writer,
-            &mut self.streaming_encoder,
-            retval,
-            value_policy,
-            telemetry,
-            candidate_name,
-        );
+        if capture_label == Some(GENERATOR_RETURN_LABEL) {
+            record_generator_return_streaming(
+                py,
+                &mut *self.writer,
+                &mut self.streaming_encoder,
+                retval,
+                value_policy,
+                telemetry,
+                self.keep_dropped_return_type,
+            );
+        } else {
+            record_return_value_streaming(
+                py,
+                &mut *self.writer,
+                &mut self.streaming_encoder,
+                retval,
+                value_policy,
+                telemetry,
+                candidate_name,
+                self.keep_dropped_return_type,
+            );
+        }
+        self.emit_call_timestamp("return");
         self.mark_event();
+        let phase = label.trim_start_matches("on_py_");
+        let finished = exit_kind == Some(ActivationExitKind::Completed);
+        self.emit_generator_link(py, code, phase, finished);
+        if let Some(offset) = return_offset {
+            self.emit_return_site(py, code, offset);
+        }
+        self.emit_env_changes(py);
+
+        self.handle_activation_exit(code, exit_kind);
+
+        Ok(CallbackOutcome::Continue)
+    }
 
+    fn handle_activation_exit(
+        &mut self,
+        code: &CodeObjectWrapper,
+        exit_kind: Option<ActivationExitKind>,
+    ) {
         if let Some(kind) = exit_kind {
             if self.lifecycle.activation_mut().handle_exit(code.id(), kind) {
                 let _mute = ScopedMuteIoCapture::new();
                 log::debug!("[RuntimeTracer] deactivated on activation return");
             }
         }
-
-        Ok(CallbackOutcome::Continue)
     }
 }
@@ -0,0 +1,71 @@
+//! Per-scope record of which filter rule skipped each code object.
+
+use serde_json::json;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+struct SkipEntry {
+    qualname: String,
+    path: String,
+    module: Option<String>,
+    /// Filter file that declared the matching rule; `None` when the skip
+    /// came from `default_exec` rather than a rule.
+    source: Option<PathBuf>,
+    /// Index of the matching rule within `source`.
+    rule_index: Option<usize>,
+}
+
+/// Debug log attributing every filter skip to its filter file and rule, so
+/// layered filter chains can be inspected after a run.
+#[derive(Debug, Default)]
+pub(crate) struct FilterDecisionLog {
+    skips: Vec<SkipEntry>,
+}
+
+impl FilterDecisionLog {
+    pub(crate) fn record_skip(
+        &mut self,
+        qualname: &str,
+        path: &str,
+        module: Option<&str>,
+        origin: Option<(&Path, usize)>,
+    ) {
+        self.skips.push(SkipEntry {
+            qualname: qualname.to_string(),
+            path: path.to_string(),
+            module: module.map(str::to_string),
+            source: origin.map(|(source, _)| source.to_path_buf()),
+            rule_index: origin.map(|(_, index)| index),
+        });
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.skips.clear();
+    }
+
+    /// `{"skips": [{"qualname", "path", "module", "source", "rule_index"}]}`
+    /// in the order the scopes were first skipped.
+    pub(crate) fn report_json(&self) -> serde_json::Value {
+        let skips: Vec<serde_json::Value> = self
+            .skips
+            .iter()
+            .map(|entry| {
+                json!({
+                    "qualname": entry.qualname,
+                    "path": entry.path,
+                    "module": entry.module,
+                    "source": entry.source.as_ref().map(|p| p.display().to_string()),
+                    "rule_index": entry.rule_index,
+                })
+            })
+            .collect();
+        json!({ "skips": skips })
+    }
+
+    pub(crate) fn write_report(&self, destination: &Path) -> io::Result<()> {
+        let report = serde_json::to_vec_pretty(&self.report_json())?;
+        fs::write(destination, report)
+    }
+}
@@ -10,12 +10,15 @@
 
 use crate::code_object::CodeObjectWrapper;
 use crate::logging::{record_dropped_event, with_error_code};
+use crate::policy::RecorderPolicy;
 use crate::runtime::io_capture::ScopedMuteIoCapture;
+use crate::runtime::tracer::filter_decisions::FilterDecisionLog;
 use crate::runtime::value_capture::ValueFilterStats;
 use crate::trace_filter::engine::{ExecDecision, ScopeResolution, TraceFilterEngine, ValueKind};
 use pyo3::prelude::*;
 use serde_json::{self, json};
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Filtering outcome for a code object.
@@ -37,7 +40,21 @@ pub(crate) struct FilterCoordinator {
     /// the classifier so package selectors resolve correctly even when
     /// the filename doesn't lie under a `__init__.py`-style package tree.
     module_name_hints: HashMap<usize, String>,
+    /// Codes that passed the filter at least once, so `scopes_traced`
+    /// counts scopes rather than events.
+    traced_code_ids: HashSet<usize>,
     stats: FilterStats,
+    /// Present when skip decisions should be attributed to their filter
+    /// source for the `filter_decisions.json` sidecar.
+    decisions: Option<FilterDecisionLog>,
+    exclude_stdlib: bool,
+    skip_comprehensions: bool,
+    /// Standard library directories, resolved on the first decision made
+    /// with `exclude_stdlib` on and kept for the lifetime of the tracer.
+    stdlib_dirs: Option<StdlibDirs>,
+    /// Canonical directory of the imported recorder package, resolved once
+    /// the package appears in `sys.modules`.
+    recorder_dir: Option<PathBuf>,
 }
 
 impl FilterCoordinator {
@@ -46,7 +63,13 @@ impl FilterCoordinator {
             engine,
             ignored_code_ids: HashSet::new(),
             module_name_hints: HashMap::new(),
+            traced_code_ids: HashSet::new(),
             stats: FilterStats::default(),
+            decisions: None,
+            exclude_stdlib: false,
+            skip_comprehensions: false,
+            stdlib_dirs: None,
+            recorder_dir: None,
         }
     }
 
@@ -80,8 +103,35 @@ impl FilterCoordinator {
         }
     }
 
+    /// Apply the policy settings that widen or narrow which scopes are traced
+    /// and whether each decision is logged.
+    pub(crate) fn apply_policy(&mut self, policy: &RecorderPolicy) {
+        let log_decisions = policy.log_filter_decisions;
+        if log_decisions != self.decisions.is_some() {
+            self.decisions = log_decisions.then(FilterDecisionLog::default);
+        }
+        self.exclude_stdlib = policy.exclude_stdlib;
+        self.skip_comprehensions = policy.skip_comprehensions;
+    }
+
+    pub(crate) fn decision_log(&self) -> Option<&FilterDecisionLog> {
+        self.decisions.as_ref()
+    }
+
     pub(crate) fn summary_json(&self) -> serde_json::Value {
-        self.stats.summary_json()
+        let mut summary = self.stats.summary_json();
+        if let Some(engine) = self.engine.as_ref() {
+            let cache = engine.cache_stats();
+            summary["cache_hits"] = json!(cache.hits);
+            summary["cache_misses"] = json!(cache.misses);
+        }
+        summary
+    }
+
+    /// True when the filter evaluated at least one scope and skipped all of
+    /// them, i.e. the session will produce an empty trace.
+    pub(crate) fn everything_skipped(&self) -> bool {
+        self.stats.everything_skipped()
     }
 
     pub(crate) fn values_mut(&mut self) -> &mut ValueFilterStats {
@@ -114,7 +164,14 @@ impl FilterCoordinator {
 
     pub(crate) fn reset(&mut self) {
         self.clear_caches();
+        self.traced_code_ids.clear();
         self.stats.reset();
+        if let Some(engine) = self.engine.as_ref() {
+            engine.reset_cache_stats();
+        }
+        if let Some(decisions) = self.decisions.as_mut() {
+            decisions.reset();
+        }
     }
 
     pub(crate) fn decide(&mut self, py: Python<'_>, code: &CodeObjectWrapper) -> TraceDecision {
@@ -125,6 +182,7 @@ impl FilterCoordinator {
 
         if let Some(resolution) = self.resolve(py, code) {
             if resolution.exec() == ExecDecision::Skip {
+                self.record_skip_origin(py, code, &resolution);
                 self.mark_ignored(code_id);
                 self.stats.record_skip();
                 record_dropped_event("filter_scope_skip");
@@ -145,7 +203,36 @@ impl FilterCoordinator {
             }
         };
 
+        // Monitoring analog of the IO capture reentrancy guard: never trace
+        // the recorder's own Python glue, even if user code ends up running
+        // through it (e.g. calling `stop()` from a traced function).
+        if self
+            .recorder_dir(py)
+            .is_some_and(|dir| is_within_dir(filename, dir))
+        {
+            record_dropped_event("recorder_code");
+            self.mark_ignored(code_id);
+            return TraceDecision::SkipAndDisable;
+        }
+
+        if self.exclude_stdlib && self.stdlib_dirs(py).contains(filename) {
+            record_dropped_event("stdlib_code");
+            self.mark_ignored(code_id);
+            self.stats.record_skip();
+            return TraceDecision::SkipAndDisable;
+        }
+
+        if self.skip_comprehensions && matches!(code.comprehension_form(py), Ok(Some(_))) {
+            record_dropped_event("comprehension_code");
+            self.mark_ignored(code_id);
+            self.stats.record_skip();
+            return TraceDecision::SkipAndDisable;
+        }
+
         if is_real_filename(filename) {
+            if self.traced_code_ids.insert(code_id) {
+                self.stats.record_trace();
+            }
             TraceDecision::Trace
         } else {
             record_dropped_event("synthetic_filename");
@@ -154,6 +241,28 @@ impl FilterCoordinator {
         }
     }
 
+    fn record_skip_origin(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        resolution: &ScopeResolution,
+    ) {
+        let (Some(decisions), Some(engine)) = (self.decisions.as_mut(), self.engine.as_ref())
+        else {
+            return;
+        };
+        let origin = resolution
+            .matched_rule_index()
+            .and_then(|index| engine.rule_origin(index))
+            .map(|(source, local)| (source.path.as_path(), local));
+        decisions.record_skip(
+            code.qualname(py).unwrap_or("<unknown>"),
+            code.filename(py).unwrap_or("<unknown>"),
+            resolution.module_name(),
+            origin,
+        );
+    }
+
     fn resolve(
         &mut self,
         py: Python<'_>,
@@ -181,6 +290,27 @@ impl FilterCoordinator {
         }
     }
 
+    fn stdlib_dirs(&mut self, py: Python<'_>) -> &StdlibDirs {
+        self.stdlib_dirs.get_or_insert_with(|| {
+            StdlibDirs::detect(py).unwrap_or_else(|err| {
+                with_error_code(recorder_errors::ErrorCode::Io, || {
+                    let _mute = ScopedMuteIoCapture::new();
+                    log::error!("failed to resolve standard library paths: {err}");
+                });
+                StdlibDirs::default()
+            })
+        })
+    }
+
+    /// Looked up again on each call until found: the package can only run
+    /// once it has been imported.
+    fn recorder_dir(&mut self, py: Python<'_>) -> Option<&Path> {
+        if self.recorder_dir.is_none() {
+            self.recorder_dir = recorder_package_dir(py);
+        }
+        self.recorder_dir.as_deref()
+    }
+
     fn mark_ignored(&mut self, code_id: usize) {
         self.ignored_code_ids.insert(code_id);
         self.module_name_hints.remove(&code_id);
@@ -193,9 +323,74 @@ pub(crate) fn is_real_filename(filename: &str) -> bool {
     !(trimmed.starts_with('<') && trimmed.ends_with('>'))
 }
 
+/// Import name of the recorder's Python package.
+const RECORDER_PACKAGE: &str = "codetracer_python_recorder";
+
+/// Canonical directory holding the imported recorder package's
+/// `__init__.py`, or `None` while the package is not imported.
+fn recorder_package_dir(py: Python<'_>) -> Option<PathBuf> {
+    let package = py
+        .import("sys")
+        .and_then(|sys| sys.getattr("modules"))
+        .and_then(|modules| modules.get_item(RECORDER_PACKAGE))
+        .ok()?;
+    let file: String = package.getattr("__file__").ok()?.extract().ok()?;
+    let dir = Path::new(&file).parent()?;
+    Some(dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()))
+}
+
+/// Return true when `filename` lies under the canonical directory `dir`.
+pub(crate) fn is_within_dir(filename: &str, dir: &Path) -> bool {
+    let path = Path::new(filename);
+    path.starts_with(dir)
+        || path
+            .canonicalize()
+            .is_ok_and(|canonical| canonical.starts_with(dir))
+}
+
+/// Standard library locations reported by `sysconfig`.
+///
+/// Installs without a virtual environment keep `site-packages` inside the
+/// standard library directory, so the package directories are carved out
+/// again: third-party code is not standard library code.
+#[derive(Debug, Default)]
+struct StdlibDirs {
+    stdlib: Vec<PathBuf>,
+    site_packages: Vec<PathBuf>,
+}
+
+impl StdlibDirs {
+    fn detect(py: Python<'_>) -> PyResult<Self> {
+        let paths = py.import("sysconfig")?.call_method0("get_paths")?;
+        let lookup = |keys: &[&str]| -> PyResult<Vec<PathBuf>> {
+            let mut dirs: Vec<PathBuf> = Vec::new();
+            for key in keys {
+                if let Ok(value) = paths.get_item(*key) {
+                    let dir = PathBuf::from(value.extract::<String>()?);
+                    if !dirs.contains(&dir) {
+                        dirs.push(dir);
+                    }
+                }
+            }
+            Ok(dirs)
+        };
+        Ok(Self {
+            stdlib: lookup(&["stdlib", "platstdlib"])?,
+            site_packages: lookup(&["purelib", "platlib"])?,
+        })
+    }
+
+    fn contains(&self, filename: &str) -> bool {
+        let path = Path::new(filename);
+        self.stdlib.iter().any(|dir| path.starts_with(dir))
+            && !self.site_packages.iter().any(|dir| path.starts_with(dir))
+    }
+}
+
 #[derive(Debug, Default)]
 struct FilterStats {
     skipped_scopes: u64,
+    traced_scopes: u64,
     values: ValueFilterStats,
 }
 
@@ -204,12 +399,21 @@ impl FilterStats {
         self.skipped_scopes += 1;
     }
 
+    fn record_trace(&mut self) {
+        self.traced_scopes += 1;
+    }
+
+    fn everything_skipped(&self) -> bool {
+        self.skipped_scopes > 0 && self.traced_scopes == 0
+    }
+
     fn values_mut(&mut self) -> &mut ValueFilterStats {
         &mut self.values
     }
 
     fn reset(&mut self) {
         self.skipped_scopes = 0;
+        self.traced_scopes = 0;
         self.values = ValueFilterStats::default();
     }
 
@@ -228,6 +432,8 @@ impl FilterStats {
         }
         json!({
             "scopes_skipped": self.skipped_scopes,
+            "scopes_traced": self.traced_scopes,
+            "empty_trace_warning": self.everything_skipped(),
             "value_redactions": serde_json::Value::Object(redactions),
             "value_drops": serde_json::Value::Object(drops),
         })
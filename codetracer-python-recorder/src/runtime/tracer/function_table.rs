@@ -0,0 +1,303 @@
+//! Name, file and first line of every function the trace refers to.
+
+use crate::code_object::CodeObjectWrapper;
+use crate::runtime::statement_lines::parse_source;
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyString};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Set on function code objects; module and class bodies lack it.
+const CO_OPTIMIZED: u32 = 0x01;
+const CO_VARARGS: u32 = 0x04;
+const CO_VARKEYWORDS: u32 = 0x08;
+/// Set on 3.14+ code objects whose `co_consts[0]` is the docstring.
+const CO_HAS_DOCSTRING: u32 = 0x0400_0000;
+
+#[derive(Debug)]
+struct FunctionEntry {
+    name: String,
+    path: String,
+    line: u32,
+    params: Vec<String>,
+    doc: Option<String>,
+    /// Declared annotations, present when annotation capture is on.
+    annotations: Option<BTreeMap<String, String>>,
+}
+
+/// Parameter names of `code` in declaration order, read from `co_varnames`,
+/// with `*` and `**` prefixing the variadic ones.
+pub(crate) fn parameter_names(py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<Vec<String>> {
+    let code_bound = code.as_bound(py);
+    let argcount = code.arg_count(py)? as usize;
+    let kwonly: usize = code_bound.getattr("co_kwonlyargcount")?.extract()?;
+    let flags = code.flags(py)?;
+    let varnames: Vec<String> = code_bound.getattr("co_varnames")?.extract()?;
+
+    // `co_varnames` lists positional, then keyword-only parameters, then
+    // `*args` and `**kwargs`; reorder them to match the `def` line.
+    let mut names = varnames.iter();
+    let positional: Vec<String> = names.by_ref().take(argcount).cloned().collect();
+    let keyword_only: Vec<String> = names.by_ref().take(kwonly).cloned().collect();
+    let varargs = (flags & CO_VARARGS != 0)
+        .then(|| names.next().map(|name| format!("*{name}")))
+        .flatten();
+    let varkw = (flags & CO_VARKEYWORDS != 0)
+        .then(|| names.next().map(|name| format!("**{name}")))
+        .flatten();
+
+    let mut params = positional;
+    params.extend(varargs);
+    params.extend(keyword_only);
+    params.extend(varkw);
+    Ok(params)
+}
+
+/// First non-empty line of the docstring of `code`, if it has one.
+pub(crate) fn docstring_summary(
+    py: Python<'_>,
+    code: &CodeObjectWrapper,
+) -> PyResult<Option<String>> {
+    // Only functions reserve `co_consts[0]` for their docstring. In a module
+    // or class body it is whatever constant comes first, and comprehensions
+    // are functions without a docstring slot.
+    if code.flags(py)? & CO_OPTIMIZED == 0 || code.comprehension_form(py)?.is_some() {
+        return Ok(None);
+    }
+    // Before 3.14 the compiler always reserves `co_consts[0]` for the
+    // docstring (or `None`); 3.14 flags its presence instead.
+    if py.version_info() >= (3, 14) && code.flags(py)? & CO_HAS_DOCSTRING == 0 {
+        return Ok(None);
+    }
+    let consts = code.as_bound(py).getattr("co_consts")?;
+    let Ok(first) = consts.get_item(0) else {
+        return Ok(None);
+    };
+    let Ok(text) = first.downcast::<PyString>() else {
+        return Ok(None);
+    };
+    Ok(text
+        .to_str()?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string))
+}
+
+/// Declared annotations of `code` as source text, keyed like
+/// `__annotations__`: parameters, `return`, and locals declared with
+/// `name: T`. Local annotations are not kept at runtime (PEP 526), so all of
+/// them are read from the definition in the source file. Nested functions
+/// and classes keep their own annotations.
+pub(crate) fn declared_annotations(
+    py: Python<'_>,
+    code: &CodeObjectWrapper,
+) -> PyResult<BTreeMap<String, String>> {
+    let mut annotations = BTreeMap::new();
+    let Some(tree) = parse_source(py, code.filename(py)?)? else {
+        return Ok(annotations);
+    };
+    let ast = py.import("ast")?;
+    let name: String = code.as_bound(py).getattr("co_name")?.extract()?;
+    let definition = if name == "<module>" {
+        Some(tree)
+    } else {
+        find_definition(&ast, &tree, &name, code.first_line(py)?)?
+    };
+    let Some(definition) = definition else {
+        return Ok(annotations);
+    };
+    let unparse = |node: &Bound<'_, PyAny>| -> PyResult<String> {
+        ast.call_method1("unparse", (node,))?.extract()
+    };
+
+    if let Ok(arguments) = definition.getattr("args") {
+        let mut params = Vec::new();
+        for group in ["posonlyargs", "args", "kwonlyargs"] {
+            for arg in arguments.getattr(group)?.try_iter()? {
+                params.push(arg?);
+            }
+        }
+        for variadic in ["vararg", "kwarg"] {
+            let arg = arguments.getattr(variadic)?;
+            if !arg.is_none() {
+                params.push(arg);
+            }
+        }
+        for arg in params {
+            let annotation = arg.getattr("annotation")?;
+            if !annotation.is_none() {
+                annotations.insert(arg.getattr("arg")?.extract()?, unparse(&annotation)?);
+            }
+        }
+        let returns = definition.getattr("returns")?;
+        if !returns.is_none() {
+            annotations.insert("return".to_string(), unparse(&returns)?);
+        }
+    }
+
+    let ann_assign = ast.getattr("AnnAssign")?;
+    let name_type = ast.getattr("Name")?;
+    let scopes = [
+        ast.getattr("FunctionDef")?,
+        ast.getattr("AsyncFunctionDef")?,
+        ast.getattr("ClassDef")?,
+        ast.getattr("Lambda")?,
+    ];
+    let body = definition.getattr("body")?;
+    let mut pending: Vec<Bound<'_, PyAny>> = body.downcast::<PyList>()?.iter().collect();
+    while let Some(node) = pending.pop() {
+        if node.is_instance(&ann_assign)? {
+            let target = node.getattr("target")?;
+            if target.is_instance(&name_type)? {
+                annotations.insert(
+                    target.getattr("id")?.extract()?,
+                    unparse(&node.getattr("annotation")?)?,
+                );
+            }
+            continue;
+        }
+        let mut nested = false;
+        for scope in &scopes {
+            nested |= node.is_instance(scope)?;
+        }
+        if nested {
+            continue;
+        }
+        for child in ast.call_method1("iter_child_nodes", (node,))?.try_iter()? {
+            pending.push(child?);
+        }
+    }
+    Ok(annotations)
+}
+
+/// The `def` or `class` statement named `name` whose code starts on
+/// `first_line`, which is the line of its first decorator when decorated.
+fn find_definition<'py>(
+    ast: &Bound<'py, PyModule>,
+    tree: &Bound<'py, PyAny>,
+    name: &str,
+    first_line: u32,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let kinds = [
+        ast.getattr("FunctionDef")?,
+        ast.getattr("AsyncFunctionDef")?,
+        ast.getattr("ClassDef")?,
+    ];
+    for node in ast.call_method1("walk", (tree,))?.try_iter()? {
+        let node = node?;
+        let mut is_definition = false;
+        for kind in &kinds {
+            is_definition |= node.is_instance(kind)?;
+        }
+        if !is_definition || node.getattr("name")?.extract::<String>()? != name {
+            continue;
+        }
+        let mut lines = vec![node.getattr("lineno")?.extract::<u32>()?];
+        for decorator in node.getattr("decorator_list")?.try_iter()? {
+            lines.push(decorator?.getattr("lineno")?.extract()?);
+        }
+        if lines.contains(&first_line) {
+            return Ok(Some(node));
+        }
+    }
+    Ok(None)
+}
+
+/// Mirrors the tracer's `function_ids` map so consumers can build a
+/// function index without scanning the event stream.
+#[derive(Debug, Default)]
+pub(crate) struct FunctionTable {
+    entries: BTreeMap<usize, FunctionEntry>,
+    /// Calls started per function; generator and coroutine resumptions do
+    /// not count.
+    call_counts: BTreeMap<usize, u64>,
+}
+
+impl FunctionTable {
+    pub(crate) fn record(
+        &mut self,
+        function_id: usize,
+        name: &str,
+        path: &str,
+        line: u32,
+        params: Vec<String>,
+        doc: Option<String>,
+    ) {
+        self.entries.insert(
+            function_id,
+            FunctionEntry {
+                name: name.to_string(),
+                path: path.to_string(),
+                line,
+                params,
+                doc,
+                annotations: None,
+            },
+        );
+    }
+
+    /// Attach the declared annotations of an already recorded function.
+    pub(crate) fn set_annotations(
+        &mut self,
+        function_id: usize,
+        annotations: BTreeMap<String, String>,
+    ) {
+        if let Some(entry) = self.entries.get_mut(&function_id) {
+            entry.annotations = Some(annotations);
+        }
+    }
+
+    pub(crate) fn record_call(&mut self, function_id: usize) {
+        *self.call_counts.entry(function_id).or_insert(0) += 1;
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.entries.clear();
+        self.call_counts.clear();
+    }
+
+    /// `{"functions": [{"id", "name", "path", "line", "params", "doc",
+    /// "annotations"}], "call_counts": [{"id", "name", "calls"}]}`. Functions
+    /// are in `FunctionId` order; `annotations` is `null` unless annotation
+    /// capture is on. Call counts list the most called functions first, with
+    /// ties in `FunctionId` order.
+    pub(crate) fn report_json(&self) -> serde_json::Value {
+        let functions: Vec<serde_json::Value> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| {
+                json!({
+                    "id": id,
+                    "name": entry.name,
+                    "path": entry.path,
+                    "line": entry.line,
+                    "params": entry.params,
+                    "doc": entry.doc,
+                    "annotations": entry.annotations,
+                })
+            })
+            .collect();
+        let mut counts: Vec<(&usize, &u64)> = self.call_counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let call_counts: Vec<serde_json::Value> = counts
+            .into_iter()
+            .map(|(id, calls)| {
+                json!({
+                    "id": id,
+                    "name": self.entries.get(id).map(|entry| entry.name.as_str()),
+                    "calls": calls,
+                })
+            })
+            .collect();
+        json!({ "functions": functions, "call_counts": call_counts })
+    }
+
+    pub(crate) fn write_report(&self, destination: &Path) -> io::Result<()> {
+        let report = serde_json::to_vec_pretty(&self.report_json())?;
+        fs::write(destination, report)
+    }
+}
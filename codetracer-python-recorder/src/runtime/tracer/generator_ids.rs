@@ -0,0 +1,34 @@
+//! Stable ids for live generators and coroutines, keyed by frame address.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub(crate) struct GeneratorIds {
+    frames: HashMap<u64, u64>,
+    next_id: u64,
+}
+
+impl GeneratorIds {
+    /// Return the id of the generator running in `frame`, assigning the next
+    /// sequential id on first sight. Sequential ids keep traces reproducible
+    /// where raw addresses would not be.
+    pub(crate) fn id_for(&mut self, frame: u64) -> u64 {
+        let next_id = &mut self.next_id;
+        *self.frames.entry(frame).or_insert_with(|| {
+            let id = *next_id;
+            *next_id += 1;
+            id
+        })
+    }
+
+    /// Release `frame` once its generator finishes so a frame later allocated
+    /// at the same address gets a fresh id.
+    pub(crate) fn forget_frame(&mut self, frame: u64) {
+        self.frames.remove(&frame);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.frames.clear();
+        self.next_id = 0;
+    }
+}
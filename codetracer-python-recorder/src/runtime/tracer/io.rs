@@ -76,6 +76,9 @@ impl IoCoordinator {
         }
 
         pipeline.uninstall(py);
+        // Restoring the original streams can hand the batcher one more
+        // partial line; flush it so it is not lost with the pipeline.
+        pipeline.flush_all();
 
         for chunk in pipeline.drain_chunks() {
             recorded |= self.record_chunk(writer, chunk);
@@ -9,11 +9,140 @@ use crate::runtime::tracer::filtering::FilterCoordinator;
 use crate::runtime::tracer::runtime_tracer::ExitSummary;
 use codetracer_trace_writer_nim::trace_writer::TraceWriter;
 use log::debug;
+use pyo3::prelude::*;
 use recorder_errors::{enverr, usage, ErrorCode, RecorderResult};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 use uuid::Uuid;
 
+/// Details of the interpreter running the traced program.
+///
+/// Distinct from the recorder version: these describe the target runtime so
+/// version-specific behaviour can be reproduced from a trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpreterInfo {
+    /// `sys.version`
+    pub version: String,
+    /// `sys.implementation.name` (e.g. `cpython`, `pypy`)
+    pub implementation: String,
+    /// `platform.platform()`
+    pub platform: String,
+}
+
+impl InterpreterInfo {
+    pub fn capture(py: Python<'_>) -> PyResult<Self> {
+        let sys = py.import("sys")?;
+        let version = sys.getattr("version")?.extract()?;
+        let implementation = sys.getattr("implementation")?.getattr("name")?.extract()?;
+        let platform = py.import("platform")?.call_method0("platform")?.extract()?;
+        Ok(Self {
+            version,
+            implementation,
+            platform,
+        })
+    }
+}
+
+/// Replacement for command-line arguments matching a `redact_argv` pattern.
+const REDACTED_ARG: &str = "<redacted>";
+
+/// Full command line and working directory of the traced process, so a run
+/// can be reproduced from its trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandLine {
+    /// `sys.argv`, with sensitive entries redacted.
+    pub argv: Vec<String>,
+    /// `os.getcwd()`
+    pub cwd: String,
+}
+
+impl CommandLine {
+    pub fn capture(py: Python<'_>, redact_patterns: &[String]) -> PyResult<Self> {
+        let argv: Vec<String> = py.import("sys")?.getattr("argv")?.extract()?;
+        let cwd = py.import("os")?.call_method0("getcwd")?.extract()?;
+        Ok(Self {
+            argv: redact_argv(&argv, redact_patterns),
+            cwd,
+        })
+    }
+}
+
+/// Replace the arguments that contain one of `patterns` (case-insensitive)
+/// with `<redacted>`. A matching `--option=value` keeps its option name, and
+/// a matching `--option` without a value redacts the argument after it unless
+/// that argument is itself an option.
+pub fn redact_argv(argv: &[String], patterns: &[String]) -> Vec<String> {
+    let patterns = lowercase_patterns(patterns);
+    if patterns.is_empty() {
+        return argv.to_vec();
+    }
+    let matches = |text: &str| matches_any(text, &patterns);
+    let mut redacted = Vec::with_capacity(argv.len());
+    let mut redact_next = false;
+    for arg in argv {
+        // A following option means the matching flag took no value.
+        if std::mem::take(&mut redact_next) && !arg.starts_with('-') {
+            redacted.push(REDACTED_ARG.to_string());
+            continue;
+        }
+        if !matches(arg) {
+            redacted.push(arg.clone());
+            continue;
+        }
+        match arg.split_once('=') {
+            Some((option, _)) if option.starts_with('-') && matches(option) => {
+                redacted.push(format!("{option}={REDACTED_ARG}"));
+            }
+            None if arg.starts_with('-') => {
+                redacted.push(arg.clone());
+                redact_next = true;
+            }
+            _ => redacted.push(REDACTED_ARG.to_string()),
+        }
+    }
+    redacted
+}
+
+/// Replace the value of each environment variable whose name or value
+/// contains one of `patterns` (case-insensitive) with `<redacted>`.
+pub fn redact_env_values(values: &mut BTreeMap<String, String>, patterns: &[String]) {
+    let patterns = lowercase_patterns(patterns);
+    if patterns.is_empty() {
+        return;
+    }
+    for (name, value) in values.iter_mut() {
+        if matches_any(name, &patterns) || matches_any(value, &patterns) {
+            *value = REDACTED_ARG.to_string();
+        }
+    }
+}
+
+fn lowercase_patterns(patterns: &[String]) -> Vec<String> {
+    patterns
+        .iter()
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| pattern.to_lowercase())
+        .collect()
+}
+
+fn matches_any(text: &str, lowercase_patterns: &[String]) -> bool {
+    let text = text.to_lowercase();
+    lowercase_patterns
+        .iter()
+        .any(|pattern| text.contains(pattern.as_str()))
+}
+
+/// How the first traced module-level frame was entered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryModule {
+    /// `__name__` seen in the frame's globals.
+    pub name: String,
+    /// Whether the frame ran as the program entry point (`__name__ == "__main__"`).
+    pub ran_as_main: bool,
+}
+
 /// Coordinates writer setup, activation, and teardown flows.
 #[derive(Debug)]
 pub struct LifecycleController {
@@ -23,6 +152,13 @@ pub struct LifecycleController {
     events_recorded: bool,
     encountered_failure: bool,
     trace_id: String,
+    interpreter: Option<InterpreterInfo>,
+    command_line: Option<CommandLine>,
+    entry_module: Option<EntryModule>,
+    /// Set at `begin` when the configured activation path does not exist.
+    activation_path_missing: bool,
+    /// When `begin` ran, so finish can report the session's duration.
+    started: Option<Instant>,
 }
 
 impl LifecycleController {
@@ -34,6 +170,11 @@ impl LifecycleController {
             events_recorded: false,
             encountered_failure: false,
             trace_id: Uuid::new_v4().to_string(),
+            interpreter: None,
+            command_line: None,
+            entry_module: None,
+            activation_path_missing: false,
+            started: None,
         }
     }
 
@@ -53,18 +194,97 @@ impl LifecycleController {
         start_line: u32,
     ) -> RecorderResult<()> {
         let start_path = self.activation.start_path(&self.program_path);
+        self.activation_path_missing = self.activation.activation_path_missing();
         {
             let _mute = ScopedMuteIoCapture::new();
             log::debug!("{}", start_path.display());
+            if self.activation_path_missing {
+                log::warn!(
+                    "activation path {} does not exist; tracing will not activate",
+                    start_path.display()
+                );
+            }
         }
         outputs.configure_writer(writer, start_path, start_line)?;
         self.output_paths = Some(outputs.clone());
         self.events_recorded = false;
         self.encountered_failure = false;
+        self.entry_module = None;
+        self.started = Some(Instant::now());
         self.set_trace_id_active();
         Ok(())
     }
 
+    /// Wall-clock milliseconds since `begin`, or `None` before it ran.
+    pub fn duration_ms(&self) -> Option<u64> {
+        self.started
+            .map(|started| u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX))
+    }
+
+    pub fn record_interpreter(&mut self, info: InterpreterInfo) {
+        {
+            let _mute = ScopedMuteIoCapture::new();
+            debug!(
+                "[Lifecycle] interpreter: {} {} on {}",
+                info.implementation, info.version, info.platform
+            );
+        }
+        self.interpreter = Some(info);
+    }
+
+    pub fn interpreter(&self) -> Option<&InterpreterInfo> {
+        self.interpreter.as_ref()
+    }
+
+    pub fn record_command_line(&mut self, command_line: CommandLine) {
+        {
+            let _mute = ScopedMuteIoCapture::new();
+            debug!(
+                "[Lifecycle] command line: {:?} in {}",
+                command_line.argv, command_line.cwd
+            );
+        }
+        self.command_line = Some(command_line);
+    }
+
+    pub fn command_line(&self) -> Option<&CommandLine> {
+        self.command_line.as_ref()
+    }
+
+    /// Record the entry mode from the first traced module-level frame; later
+    /// module frames (imports) are ignored. Returns whether `name` was recorded.
+    pub fn record_entry_module(&mut self, name: &str) -> bool {
+        if self.entry_module.is_some() {
+            return false;
+        }
+        let entry = EntryModule {
+            name: name.to_string(),
+            ran_as_main: name == "__main__",
+        };
+        {
+            let _mute = ScopedMuteIoCapture::new();
+            debug!(
+                "[Lifecycle] entry module: {} (main={})",
+                entry.name, entry.ran_as_main
+            );
+        }
+        self.entry_module = Some(entry);
+        true
+    }
+
+    pub fn entry_module(&self) -> Option<&EntryModule> {
+        self.entry_module.as_ref()
+    }
+
+    pub fn activation_path_missing(&self) -> bool {
+        self.activation_path_missing
+    }
+
+    /// The activation path, or the program path when none is configured.
+    pub fn start_path(&self) -> &Path {
+        self.activation.start_path(&self.program_path)
+    }
+
     pub fn mark_event(&mut self) {
         self.events_recorded = true;
     }
@@ -125,8 +345,8 @@ impl LifecycleController {
                     .with_context("source", err.to_string())
             })?;
         debug!(
-            "[Lifecycle] writing exit metadata: code={:?}, label={:?}",
-            exit_summary.code, exit_summary.label
+            "[Lifecycle] writing exit metadata: code={:?}, label={:?}, duration_ms={:?}",
+            exit_summary.code, exit_summary.label, exit_summary.duration_ms
         );
         self.append_filter_metadata(filter)?;
         self.append_exit_metadata(exit_summary)?;
@@ -197,6 +417,7 @@ impl LifecycleController {
         self.output_paths = None;
         self.events_recorded = false;
         self.encountered_failure = false;
+        self.started = None;
     }
 
     fn append_exit_metadata(&self, _exit_summary: &ExitSummary) -> RecorderResult<()> {
@@ -341,6 +562,61 @@ mod tests {
         );
     }
 
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn redact_argv_hides_matching_arguments_and_option_values() {
+        let argv = args(&[
+            "app.py",
+            "--api-token=abc",
+            "--password",
+            "hunter2",
+            "SECRET_VALUE",
+            "--verbose",
+        ]);
+        let patterns = args(&["token", "password", "secret"]);
+
+        assert_eq!(
+            redact_argv(&argv, &patterns),
+            args(&[
+                "app.py",
+                "--api-token=<redacted>",
+                "--password",
+                "<redacted>",
+                "<redacted>",
+                "--verbose",
+            ])
+        );
+        assert_eq!(redact_argv(&argv, &[]), argv);
+    }
+
+    #[test]
+    fn redact_argv_keeps_an_option_that_follows_a_matching_switch() {
+        let argv = args(&[
+            "app.py",
+            "--token-refresh",
+            "--verbose",
+            "--token-file",
+            "-q",
+            "input.txt",
+        ]);
+        let patterns = args(&["token"]);
+
+        assert_eq!(
+            redact_argv(&argv, &patterns),
+            args(&[
+                "app.py",
+                "--token-refresh",
+                "--verbose",
+                "--token-file",
+                "-q",
+                "input.txt",
+            ])
+        );
+    }
+
     #[test]
     fn trace_id_scope_sets_and_clears_active_id() {
         init_rust_logging_with_default("codetracer_python_recorder=error");
@@ -0,0 +1,48 @@
+//! Per-line hit counts collected in coverage mode instead of step records.
+
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Counts how often each `(file, line)` pair received a `LINE` event.
+#[derive(Debug, Default)]
+pub(crate) struct LineCoverage {
+    /// Keyed by file first so a hit in a known file allocates nothing.
+    hits: HashMap<String, HashMap<u32, u64>>,
+}
+
+impl LineCoverage {
+    pub(crate) fn record(&mut self, path: &str, line: u32) {
+        if !self.hits.contains_key(path) {
+            self.hits.insert(path.to_string(), HashMap::new());
+        }
+        if let Some(lines) = self.hits.get_mut(path) {
+            *lines.entry(line).or_insert(0) += 1;
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.hits.clear();
+    }
+
+    /// `{"files": {path: {line: hits}}}` with files and lines in ascending
+    /// order so reports diff cleanly between runs.
+    pub(crate) fn report_json(&self) -> serde_json::Value {
+        let files: BTreeMap<&str, BTreeMap<u32, u64>> = self
+            .hits
+            .iter()
+            .map(|(path, lines)| {
+                let lines = lines.iter().map(|(line, hits)| (*line, *hits)).collect();
+                (path.as_str(), lines)
+            })
+            .collect();
+        json!({ "files": files })
+    }
+
+    pub(crate) fn write_report(&self, destination: &Path) -> io::Result<()> {
+        let report = serde_json::to_vec_pretty(&self.report_json())?;
+        fs::write(destination, report)
+    }
+}
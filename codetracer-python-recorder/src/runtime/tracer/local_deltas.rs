@@ -0,0 +1,26 @@
+//! Per-frame encoded value of each local, used to record delta snapshots.
+
+use crate::runtime::value_capture::LastValues;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub(crate) struct LocalDeltas {
+    frames: HashMap<u64, LastValues>,
+}
+
+impl LocalDeltas {
+    /// Values last recorded for `frame`; empty until its first snapshot.
+    pub(crate) fn frame_mut(&mut self, frame: u64) -> &mut LastValues {
+        self.frames.entry(frame).or_default()
+    }
+
+    /// Drop the values recorded for `frame` so a frame later allocated at
+    /// the same address starts with a full snapshot.
+    pub(crate) fn forget_frame(&mut self, frame: u64) {
+        self.frames.remove(&frame);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.frames.clear();
+    }
+}
@@ -3,10 +3,23 @@
 //! Re-exports [`RuntimeTracer`] so downstream callers continue using `crate::runtime::RuntimeTracer`
 //! without exposing the implementation modules outside the crate.
 
+pub(crate) mod c_calls;
+pub(crate) mod call_depth;
+pub(crate) mod env_changes;
 pub(crate) mod events;
+pub(crate) mod filter_decisions;
 pub(crate) mod filtering;
+pub(crate) mod function_table;
+pub(crate) mod generator_ids;
 pub(crate) mod io;
 pub(crate) mod lifecycle;
+pub(crate) mod line_coverage;
+pub(crate) mod local_deltas;
+pub(crate) mod module_names;
+pub(crate) mod path_stats;
+pub(crate) mod thread_switches;
+pub(crate) mod type_changes;
+pub(crate) mod warnings;
 
 mod runtime_tracer;
 
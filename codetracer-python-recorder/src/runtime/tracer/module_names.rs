@@ -0,0 +1,31 @@
+//! Per-code-object memo of derived module names, failed derivations included.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub(crate) struct ModuleNames {
+    names: HashMap<usize, Option<String>>,
+    derivations: usize,
+}
+
+impl ModuleNames {
+    /// Cached result for `code_id`; `Some(None)` when derivation failed.
+    pub(crate) fn get(&self, code_id: usize) -> Option<Option<String>> {
+        self.names.get(&code_id).cloned()
+    }
+
+    pub(crate) fn insert(&mut self, code_id: usize, name: Option<String>) {
+        self.derivations += 1;
+        self.names.insert(code_id, name);
+    }
+
+    /// Number of derivations recorded since the last reset.
+    pub(crate) fn derivations(&self) -> usize {
+        self.derivations
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.names.clear();
+        self.derivations = 0;
+    }
+}
@@ -0,0 +1,62 @@
+//! Per-file event counts used to summarise which sources dominate a trace.
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Number of files listed in the finish-time summary.
+const TOP_PATHS_LIMIT: usize = 10;
+
+/// Counts call and step events per source path for the finish-time summary.
+#[derive(Debug, Default)]
+pub(crate) struct PathStats {
+    events: HashMap<PathBuf, u64>,
+}
+
+impl PathStats {
+    pub(crate) fn record(&mut self, path: &Path) {
+        if let Some(count) = self.events.get_mut(path) {
+            *count += 1;
+        } else {
+            self.events.insert(path.to_path_buf(), 1);
+        }
+    }
+
+    pub(crate) fn distinct_paths(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Paths ordered by descending event count; ties are broken by path so
+    /// the summary is stable across runs.
+    pub(crate) fn top_paths(&self, limit: usize) -> Vec<(&Path, u64)> {
+        let mut entries: Vec<(&Path, u64)> = self
+            .events
+            .iter()
+            .map(|(path, count)| (path.as_path(), *count))
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        entries.truncate(limit);
+        entries
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.events.clear();
+    }
+
+    pub(crate) fn summary_json(&self) -> serde_json::Value {
+        let top: Vec<serde_json::Value> = self
+            .top_paths(TOP_PATHS_LIMIT)
+            .into_iter()
+            .map(|(path, events)| {
+                json!({
+                    "path": path.display().to_string(),
+                    "events": events,
+                })
+            })
+            .collect();
+        json!({
+            "distinct_paths": self.distinct_paths(),
+            "top_paths": top,
+        })
+    }
+}
@@ -1,7 +1,21 @@
+use super::c_calls::CCallPatterns;
+use super::call_depth::CallDepth;
+use super::env_changes::EnvChanges;
 use super::events::suppress_events;
 use super::filtering::{FilterCoordinator, TraceDecision};
+use super::function_table::{
+    declared_annotations, docstring_summary, parameter_names, FunctionTable,
+};
+use super::generator_ids::GeneratorIds;
 use super::io::IoCoordinator;
-use super::lifecycle::LifecycleController;
+use super::lifecycle::{redact_env_values, CommandLine, InterpreterInfo, LifecycleController};
+use super::line_coverage::LineCoverage;
+use super::local_deltas::LocalDeltas;
+use super::module_names::ModuleNames;
+use super::path_stats::PathStats;
+use super::thread_switches::ThreadSwitches;
+use super::type_changes::TypeChanges;
+use super::warnings::WarningsHook;
 use crate::code_object::CodeObjectWrapper;
 use crate::ffi;
 use crate::module_identity::{
@@ -10,23 +24,30 @@ use crate::module_identity::{
 use crate::monitoring::CallbackOutcome;
 use crate::policy::RecorderPolicy;
 use crate::runtime::assignment_reconstructor::AssignmentReconstructor;
+use crate::runtime::frame_inspector::{capture_frame, frame_identity, FrameSnapshot};
 use crate::runtime::io_capture::{IoCaptureSettings, ScopedMuteIoCapture};
 use crate::runtime::line_snapshots::LineSnapshotStore;
 use crate::runtime::output_paths::TraceOutputPaths;
-use crate::runtime::value_encoder::encode_value_streaming;
-use crate::trace_filter::engine::TraceFilterEngine;
-use codetracer_trace_types::Line;
+use crate::runtime::statement_lines::StatementLines;
+use crate::runtime::value_capture::default_argument_names;
+use crate::runtime::value_encoder::{
+    encode_value, encode_value_streaming, redact_large_value, CaptureEncoder, EncodingOptions,
+};
+use crate::runtime::with_blocks::{WithBlockEvent, WithBlocks};
+use crate::trace_filter::engine::{TraceFilterEngine, ValueAction, ValueKind, ValuePolicy};
+use codetracer_trace_types::{EventLogKind, Line};
 use codetracer_trace_writer_nim::create_trace_writer;
 use codetracer_trace_writer_nim::trace_writer::TraceWriter;
-use codetracer_trace_writer_nim::StreamingValueEncoder;
 use codetracer_trace_writer_nim::TraceEventsFileFormat;
 use pyo3::prelude::*;
-use pyo3::types::{PyAny, PyInt, PyString};
+use pyo3::types::{PyAny, PyBool, PyBytes, PyFloat, PyInt, PyString};
+use recorder_errors::{enverr, ErrorCode, RecorderResult};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use std::thread::ThreadId;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
 enum ExitPayload {
@@ -38,6 +59,8 @@ enum ExitPayload {
 pub(crate) struct ExitSummary {
     pub code: Option<i32>,
     pub label: Option<String>,
+    /// Wall-clock milliseconds from `begin` to finish.
+    pub duration_ms: Option<u64>,
 }
 
 impl ExitPayload {
@@ -112,10 +135,12 @@ impl SessionExitState {
             ExitPayload::Code(value) => ExitSummary {
                 code: Some(*value),
                 label: None,
+                duration_ms: None,
             },
             ExitPayload::Text(text) => ExitSummary {
                 code: None,
                 label: Some(text.as_ref().to_string()),
+                duration_ms: None,
             },
         }
     }
@@ -134,7 +159,7 @@ pub struct RuntimeTracer {
     /// Streaming value encoder (M58). Encodes Python values directly to CBOR
     /// bytes without building intermediate `ValueRecord` trees. Reused across
     /// steps to avoid per-value allocation overhead.
-    pub(super) streaming_encoder: StreamingValueEncoder,
+    pub(super) streaming_encoder: CaptureEncoder,
     /// M15: Assignment reconstructor. Caches per-code-object bytecode tables
     /// so we can reconstruct Assignment / BindVariable events on every
     /// `on_line` callback without re-disassembling the function.
@@ -178,6 +203,81 @@ pub struct RuntimeTracer {
     /// can stamp `RValue::FunctionReturn { call_key }` with the
     /// most-recently-popped call.
     pub(super) last_call_key: i64,
+    /// Calls-only mode: drop `LINE` from the monitoring interest so the
+    /// trace carries call/return edges (with arguments and return values)
+    /// but no per-line steps or variable snapshots.
+    pub(super) calls_only: bool,
+    /// Report Step lines at the first line of their statement rather than
+    /// the physical line `sys.monitoring` delivers.
+    pub(super) statement_start_lines: bool,
+    pub(super) statement_lines: StatementLines,
+    /// Emit `__enter__`/`__exit__` markers around `with` block bodies.
+    pub(super) capture_with_blocks: bool,
+    pub(super) with_blocks: WithBlocks,
+    /// Per-file call/step counts reported when the trace finishes.
+    pub(super) path_stats: PathStats,
+    /// Coverage mode: count `LINE` hits per file and line and write them to
+    /// `coverage.json` instead of recording steps.
+    pub(super) line_coverage: bool,
+    pub(super) coverage: LineCoverage,
+    /// Emit a wall-clock timestamp marker after each call and return record.
+    pub(super) call_timestamps: bool,
+    /// Keep call arguments, return values and steps but skip the per-line
+    /// variable snapshots and assignment events.
+    pub(super) args_and_returns_only: bool,
+    /// Emit a `type_changed` marker when a local's type differs from the
+    /// previous step in the same frame.
+    pub(super) flag_type_changes: bool,
+    pub(super) type_changes: TypeChanges,
+    /// Ids linking the start, suspensions and resumptions of each generator
+    /// or coroutine.
+    pub(super) generator_ids: GeneratorIds,
+    /// Subscribe to `JUMP` and emit a `loop_iteration` marker for each
+    /// backward jump.
+    pub(super) record_loop_iterations: bool,
+    /// Cap on the locals encoded per line snapshot; `None` records all.
+    pub(super) max_locals_per_step: Option<usize>,
+    /// Attach the current `contextvars` entries to each call record.
+    pub(super) capture_contextvars: bool,
+    /// Rewrite a finished `Json` events file with indentation.
+    pub(super) pretty_json: bool,
+    /// Tag dropped return values with their own type.
+    pub(super) keep_dropped_return_type: bool,
+    /// Add declared annotations to each `functions.json` entry.
+    pub(super) capture_annotations: bool,
+    /// Patterns of `sys.argv` entries to redact from the recorded command line.
+    redact_argv: Vec<String>,
+    /// Emit an `object_ids` marker with the identity of non-primitive locals.
+    pub(super) capture_object_ids: bool,
+    /// Subscribe to `CALL` and emit an `instruction_offset` marker for each
+    /// call site, so sub-steps of one line can be told apart.
+    pub(super) record_instruction_offsets: bool,
+    /// Re-read a finished `Json` events file and log any structural problems.
+    pub(super) validate_trace: bool,
+    /// Depth of traced frames; frames past `max_call_depth` run unrecorded.
+    pub(super) call_depth: CallDepth,
+    /// Follow each call record with the arguments still holding their default.
+    pub(super) mark_default_args: bool,
+    /// Record only the locals whose value changed since the previous step
+    /// in the same frame.
+    pub(super) delta_locals: bool,
+    pub(super) local_deltas: LocalDeltas,
+    /// Diff `os.environ` at each call and return and record what changed.
+    pub(super) capture_env_changes: bool,
+    pub(super) env_changes: EnvChanges,
+    /// Module names derived for `<module>` code objects, keyed by code id.
+    pub(super) module_names: ModuleNames,
+    /// Callables whose call sites are recorded together with their first argument.
+    pub(super) c_calls: CCallPatterns,
+    /// Thread that recorded last, so a switch to another thread is marked.
+    pub(super) thread_switches: ThreadSwitches,
+    /// `warnings.showwarning` replacement installed when warnings are captured.
+    pub(super) warnings_hook: WarningsHook,
+    /// Events already appended to an NDJSON events file by `flush`.
+    pub(super) ndjson_flushed: usize,
+    /// Name, file and first line of each registered function, written to
+    /// `functions.json` when the trace finishes.
+    pub(super) function_table: FunctionTable,
     session_exit: SessionExitState,
 }
 
@@ -207,7 +307,7 @@ impl RuntimeTracer {
             io: IoCoordinator::new(),
             filter: FilterCoordinator::new(trace_filter),
             module_name_from_globals,
-            streaming_encoder: StreamingValueEncoder::new(),
+            streaming_encoder: CaptureEncoder::new(),
             assignment_reconstructor: AssignmentReconstructor::new(),
             frame_bound_names: HashMap::new(),
             last_line_per_frame: HashMap::new(),
@@ -215,6 +315,41 @@ impl RuntimeTracer {
             column_aware,
             paths_with_line_lengths: std::collections::HashSet::new(),
             last_call_key: -1,
+            calls_only: false,
+            statement_start_lines: false,
+            statement_lines: StatementLines::default(),
+            capture_with_blocks: false,
+            with_blocks: WithBlocks::default(),
+            path_stats: PathStats::default(),
+            line_coverage: false,
+            coverage: LineCoverage::default(),
+            call_timestamps: false,
+            args_and_returns_only: false,
+            flag_type_changes: false,
+            type_changes: TypeChanges::default(),
+            generator_ids: GeneratorIds::default(),
+            record_loop_iterations: false,
+            max_locals_per_step: None,
+            capture_contextvars: false,
+            pretty_json: false,
+            keep_dropped_return_type: false,
+            capture_annotations: false,
+            redact_argv: Vec::new(),
+            capture_object_ids: false,
+            record_instruction_offsets: false,
+            validate_trace: false,
+            call_depth: CallDepth::default(),
+            mark_default_args: false,
+            delta_locals: false,
+            local_deltas: LocalDeltas::default(),
+            capture_env_changes: false,
+            env_changes: EnvChanges::default(),
+            module_names: ModuleNames::default(),
+            c_calls: CCallPatterns::default(),
+            thread_switches: ThreadSwitches::default(),
+            warnings_hook: WarningsHook::default(),
+            ndjson_flushed: 0,
+            function_table: FunctionTable::default(),
             session_exit: SessionExitState::default(),
         }
     }
@@ -228,11 +363,611 @@ impl RuntimeTracer {
     pub fn install_io_capture(&mut self, py: Python<'_>, policy: &RecorderPolicy) -> PyResult<()> {
         let settings = IoCaptureSettings {
             line_proxies: policy.io_capture.line_proxies,
-            fd_mirror: policy.io_capture.fd_fallback,
+            fd_mirror_stdout: policy.io_capture.fd_mirror_stdout,
+            fd_mirror_stderr: policy.io_capture.fd_mirror_stderr,
         };
         self.io.install(py, settings)
     }
 
+    /// Route `warnings.showwarning` to this tracer when `capture_warnings` is on.
+    pub fn install_warnings_hook(
+        &mut self,
+        py: Python<'_>,
+        policy: &RecorderPolicy,
+    ) -> PyResult<()> {
+        if policy.capture_warnings {
+            self.warnings_hook.install(py)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the capture settings from `policy` that shape which events the
+    /// tracer records and how values are encoded. Must run before the tracer
+    /// is installed because `interest` is only consulted once at install time.
+    pub fn apply_policy(&mut self, policy: &RecorderPolicy) {
+        self.calls_only = policy.calls_only;
+        self.statement_start_lines = policy.statement_start_lines;
+        self.capture_with_blocks = policy.capture_with_blocks;
+        self.line_coverage = policy.line_coverage;
+        // Wall-clock markers differ on every run, so deterministic mode drops them.
+        self.call_timestamps = policy.call_timestamps && !policy.deterministic;
+        self.args_and_returns_only = policy.args_and_returns_only;
+        self.flag_type_changes = policy.flag_type_changes;
+        self.record_loop_iterations = policy.record_loop_iterations;
+        self.max_locals_per_step = policy.max_locals_per_step;
+        self.capture_contextvars = policy.capture_contextvars;
+        self.pretty_json = policy.pretty_json;
+        self.keep_dropped_return_type = policy.keep_dropped_return_type;
+        self.capture_annotations = policy.capture_annotations;
+        self.redact_argv = policy.redact_argv.clone();
+        // Addresses and reference counts differ between runs.
+        self.capture_object_ids = policy.capture_object_ids && !policy.deterministic;
+        self.record_instruction_offsets = policy.record_instruction_offsets;
+        self.validate_trace = policy.validate_trace;
+        self.call_depth.set_limit(policy.max_call_depth);
+        self.mark_default_args = policy.mark_default_args;
+        self.delta_locals = policy.delta_locals;
+        self.capture_env_changes = policy.capture_env_changes;
+        self.c_calls = CCallPatterns::new(&policy.capture_c_calls);
+        self.filter.apply_policy(policy);
+        self.streaming_encoder
+            .set_options(EncodingOptions::from_policy(policy));
+    }
+
+    /// Line to record for a `LINE` event at `lineno`: the physical line, or
+    /// the first line of its statement when statement-start lines are on.
+    pub(super) fn reported_line(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        lineno: u32,
+    ) -> u32 {
+        if !self.statement_start_lines {
+            return lineno;
+        }
+        match code.filename(py) {
+            Ok(filename) => self
+                .statement_lines
+                .map_for(py, filename)
+                .statement_start(lineno),
+            Err(_) => lineno,
+        }
+    }
+
+    /// Record the `with` blocks entered or left by the `LINE` event at
+    /// physical line `lineno` of `frame`.
+    pub(super) fn track_with_blocks(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        frame: u64,
+        lineno: u32,
+    ) {
+        if !self.capture_with_blocks {
+            return;
+        }
+        let Ok(filename) = code.filename(py) else {
+            return;
+        };
+        let map = self.with_blocks.map_for(py, filename);
+        let events = self.with_blocks.on_line(frame, lineno, &map);
+        self.emit_with_block_events(filename, &events);
+    }
+
+    /// Close the `with` blocks left open by a frame that returned or unwound.
+    pub(super) fn close_with_blocks(&mut self, py: Python<'_>, code: &CodeObjectWrapper) {
+        if !self.capture_with_blocks || !self.with_blocks.has_open_blocks() {
+            return;
+        }
+        let Ok(snapshot) = capture_frame(py, code) else {
+            return;
+        };
+        let events = self
+            .with_blocks
+            .close_frame(snapshot.frame_ptr() as usize as u64);
+        if let Ok(filename) = code.filename(py) {
+            self.emit_with_block_events(filename, &events);
+        }
+    }
+
+    /// Write a trace-log event with `metadata` (a JSON object carrying its
+    /// `kind`) and `label` as its content. Per-event annotations and the
+    /// session details `meta.dat` has no slot for (interpreter, command line,
+    /// duration, finish-time summaries) all go in the event stream this way.
+    pub(super) fn emit_trace_log(&mut self, label: &str, metadata: serde_json::Value) {
+        TraceWriter::register_special_event(
+            &mut *self.writer,
+            EventLogKind::TraceLogEvent,
+            &metadata.to_string(),
+            label,
+        );
+    }
+
+    fn emit_with_block_events(&mut self, filename: &str, events: &[WithBlockEvent]) {
+        for event in events {
+            let (phase, header) = match *event {
+                WithBlockEvent::Enter(header) => ("__enter__", header),
+                WithBlockEvent::Exit(header) => ("__exit__", header),
+            };
+            self.emit_trace_log(
+                phase,
+                serde_json::json!({
+                    "kind": "with_block",
+                    "phase": phase,
+                    "path": filename,
+                    "line": header,
+                }),
+            );
+            self.mark_event();
+        }
+    }
+
+    /// Record when the call or return record just written happened, in
+    /// nanoseconds since the Unix epoch, so consumers can compute durations.
+    pub(super) fn emit_call_timestamp(&mut self, phase: &'static str) {
+        if !self.call_timestamps {
+            return;
+        }
+        let unix_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        self.emit_trace_log(
+            phase,
+            serde_json::json!({
+                "kind": "call_timestamp",
+                "phase": phase,
+                "unix_ns": unix_ns,
+            }),
+        );
+    }
+
+    /// Compare each local's type with the previous step in this frame and
+    /// emit a `type_changed` marker for the ones that differ. Locals the
+    /// value policy hides are skipped so their types do not leak either.
+    pub(super) fn flag_local_type_changes(
+        &mut self,
+        snapshot: &FrameSnapshot<'_>,
+        frame: u64,
+        value_policy: Option<&ValuePolicy>,
+    ) {
+        if !self.flag_type_changes {
+            return;
+        }
+        for (key, value) in snapshot.locals().iter() {
+            let Ok(name) = key.extract::<String>() else {
+                continue;
+            };
+            let hidden = value_policy
+                .is_some_and(|policy| policy.decide(ValueKind::Local, &name) != ValueAction::Allow);
+            if hidden {
+                continue;
+            }
+            let Ok(type_name) = value.get_type().name() else {
+                continue;
+            };
+            let type_name = type_name.to_string();
+            let Some(previous) = self.type_changes.observe(frame, &name, &type_name) else {
+                continue;
+            };
+            self.emit_trace_log(
+                "type_changed",
+                serde_json::json!({
+                    "kind": "type_changed",
+                    "name": name,
+                    "from": previous,
+                    "to": type_name,
+                }),
+            );
+        }
+    }
+
+    /// Emit one `object_ids` marker with `id()` and the reference count of
+    /// each non-primitive local, so aliasing can be followed across steps.
+    /// The count includes the references the recorder holds while reading it.
+    pub(super) fn emit_object_ids(
+        &mut self,
+        snapshot: &FrameSnapshot<'_>,
+        value_policy: Option<&ValuePolicy>,
+    ) {
+        if !self.capture_object_ids {
+            return;
+        }
+        let mut objects = serde_json::Map::new();
+        for (key, value) in snapshot.locals().iter() {
+            let Ok(name) = key.extract::<String>() else {
+                continue;
+            };
+            let hidden = value_policy
+                .is_some_and(|policy| policy.decide(ValueKind::Local, &name) != ValueAction::Allow);
+            if hidden || is_primitive(&value) {
+                continue;
+            }
+            objects.insert(
+                name,
+                serde_json::json!({
+                    "id": value.as_ptr() as usize,
+                    "refcount": value.get_refcnt(),
+                }),
+            );
+        }
+        if objects.is_empty() {
+            return;
+        }
+        self.emit_trace_log(
+            "object_ids",
+            serde_json::json!({
+                "kind": "object_ids",
+                "objects": objects,
+            }),
+        );
+    }
+
+    /// Mark the start of another loop iteration at the jump target.
+    pub(super) fn emit_loop_iteration(&mut self, filename: &str, line: Option<u32>) {
+        self.emit_trace_log(
+            "loop_iteration",
+            serde_json::json!({
+                "kind": "loop_iteration",
+                "path": filename,
+                "line": line,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Mark the bytecode offset of a call site within its line.
+    pub(super) fn emit_instruction_offset(
+        &mut self,
+        filename: &str,
+        line: Option<u32>,
+        offset: i32,
+    ) {
+        self.emit_trace_log(
+            "instruction_offset",
+            serde_json::json!({
+                "kind": "instruction_offset",
+                "path": filename,
+                "line": line,
+                "offset": offset,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Record a call to a callable selected by the `capture_c_calls` globs.
+    /// `sys.monitoring` only exposes the first argument of a call, so that
+    /// is the one value recorded; `arg0` is null for calls without arguments.
+    pub(super) fn emit_c_call(
+        &mut self,
+        py: Python<'_>,
+        filename: &str,
+        line: Option<u32>,
+        callable: &str,
+        arg0: Option<&Bound<'_, PyAny>>,
+    ) {
+        let missing = py
+            .import("sys")
+            .and_then(|sys| sys.getattr("monitoring"))
+            .and_then(|monitoring| monitoring.getattr("MISSING"))
+            .ok();
+        let arg0 = arg0
+            .filter(|value| !missing.as_ref().is_some_and(|missing| value.is(missing)))
+            .map(|value| {
+                let options = self.streaming_encoder.options().clone();
+                let record = encode_value(py, &mut *self.writer, value, &options);
+                let record = redact_large_value(&mut *self.writer, record, &options);
+                serde_json::to_value(record).unwrap_or(serde_json::Value::Null)
+            });
+        self.emit_trace_log(
+            "c_call",
+            serde_json::json!({
+                "kind": "c_call",
+                "callable": callable,
+                "path": filename,
+                "line": line,
+                "arg0": arg0,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Mark which `return` statement produced the return record just
+    /// written, so functions with several return sites can be told apart.
+    pub(super) fn emit_return_site(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        offset: i32,
+    ) {
+        let Ok(filename) = code.filename(py) else {
+            return;
+        };
+        let line = u32::try_from(offset)
+            .ok()
+            .and_then(|offset| code.line_for_offset(py, offset).ok().flatten());
+        self.emit_trace_log(
+            "return_site",
+            serde_json::json!({
+                "kind": "return_site",
+                "path": filename,
+                "line": line,
+                "offset": offset,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Record a `trace_mark` annotation at the line of the Python code that
+    /// called it. Output written before the mark is recorded first so the
+    /// two stay in program order.
+    pub(super) fn emit_mark(
+        &mut self,
+        py: Python<'_>,
+        label: &str,
+        metadata: Option<&str>,
+    ) -> PyResult<()> {
+        // `trace_mark` is a builtin, so frame 0 is its Python caller.
+        let frame = py.import("sys")?.call_method1("_getframe", (0,))?;
+        let path: String = frame.getattr("f_code")?.getattr("co_filename")?.extract()?;
+        let line: Option<i64> = frame.getattr("f_lineno")?.extract()?;
+        self.flush_pending_io();
+        self.emit_trace_log(
+            label,
+            serde_json::json!({
+                "kind": "mark",
+                "label": label,
+                "path": path,
+                "line": line,
+                "metadata": metadata,
+            }),
+        );
+        self.mark_event();
+        Ok(())
+    }
+
+    /// Record a warning intercepted from `warnings.showwarning` with the
+    /// location Python attributed it to.
+    pub(super) fn emit_warning(
+        &mut self,
+        category: &str,
+        message: &str,
+        path: &str,
+        line: Option<i64>,
+    ) {
+        self.flush_pending_io();
+        self.emit_trace_log(
+            &format!("{category}: {message}"),
+            serde_json::json!({
+                "kind": "warning",
+                "category": category,
+                "message": message,
+                "path": path,
+                "line": line,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Record the `os.environ` variables set or removed since the previous
+    /// call or return record. Values matching a `redact_argv` pattern are
+    /// recorded as `<redacted>`.
+    pub(super) fn emit_env_changes(&mut self, py: Python<'_>) {
+        if !self.capture_env_changes {
+            return;
+        }
+        let mut diff = match self.env_changes.observe(py) {
+            Ok(Some(diff)) => diff,
+            Ok(None) => return,
+            Err(err) => {
+                let _mute = ScopedMuteIoCapture::new();
+                log::debug!("[RuntimeTracer] failed to read os.environ: {err}");
+                return;
+            }
+        };
+        redact_env_values(&mut diff.set, &self.redact_argv);
+        self.emit_trace_log(
+            "env_changed",
+            serde_json::json!({
+                "kind": "env_changed",
+                "set": diff.set,
+                "removed": diff.removed,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Name the arguments of the call record just written that were not
+    /// passed and still hold their default value.
+    pub(super) fn emit_default_args(&mut self, py: Python<'_>, code: &CodeObjectWrapper) {
+        let names = match default_argument_names(py, code) {
+            Ok(names) if !names.is_empty() => names,
+            Ok(_) => return,
+            Err(err) => {
+                let _mute = ScopedMuteIoCapture::new();
+                log::debug!("[RuntimeTracer] failed to resolve argument defaults: {err}");
+                return;
+            }
+        };
+        self.emit_trace_log(
+            "from_default",
+            serde_json::json!({
+                "kind": "from_default",
+                "args": names,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Tag the call record just written as entering a comprehension's
+    /// synthetic code object rather than a function the user wrote.
+    pub(super) fn emit_comprehension_tag(&mut self, py: Python<'_>, code: &CodeObjectWrapper) {
+        let Ok(Some(form)) = code.comprehension_form(py) else {
+            return;
+        };
+        self.emit_trace_log(
+            "comprehension",
+            serde_json::json!({
+                "kind": "comprehension",
+                "form": form,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Tag the call or return record just written for a generator-like
+    /// `code` with its generator id, so a replay can thread the start,
+    /// suspensions and resumptions of one generator together.
+    pub(super) fn emit_generator_link(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+        phase: &'static str,
+        finished: bool,
+    ) {
+        if !code.is_generator_like(py).unwrap_or(false) {
+            return;
+        }
+        let Ok(frame) = frame_identity(py, code) else {
+            return;
+        };
+        let generator_id = self.generator_ids.id_for(frame);
+        if finished {
+            self.generator_ids.forget_frame(frame);
+        }
+        self.emit_trace_log(
+            "generator",
+            serde_json::json!({
+                "kind": "generator",
+                "phase": phase,
+                "generator_id": generator_id,
+            }),
+        );
+        self.mark_event();
+    }
+
+    /// Forget the local types and values of the frame executing `code` once
+    /// it exits.
+    pub(super) fn forget_frame_locals(&mut self, py: Python<'_>, code: &CodeObjectWrapper) {
+        if !self.flag_type_changes && !self.delta_locals {
+            return;
+        }
+        if let Ok(snapshot) = capture_frame(py, code) {
+            let frame = snapshot.frame_ptr() as usize as u64;
+            self.type_changes.forget_frame(frame);
+            self.local_deltas.forget_frame(frame);
+        }
+    }
+
+    /// Write the registered functions next to the trace container.
+    pub(super) fn write_function_table(&self) -> RecorderResult<()> {
+        let Some(outputs) = self.lifecycle.output_paths() else {
+            return Ok(());
+        };
+        let destination = outputs.functions();
+        self.function_table
+            .write_report(destination)
+            .map_err(|err| {
+                enverr!(ErrorCode::Io, "failed to write function table")
+                    .with_context("path", destination.display().to_string())
+                    .with_context("io", err.to_string())
+            })
+    }
+
+    /// Lay out the finished `Json` events file: one event per line for
+    /// NDJSON, indented when pretty output was requested, otherwise left as
+    /// the compact array the writer emits.
+    pub(super) fn write_json_layout(&self) -> RecorderResult<()> {
+        match self.lifecycle.output_paths() {
+            Some(outputs) if outputs.is_ndjson() => outputs.apply_ndjson_layout(),
+            Some(outputs) if self.pretty_json => outputs.apply_json_layout(true),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the session writes its events as NDJSON.
+    pub(super) fn writes_ndjson(&self) -> bool {
+        self.lifecycle
+            .output_paths()
+            .is_some_and(|outputs| outputs.is_ndjson())
+    }
+
+    /// Append the events recorded since the previous flush to the NDJSON
+    /// events file, leaving the writer's buffer for the final layout.
+    pub(super) fn append_ndjson_events(&mut self) -> RecorderResult<()> {
+        let Some(outputs) = self.lifecycle.output_paths() else {
+            return Ok(());
+        };
+        let events = self.writer.events();
+        let flushed = events.len();
+        outputs.append_ndjson_events(events.get(self.ndjson_flushed..).unwrap_or_default())?;
+        self.ndjson_flushed = flushed;
+        Ok(())
+    }
+
+    /// Run the post-trace self-check over a finished `Json` events file and
+    /// log each issue; other formats cannot be read back here.
+    pub(super) fn validate_written_trace(&self) -> RecorderResult<()> {
+        let Some(outputs) = self.lifecycle.output_paths() else {
+            return Ok(());
+        };
+        if !self.validate_trace || !matches!(outputs.format(), TraceEventsFileFormat::Json) {
+            return Ok(());
+        }
+        for issue in crate::trace_validation::validate_events_file(outputs.events())? {
+            log::warn!("trace validation: {issue}");
+        }
+        Ok(())
+    }
+
+    /// Write the filter skip attributions next to the trace container.
+    pub(super) fn write_filter_decisions(&self) -> RecorderResult<()> {
+        let (Some(outputs), Some(decisions)) =
+            (self.lifecycle.output_paths(), self.filter.decision_log())
+        else {
+            return Ok(());
+        };
+        let destination = outputs.filter_decisions();
+        decisions.write_report(destination).map_err(|err| {
+            enverr!(ErrorCode::Io, "failed to write filter decisions")
+                .with_context("path", destination.display().to_string())
+                .with_context("io", err.to_string())
+        })
+    }
+
+    /// Write the accumulated line hits next to the trace container.
+    pub(super) fn write_line_coverage(&self) -> RecorderResult<()> {
+        let Some(outputs) = self.lifecycle.output_paths() else {
+            return Ok(());
+        };
+        let destination = outputs.coverage();
+        self.coverage.write_report(destination).map_err(|err| {
+            enverr!(ErrorCode::Io, "failed to write coverage report")
+                .with_context("path", destination.display().to_string())
+                .with_context("io", err.to_string())
+        })
+    }
+
+    /// Clear the per-session state once a trace is finished or discarded, so
+    /// the tracer can begin another session.
+    pub(super) fn reset_session_state(&mut self) {
+        self.function_ids.clear();
+        self.function_table.reset();
+        self.type_changes.reset();
+        self.local_deltas.reset();
+        self.env_changes.reset();
+        self.module_names.reset();
+        self.thread_switches.reset();
+        self.generator_ids.reset();
+        self.call_depth.reset();
+        self.ndjson_flushed = 0;
+        self.filter.reset();
+        self.path_stats.reset();
+        self.coverage.reset();
+        self.io.clear_snapshots();
+        self.lifecycle.reset_event_state();
+    }
+
     pub(super) fn flush_io_before_step(&mut self, thread_id: ThreadId) {
         if self.io.flush_before_step(thread_id, &mut *self.writer) {
             self.mark_event();
@@ -250,6 +985,7 @@ impl RuntimeTracer {
             return;
         }
 
+        self.note_thread(py);
         self.flush_pending_io();
         let value = self.session_exit.as_bound(py);
         let cbor =
@@ -263,9 +999,93 @@ impl RuntimeTracer {
         self.lifecycle
             .begin(&mut *self.writer, outputs, start_line)
             .map_err(ffi::map_recorder_error)?;
+        self.emit_activation_path_missing();
+        // Interpreter details are best-effort: a broken `platform` module
+        // should not prevent the trace from starting.
+        match Python::with_gil(InterpreterInfo::capture) {
+            Ok(info) => {
+                self.lifecycle.record_interpreter(info);
+                self.emit_interpreter();
+            }
+            Err(err) => {
+                let _mute = ScopedMuteIoCapture::new();
+                log::warn!("failed to capture interpreter details: {err}");
+            }
+        }
+        match Python::with_gil(|py| CommandLine::capture(py, &self.redact_argv)) {
+            Ok(command_line) => {
+                self.lifecycle.record_command_line(command_line);
+                self.emit_command_line();
+            }
+            Err(err) => {
+                let _mute = ScopedMuteIoCapture::new();
+                log::warn!("failed to capture command line: {err}");
+            }
+        }
         Ok(())
     }
 
+    /// Record which interpreter produced the trace.
+    fn emit_interpreter(&mut self) {
+        let Some(info) = self.lifecycle.interpreter() else {
+            return;
+        };
+        self.emit_trace_log(
+            "interpreter",
+            serde_json::json!({
+                "kind": "interpreter",
+                "version": info.version,
+                "implementation": info.implementation,
+                "platform": info.platform,
+            }),
+        );
+    }
+
+    /// Record that the configured activation path does not exist, so a trace
+    /// that never activated can be explained from the trace itself.
+    fn emit_activation_path_missing(&mut self) {
+        if !self.lifecycle.activation_path_missing() {
+            return;
+        }
+        self.emit_trace_log(
+            "activation_path_missing",
+            serde_json::json!({
+                "kind": "activation_path_missing",
+                "path": self.lifecycle.start_path().to_string_lossy(),
+            }),
+        );
+    }
+
+    /// Record how the entry module ran, once the lifecycle has seen it.
+    pub(super) fn emit_entry_module(&mut self) {
+        let Some(entry) = self.lifecycle.entry_module() else {
+            return;
+        };
+        self.emit_trace_log(
+            "entry_module",
+            serde_json::json!({
+                "kind": "entry_module",
+                "name": entry.name,
+                "ran_as_main": entry.ran_as_main,
+            }),
+        );
+    }
+
+    /// Record the full argv and working directory so the run can be reproduced.
+    fn emit_command_line(&mut self) {
+        let Some(command_line) = self.lifecycle.command_line() else {
+            return;
+        };
+        self.emit_trace_log(
+            "command_line",
+            serde_json::json!({
+                "kind": "command_line",
+                "argv": command_line.argv,
+                "cwd": command_line.cwd,
+            }),
+        );
+    }
+
     pub(super) fn mark_event(&mut self) {
         if suppress_events() {
             let _mute = ScopedMuteIoCapture::new();
@@ -291,7 +1111,39 @@ impl RuntimeTracer {
     }
 
     pub(super) fn exit_summary(&self) -> ExitSummary {
-        self.session_exit.summary()
+        ExitSummary {
+            duration_ms: self.lifecycle.duration_ms(),
+            ..self.session_exit.summary()
+        }
+    }
+
+    /// Record the session's wall-clock duration.
+    pub(super) fn emit_session_duration(&mut self, exit_summary: &ExitSummary) {
+        let Some(duration_ms) = exit_summary.duration_ms else {
+            return;
+        };
+        self.emit_trace_log(
+            &format!("{duration_ms} ms"),
+            serde_json::json!({
+                "kind": "session_duration",
+                "duration_ms": duration_ms,
+            }),
+        );
+    }
+
+    /// Record which source files produced the most call and step events.
+    pub(super) fn emit_path_summary(&mut self) {
+        let mut metadata = self.path_stats.summary_json();
+        metadata["kind"] = serde_json::json!("path_summary");
+        self.emit_trace_log("path_summary", metadata);
+    }
+
+    /// Record the filter counters, including the empty-trace flag, alongside
+    /// the path summary.
+    pub(super) fn emit_filter_summary(&mut self) {
+        let mut metadata = self.filter.summary_json();
+        metadata["kind"] = serde_json::json!("filter_summary");
+        self.emit_trace_log("filter_summary", metadata);
     }
 
     pub(super) fn evaluate_gate(
@@ -317,9 +1169,36 @@ impl RuntimeTracer {
         if !is_active {
             return Some(CallbackOutcome::Continue);
         }
+        self.note_thread(py);
         None
     }
 
+    /// Mark that the events that follow come from the calling thread when
+    /// another thread recorded the previous ones. Threads spawned after the
+    /// session started are picked up here on their first traced event.
+    pub(super) fn note_thread(&mut self, py: Python<'_>) {
+        let Some(switch) = self.thread_switches.enter_current() else {
+            return;
+        };
+        let thread = py
+            .import("threading")
+            .and_then(|threading| threading.call_method0("current_thread"))
+            .ok();
+        let attr = |name: &str| thread.as_ref().and_then(|thread| thread.getattr(name).ok());
+        let name: Option<String> = attr("name").and_then(|value| value.extract().ok());
+        let ident: Option<u64> = attr("ident").and_then(|value| value.extract().ok());
+        self.emit_trace_log(
+            "thread_switch",
+            serde_json::json!({
+                "kind": "thread_switch",
+                "name": name,
+                "ident": ident,
+                "first": switch.first,
+            }),
+        );
+        self.mark_event();
+    }
+
     pub(super) fn ensure_function_id(
         &mut self,
         py: Python<'_>,
@@ -338,6 +1217,24 @@ impl RuntimeTracer {
             Line(first_line as i64),
         );
         self.function_ids.insert(code.id(), function_id);
+        // The signature and docstring only enrich `functions.json`; a code
+        // object that resists introspection still gets its entry.
+        let params = parameter_names(py, code).unwrap_or_default();
+        let doc = docstring_summary(py, code).unwrap_or_default();
+        self.function_table.record(
+            function_id.0,
+            name.as_str(),
+            filename,
+            first_line,
+            params,
+            doc,
+        );
+        if self.capture_annotations {
+            self.function_table.set_annotations(
+                function_id.0,
+                declared_annotations(py, code).unwrap_or_default(),
+            );
+        }
         Ok(function_id)
     }
 
@@ -349,16 +1246,24 @@ impl RuntimeTracer {
         self.filter.decide(py, code)
     }
 
-    fn function_name(&self, py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<String> {
+    fn function_name(&mut self, py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<String> {
         let qualname = code.qualname(py)?;
-        if qualname == "<module>" {
-            Ok(self
-                .derive_module_name(py, code)
-                .map(|module| format!("<{module}>"))
-                .unwrap_or_else(|| qualname.to_string()))
-        } else {
-            Ok(qualname.to_string())
+        if qualname != "<module>" {
+            return Ok(qualname.to_string());
         }
+        // Derivation walks `sys.path` and the filesystem, so the outcome is
+        // memoised per code object, including when no name could be found.
+        let module = match self.module_names.get(code.id()) {
+            Some(cached) => cached,
+            None => {
+                let derived = self.derive_module_name(py, code);
+                self.module_names.insert(code.id(), derived.clone());
+                derived
+            }
+        };
+        Ok(module
+            .map(|module| format!("<{module}>"))
+            .unwrap_or_else(|| qualname.to_string()))
     }
 
     fn derive_module_name(&self, py: Python<'_>, code: &CodeObjectWrapper) -> Option<String> {
@@ -402,2273 +1307,26 @@ impl RuntimeTracer {
     }
 }
 
+/// Values whose identity carries no aliasing information.
+fn is_primitive(value: &Bound<'_, PyAny>) -> bool {
+    value.is_none()
+        || value.is_instance_of::<PyBool>()
+        || value.is_instance_of::<PyInt>()
+        || value.is_instance_of::<PyFloat>()
+        || value.is_instance_of::<PyString>()
+        || value.is_instance_of::<PyBytes>()
+}
+
 #[cfg(test)]
 impl RuntimeTracer {
-    fn function_name_for_test(&self, py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<String> {
+    fn function_name_for_test(
+        &mut self,
+        py: Python<'_>,
+        code: &CodeObjectWrapper,
+    ) -> PyResult<String> {
         self.function_name(py, code)
     }
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::monitoring::{CallbackOutcome, Tracer};
-    use crate::policy;
-    use crate::runtime::tracer::filtering::is_real_filename;
-    use crate::trace_filter::config::TraceFilterConfig;
-    use codetracer_trace_types::{FullValueRecord, StepRecord, TraceLowLevelEvent, ValueRecord};
-    use pyo3::types::{PyAny, PyCode, PyModule};
-    use pyo3::wrap_pyfunction;
-    use serde::Deserialize;
-    use std::cell::Cell;
-    use std::collections::BTreeMap;
-    use std::ffi::CString;
-    use std::fs;
-    use std::path::{Path, PathBuf};
-    use std::sync::Arc;
-    use std::thread;
-
-    thread_local! {
-        static ACTIVE_TRACER: Cell<*mut RuntimeTracer> = Cell::new(std::ptr::null_mut());
-        static LAST_OUTCOME: Cell<Option<CallbackOutcome>> = Cell::new(None);
-    }
-
-    const BUILTIN_TRACE_FILTER: &str =
-        include_str!("../../../resources/trace_filters/builtin_default.toml");
-
-    struct ScopedTracer;
-
-    impl ScopedTracer {
-        fn new(tracer: &mut RuntimeTracer) -> Self {
-            let ptr = tracer as *mut _;
-            ACTIVE_TRACER.with(|cell| cell.set(ptr));
-            ScopedTracer
-        }
-    }
-
-    impl Drop for ScopedTracer {
-        fn drop(&mut self) {
-            ACTIVE_TRACER.with(|cell| cell.set(std::ptr::null_mut()));
-        }
-    }
-
-    fn last_outcome() -> Option<CallbackOutcome> {
-        LAST_OUTCOME.with(|cell| cell.get())
-    }
-
-    fn reset_policy(_py: Python<'_>) {
-        policy::configure_policy_py(
-            Some("abort"),
-            Some(false),
-            Some(false),
-            None,
-            None,
-            Some(false),
-            None,
-            None,
-            Some(false),
-            Some(false),
-        )
-        .expect("reset recorder policy");
-    }
-
-    #[test]
-    fn detects_real_filenames() {
-        assert!(is_real_filename("example.py"));
-        assert!(is_real_filename(" /tmp/module.py "));
-        assert!(is_real_filename("src/<tricky>.py"));
-        assert!(!is_real_filename("<string>"));
-        assert!(!is_real_filename("  <stdin>  "));
-        assert!(!is_real_filename("<frozen importlib._bootstrap>"));
-    }
-
-    #[test]
-    fn skips_synthetic_filename_events() {
-        Python::with_gil(|py| {
-            let mut tracer = RuntimeTracer::new(
-                "test.py",
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            ensure_test_module(py);
-            let script = format!("{PRELUDE}\nsnapshot()\n");
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let script_c = CString::new(script).expect("script contains nul byte");
-                py.run(script_c.as_c_str(), None, None)
-                    .expect("execute synthetic script");
-            }
-            assert!(
-                tracer.writer.events().is_empty(),
-                "expected no events for synthetic filename"
-            );
-            let outcome = last_outcome();
-            assert!(
-                matches!(
-                    outcome,
-                    Some(CallbackOutcome::DisableLocation | CallbackOutcome::Continue)
-                ),
-                "expected DisableLocation or Continue (when CPython refuses to disable an event), got {:?}",
-                outcome
-            );
-
-            let compile_fn = py
-                .import("builtins")
-                .expect("import builtins")
-                .getattr("compile")
-                .expect("fetch compile");
-            let binding = compile_fn
-                .call1(("pass", "<string>", "exec"))
-                .expect("compile code object");
-            let code_obj = binding.downcast::<PyCode>().expect("downcast code object");
-            let wrapper = CodeObjectWrapper::new(py, &code_obj);
-            assert_eq!(
-                tracer.should_trace_code(py, &wrapper),
-                TraceDecision::SkipAndDisable
-            );
-        });
-    }
-
-    #[test]
-    fn traces_real_file_events() {
-        let snapshots = run_traced_script("snapshot()\n");
-        assert!(
-            !snapshots.is_empty(),
-            "expected snapshots for real file execution"
-        );
-        assert_eq!(last_outcome(), Some(CallbackOutcome::Continue));
-    }
-
-    #[test]
-    fn callbacks_do_not_import_sys_monitoring() {
-        let body = r#"
-import builtins
-_orig_import = builtins.__import__
-
-def guard(name, *args, **kwargs):
-    if name == "sys.monitoring":
-        raise RuntimeError("callback imported sys.monitoring")
-    return _orig_import(name, *args, **kwargs)
-
-builtins.__import__ = guard
-try:
-    snapshot()
-finally:
-    builtins.__import__ = _orig_import
-"#;
-        let snapshots = run_traced_script(body);
-        assert!(
-            !snapshots.is_empty(),
-            "expected snapshots when import guard active"
-        );
-        assert_eq!(last_outcome(), Some(CallbackOutcome::Continue));
-    }
-
-    #[test]
-    fn records_return_values_and_deactivates_activation() {
-        Python::with_gil(|py| {
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("create temp dir");
-            let script_path = tmp.path().join("activation_script.py");
-            let script = format!(
-                "{PRELUDE}\n\n\
-def compute():\n    emit_return(\"tail\")\n    return \"tail\"\n\n\
-result = compute()\n"
-            );
-            std::fs::write(&script_path, &script).expect("write script");
-
-            let program = script_path.to_string_lossy().into_owned();
-            let mut tracer = RuntimeTracer::new(
-                &program,
-                &[],
-                TraceEventsFileFormat::Json,
-                Some(script_path.as_path()),
-                None,
-                false,
-            );
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute test script");
-            }
-
-            let returns: Vec<SimpleValue> = tracer
-                .writer
-                .events()
-                .iter()
-                .filter_map(|event| match event {
-                    TraceLowLevelEvent::Return(record) => {
-                        Some(SimpleValue::from_value(&record.return_value))
-                    }
-                    _ => None,
-                })
-                .collect();
-
-            assert!(
-                returns.contains(&SimpleValue::String("tail".to_string())),
-                "expected recorded string return, got {:?}",
-                returns
-            );
-            assert_eq!(last_outcome(), Some(CallbackOutcome::Continue));
-            assert!(!tracer.lifecycle.activation().is_active());
-        });
-    }
-
-    #[test]
-    fn line_snapshot_store_tracks_last_step() {
-        Python::with_gil(|py| {
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("create temp dir");
-            let script_path = tmp.path().join("snapshot_script.py");
-            let script = format!("{PRELUDE}\n\nsnapshot()\n");
-            std::fs::write(&script_path, &script).expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                "snapshot_script.py",
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            let store = tracer.line_snapshot_store();
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute snapshot script");
-            }
-
-            let last_step: StepRecord = tracer
-                .writer
-                .events()
-                .iter()
-                .rev()
-                .find_map(|event| match event {
-                    TraceLowLevelEvent::Step(step) => Some(step.clone()),
-                    _ => None,
-                })
-                .expect("expected one step event");
-
-            let thread_id = thread::current().id();
-            let snapshot = store
-                .snapshot_for_thread(thread_id)
-                .expect("snapshot should be recorded");
-
-            assert_eq!(snapshot.line(), last_step.line);
-            assert_eq!(snapshot.path_id(), last_step.path_id);
-            assert!(snapshot.captured_at().elapsed().as_secs_f64() >= 0.0);
-        });
-    }
-
-    #[derive(Debug, Deserialize)]
-    struct IoMetadata {
-        stream: String,
-        path_id: Option<usize>,
-        line: Option<i64>,
-        flags: Vec<String>,
-    }
-
-    #[test]
-    fn io_capture_records_python_and_native_output() {
-        Python::with_gil(|py| {
-            reset_policy(py);
-            policy::configure_policy_py(
-                Some("abort"),
-                Some(false),
-                Some(false),
-                None,
-                None,
-                Some(false),
-                Some(true),
-                Some(false),
-                Some(false),
-                Some(false),
-            )
-            .expect("enable io capture proxies");
-
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("create temp dir");
-            let script_path = tmp.path().join("io_script.py");
-            let script = format!(
-                "{PRELUDE}\n\nprint('python out')\nfrom ctypes import pythonapi, c_char_p\npythonapi.PySys_WriteStdout(c_char_p(b'native out\\n'))\n"
-            );
-            std::fs::write(&script_path, &script).expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                script_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
-            tracer.begin(&outputs, 1).expect("begin tracer");
-            tracer
-                .install_io_capture(py, &policy::policy_snapshot())
-                .expect("install io capture");
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute io script");
-            }
-
-            tracer.finish(py).expect("finish tracer");
-
-            let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
-                .writer
-                .events()
-                .iter()
-                .filter_map(|event| match event {
-                    TraceLowLevelEvent::Event(record) => {
-                        let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
-                        Some((metadata, record.content.as_bytes().to_vec()))
-                    }
-                    _ => None,
-                })
-                .collect();
-
-            assert!(io_events
-                .iter()
-                .any(|(meta, payload)| meta.stream == "stdout"
-                    && String::from_utf8_lossy(payload).contains("python out")));
-            assert!(io_events
-                .iter()
-                .any(|(meta, payload)| meta.stream == "stdout"
-                    && String::from_utf8_lossy(payload).contains("native out")));
-            assert!(io_events.iter().all(|(meta, _)| {
-                if meta.stream == "stdout" {
-                    meta.path_id.is_some() && meta.line.is_some()
-                } else {
-                    true
-                }
-            }));
-            assert!(io_events
-                .iter()
-                .filter(|(meta, _)| meta.stream == "stdout")
-                .any(|(meta, _)| meta.flags.iter().any(|flag| flag == "newline")));
-
-            reset_policy(py);
-        });
-    }
-
-    #[cfg(unix)]
-    #[test]
-    fn fd_mirror_captures_os_write_payloads() {
-        Python::with_gil(|py| {
-            reset_policy(py);
-            policy::configure_policy_py(
-                Some("abort"),
-                Some(false),
-                Some(false),
-                None,
-                None,
-                Some(false),
-                Some(true),
-                Some(true),
-                Some(false),
-                Some(false),
-            )
-            .expect("enable io capture with fd fallback");
-
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("tempdir");
-            let script_path = tmp.path().join("fd_mirror.py");
-            std::fs::write(
-                &script_path,
-                format!(
-                    "{PRELUDE}\nimport os\nprint('proxy line')\nos.write(1, b'fd stdout\\n')\nos.write(2, b'fd stderr\\n')\n"
-                ),
-            )
-            .expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                script_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
-            tracer.begin(&outputs, 1).expect("begin tracer");
-            tracer
-                .install_io_capture(py, &policy::policy_snapshot())
-                .expect("install io capture");
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute fd script");
-            }
-
-            tracer.finish(py).expect("finish tracer");
-
-            let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
-                .writer
-                .events()
-                .iter()
-                .filter_map(|event| match event {
-                    TraceLowLevelEvent::Event(record) => {
-                        let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
-                        Some((metadata, record.content.as_bytes().to_vec()))
-                    }
-                    _ => None,
-                })
-                .collect();
-
-            let stdout_mirror = io_events.iter().find(|(meta, _)| {
-                meta.stream == "stdout" && meta.flags.iter().any(|flag| flag == "mirror")
-            });
-            assert!(
-                stdout_mirror.is_some(),
-                "expected mirror event for stdout: {:?}",
-                io_events
-            );
-            let stdout_payload = &stdout_mirror.expect("stdout mirror event present").1;
-            assert!(
-                String::from_utf8_lossy(stdout_payload).contains("fd stdout"),
-                "mirror stdout payload missing expected text"
-            );
-
-            let stderr_mirror = io_events.iter().find(|(meta, _)| {
-                meta.stream == "stderr" && meta.flags.iter().any(|flag| flag == "mirror")
-            });
-            assert!(
-                stderr_mirror.is_some(),
-                "expected mirror event for stderr: {:?}",
-                io_events
-            );
-            let stderr_payload = &stderr_mirror.expect("stderr mirror event present").1;
-            assert!(
-                String::from_utf8_lossy(stderr_payload).contains("fd stderr"),
-                "mirror stderr payload missing expected text"
-            );
-
-            assert!(io_events.iter().any(|(meta, payload)| {
-                meta.stream == "stdout"
-                    && !meta.flags.iter().any(|flag| flag == "mirror")
-                    && String::from_utf8_lossy(payload).contains("proxy line")
-            }));
-
-            reset_policy(py);
-        });
-    }
-
-    #[cfg(unix)]
-    #[test]
-    fn fd_mirror_disabled_does_not_capture_os_write() {
-        Python::with_gil(|py| {
-            reset_policy(py);
-            policy::configure_policy_py(
-                Some("abort"),
-                Some(false),
-                Some(false),
-                None,
-                None,
-                Some(false),
-                Some(true),
-                Some(false),
-                Some(false),
-                Some(false),
-            )
-            .expect("enable proxies without fd fallback");
-
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("tempdir");
-            let script_path = tmp.path().join("fd_disabled.py");
-            std::fs::write(
-                &script_path,
-                format!(
-                    "{PRELUDE}\nimport os\nprint('proxy line')\nos.write(1, b'fd stdout\\n')\nos.write(2, b'fd stderr\\n')\n"
-                ),
-            )
-            .expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                script_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
-            tracer.begin(&outputs, 1).expect("begin tracer");
-            tracer
-                .install_io_capture(py, &policy::policy_snapshot())
-                .expect("install io capture");
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute fd script");
-            }
-
-            tracer.finish(py).expect("finish tracer");
-
-            let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
-                .writer
-                .events()
-                .iter()
-                .filter_map(|event| match event {
-                    TraceLowLevelEvent::Event(record) => {
-                        let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
-                        Some((metadata, record.content.as_bytes().to_vec()))
-                    }
-                    _ => None,
-                })
-                .collect();
-
-            assert!(
-                !io_events
-                    .iter()
-                    .any(|(meta, _)| meta.flags.iter().any(|flag| flag == "mirror")),
-                "mirror events should not be present when fallback disabled"
-            );
-
-            assert!(
-                !io_events.iter().any(|(_, payload)| {
-                    String::from_utf8_lossy(payload).contains("fd stdout")
-                        || String::from_utf8_lossy(payload).contains("fd stderr")
-                }),
-                "native os.write payload unexpectedly captured without fallback"
-            );
-
-            assert!(io_events.iter().any(|(meta, payload)| {
-                meta.stream == "stdout" && String::from_utf8_lossy(payload).contains("proxy line")
-            }));
-
-            reset_policy(py);
-        });
-    }
-
-    #[pyfunction]
-    fn capture_py_start(py: Python<'_>, code: Bound<'_, PyCode>, offset: i32) -> PyResult<()> {
-        ffi::wrap_pyfunction("test_capture_py_start", || {
-            ACTIVE_TRACER.with(|cell| -> PyResult<()> {
-                let ptr = cell.get();
-                if ptr.is_null() {
-                    panic!("No active RuntimeTracer for capture_py_start");
-                }
-                unsafe {
-                    let tracer = &mut *ptr;
-                    let wrapper = CodeObjectWrapper::new(py, &code);
-                    match tracer.on_py_start(py, &wrapper, offset) {
-                        Ok(outcome) => {
-                            LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
-                            Ok(())
-                        }
-                        Err(err) => Err(err),
-                    }
-                }
-            })?;
-            Ok(())
-        })
-    }
-
-    #[pyfunction]
-    fn capture_line(py: Python<'_>, code: Bound<'_, PyCode>, lineno: u32) -> PyResult<()> {
-        ffi::wrap_pyfunction("test_capture_line", || {
-            ACTIVE_TRACER.with(|cell| -> PyResult<()> {
-                let ptr = cell.get();
-                if ptr.is_null() {
-                    panic!("No active RuntimeTracer for capture_line");
-                }
-                unsafe {
-                    let tracer = &mut *ptr;
-                    let wrapper = CodeObjectWrapper::new(py, &code);
-                    match tracer.on_line(py, &wrapper, lineno) {
-                        Ok(outcome) => {
-                            LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
-                            Ok(())
-                        }
-                        Err(err) => Err(err),
-                    }
-                }
-            })?;
-            Ok(())
-        })
-    }
-
-    #[pyfunction]
-    fn capture_return_event(
-        py: Python<'_>,
-        code: Bound<'_, PyCode>,
-        value: Bound<'_, PyAny>,
-    ) -> PyResult<()> {
-        ffi::wrap_pyfunction("test_capture_return_event", || {
-            ACTIVE_TRACER.with(|cell| -> PyResult<()> {
-                let ptr = cell.get();
-                if ptr.is_null() {
-                    panic!("No active RuntimeTracer for capture_return_event");
-                }
-                unsafe {
-                    let tracer = &mut *ptr;
-                    let wrapper = CodeObjectWrapper::new(py, &code);
-                    match tracer.on_py_return(py, &wrapper, 0, &value) {
-                        Ok(outcome) => {
-                            LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
-                            Ok(())
-                        }
-                        Err(err) => Err(err),
-                    }
-                }
-            })?;
-            Ok(())
-        })
-    }
-
-    const PRELUDE: &str = r#"
-import inspect
-from test_tracer import capture_line, capture_return_event, capture_py_start
-
-def snapshot(line=None):
-    frame = inspect.currentframe().f_back
-    lineno = frame.f_lineno if line is None else line
-    capture_line(frame.f_code, lineno)
-
-def snap(value):
-    frame = inspect.currentframe().f_back
-    capture_line(frame.f_code, frame.f_lineno)
-    return value
-
-def emit_return(value):
-    frame = inspect.currentframe().f_back
-    capture_return_event(frame.f_code, value)
-    return value
-
-def start_call():
-    frame = inspect.currentframe().f_back
-    capture_py_start(frame.f_code, frame.f_lasti)
-"#;
-
-    #[derive(Debug, Clone, PartialEq)]
-    enum SimpleValue {
-        None,
-        Bool(bool),
-        Int(i64),
-        String(String),
-        Tuple(Vec<SimpleValue>),
-        Sequence(Vec<SimpleValue>),
-        Raw(String),
-    }
-
-    impl SimpleValue {
-        fn from_value(value: &ValueRecord) -> Self {
-            match value {
-                ValueRecord::None { .. } => SimpleValue::None,
-                ValueRecord::Bool { b, .. } => SimpleValue::Bool(*b),
-                ValueRecord::Int { i, .. } => SimpleValue::Int(*i),
-                ValueRecord::String { text, .. } => SimpleValue::String(text.clone()),
-                ValueRecord::Tuple { elements, .. } => {
-                    SimpleValue::Tuple(elements.iter().map(SimpleValue::from_value).collect())
-                }
-                ValueRecord::Sequence { elements, .. } => {
-                    SimpleValue::Sequence(elements.iter().map(SimpleValue::from_value).collect())
-                }
-                ValueRecord::Raw { r, .. } => SimpleValue::Raw(r.clone()),
-                ValueRecord::Error { msg, .. } => SimpleValue::Raw(msg.clone()),
-                other => SimpleValue::Raw(format!("{other:?}")),
-            }
-        }
-    }
-
-    #[derive(Debug)]
-    struct Snapshot {
-        line: i64,
-        vars: BTreeMap<String, SimpleValue>,
-    }
-
-    fn collect_snapshots(events: &[TraceLowLevelEvent]) -> Vec<Snapshot> {
-        let mut names: Vec<String> = Vec::new();
-        let mut snapshots: Vec<Snapshot> = Vec::new();
-        let mut current: Option<Snapshot> = None;
-        for event in events {
-            match event {
-                TraceLowLevelEvent::VariableName(name) => names.push(name.clone()),
-                TraceLowLevelEvent::Step(step) => {
-                    if let Some(snapshot) = current.take() {
-                        snapshots.push(snapshot);
-                    }
-                    current = Some(Snapshot {
-                        line: step.line.0,
-                        vars: BTreeMap::new(),
-                    });
-                }
-                TraceLowLevelEvent::Value(FullValueRecord { variable_id, value }) => {
-                    if let Some(snapshot) = current.as_mut() {
-                        let index = variable_id.0;
-                        let name = names
-                            .get(index)
-                            .cloned()
-                            .unwrap_or_else(|| panic!("Missing variable name for id {}", index));
-                        snapshot.vars.insert(name, SimpleValue::from_value(value));
-                    }
-                }
-                _ => {}
-            }
-        }
-        if let Some(snapshot) = current.take() {
-            snapshots.push(snapshot);
-        }
-        snapshots
-    }
-
-    fn ensure_test_module(py: Python<'_>) {
-        let module = PyModule::new(py, "test_tracer").expect("create module");
-        module
-            .add_function(
-                wrap_pyfunction!(capture_py_start, &module).expect("wrap capture_py_start"),
-            )
-            .expect("add py_start capture function");
-        module
-            .add_function(wrap_pyfunction!(capture_line, &module).expect("wrap capture_line"))
-            .expect("add line capture function");
-        module
-            .add_function(
-                wrap_pyfunction!(capture_return_event, &module).expect("wrap capture_return_event"),
-            )
-            .expect("add return capture function");
-        py.import("sys")
-            .expect("import sys")
-            .getattr("modules")
-            .expect("modules attr")
-            .set_item("test_tracer", module)
-            .expect("insert module");
-    }
-
-    fn run_traced_script(body: &str) -> Vec<Snapshot> {
-        Python::with_gil(|py| {
-            let mut tracer = RuntimeTracer::new(
-                "test.py",
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("create temp dir");
-            let script_path = tmp.path().join("script.py");
-            let script = format!("{PRELUDE}\n{body}");
-            std::fs::write(&script_path, &script).expect("write script");
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute test script");
-            }
-            collect_snapshots(tracer.writer.events())
-        })
-    }
-
-    fn write_filter(path: &Path, contents: &str) {
-        fs::write(path, contents.trim_start()).expect("write filter");
-    }
-
-    fn install_drop_everything_filter(project_root: &Path) -> PathBuf {
-        let filters_dir = project_root.join(".codetracer");
-        fs::create_dir(&filters_dir).expect("create .codetracer");
-        let drop_filter_path = filters_dir.join("drop-filter.toml");
-        write_filter(
-            &drop_filter_path,
-            r#"
-            [meta]
-            name = "drop-all"
-            version = 1
-
-            [scope]
-            default_exec = "trace"
-            default_value_action = "drop"
-            "#,
-        );
-        drop_filter_path
-    }
-
-    #[test]
-    fn trace_filter_redacts_values() {
-        Python::with_gil(|py| {
-            ensure_test_module(py);
-
-            let project = tempfile::tempdir().expect("project dir");
-            let project_root = project.path();
-            let filters_dir = project_root.join(".codetracer");
-            fs::create_dir(&filters_dir).expect("create .codetracer");
-            let filter_path = filters_dir.join("filters.toml");
-            write_filter(
-                &filter_path,
-                r#"
-                [meta]
-                name = "redact"
-                version = 1
-
-                [scope]
-                default_exec = "trace"
-                default_value_action = "allow"
-
-                [[scope.rules]]
-                selector = "pkg:app.sec"
-                exec = "trace"
-                value_default = "allow"
-
-                [[scope.rules.value_patterns]]
-                selector = "arg:password"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "local:password"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "local:secret"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "global:shared_secret"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "ret:literal:app.sec.sensitive"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "local:internal"
-                action = "drop"
-                "#,
-            );
-            let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
-            let engine = Arc::new(TraceFilterEngine::new(config));
-
-            let app_dir = project_root.join("app");
-            fs::create_dir_all(&app_dir).expect("create app dir");
-            let script_path = app_dir.join("sec.py");
-            let body = r#"
-shared_secret = "initial"
-
-def sensitive(password):
-    secret = "token"
-    internal = "hidden"
-    public = "visible"
-    globals()['shared_secret'] = password
-    snapshot()
-    emit_return(password)
-    return password
-
-sensitive("s3cr3t")
-"#;
-            let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
-            fs::write(&script_path, script).expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                script_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                Some(engine),
-                false,
-            );
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
-                    project_root.display(),
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute filtered script");
-            }
-
-            let mut variable_names: Vec<String> = Vec::new();
-            for event in tracer.writer.events() {
-                if let TraceLowLevelEvent::VariableName(name) = event {
-                    variable_names.push(name.clone());
-                }
-            }
-            assert!(
-                !variable_names.iter().any(|name| name == "internal"),
-                "internal variable should not be recorded"
-            );
-
-            let password_index = variable_names
-                .iter()
-                .position(|name| name == "password")
-                .expect("password variable recorded");
-            let password_value = tracer
-                .writer
-                .events()
-                .iter()
-                .find_map(|event| match event {
-                    TraceLowLevelEvent::Value(record) if record.variable_id.0 == password_index => {
-                        Some(record.value.clone())
-                    }
-                    _ => None,
-                })
-                .expect("password value recorded");
-            match password_value {
-                ValueRecord::Error { ref msg, .. } => assert_eq!(msg, "<redacted>"),
-                ref other => panic!("expected password argument redacted, got {other:?}"),
-            }
-
-            let snapshots = collect_snapshots(tracer.writer.events());
-            let snapshot = find_snapshot_with_vars(
-                &snapshots,
-                &["secret", "public", "shared_secret", "password"],
-            );
-            assert_var(
-                snapshot,
-                "secret",
-                SimpleValue::Raw("<redacted>".to_string()),
-            );
-            assert_var(
-                snapshot,
-                "public",
-                SimpleValue::String("visible".to_string()),
-            );
-            assert_var(
-                snapshot,
-                "shared_secret",
-                SimpleValue::Raw("<redacted>".to_string()),
-            );
-            assert_var(
-                snapshot,
-                "password",
-                SimpleValue::Raw("<redacted>".to_string()),
-            );
-            assert_no_variable(&snapshots, "internal");
-
-            let return_record = tracer
-                .writer
-                .events()
-                .iter()
-                .find_map(|event| match event {
-                    TraceLowLevelEvent::Return(record) => Some(record.clone()),
-                    _ => None,
-                })
-                .expect("return record");
-
-            match return_record.return_value {
-                ValueRecord::Error { ref msg, .. } => assert_eq!(msg, "<redacted>"),
-                ref other => panic!("expected redacted return value, got {other:?}"),
-            }
-        });
-    }
-
-    #[test]
-    fn module_import_records_module_name() {
-        Python::with_gil(|py| {
-            let project = tempfile::tempdir().expect("project dir");
-            let pkg_root = project.path().join("lib");
-            let pkg_dir = pkg_root.join("my_pkg");
-            fs::create_dir_all(&pkg_dir).expect("create package dir");
-            let module_path = pkg_dir.join("mod.py");
-            fs::write(&module_path, "value = 1\n").expect("write module file");
-
-            let sys = py.import("sys").expect("import sys");
-            let sys_path = sys.getattr("path").expect("sys.path");
-            sys_path
-                .call_method1("insert", (0, pkg_root.to_string_lossy().as_ref()))
-                .expect("insert temp root");
-
-            let tracer = RuntimeTracer::new(
-                "runner.py",
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-
-            let builtins = py.import("builtins").expect("builtins");
-            let compile = builtins.getattr("compile").expect("compile builtin");
-            let code_obj: Bound<'_, PyCode> = compile
-                .call1((
-                    "value = 1\n",
-                    module_path.to_string_lossy().as_ref(),
-                    "exec",
-                ))
-                .expect("compile module code")
-                .downcast_into()
-                .expect("PyCode");
-
-            let wrapper = CodeObjectWrapper::new(py, &code_obj);
-            let resolved = tracer
-                .function_name_for_test(py, &wrapper)
-                .expect("derive function name");
-
-            assert_eq!(resolved, "<my_pkg.mod>");
-
-            sys_path.call_method1("pop", (0,)).expect("pop temp root");
-        });
-    }
-
-    #[test]
-    fn user_drop_default_overrides_builtin_allowance() {
-        Python::with_gil(|py| {
-            ensure_test_module(py);
-
-            let project = tempfile::tempdir().expect("project dir");
-            let project_root = project.path();
-            let drop_filter_path = install_drop_everything_filter(project_root);
-
-            let config = TraceFilterConfig::from_inline_and_paths(
-                &[("builtin-default", BUILTIN_TRACE_FILTER)],
-                &[drop_filter_path.clone()],
-            )
-            .expect("load filter chain");
-            let engine = Arc::new(TraceFilterEngine::new(config));
-
-            let app_dir = project_root.join("app");
-            fs::create_dir_all(&app_dir).expect("create app dir");
-            let script_path = app_dir.join("dropper.py");
-            let body = r#"
-def dropper():
-    secret = "token"
-    public = 42
-    snapshot()
-    emit_return(secret)
-    return secret
-
-dropper()
-"#;
-            let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
-            fs::write(&script_path, script).expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                script_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                Some(engine),
-                false,
-            );
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
-                    project_root.display(),
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute dropper script");
-            }
-
-            let mut variable_names: Vec<String> = Vec::new();
-            let mut return_values: Vec<ValueRecord> = Vec::new();
-            for event in tracer.writer.events() {
-                match event {
-                    TraceLowLevelEvent::VariableName(name) => variable_names.push(name.clone()),
-                    TraceLowLevelEvent::Return(record) => {
-                        return_values.push(record.return_value.clone())
-                    }
-                    _ => {}
-                }
-            }
-            assert!(
-                variable_names.is_empty(),
-                "expected no variables captured, found {:?}",
-                variable_names
-            );
-            assert_eq!(
-                return_values.len(),
-                1,
-                "return event should remain balanced"
-            );
-            match &return_values[0] {
-                ValueRecord::Error { msg, .. } => assert_eq!(msg, "<dropped>"),
-                other => panic!("expected dropped sentinel return value, got {other:?}"),
-            }
-        });
-    }
-
-    #[test]
-    fn drop_filters_keep_call_return_pairs_balanced() {
-        Python::with_gil(|py| {
-            ensure_test_module(py);
-
-            let project = tempfile::tempdir().expect("project dir");
-            let project_root = project.path();
-            let drop_filter_path = install_drop_everything_filter(project_root);
-
-            let config = TraceFilterConfig::from_inline_and_paths(
-                &[("builtin-default", BUILTIN_TRACE_FILTER)],
-                &[drop_filter_path.clone()],
-            )
-            .expect("load filter chain");
-            let engine = Arc::new(TraceFilterEngine::new(config));
-
-            let app_dir = project_root.join("app");
-            fs::create_dir_all(&app_dir).expect("create app dir");
-            let script_path = app_dir.join("classes.py");
-            let body = r#"
-def initializer(label):
-    start_call()
-    return emit_return(label.upper())
-
-class Alpha:
-    TOKEN = initializer("alpha")
-
-class Beta:
-    TOKEN = initializer("beta")
-
-class Gamma:
-    TOKEN = initializer("gamma")
-
-initializer("omega")
-"#;
-            let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
-            fs::write(&script_path, script).expect("write script");
-
-            let mut tracer = RuntimeTracer::new(
-                script_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                Some(engine),
-                false,
-            );
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
-                    project_root.display(),
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute classes script");
-            }
-
-            let mut call_count = 0usize;
-            let mut return_count = 0usize;
-            for event in tracer.writer.events() {
-                match event {
-                    TraceLowLevelEvent::Call(_) => call_count += 1,
-                    TraceLowLevelEvent::Return(_) => return_count += 1,
-                    _ => {}
-                }
-            }
-            assert!(
-                call_count >= 4,
-                "expected at least four call events, saw {call_count}"
-            );
-            assert_eq!(
-                call_count, return_count,
-                "drop filters must keep call/return pairs balanced"
-            );
-        });
-    }
-
-    #[test]
-    fn finish_emits_toplevel_return_with_exit_code() {
-        Python::with_gil(|py| {
-            reset_policy(py);
-
-            let script_dir = tempfile::tempdir().expect("script dir");
-            let program_path = script_dir.path().join("program.py");
-            std::fs::write(&program_path, "print('hi')\n").expect("write program");
-
-            let outputs_dir = tempfile::tempdir().expect("outputs dir");
-            let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
-
-            let mut tracer = RuntimeTracer::new(
-                program_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            tracer.begin(&outputs, 1).expect("begin tracer");
-            tracer.record_exit_status(Some(7));
-
-            tracer.finish(py).expect("finish tracer");
-
-            let mut exit_value: Option<ValueRecord> = None;
-            for event in tracer.writer.events() {
-                if let TraceLowLevelEvent::Return(record) = event {
-                    exit_value = Some(record.return_value.clone());
-                }
-            }
-
-            let exit_value = exit_value.expect("expected toplevel return value");
-            match exit_value {
-                ValueRecord::Int { i, .. } => assert_eq!(i, 7),
-                other => panic!("expected integer exit value, got {other:?}"),
-            }
-        });
-    }
-
-    // FIXME(follow-up #254 phase 2): rewrite using `MetaDatReader` once the
-    // crate exposes filter-provenance accessors.  The original assertion
-    // read the trace_filter chain from the `trace_metadata.json` sidecar,
-    // which the v3 CTFS rollout retired.  Filter provenance is still
-    // recorded — it just lives inside `meta.dat` now (see TF-M7 spec § 7).
-    #[ignore]
-    #[test]
-    fn trace_filter_metadata_includes_summary() {
-        Python::with_gil(|py| {
-            reset_policy(py);
-            ensure_test_module(py);
-
-            let project = tempfile::tempdir().expect("project dir");
-            let project_root = project.path();
-            let filters_dir = project_root.join(".codetracer");
-            fs::create_dir(&filters_dir).expect("create .codetracer");
-            let filter_path = filters_dir.join("filters.toml");
-            write_filter(
-                &filter_path,
-                r#"
-                [meta]
-                name = "redact"
-                version = 1
-
-                [scope]
-                default_exec = "trace"
-                default_value_action = "allow"
-
-                [[scope.rules]]
-                selector = "pkg:app.sec"
-                exec = "trace"
-                value_default = "allow"
-
-                [[scope.rules.value_patterns]]
-                selector = "arg:password"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "local:password"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "local:secret"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "global:shared_secret"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "ret:literal:app.sec.sensitive"
-                action = "redact"
-
-                [[scope.rules.value_patterns]]
-                selector = "local:internal"
-                action = "drop"
-                "#,
-            );
-            let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
-            let engine = Arc::new(TraceFilterEngine::new(config));
-
-            let app_dir = project_root.join("app");
-            fs::create_dir_all(&app_dir).expect("create app dir");
-            let script_path = app_dir.join("sec.py");
-            let body = r#"
-shared_secret = "initial"
-
-def sensitive(password):
-    secret = "token"
-    internal = "hidden"
-    public = "visible"
-    globals()['shared_secret'] = password
-    snapshot()
-    emit_return(password)
-    return password
-
-sensitive("s3cr3t")
-"#;
-            let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
-            fs::write(&script_path, script).expect("write script");
-
-            let outputs_dir = tempfile::tempdir().expect("outputs dir");
-            let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
-
-            let program = script_path.to_string_lossy().into_owned();
-            let mut tracer = RuntimeTracer::new(
-                &program,
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                Some(engine),
-                false,
-            );
-            tracer.begin(&outputs, 1).expect("begin tracer");
-
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
-                    project_root.display(),
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute script");
-            }
-
-            tracer.finish(py).expect("finish tracer");
-
-            // Filter chain provenance is now embedded in `meta.dat` inside
-            // the CTFS container (TF-M7 spec § 7).  This test is gated with
-            // `#[ignore]` above until the `MetaDatReader` exposes filter-
-            // provenance accessors; the original assertions read the
-            // retired `trace_metadata.json` sidecar.
-            let _ = outputs;
-        });
-    }
-
-    fn assert_var(snapshot: &Snapshot, name: &str, expected: SimpleValue) {
-        let actual = snapshot
-            .vars
-            .get(name)
-            .unwrap_or_else(|| panic!("{name} missing at line {}", snapshot.line));
-        assert_eq!(
-            actual, &expected,
-            "Unexpected value for {name} at line {}",
-            snapshot.line
-        );
-    }
-
-    fn find_snapshot_with_vars<'a>(snapshots: &'a [Snapshot], names: &[&str]) -> &'a Snapshot {
-        snapshots
-            .iter()
-            .find(|snap| names.iter().all(|n| snap.vars.contains_key(*n)))
-            .unwrap_or_else(|| panic!("No snapshot containing variables {:?}", names))
-    }
-
-    fn assert_no_variable(snapshots: &[Snapshot], name: &str) {
-        if snapshots.iter().any(|snap| snap.vars.contains_key(name)) {
-            panic!("Variable {name} unexpectedly captured");
-        }
-    }
-
-    #[test]
-    fn captures_simple_function_locals() {
-        let snapshots = run_traced_script(
-            r#"
-def simple_function(x):
-    snapshot()
-    a = 1
-    snapshot()
-    b = a + x
-    snapshot()
-    return a, b
-
-simple_function(5)
-"#,
-        );
-
-        assert_var(&snapshots[0], "x", SimpleValue::Int(5));
-        assert!(!snapshots[0].vars.contains_key("a"));
-        assert_var(&snapshots[1], "a", SimpleValue::Int(1));
-        assert_var(&snapshots[2], "b", SimpleValue::Int(6));
-    }
-
-    #[test]
-    fn captures_closure_variables() {
-        let snapshots = run_traced_script(
-            r#"
-def outer_func(x):
-    snapshot()
-    y = 1
-    snapshot()
-    def inner_func(z):
-        nonlocal y
-        snapshot()
-        w = x + y + z
-        snapshot()
-        y = w
-        snapshot()
-        return w
-    total = inner_func(5)
-    snapshot()
-    return y, total
-
-result = outer_func(2)
-"#,
-        );
-
-        let inner_entry = find_snapshot_with_vars(&snapshots, &["x", "y", "z"]);
-        assert_var(inner_entry, "x", SimpleValue::Int(2));
-        assert_var(inner_entry, "y", SimpleValue::Int(1));
-
-        let w_snapshot = find_snapshot_with_vars(&snapshots, &["w", "x", "y", "z"]);
-        assert_var(w_snapshot, "w", SimpleValue::Int(8));
-
-        let outer_after = find_snapshot_with_vars(&snapshots, &["total", "y"]);
-        assert_var(outer_after, "total", SimpleValue::Int(8));
-        assert_var(outer_after, "y", SimpleValue::Int(8));
-    }
-
-    #[test]
-    fn captures_globals() {
-        let snapshots = run_traced_script(
-            r#"
-GLOBAL_VAL = 10
-counter = 0
-snapshot()
-
-def global_test():
-    snapshot()
-    local_copy = GLOBAL_VAL
-    snapshot()
-    global counter
-    counter += 1
-    snapshot()
-    return local_copy, counter
-
-before = counter
-snapshot()
-result = global_test()
-snapshot()
-after = counter
-snapshot()
-"#,
-        );
-
-        let access_global = find_snapshot_with_vars(&snapshots, &["local_copy", "GLOBAL_VAL"]);
-        assert_var(access_global, "GLOBAL_VAL", SimpleValue::Int(10));
-        assert_var(access_global, "local_copy", SimpleValue::Int(10));
-
-        let last_counter = snapshots
-            .iter()
-            .rev()
-            .find(|snap| snap.vars.contains_key("counter"))
-            .expect("Expected at least one counter snapshot");
-        assert_var(last_counter, "counter", SimpleValue::Int(1));
-    }
-
-    #[test]
-    fn captures_class_scope() {
-        let snapshots = run_traced_script(
-            r#"
-CONSTANT = 42
-snapshot()
-
-class MetaCounter(type):
-    count = 0
-    snapshot()
-    def __init__(cls, name, bases, attrs):
-        snapshot()
-        MetaCounter.count += 1
-        super().__init__(name, bases, attrs)
-
-class Sample(metaclass=MetaCounter):
-    snapshot()
-    a = 10
-    snapshot()
-    b = a + 5
-    snapshot()
-    print(a, b, CONSTANT)
-    snapshot()
-    def method(self):
-        snapshot()
-        return self.a + self.b
-
-instance = Sample()
-snapshot()
-instances = MetaCounter.count
-snapshot()
-_ = instance.method()
-snapshot()
-"#,
-        );
-
-        let meta_init = find_snapshot_with_vars(&snapshots, &["cls", "name", "attrs"]);
-        assert_var(meta_init, "name", SimpleValue::String("Sample".to_string()));
-
-        let class_body = find_snapshot_with_vars(&snapshots, &["a", "b"]);
-        assert_var(class_body, "a", SimpleValue::Int(10));
-        assert_var(class_body, "b", SimpleValue::Int(15));
-
-        let method_snapshot = find_snapshot_with_vars(&snapshots, &["self"]);
-        assert!(method_snapshot.vars.contains_key("self"));
-    }
-
-    #[test]
-    fn captures_lambda_and_comprehensions() {
-        let snapshots = run_traced_script(
-            r#"
-factor = 2
-snapshot()
-double = lambda y: snap(y * factor)
-snapshot()
-lambda_value = double(5)
-snapshot()
-squares = [snap(n ** 2) for n in range(3)]
-snapshot()
-scaled_set = {snap(n * factor) for n in range(3)}
-snapshot()
-mapping = {n: snap(n * factor) for n in range(3)}
-snapshot()
-gen_exp = (snap(n * factor) for n in range(3))
-snapshot()
-result_list = list(gen_exp)
-snapshot()
-"#,
-        );
-
-        let lambda_snapshot = find_snapshot_with_vars(&snapshots, &["y", "factor"]);
-        assert_var(lambda_snapshot, "y", SimpleValue::Int(5));
-        assert_var(lambda_snapshot, "factor", SimpleValue::Int(2));
-
-        let list_comp = find_snapshot_with_vars(&snapshots, &["n", "factor"]);
-        assert!(matches!(list_comp.vars.get("n"), Some(SimpleValue::Int(_))));
-
-        let result_snapshot = find_snapshot_with_vars(&snapshots, &["result_list"]);
-        assert!(matches!(
-            result_snapshot.vars.get("result_list"),
-            Some(SimpleValue::Sequence(_))
-        ));
-    }
-
-    #[test]
-    fn captures_generators_and_coroutines() {
-        let snapshots = run_traced_script(
-            r#"
-import asyncio
-snapshot()
-
-
-def counter_gen(n):
-    snapshot()
-    total = 0
-    for i in range(n):
-        total += i
-        snapshot()
-        yield total
-    snapshot()
-    return total
-
-async def async_sum(data):
-    snapshot()
-    total = 0
-    for x in data:
-        total += x
-        snapshot()
-        await asyncio.sleep(0)
-    snapshot()
-    return total
-
-gen = counter_gen(3)
-gen_results = list(gen)
-snapshot()
-coroutine_result = asyncio.run(async_sum([1, 2, 3]))
-snapshot()
-"#,
-        );
-
-        let generator_step = find_snapshot_with_vars(&snapshots, &["i", "total"]);
-        assert!(matches!(
-            generator_step.vars.get("i"),
-            Some(SimpleValue::Int(_))
-        ));
-
-        let coroutine_steps: Vec<&Snapshot> = snapshots
-            .iter()
-            .filter(|snap| snap.vars.contains_key("x"))
-            .collect();
-        assert!(!coroutine_steps.is_empty());
-        let final_coroutine_step = coroutine_steps.last().expect("at least one coroutine step");
-        assert_var(final_coroutine_step, "total", SimpleValue::Int(6));
-
-        let coroutine_result_snapshot = find_snapshot_with_vars(&snapshots, &["coroutine_result"]);
-        assert!(coroutine_result_snapshot
-            .vars
-            .contains_key("coroutine_result"));
-    }
-
-    #[test]
-    fn captures_exception_and_with_blocks() {
-        let snapshots = run_traced_script(
-            r#"
-import io
-__file__ = "test_script.py"
-
-def exception_and_with_demo(x):
-    snapshot()
-    try:
-        inv = 10 / x
-        snapshot()
-    except ZeroDivisionError as e:
-        snapshot()
-        error_msg = f"Error: {e}"
-        snapshot()
-    else:
-        snapshot()
-        inv += 1
-        snapshot()
-    finally:
-        snapshot()
-        final_flag = True
-        snapshot()
-    with io.StringIO("dummy line") as f:
-        snapshot()
-        first_line = f.readline()
-        snapshot()
-    snapshot()
-    return locals()
-
-result1 = exception_and_with_demo(0)
-snapshot()
-result2 = exception_and_with_demo(5)
-snapshot()
-"#,
-        );
-
-        let except_snapshot = find_snapshot_with_vars(&snapshots, &["e", "error_msg"]);
-        assert!(matches!(
-            except_snapshot.vars.get("error_msg"),
-            Some(SimpleValue::String(_))
-        ));
-
-        let finally_snapshot = find_snapshot_with_vars(&snapshots, &["final_flag"]);
-        assert_var(finally_snapshot, "final_flag", SimpleValue::Bool(true));
-
-        let with_snapshot = find_snapshot_with_vars(&snapshots, &["f", "first_line"]);
-        assert!(with_snapshot.vars.contains_key("first_line"));
-    }
-
-    #[test]
-    fn captures_decorators() {
-        let snapshots = run_traced_script(
-            r#"
-setting = "Hello"
-snapshot()
-
-
-def my_decorator(func):
-    snapshot()
-    def wrapper(*args, **kwargs):
-        snapshot()
-        return func(*args, **kwargs)
-    return wrapper
-
-@my_decorator
-def greet(name):
-    snapshot()
-    message = f"Hi, {name}"
-    snapshot()
-    return message
-
-output = greet("World")
-snapshot()
-"#,
-        );
-
-        let decorator_snapshot = find_snapshot_with_vars(&snapshots, &["func", "setting"]);
-        assert!(decorator_snapshot.vars.contains_key("func"));
-
-        let wrapper_snapshot = find_snapshot_with_vars(&snapshots, &["args", "kwargs", "setting"]);
-        assert!(wrapper_snapshot.vars.contains_key("args"));
-
-        let greet_snapshot = find_snapshot_with_vars(&snapshots, &["name", "message"]);
-        assert_var(
-            greet_snapshot,
-            "name",
-            SimpleValue::String("World".to_string()),
-        );
-    }
-
-    #[test]
-    fn captures_dynamic_execution() {
-        let snapshots = run_traced_script(
-            r#"
-expr_code = "dynamic_var = 99"
-snapshot()
-exec(expr_code)
-snapshot()
-check = dynamic_var + 1
-snapshot()
-
-def eval_test():
-    snapshot()
-    value = 10
-    formula = "value * 2"
-    snapshot()
-    result = eval(formula)
-    snapshot()
-    return result
-
-out = eval_test()
-snapshot()
-"#,
-        );
-
-        let exec_snapshot = find_snapshot_with_vars(&snapshots, &["dynamic_var"]);
-        assert_var(exec_snapshot, "dynamic_var", SimpleValue::Int(99));
-
-        let eval_snapshot = find_snapshot_with_vars(&snapshots, &["value", "formula"]);
-        assert_var(eval_snapshot, "value", SimpleValue::Int(10));
-    }
-
-    #[test]
-    fn captures_imports() {
-        let snapshots = run_traced_script(
-            r#"
-import math
-snapshot()
-
-def import_test():
-    snapshot()
-    import os
-    snapshot()
-    constant = math.pi
-    snapshot()
-    cwd = os.getcwd()
-    snapshot()
-    return constant, cwd
-
-val, path = import_test()
-snapshot()
-"#,
-        );
-
-        let global_import = find_snapshot_with_vars(&snapshots, &["math"]);
-        assert!(matches!(
-            global_import.vars.get("math"),
-            Some(SimpleValue::Raw(_))
-        ));
-
-        let local_import = find_snapshot_with_vars(&snapshots, &["os", "constant"]);
-        assert!(local_import.vars.contains_key("os"));
-    }
-
-    #[test]
-    fn builtins_not_recorded() {
-        let snapshots = run_traced_script(
-            r#"
-def builtins_test(seq):
-    snapshot()
-    n = len(seq)
-    snapshot()
-    m = max(seq)
-    snapshot()
-    return n, m
-
-result = builtins_test([5, 3, 7])
-snapshot()
-"#,
-        );
-
-        let len_snapshot = find_snapshot_with_vars(&snapshots, &["n"]);
-        assert_var(len_snapshot, "n", SimpleValue::Int(3));
-        assert_no_variable(&snapshots, "len");
-    }
-
-    #[test]
-    fn finish_enforces_require_trace_policy() {
-        Python::with_gil(|py| {
-            policy::configure_policy_py(
-                Some("abort"),
-                Some(true),
-                Some(false),
-                None,
-                None,
-                Some(false),
-                None,
-                None,
-                Some(false),
-                Some(false),
-            )
-            .expect("enable require_trace policy");
-
-            let script_dir = tempfile::tempdir().expect("script dir");
-            let program_path = script_dir.path().join("program.py");
-            std::fs::write(&program_path, "print('hi')\n").expect("write program");
-
-            let outputs_dir = tempfile::tempdir().expect("outputs dir");
-            let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
-
-            let mut tracer = RuntimeTracer::new(
-                program_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            tracer.begin(&outputs, 1).expect("begin tracer");
-
-            let err = tracer
-                .finish(py)
-                .expect_err("finish should error when require_trace true");
-            let message = err.to_string();
-            assert!(
-                message.contains("ERR_TRACE_MISSING"),
-                "expected trace missing error, got {message}"
-            );
-
-            reset_policy(py);
-        });
-    }
-
-    #[test]
-    fn finish_removes_partial_outputs_when_policy_forbids_keep() {
-        Python::with_gil(|py| {
-            reset_policy(py);
-
-            let script_dir = tempfile::tempdir().expect("script dir");
-            let program_path = script_dir.path().join("program.py");
-            std::fs::write(&program_path, "print('hi')\n").expect("write program");
-
-            let outputs_dir = tempfile::tempdir().expect("outputs dir");
-            let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
-
-            let mut tracer = RuntimeTracer::new(
-                program_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            tracer.begin(&outputs, 1).expect("begin tracer");
-            tracer.mark_failure();
-
-            tracer.finish(py).expect("finish after failure");
-
-            assert!(!outputs.events().exists(), "expected events file removed");
-        });
-    }
-
-    #[test]
-    fn finish_keeps_partial_outputs_when_policy_allows() {
-        Python::with_gil(|py| {
-            policy::configure_policy_py(
-                Some("abort"),
-                Some(false),
-                Some(true),
-                None,
-                None,
-                Some(false),
-                None,
-                None,
-                Some(false),
-                Some(false),
-            )
-            .expect("enable keep_partial policy");
-
-            let script_dir = tempfile::tempdir().expect("script dir");
-            let program_path = script_dir.path().join("program.py");
-            std::fs::write(&program_path, "print('hi')\n").expect("write program");
-
-            let outputs_dir = tempfile::tempdir().expect("outputs dir");
-            let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
-
-            let mut tracer = RuntimeTracer::new(
-                program_path.to_string_lossy().as_ref(),
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            tracer.begin(&outputs, 1).expect("begin tracer");
-            tracer.mark_failure();
-
-            tracer.finish(py).expect("finish after failure");
-
-            assert!(outputs.events().exists(), "expected events file retained");
-
-            reset_policy(py);
-        });
-    }
-
-    // ------------------------------------------------------------------
-    // M15: Python recorder Assignment events
-    // ------------------------------------------------------------------
-
-    /// Resolve a `VariableId` back to its source name by walking the
-    /// `VariableName` events emitted at the point the id was minted (the
-    /// NonStreamingTraceWriter assigns ids in the order names are first
-    /// observed; see `codetracer_trace_writer_nim::NonStreamingTraceWriter
-    /// ::ensure_variable_id`).
-    fn variable_name_for(
-        events: &[TraceLowLevelEvent],
-        id: codetracer_trace_types::VariableId,
-    ) -> Option<String> {
-        let mut counter = 0usize;
-        for event in events {
-            if let TraceLowLevelEvent::VariableName(name) = event {
-                if counter == id.0 {
-                    return Some(name.clone());
-                }
-                counter += 1;
-            }
-        }
-        None
-    }
-
-    /// Convenience: collect every `(target_name, RValue)` pair seen in
-    /// `events`, in order.
-    fn collect_assignments(
-        events: &[TraceLowLevelEvent],
-    ) -> Vec<(String, codetracer_trace_types::RValue)> {
-        events
-            .iter()
-            .filter_map(|e| match e {
-                TraceLowLevelEvent::Assignment(rec) => Some((
-                    variable_name_for(events, rec.to).unwrap_or_else(|| format!("?{}", rec.to.0)),
-                    rec.from.clone(),
-                )),
-                _ => None,
-            })
-            .collect()
-    }
-
-    /// Convenience: collect every name that received a `BindVariable` event.
-    fn collect_bind_variable_names(events: &[TraceLowLevelEvent]) -> Vec<String> {
-        events
-            .iter()
-            .filter_map(|e| match e {
-                TraceLowLevelEvent::BindVariable(rec) => {
-                    Some(variable_name_for(events, rec.variable_id).unwrap_or_default())
-                }
-                _ => None,
-            })
-            .collect()
-    }
-
-    /// Capture the buffered events from a script-driven trace.
-    ///
-    /// Like `run_traced_script` but exposes the raw events vector instead of
-    /// the simplified snapshots — needed so the M15 tests can inspect
-    /// `Assignment`, `BindVariable`, and the column on `StepRecord`.
-    fn run_traced_script_events(body: &str) -> Vec<TraceLowLevelEvent> {
-        Python::with_gil(|py| {
-            let mut tracer = RuntimeTracer::new(
-                "test.py",
-                &[],
-                TraceEventsFileFormat::Json,
-                None,
-                None,
-                false,
-            );
-            ensure_test_module(py);
-            let tmp = tempfile::tempdir().expect("create temp dir");
-            let script_path = tmp.path().join("script.py");
-            let script = format!("{PRELUDE}\n{body}");
-            std::fs::write(&script_path, &script).expect("write script");
-            {
-                let _guard = ScopedTracer::new(&mut tracer);
-                LAST_OUTCOME.with(|cell| cell.set(None));
-                let run_code = format!(
-                    "import runpy\nrunpy.run_path(r\"{}\")",
-                    script_path.display()
-                );
-                let run_code_c = CString::new(run_code).expect("script contains nul byte");
-                py.run(run_code_c.as_c_str(), None, None)
-                    .expect("execute test script");
-            }
-            tracer.writer.events().to_vec()
-        })
-    }
-
-    #[test]
-    fn test_python_recorder_emits_assignment_for_simple_assignment() {
-        // `a = 10` must surface as Assignment { from: Literal }. The trailing
-        // `snapshot()` triggers an extra on_line event so the Assignment for
-        // the previous line gets flushed (see the on_line emit-on-previous
-        // ordering in `events.rs::on_line`).
-        let body = r#"
-a = 10
-snapshot()
-"#;
-        let events = run_traced_script_events(body);
-        let assignments = collect_assignments(&events);
-        let a_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "a")
-            .unwrap_or_else(|| {
-                panic!(
-                    "expected Assignment for `a`, got assignments={:?}",
-                    assignments
-                )
-            });
-        assert!(
-            matches!(a_assign.1, codetracer_trace_types::RValue::Literal),
-            "expected RValue::Literal for `a = 10`, got {:?}",
-            a_assign.1
-        );
-
-        let binds = collect_bind_variable_names(&events);
-        assert!(
-            binds.contains(&"a".to_string()),
-            "expected BindVariable for `a`, got {:?}",
-            binds
-        );
-    }
-
-    #[test]
-    fn test_python_recorder_emits_assignment_for_local_copy() {
-        // `b = a` must surface as Assignment { from: Simple(var_id(a)) }.
-        let body = r#"
-a = 10
-b = a
-snapshot()
-"#;
-        let events = run_traced_script_events(body);
-        let assignments = collect_assignments(&events);
-        let b_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "b")
-            .unwrap_or_else(|| {
-                panic!(
-                    "expected Assignment for `b`, got assignments={:?}",
-                    assignments
-                )
-            });
-        match &b_assign.1 {
-            codetracer_trace_types::RValue::Simple(id) => {
-                let src = variable_name_for(&events, *id).unwrap_or_default();
-                assert_eq!(
-                    src, "a",
-                    "expected RValue::Simple(a), got Simple({:?})",
-                    src
-                );
-            }
-            other => panic!("expected RValue::Simple, got {:?}", other),
-        }
-    }
-
-    #[test]
-    fn test_python_recorder_emits_function_return_rvalue() {
-        // `result = foo()` must surface as Assignment { from: FunctionReturn{call_key} }.
-        //
-        // The test harness drives the tracer manually via PRELUDE helpers:
-        // - `start_call()` calls into on_py_start so the writer registers a
-        //   CallRecord and `last_call_key` advances. We invoke it from inside
-        //   foo() so it fires when foo executes.
-        // - The final `snapshot()` flushes the per-line Assignment emit pass.
-        //
-        // The bytecode reconstructor recognises `result = foo()` as a CALL
-        // pattern regardless of whether the harness actually invoked
-        // on_py_start; the `last_call_key` only affects which CallKey value
-        // gets stamped on the RValue::FunctionReturn variant.
-        let body = r#"
-def foo():
-    start_call()
-    return 42
-
-result = foo()
-snapshot()
-"#;
-        let events = run_traced_script_events(body);
-        let assignments = collect_assignments(&events);
-        let result_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "result")
-            .unwrap_or_else(|| {
-                panic!(
-                    "expected Assignment for `result`, got assignments={:?}",
-                    assignments
-                )
-            });
-        assert!(
-            matches!(
-                result_assign.1,
-                codetracer_trace_types::RValue::FunctionReturn { .. }
-            ),
-            "expected RValue::FunctionReturn for `result = foo()`, got {:?}",
-            result_assign.1
-        );
-    }
-
-    #[test]
-    fn test_python_recorder_emits_destructuring_assignments() {
-        // `a, b = pair` must surface as TWO Assignment events with
-        // RValue::IndexAccess { receiver: pair, index: i }.
-        let body = r#"
-pair = (11, 22)
-a, b = pair
-snapshot()
-"#;
-        let events = run_traced_script_events(body);
-        let assignments = collect_assignments(&events);
-        let a_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "a")
-            .unwrap_or_else(|| {
-                panic!(
-                    "expected Assignment for `a`, got assignments={:?}",
-                    assignments
-                )
-            });
-        let b_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "b")
-            .unwrap_or_else(|| {
-                panic!(
-                    "expected Assignment for `b`, got assignments={:?}",
-                    assignments
-                )
-            });
-        match &a_assign.1 {
-            codetracer_trace_types::RValue::IndexAccess { receiver, index } => {
-                let src = variable_name_for(&events, *receiver).unwrap_or_default();
-                assert_eq!(src, "pair", "a: receiver expected pair, got {:?}", src);
-                assert_eq!(*index, 0, "a: expected index 0, got {}", index);
-            }
-            other => panic!("expected RValue::IndexAccess for a, got {:?}", other),
-        }
-        match &b_assign.1 {
-            codetracer_trace_types::RValue::IndexAccess { receiver, index } => {
-                let src = variable_name_for(&events, *receiver).unwrap_or_default();
-                assert_eq!(src, "pair", "b: receiver expected pair, got {:?}", src);
-                assert_eq!(*index, 1, "b: expected index 1, got {}", index);
-            }
-            other => panic!("expected RValue::IndexAccess for b, got {:?}", other),
-        }
-    }
-
-    #[test]
-    fn test_python_recorder_emits_step_records_for_column_carrying_lines() {
-        // P1.1/P1.2 (was: M14/M15 `StepRecord.column` test).  The
-        // canonical CTFS column-encoding path emits column-only
-        // `DeltaColumn` (tag 0x07) events on the multi-stream backend,
-        // not a column field on `StepRecord`.  Column-aware acceptance
-        // lives in `tests/python/test_column_aware_steps.py`, which
-        // drives a full CTFS round-trip via the recorder CLI.
-        //
-        // This unit test now keeps a weaker but still useful smoke
-        // assertion against the JSON test-double backend: a script with
-        // a STORE on every body line produces at least one `Step`
-        // event per line, proving the extraction-and-emission glue is
-        // intact for the non-column-aware path.  The JSON test double
-        // (`NonStreamingTraceWriter`) drops column data by design — see
-        // its `register_step_with_column` impl.
-        let body = r#"
-a = snap(10)
-b = snap(20)
-"#;
-        let events = run_traced_script_events(body);
-        let step_count = events
-            .iter()
-            .filter(|e| matches!(e, TraceLowLevelEvent::Step(_)))
-            .count();
-        assert!(
-            step_count >= 2,
-            "expected >=2 Step events (one per body line), got {} in {:?}",
-            step_count,
-            events
-                .iter()
-                .filter_map(|e| match e {
-                    TraceLowLevelEvent::Step(rec) => Some(rec),
-                    _ => None,
-                })
-                .collect::<Vec<_>>()
-        );
-    }
-
-    /// M15 verification 6: Path A confidence-1.0 is a property of the
-    /// db-backend's classifier, not the recorder. The recorder satisfies its
-    /// half of the contract by ensuring the buffered event stream contains
-    /// `Assignment` events for every store on a real-frame line. This test
-    /// asserts that property: every `b = a`-style local-copy assignment
-    /// emits an Assignment event with RValue::Simple, which is exactly what
-    /// triggers Path A activation in `trace_processor.rs`. The db-backend
-    /// then upgrades the per-hop confidence to 1.0 (spec §6.1.5).
-    #[test]
-    fn test_origin_chain_path_a_confidence_one() {
-        let body = r#"
-a = 10
-b = a
-c = b
-snapshot()
-"#;
-        let events = run_traced_script_events(body);
-        let assignments = collect_assignments(&events);
-
-        // b = a -> Simple(a)
-        let b_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "b")
-            .expect("Assignment(b) present");
-        assert!(
-            matches!(b_assign.1, codetracer_trace_types::RValue::Simple(_)),
-            "Path A: `b = a` needs Simple, got {:?}",
-            b_assign.1
-        );
-
-        // c = b -> Simple(b)
-        let c_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "c")
-            .expect("Assignment(c) present");
-        match &c_assign.1 {
-            codetracer_trace_types::RValue::Simple(id) => {
-                let src = variable_name_for(&events, *id).unwrap_or_default();
-                assert_eq!(
-                    src, "b",
-                    "Path A: `c = b` source should be b, got {:?}",
-                    src
-                );
-            }
-            other => panic!("Path A: `c = b` needs Simple, got {:?}", other),
-        }
-
-        // The chain a (Literal) -> b (Simple a) -> c (Simple b) is
-        // wholly Path A reconstructible from the recorder events alone.
-        let a_assign = assignments
-            .iter()
-            .find(|(name, _)| name == "a")
-            .expect("Assignment(a) present");
-        assert!(
-            matches!(a_assign.1, codetracer_trace_types::RValue::Literal),
-            "Path A chain root must be Literal, got {:?}",
-            a_assign.1
-        );
-    }
-}
+mod tests;
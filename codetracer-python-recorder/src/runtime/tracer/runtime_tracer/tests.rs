@@ -0,0 +1,1002 @@
+use super::*;
+use crate::monitoring::{CallbackOutcome, Tracer};
+use crate::policy;
+use crate::policy::UnreprFallback;
+use crate::runtime::tracer::filtering::is_real_filename;
+use crate::runtime::tracer::warnings::WarningFields;
+use crate::runtime::value_capture::{
+    CONTEXTVARS_LABEL, GENERATOR_RETURN_LABEL, OMITTED_LOCALS_LABEL,
+};
+use crate::runtime::value_encoder::{BIG_INT_TYPE_NAME, MAX_RAW_CHARS, MAX_STREAMING_DEPTH};
+use crate::trace_filter::config::TraceFilterConfig;
+use codetracer_trace_types::{FullValueRecord, StepRecord, TraceLowLevelEvent, ValueRecord};
+use pyo3::types::{PyAny, PyCode, PyModule};
+use pyo3::wrap_pyfunction;
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+
+mod assignments;
+mod filters;
+mod io_capture;
+mod markers;
+mod session;
+mod values;
+
+thread_local! {
+    static ACTIVE_TRACER: Cell<*mut RuntimeTracer> = Cell::new(std::ptr::null_mut());
+    static LAST_OUTCOME: Cell<Option<CallbackOutcome>> = Cell::new(None);
+}
+
+const BUILTIN_TRACE_FILTER: &str =
+    include_str!("../../../../resources/trace_filters/builtin_default.toml");
+
+struct ScopedTracer;
+
+impl ScopedTracer {
+    fn new(tracer: &mut RuntimeTracer) -> Self {
+        let ptr = tracer as *mut _;
+        ACTIVE_TRACER.with(|cell| cell.set(ptr));
+        ScopedTracer
+    }
+}
+
+impl Drop for ScopedTracer {
+    fn drop(&mut self) {
+        ACTIVE_TRACER.with(|cell| cell.set(std::ptr::null_mut()));
+    }
+}
+
+fn last_outcome() -> Option<CallbackOutcome> {
+    LAST_OUTCOME.with(|cell| cell.get())
+}
+
+fn reset_policy(py: Python<'_>) {
+    policy::test_support::configure_policy_with(
+        py,
+        "on_recorder_error='abort', require_trace=False, keep_partial_trace=False, \
+             json_errors=False, module_name_from_globals=False, propagate_script_exit=False",
+    )
+    .expect("reset recorder policy");
+}
+
+#[test]
+fn detects_real_filenames() {
+    assert!(is_real_filename("example.py"));
+    assert!(is_real_filename(" /tmp/module.py "));
+    assert!(is_real_filename("src/<tricky>.py"));
+    assert!(!is_real_filename("<string>"));
+    assert!(!is_real_filename("  <stdin>  "));
+    assert!(!is_real_filename("<frozen importlib._bootstrap>"));
+}
+
+#[test]
+fn skips_synthetic_filename_events() {
+    Python::with_gil(|py| {
+        let mut tracer = RuntimeTracer::new(
+            "test.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        ensure_test_module(py);
+        let script = format!("{PRELUDE}\nsnapshot()\n");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let script_c = CString::new(script).expect("script contains nul byte");
+            py.run(script_c.as_c_str(), None, None)
+                .expect("execute synthetic script");
+        }
+        assert!(
+            tracer.writer.events().is_empty(),
+            "expected no events for synthetic filename"
+        );
+        let outcome = last_outcome();
+        assert!(
+            matches!(
+                outcome,
+                Some(CallbackOutcome::DisableLocation | CallbackOutcome::Continue)
+            ),
+            "expected DisableLocation or Continue (when CPython refuses to disable an event), got {:?}",
+            outcome
+        );
+
+        let compile_fn = py
+            .import("builtins")
+            .expect("import builtins")
+            .getattr("compile")
+            .expect("fetch compile");
+        let binding = compile_fn
+            .call1(("pass", "<string>", "exec"))
+            .expect("compile code object");
+        let code_obj = binding.downcast::<PyCode>().expect("downcast code object");
+        let wrapper = CodeObjectWrapper::new(py, &code_obj);
+        assert_eq!(
+            tracer.should_trace_code(py, &wrapper),
+            TraceDecision::SkipAndDisable
+        );
+    });
+}
+
+#[test]
+fn traces_real_file_events() {
+    let snapshots = run_traced_script("snapshot()\n");
+    assert!(
+        !snapshots.is_empty(),
+        "expected snapshots for real file execution"
+    );
+    assert_eq!(last_outcome(), Some(CallbackOutcome::Continue));
+}
+
+#[test]
+fn callbacks_do_not_import_sys_monitoring() {
+    let body = r#"
+import builtins
+_orig_import = builtins.__import__
+
+def guard(name, *args, **kwargs):
+    if name == "sys.monitoring":
+        raise RuntimeError("callback imported sys.monitoring")
+    return _orig_import(name, *args, **kwargs)
+
+builtins.__import__ = guard
+try:
+    snapshot()
+finally:
+    builtins.__import__ = _orig_import
+"#;
+    let snapshots = run_traced_script(body);
+    assert!(
+        !snapshots.is_empty(),
+        "expected snapshots when import guard active"
+    );
+    assert_eq!(last_outcome(), Some(CallbackOutcome::Continue));
+}
+
+#[test]
+fn records_return_values_and_deactivates_activation() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("activation_script.py");
+        let script = format!(
+            "{PRELUDE}\n\n\
+def compute():\n    emit_return(\"tail\")\n    return \"tail\"\n\n\
+result = compute()\n"
+        );
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let program = script_path.to_string_lossy().into_owned();
+        let mut tracer = RuntimeTracer::new(
+            &program,
+            &[],
+            TraceEventsFileFormat::Json,
+            Some(script_path.as_path()),
+            None,
+            false,
+        );
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute test script");
+        }
+
+        let returns: Vec<SimpleValue> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Return(record) => {
+                    Some(SimpleValue::from_value(&record.return_value))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert!(
+            returns.contains(&SimpleValue::String("tail".to_string())),
+            "expected recorded string return, got {:?}",
+            returns
+        );
+        assert_eq!(last_outcome(), Some(CallbackOutcome::Continue));
+        assert!(!tracer.lifecycle.activation().is_active());
+    });
+}
+
+#[test]
+fn recursive_activation_function_deactivates_on_outermost_return() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("recursive_activation.py");
+        let script = format!(
+            "{PRELUDE}\n\n\
+def countdown(n):\n    start_call()\n    if n > 0:\n        countdown(n - 1)\n    emit_return(n)\n    return n\n\n\
+countdown(2)\n"
+        );
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let program = script_path.to_string_lossy().into_owned();
+        let mut tracer = RuntimeTracer::new(
+            &program,
+            &[],
+            TraceEventsFileFormat::Json,
+            Some(script_path.as_path()),
+            None,
+            false,
+        );
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute test script");
+        }
+
+        let returns: Vec<SimpleValue> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Return(record) => {
+                    Some(SimpleValue::from_value(&record.return_value))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Returns after the innermost one are only recorded while the
+        // outer activation frames keep tracing active.
+        assert_eq!(
+            returns,
+            vec![
+                SimpleValue::Int(0),
+                SimpleValue::Int(1),
+                SimpleValue::Int(2)
+            ]
+        );
+        assert!(!tracer.lifecycle.activation().is_active());
+    });
+}
+
+#[test]
+fn line_snapshot_store_tracks_last_step() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("snapshot_script.py");
+        let script = format!("{PRELUDE}\n\nsnapshot()\n");
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            "snapshot_script.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let store = tracer.line_snapshot_store();
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute snapshot script");
+        }
+
+        let last_step: StepRecord = tracer
+            .writer
+            .events()
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Step(step) => Some(step.clone()),
+                _ => None,
+            })
+            .expect("expected one step event");
+
+        let thread_id = thread::current().id();
+        let snapshot = store
+            .snapshot_for_thread(thread_id)
+            .expect("snapshot should be recorded");
+
+        assert_eq!(snapshot.line(), last_step.line);
+        assert_eq!(snapshot.path_id(), last_step.path_id);
+        assert!(snapshot.captured_at().elapsed().as_secs_f64() >= 0.0);
+    });
+}
+
+#[pyfunction]
+fn capture_py_start(py: Python<'_>, code: Bound<'_, PyCode>, offset: i32) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_py_start", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_py_start");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_py_start(py, &wrapper, offset) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn capture_py_resume(py: Python<'_>, code: Bound<'_, PyCode>, offset: i32) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_py_resume", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_py_resume");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_py_resume(py, &wrapper, offset) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn capture_line(py: Python<'_>, code: Bound<'_, PyCode>, lineno: u32) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_line", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_line");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_line(py, &wrapper, lineno) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn capture_jump(
+    py: Python<'_>,
+    code: Bound<'_, PyCode>,
+    offset: i32,
+    destination_offset: i32,
+) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_jump", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_jump");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_jump(py, &wrapper, offset, destination_offset) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn capture_call(
+    py: Python<'_>,
+    code: Bound<'_, PyCode>,
+    offset: i32,
+    callable: Bound<'_, PyAny>,
+    arg0: Bound<'_, PyAny>,
+) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_call", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_call");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_call(py, &wrapper, offset, &callable, Some(&arg0)) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn capture_py_return(
+    py: Python<'_>,
+    code: Bound<'_, PyCode>,
+    offset: i32,
+    value: Bound<'_, PyAny>,
+) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_py_return", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_py_return");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_py_return(py, &wrapper, offset, &value) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction]
+fn capture_return_event(
+    py: Python<'_>,
+    code: Bound<'_, PyCode>,
+    value: Bound<'_, PyAny>,
+) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_return_event", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_return_event");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_py_return(py, &wrapper, 0, &value) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+#[pyfunction(signature = (label, metadata=None))]
+fn capture_mark(py: Python<'_>, label: &str, metadata: Option<&str>) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_mark", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_mark");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                tracer.mark(py, label, metadata)
+            }
+        })
+    })
+}
+
+#[pyfunction(signature = (message, category, filename, lineno, file=None, line=None))]
+fn capture_warning(
+    py: Python<'_>,
+    message: Bound<'_, PyAny>,
+    category: Bound<'_, PyAny>,
+    filename: Bound<'_, PyAny>,
+    lineno: Bound<'_, PyAny>,
+    file: Option<Bound<'_, PyAny>>,
+    line: Option<Bound<'_, PyAny>>,
+) -> PyResult<()> {
+    // `file` and `line` only matter when printing the warning.
+    let _ = (file, line);
+    ffi::wrap_pyfunction("test_capture_warning", || {
+        let fields = WarningFields::from_args(&message, &category, &filename, &lineno)?;
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_warning");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                tracer.warning(
+                    py,
+                    &fields.category,
+                    &fields.message,
+                    &fields.path,
+                    fields.line,
+                )
+            }
+        })
+    })
+}
+
+#[pyfunction]
+fn capture_yield_event(
+    py: Python<'_>,
+    code: Bound<'_, PyCode>,
+    value: Bound<'_, PyAny>,
+) -> PyResult<()> {
+    ffi::wrap_pyfunction("test_capture_yield_event", || {
+        ACTIVE_TRACER.with(|cell| -> PyResult<()> {
+            let ptr = cell.get();
+            if ptr.is_null() {
+                panic!("No active RuntimeTracer for capture_yield_event");
+            }
+            unsafe {
+                let tracer = &mut *ptr;
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                match tracer.on_py_yield(py, &wrapper, 0, &value) {
+                    Ok(outcome) => {
+                        LAST_OUTCOME.with(|cell| cell.set(Some(outcome)));
+                        Ok(())
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+        })?;
+        Ok(())
+    })
+}
+
+const PRELUDE: &str = r#"
+import inspect
+from test_tracer import capture_line, capture_return_event, capture_py_start, capture_yield_event
+
+def snapshot(line=None):
+    frame = inspect.currentframe().f_back
+    lineno = frame.f_lineno if line is None else line
+    capture_line(frame.f_code, lineno)
+
+def snap(value):
+    frame = inspect.currentframe().f_back
+    capture_line(frame.f_code, frame.f_lineno)
+    return value
+
+def emit_return(value):
+    frame = inspect.currentframe().f_back
+    capture_return_event(frame.f_code, value)
+    return value
+
+def emit_yield(value):
+    frame = inspect.currentframe().f_back
+    capture_yield_event(frame.f_code, value)
+    return value
+
+def start_call():
+    frame = inspect.currentframe().f_back
+    capture_py_start(frame.f_code, frame.f_lasti)
+"#;
+
+#[derive(Debug, Clone, PartialEq)]
+enum SimpleValue {
+    None,
+    Bool(bool),
+    Int(i64),
+    String(String),
+    Tuple(Vec<SimpleValue>),
+    Sequence(Vec<SimpleValue>),
+    Raw(String),
+}
+
+impl SimpleValue {
+    fn from_value(value: &ValueRecord) -> Self {
+        match value {
+            ValueRecord::None { .. } => SimpleValue::None,
+            ValueRecord::Bool { b, .. } => SimpleValue::Bool(*b),
+            ValueRecord::Int { i, .. } => SimpleValue::Int(*i),
+            ValueRecord::String { text, .. } => SimpleValue::String(text.clone()),
+            ValueRecord::Tuple { elements, .. } => {
+                SimpleValue::Tuple(elements.iter().map(SimpleValue::from_value).collect())
+            }
+            ValueRecord::Sequence { elements, .. } => {
+                SimpleValue::Sequence(elements.iter().map(SimpleValue::from_value).collect())
+            }
+            ValueRecord::Raw { r, .. } => SimpleValue::Raw(r.clone()),
+            ValueRecord::Error { msg, .. } => SimpleValue::Raw(msg.clone()),
+            other => SimpleValue::Raw(format!("{other:?}")),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    line: i64,
+    vars: BTreeMap<String, SimpleValue>,
+}
+
+fn collect_snapshots(events: &[TraceLowLevelEvent]) -> Vec<Snapshot> {
+    let mut names: Vec<String> = Vec::new();
+    let mut snapshots: Vec<Snapshot> = Vec::new();
+    let mut current: Option<Snapshot> = None;
+    for event in events {
+        match event {
+            TraceLowLevelEvent::VariableName(name) => names.push(name.clone()),
+            TraceLowLevelEvent::Step(step) => {
+                if let Some(snapshot) = current.take() {
+                    snapshots.push(snapshot);
+                }
+                current = Some(Snapshot {
+                    line: step.line.0,
+                    vars: BTreeMap::new(),
+                });
+            }
+            TraceLowLevelEvent::Value(FullValueRecord { variable_id, value }) => {
+                if let Some(snapshot) = current.as_mut() {
+                    let index = variable_id.0;
+                    let name = names
+                        .get(index)
+                        .cloned()
+                        .unwrap_or_else(|| panic!("Missing variable name for id {}", index));
+                    snapshot.vars.insert(name, SimpleValue::from_value(value));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(snapshot) = current.take() {
+        snapshots.push(snapshot);
+    }
+    snapshots
+}
+
+fn ensure_test_module(py: Python<'_>) {
+    let module = PyModule::new(py, "test_tracer").expect("create module");
+    module
+        .add_function(wrap_pyfunction!(capture_py_start, &module).expect("wrap capture_py_start"))
+        .expect("add py_start capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_line, &module).expect("wrap capture_line"))
+        .expect("add line capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_py_resume, &module).expect("wrap capture_py_resume"))
+        .expect("add resume capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_jump, &module).expect("wrap capture_jump"))
+        .expect("add jump capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_call, &module).expect("wrap capture_call"))
+        .expect("add call capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_py_return, &module).expect("wrap capture_py_return"))
+        .expect("add py return capture function");
+    module
+        .add_function(
+            wrap_pyfunction!(capture_return_event, &module).expect("wrap capture_return_event"),
+        )
+        .expect("add return capture function");
+    module
+        .add_function(
+            wrap_pyfunction!(capture_yield_event, &module).expect("wrap capture_yield_event"),
+        )
+        .expect("add yield capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_mark, &module).expect("wrap capture_mark"))
+        .expect("add mark capture function");
+    module
+        .add_function(wrap_pyfunction!(capture_warning, &module).expect("wrap capture_warning"))
+        .expect("add warning capture function");
+    py.import("sys")
+        .expect("import sys")
+        .getattr("modules")
+        .expect("modules attr")
+        .set_item("test_tracer", module)
+        .expect("insert module");
+}
+
+fn run_traced_script(body: &str) -> Vec<Snapshot> {
+    run_traced_script_with_policy(body, &RecorderPolicy::default())
+}
+
+fn run_traced_script_with_policy(body: &str, policy: &RecorderPolicy) -> Vec<Snapshot> {
+    Python::with_gil(|py| {
+        let mut tracer = RuntimeTracer::new(
+            "test.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.apply_policy(policy);
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("script.py");
+        let script = format!("{PRELUDE}\n{body}");
+        std::fs::write(&script_path, &script).expect("write script");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute test script");
+        }
+        collect_snapshots(tracer.writer.events())
+    })
+}
+
+fn write_filter(path: &Path, contents: &str) {
+    fs::write(path, contents.trim_start()).expect("write filter");
+}
+
+fn install_drop_everything_filter(project_root: &Path) -> PathBuf {
+    let filters_dir = project_root.join(".codetracer");
+    fs::create_dir(&filters_dir).expect("create .codetracer");
+    let drop_filter_path = filters_dir.join("drop-filter.toml");
+    write_filter(
+        &drop_filter_path,
+        r#"
+            [meta]
+            name = "drop-all"
+            version = 1
+
+            [scope]
+            default_exec = "trace"
+            default_value_action = "drop"
+            "#,
+    );
+    drop_filter_path
+}
+
+#[test]
+fn module_import_records_module_name() {
+    Python::with_gil(|py| {
+        let project = tempfile::tempdir().expect("project dir");
+        let pkg_root = project.path().join("lib");
+        let pkg_dir = pkg_root.join("my_pkg");
+        fs::create_dir_all(&pkg_dir).expect("create package dir");
+        let module_path = pkg_dir.join("mod.py");
+        fs::write(&module_path, "value = 1\n").expect("write module file");
+
+        let sys = py.import("sys").expect("import sys");
+        let sys_path = sys.getattr("path").expect("sys.path");
+        sys_path
+            .call_method1("insert", (0, pkg_root.to_string_lossy().as_ref()))
+            .expect("insert temp root");
+
+        let mut tracer = RuntimeTracer::new(
+            "runner.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+
+        let builtins = py.import("builtins").expect("builtins");
+        let compile = builtins.getattr("compile").expect("compile builtin");
+        let code_obj: Bound<'_, PyCode> = compile
+            .call1((
+                "value = 1\n",
+                module_path.to_string_lossy().as_ref(),
+                "exec",
+            ))
+            .expect("compile module code")
+            .downcast_into()
+            .expect("PyCode");
+
+        let wrapper = CodeObjectWrapper::new(py, &code_obj);
+        let resolved = tracer
+            .function_name_for_test(py, &wrapper)
+            .expect("derive function name");
+
+        assert_eq!(resolved, "<my_pkg.mod>");
+
+        sys_path.call_method1("pop", (0,)).expect("pop temp root");
+    });
+}
+
+#[test]
+fn underivable_module_name_is_resolved_once_per_code_object() {
+    Python::with_gil(|py| {
+        let project = tempfile::tempdir().expect("project dir");
+        let module_path = project.path().join("not-a-module.py");
+
+        let mut tracer = RuntimeTracer::new(
+            "runner.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+
+        let builtins = py.import("builtins").expect("builtins");
+        let compile = builtins.getattr("compile").expect("compile builtin");
+        let code_obj: Bound<'_, PyCode> = compile
+            .call1((
+                "value = 1\n",
+                module_path.to_string_lossy().as_ref(),
+                "exec",
+            ))
+            .expect("compile module code")
+            .downcast_into()
+            .expect("PyCode");
+
+        let wrapper = CodeObjectWrapper::new(py, &code_obj);
+        for _ in 0..100 {
+            let resolved = tracer
+                .function_name_for_test(py, &wrapper)
+                .expect("derive function name");
+            assert_eq!(resolved, "<module>");
+        }
+
+        assert_eq!(tracer.module_names.derivations(), 1);
+        assert_eq!(tracer.module_names.get(wrapper.id()), Some(None));
+    });
+}
+
+/// Metadata of the trace-log events of `kind` in the finished events file.
+fn written_trace_log_events(outputs: &TraceOutputPaths, kind: &str) -> Vec<serde_json::Value> {
+    let text = fs::read_to_string(outputs.events()).expect("read events file");
+    let events: Vec<serde_json::Value> =
+        serde_json::from_str(&text).expect("events file is a JSON array");
+    events
+        .iter()
+        .filter_map(|event| event["Event"]["metadata"].as_str())
+        .filter_map(|metadata| serde_json::from_str::<serde_json::Value>(metadata).ok())
+        .filter(|metadata| metadata["kind"] == kind)
+        .collect()
+}
+
+fn assert_var(snapshot: &Snapshot, name: &str, expected: SimpleValue) {
+    let actual = snapshot
+        .vars
+        .get(name)
+        .unwrap_or_else(|| panic!("{name} missing at line {}", snapshot.line));
+    assert_eq!(
+        actual, &expected,
+        "Unexpected value for {name} at line {}",
+        snapshot.line
+    );
+}
+
+fn find_snapshot_with_vars<'a>(snapshots: &'a [Snapshot], names: &[&str]) -> &'a Snapshot {
+    snapshots
+        .iter()
+        .find(|snap| names.iter().all(|n| snap.vars.contains_key(*n)))
+        .unwrap_or_else(|| panic!("No snapshot containing variables {:?}", names))
+}
+
+fn assert_no_variable(snapshots: &[Snapshot], name: &str) {
+    if snapshots.iter().any(|snap| snap.vars.contains_key(name)) {
+        panic!("Variable {name} unexpectedly captured");
+    }
+}
+
+// ------------------------------------------------------------------
+// M15: Python recorder Assignment events
+// ------------------------------------------------------------------
+
+/// Resolve a `VariableId` back to its source name by walking the
+/// `VariableName` events emitted at the point the id was minted (the
+/// NonStreamingTraceWriter assigns ids in the order names are first
+/// observed; see `codetracer_trace_writer_nim::NonStreamingTraceWriter
+/// ::ensure_variable_id`).
+fn variable_name_for(
+    events: &[TraceLowLevelEvent],
+    id: codetracer_trace_types::VariableId,
+) -> Option<String> {
+    let mut counter = 0usize;
+    for event in events {
+        if let TraceLowLevelEvent::VariableName(name) = event {
+            if counter == id.0 {
+                return Some(name.clone());
+            }
+            counter += 1;
+        }
+    }
+    None
+}
+
+/// Capture the buffered events from a script-driven trace.
+///
+/// Like `run_traced_script` but exposes the raw events vector instead of
+/// the simplified snapshots — needed so the M15 tests can inspect
+/// `Assignment`, `BindVariable`, and the column on `StepRecord`.
+fn run_traced_script_events(body: &str) -> Vec<TraceLowLevelEvent> {
+    run_traced_script_events_with_policy(body, &RecorderPolicy::default())
+}
+
+fn run_traced_script_events_with_policy(
+    body: &str,
+    policy: &RecorderPolicy,
+) -> Vec<TraceLowLevelEvent> {
+    let tmp = tempfile::tempdir().expect("create temp dir");
+    let script_path = tmp.path().join("script.py");
+    let script = format!("{PRELUDE}\n{body}");
+    std::fs::write(&script_path, &script).expect("write script");
+    run_traced_file_events_with_policy(&script_path, policy)
+}
+
+fn run_traced_file_events_with_policy(
+    script_path: &Path,
+    policy: &RecorderPolicy,
+) -> Vec<TraceLowLevelEvent> {
+    Python::with_gil(|py| {
+        let mut tracer = RuntimeTracer::new(
+            "test.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.apply_policy(policy);
+        ensure_test_module(py);
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute test script");
+        }
+        tracer.writer.events().to_vec()
+    })
+}
@@ -0,0 +1,289 @@
+//! Assignment events reconstructed from bytecode.
+
+use super::*;
+
+/// Convenience: collect every `(target_name, RValue)` pair seen in
+/// `events`, in order.
+fn collect_assignments(
+    events: &[TraceLowLevelEvent],
+) -> Vec<(String, codetracer_trace_types::RValue)> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            TraceLowLevelEvent::Assignment(rec) => Some((
+                variable_name_for(events, rec.to).unwrap_or_else(|| format!("?{}", rec.to.0)),
+                rec.from.clone(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Convenience: collect every name that received a `BindVariable` event.
+fn collect_bind_variable_names(events: &[TraceLowLevelEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            TraceLowLevelEvent::BindVariable(rec) => {
+                Some(variable_name_for(events, rec.variable_id).unwrap_or_default())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_python_recorder_emits_assignment_for_simple_assignment() {
+    // `a = 10` must surface as Assignment { from: Literal }. The trailing
+    // `snapshot()` triggers an extra on_line event so the Assignment for
+    // the previous line gets flushed (see the on_line emit-on-previous
+    // ordering in `events.rs::on_line`).
+    let body = r#"
+a = 10
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let assignments = collect_assignments(&events);
+    let a_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "a")
+        .unwrap_or_else(|| {
+            panic!(
+                "expected Assignment for `a`, got assignments={:?}",
+                assignments
+            )
+        });
+    assert!(
+        matches!(a_assign.1, codetracer_trace_types::RValue::Literal),
+        "expected RValue::Literal for `a = 10`, got {:?}",
+        a_assign.1
+    );
+
+    let binds = collect_bind_variable_names(&events);
+    assert!(
+        binds.contains(&"a".to_string()),
+        "expected BindVariable for `a`, got {:?}",
+        binds
+    );
+}
+
+#[test]
+fn test_python_recorder_emits_assignment_for_local_copy() {
+    // `b = a` must surface as Assignment { from: Simple(var_id(a)) }.
+    let body = r#"
+a = 10
+b = a
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let assignments = collect_assignments(&events);
+    let b_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "b")
+        .unwrap_or_else(|| {
+            panic!(
+                "expected Assignment for `b`, got assignments={:?}",
+                assignments
+            )
+        });
+    match &b_assign.1 {
+        codetracer_trace_types::RValue::Simple(id) => {
+            let src = variable_name_for(&events, *id).unwrap_or_default();
+            assert_eq!(
+                src, "a",
+                "expected RValue::Simple(a), got Simple({:?})",
+                src
+            );
+        }
+        other => panic!("expected RValue::Simple, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_python_recorder_emits_function_return_rvalue() {
+    // `result = foo()` must surface as Assignment { from: FunctionReturn{call_key} }.
+    //
+    // The test harness drives the tracer manually via PRELUDE helpers:
+    // - `start_call()` calls into on_py_start so the writer registers a
+    //   CallRecord and `last_call_key` advances. We invoke it from inside
+    //   foo() so it fires when foo executes.
+    // - The final `snapshot()` flushes the per-line Assignment emit pass.
+    //
+    // The bytecode reconstructor recognises `result = foo()` as a CALL
+    // pattern regardless of whether the harness actually invoked
+    // on_py_start; the `last_call_key` only affects which CallKey value
+    // gets stamped on the RValue::FunctionReturn variant.
+    let body = r#"
+def foo():
+    start_call()
+    return 42
+
+result = foo()
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let assignments = collect_assignments(&events);
+    let result_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "result")
+        .unwrap_or_else(|| {
+            panic!(
+                "expected Assignment for `result`, got assignments={:?}",
+                assignments
+            )
+        });
+    assert!(
+        matches!(
+            result_assign.1,
+            codetracer_trace_types::RValue::FunctionReturn { .. }
+        ),
+        "expected RValue::FunctionReturn for `result = foo()`, got {:?}",
+        result_assign.1
+    );
+}
+
+#[test]
+fn test_python_recorder_emits_destructuring_assignments() {
+    // `a, b = pair` must surface as TWO Assignment events with
+    // RValue::IndexAccess { receiver: pair, index: i }.
+    let body = r#"
+pair = (11, 22)
+a, b = pair
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let assignments = collect_assignments(&events);
+    let a_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "a")
+        .unwrap_or_else(|| {
+            panic!(
+                "expected Assignment for `a`, got assignments={:?}",
+                assignments
+            )
+        });
+    let b_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "b")
+        .unwrap_or_else(|| {
+            panic!(
+                "expected Assignment for `b`, got assignments={:?}",
+                assignments
+            )
+        });
+    match &a_assign.1 {
+        codetracer_trace_types::RValue::IndexAccess { receiver, index } => {
+            let src = variable_name_for(&events, *receiver).unwrap_or_default();
+            assert_eq!(src, "pair", "a: receiver expected pair, got {:?}", src);
+            assert_eq!(*index, 0, "a: expected index 0, got {}", index);
+        }
+        other => panic!("expected RValue::IndexAccess for a, got {:?}", other),
+    }
+    match &b_assign.1 {
+        codetracer_trace_types::RValue::IndexAccess { receiver, index } => {
+            let src = variable_name_for(&events, *receiver).unwrap_or_default();
+            assert_eq!(src, "pair", "b: receiver expected pair, got {:?}", src);
+            assert_eq!(*index, 1, "b: expected index 1, got {}", index);
+        }
+        other => panic!("expected RValue::IndexAccess for b, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_python_recorder_emits_step_records_for_column_carrying_lines() {
+    // P1.1/P1.2 (was: M14/M15 `StepRecord.column` test).  The
+    // canonical CTFS column-encoding path emits column-only
+    // `DeltaColumn` (tag 0x07) events on the multi-stream backend,
+    // not a column field on `StepRecord`.  Column-aware acceptance
+    // lives in `tests/python/test_column_aware_steps.py`, which
+    // drives a full CTFS round-trip via the recorder CLI.
+    //
+    // This unit test now keeps a weaker but still useful smoke
+    // assertion against the JSON test-double backend: a script with
+    // a STORE on every body line produces at least one `Step`
+    // event per line, proving the extraction-and-emission glue is
+    // intact for the non-column-aware path.  The JSON test double
+    // (`NonStreamingTraceWriter`) drops column data by design — see
+    // its `register_step_with_column` impl.
+    let body = r#"
+a = snap(10)
+b = snap(20)
+"#;
+    let events = run_traced_script_events(body);
+    let step_count = events
+        .iter()
+        .filter(|e| matches!(e, TraceLowLevelEvent::Step(_)))
+        .count();
+    assert!(
+        step_count >= 2,
+        "expected >=2 Step events (one per body line), got {} in {:?}",
+        step_count,
+        events
+            .iter()
+            .filter_map(|e| match e {
+                TraceLowLevelEvent::Step(rec) => Some(rec),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    );
+}
+
+/// M15 verification 6: Path A confidence-1.0 is a property of the
+/// db-backend's classifier, not the recorder. The recorder satisfies its
+/// half of the contract by ensuring the buffered event stream contains
+/// `Assignment` events for every store on a real-frame line. This test
+/// asserts that property: every `b = a`-style local-copy assignment
+/// emits an Assignment event with RValue::Simple, which is exactly what
+/// triggers Path A activation in `trace_processor.rs`. The db-backend
+/// then upgrades the per-hop confidence to 1.0 (spec §6.1.5).
+#[test]
+fn test_origin_chain_path_a_confidence_one() {
+    let body = r#"
+a = 10
+b = a
+c = b
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let assignments = collect_assignments(&events);
+
+    // b = a -> Simple(a)
+    let b_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "b")
+        .expect("Assignment(b) present");
+    assert!(
+        matches!(b_assign.1, codetracer_trace_types::RValue::Simple(_)),
+        "Path A: `b = a` needs Simple, got {:?}",
+        b_assign.1
+    );
+
+    // c = b -> Simple(b)
+    let c_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "c")
+        .expect("Assignment(c) present");
+    match &c_assign.1 {
+        codetracer_trace_types::RValue::Simple(id) => {
+            let src = variable_name_for(&events, *id).unwrap_or_default();
+            assert_eq!(
+                src, "b",
+                "Path A: `c = b` source should be b, got {:?}",
+                src
+            );
+        }
+        other => panic!("Path A: `c = b` needs Simple, got {:?}", other),
+    }
+
+    // The chain a (Literal) -> b (Simple a) -> c (Simple b) is
+    // wholly Path A reconstructible from the recorder events alone.
+    let a_assign = assignments
+        .iter()
+        .find(|(name, _)| name == "a")
+        .expect("Assignment(a) present");
+    assert!(
+        matches!(a_assign.1, codetracer_trace_types::RValue::Literal),
+        "Path A chain root must be Literal, got {:?}",
+        a_assign.1
+    );
+}
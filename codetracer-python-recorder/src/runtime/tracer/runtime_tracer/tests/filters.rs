@@ -0,0 +1,1014 @@
+//! Trace filters, skip decisions and their summaries.
+
+use super::*;
+
+#[test]
+fn trace_filter_redacts_values() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let filters_dir = project_root.join(".codetracer");
+        fs::create_dir(&filters_dir).expect("create .codetracer");
+        let filter_path = filters_dir.join("filters.toml");
+        write_filter(
+            &filter_path,
+            r#"
+                [meta]
+                name = "redact"
+                version = 1
+
+                [scope]
+                default_exec = "trace"
+                default_value_action = "allow"
+
+                [[scope.rules]]
+                selector = "pkg:app.sec"
+                exec = "trace"
+                value_default = "allow"
+
+                [[scope.rules.value_patterns]]
+                selector = "arg:password"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "local:password"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "local:secret"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "global:shared_secret"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "ret:literal:app.sec.sensitive"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "local:internal"
+                action = "drop"
+                "#,
+        );
+        let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let app_dir = project_root.join("app");
+        fs::create_dir_all(&app_dir).expect("create app dir");
+        let script_path = app_dir.join("sec.py");
+        let body = r#"
+shared_secret = "initial"
+
+def sensitive(password):
+    secret = "token"
+    internal = "hidden"
+    public = "visible"
+    globals()['shared_secret'] = password
+    snapshot()
+    emit_return(password)
+    return password
+
+sensitive("s3cr3t")
+"#;
+        let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
+        fs::write(&script_path, script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
+                project_root.display(),
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute filtered script");
+        }
+
+        let mut variable_names: Vec<String> = Vec::new();
+        for event in tracer.writer.events() {
+            if let TraceLowLevelEvent::VariableName(name) = event {
+                variable_names.push(name.clone());
+            }
+        }
+        assert!(
+            !variable_names.iter().any(|name| name == "internal"),
+            "internal variable should not be recorded"
+        );
+
+        let password_index = variable_names
+            .iter()
+            .position(|name| name == "password")
+            .expect("password variable recorded");
+        let password_value = tracer
+            .writer
+            .events()
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Value(record) if record.variable_id.0 == password_index => {
+                    Some(record.value.clone())
+                }
+                _ => None,
+            })
+            .expect("password value recorded");
+        match password_value {
+            ValueRecord::Error { ref msg, .. } => assert_eq!(msg, "<redacted>"),
+            ref other => panic!("expected password argument redacted, got {other:?}"),
+        }
+
+        let snapshots = collect_snapshots(tracer.writer.events());
+        let snapshot = find_snapshot_with_vars(
+            &snapshots,
+            &["secret", "public", "shared_secret", "password"],
+        );
+        assert_var(
+            snapshot,
+            "secret",
+            SimpleValue::Raw("<redacted>".to_string()),
+        );
+        assert_var(
+            snapshot,
+            "public",
+            SimpleValue::String("visible".to_string()),
+        );
+        assert_var(
+            snapshot,
+            "shared_secret",
+            SimpleValue::Raw("<redacted>".to_string()),
+        );
+        assert_var(
+            snapshot,
+            "password",
+            SimpleValue::Raw("<redacted>".to_string()),
+        );
+        assert_no_variable(&snapshots, "internal");
+
+        let return_record = tracer
+            .writer
+            .events()
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Return(record) => Some(record.clone()),
+                _ => None,
+            })
+            .expect("return record");
+
+        match return_record.return_value {
+            ValueRecord::Error { ref msg, .. } => assert_eq!(msg, "<redacted>"),
+            ref other => panic!("expected redacted return value, got {other:?}"),
+        }
+    });
+}
+
+#[test]
+fn user_drop_default_overrides_builtin_allowance() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let drop_filter_path = install_drop_everything_filter(project_root);
+
+        let config = TraceFilterConfig::from_inline_and_paths(
+            &[("builtin-default", BUILTIN_TRACE_FILTER)],
+            &[drop_filter_path.clone()],
+        )
+        .expect("load filter chain");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let app_dir = project_root.join("app");
+        fs::create_dir_all(&app_dir).expect("create app dir");
+        let script_path = app_dir.join("dropper.py");
+        let body = r#"
+def dropper():
+    secret = "token"
+    public = 42
+    snapshot()
+    emit_return(secret)
+    return secret
+
+dropper()
+"#;
+        let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
+        fs::write(&script_path, script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
+                project_root.display(),
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute dropper script");
+        }
+
+        let mut variable_names: Vec<String> = Vec::new();
+        let mut return_values: Vec<ValueRecord> = Vec::new();
+        for event in tracer.writer.events() {
+            match event {
+                TraceLowLevelEvent::VariableName(name) => variable_names.push(name.clone()),
+                TraceLowLevelEvent::Return(record) => {
+                    return_values.push(record.return_value.clone())
+                }
+                _ => {}
+            }
+        }
+        assert!(
+            variable_names.is_empty(),
+            "expected no variables captured, found {:?}",
+            variable_names
+        );
+        assert_eq!(
+            return_values.len(),
+            1,
+            "return event should remain balanced"
+        );
+        match &return_values[0] {
+            ValueRecord::Error { msg, .. } => assert_eq!(msg, "<dropped>"),
+            other => panic!("expected dropped sentinel return value, got {other:?}"),
+        }
+    });
+}
+
+#[test]
+fn keep_dropped_return_type_tags_sentinel_with_value_type() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let filters_dir = project_root.join(".codetracer");
+        fs::create_dir(&filters_dir).expect("create .codetracer");
+        let filter_path = filters_dir.join("filters.toml");
+        write_filter(
+            &filter_path,
+            r#"
+                [meta]
+                name = "drop-return"
+                version = 1
+
+                [scope]
+                default_exec = "trace"
+                default_value_action = "allow"
+
+                [[scope.rules]]
+                selector = "pkg:app.config"
+                exec = "trace"
+                value_default = "allow"
+
+                [[scope.rules.value_patterns]]
+                selector = "ret:literal:app.config.load"
+                action = "drop"
+                "#,
+        );
+        let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let app_dir = project_root.join("app");
+        fs::create_dir_all(&app_dir).expect("create app dir");
+        let script_path = app_dir.join("config.py");
+        let body = r#"
+def load():
+    settings = {"token": "s3cr3t"}
+    emit_return(settings)
+    return settings
+
+load()
+"#;
+        let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
+        fs::write(&script_path, script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+        tracer.apply_policy(&RecorderPolicy {
+            keep_dropped_return_type: true,
+            ..RecorderPolicy::default()
+        });
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
+                project_root.display(),
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute config script");
+        }
+
+        let events = tracer.writer.events();
+        let types: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Type(record) => Some(record),
+                _ => None,
+            })
+            .collect();
+        let type_id = events
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Return(record) => match &record.return_value {
+                    ValueRecord::Error { msg, type_id } if msg == "<dropped>" => Some(*type_id),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .expect("dropped return value recorded");
+        let record = types
+            .get(type_id.0)
+            .expect("type record for dropped return");
+        assert_eq!(record.lang_type, "Dict");
+        assert!(matches!(record.kind, codetracer_trace_types::TypeKind::Seq));
+        assert!(
+            !events.iter().any(|event| matches!(
+                event,
+                TraceLowLevelEvent::Return(record)
+                    if matches!(record.return_value, ValueRecord::Sequence { .. })
+            )),
+            "dropped return must not carry its contents"
+        );
+    });
+}
+
+#[test]
+fn skip_everything_filter_flags_empty_trace() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let filters_dir = project_root.join(".codetracer");
+        fs::create_dir(&filters_dir).expect("create .codetracer");
+        let filter_path = filters_dir.join("skip-all.toml");
+        write_filter(
+            &filter_path,
+            r#"
+                [meta]
+                name = "skip-all"
+                version = 1
+
+                [scope]
+                default_exec = "skip"
+                default_value_action = "allow"
+                "#,
+        );
+        let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let script_path = project_root.join("app.py");
+        let body = r#"
+def work(value):
+    start_call()
+    return emit_return(value + 1)
+
+work(1)
+"#;
+        fs::write(&script_path, format!("{PRELUDE}\n{body}")).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute skip-all script");
+        }
+
+        assert!(tracer.filter.everything_skipped());
+        tracer.finish(py).expect("finish tracer");
+
+        let summaries = written_trace_log_events(&outputs, "filter_summary");
+        assert_eq!(summaries.len(), 1, "expected one summary: {summaries:?}");
+        let summary = &summaries[0];
+        assert_eq!(summary["empty_trace_warning"], serde_json::json!(true));
+        assert_eq!(summary["scopes_traced"], serde_json::json!(0));
+        assert!(
+            summary["scopes_skipped"].as_u64().unwrap_or(0) >= 1,
+            "expected skipped scopes in summary: {summary}"
+        );
+    });
+}
+
+#[test]
+fn filter_cache_hits_dominate_repeated_calls() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let filters_dir = project_root.join(".codetracer");
+        fs::create_dir(&filters_dir).expect("create .codetracer");
+        let filter_path = filters_dir.join("trace-all.toml");
+        write_filter(
+            &filter_path,
+            r#"
+                [meta]
+                name = "trace-all"
+                version = 1
+
+                [scope]
+                default_exec = "trace"
+                default_value_action = "allow"
+                "#,
+        );
+        let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let script_path = project_root.join("app.py");
+        let body = r#"
+def work(value):
+    return value + 1
+
+total = 0
+for index in range(500):
+    total = work(total)
+"#;
+        fs::write(&script_path, format!("{PRELUDE}\n{body}")).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute loop script");
+        }
+        tracer.finish(py).expect("finish tracer");
+
+        let summaries = written_trace_log_events(&outputs, "filter_summary");
+        assert_eq!(summaries.len(), 1, "expected one summary: {summaries:?}");
+        let summary = &summaries[0];
+        let hits = summary["cache_hits"].as_u64().unwrap_or(0);
+        let misses = summary["cache_misses"].as_u64().unwrap_or(0);
+        assert!(misses >= 1, "first resolution must miss: {summary}");
+        assert!(hits >= 500, "every repeated call should hit: {summary}");
+        assert!(
+            hits > 4 * misses,
+            "cache hits should dominate misses: {summary}"
+        );
+    });
+}
+
+#[test]
+fn filter_decisions_attribute_skips_to_their_source() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let filters_dir = project_root.join(".codetracer");
+        fs::create_dir(&filters_dir).expect("create .codetracer");
+        let base_path = filters_dir.join("base.toml");
+        write_filter(
+            &base_path,
+            r#"
+                [meta]
+                name = "base"
+                version = 1
+
+                [scope]
+                default_exec = "trace"
+                default_value_action = "allow"
+
+                [[scope.rules]]
+                selector = "obj:app.unrelated"
+                exec = "trace"
+
+                [[scope.rules]]
+                selector = "obj:app.alpha"
+                exec = "skip"
+                "#,
+        );
+        let overlay_path = filters_dir.join("overlay.toml");
+        write_filter(
+            &overlay_path,
+            r#"
+                [meta]
+                name = "overlay"
+                version = 1
+
+                [scope]
+                default_exec = "trace"
+                default_value_action = "allow"
+
+                [[scope.rules]]
+                selector = "obj:app.beta"
+                exec = "skip"
+                "#,
+        );
+        let config = TraceFilterConfig::from_paths(&[base_path.clone(), overlay_path.clone()])
+            .expect("load filters");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let script_path = project_root.join("app.py");
+        let body = r#"
+def alpha():
+    start_call()
+    return emit_return(1)
+
+def beta():
+    start_call()
+    return emit_return(2)
+
+alpha()
+beta()
+"#;
+        fs::write(&script_path, format!("{PRELUDE}\n{body}")).expect("write script");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+        tracer.apply_policy(&RecorderPolicy {
+            log_filter_decisions: true,
+            ..RecorderPolicy::default()
+        });
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute filtered script");
+        }
+        tracer.finish(py).expect("finish tracer");
+
+        let report: serde_json::Value = serde_json::from_slice(
+            &fs::read(outputs.filter_decisions()).expect("read filter decisions"),
+        )
+        .expect("parse filter decisions");
+        let skips = report["skips"].as_array().expect("skips array");
+        let skip_for = |qualname: &str| {
+            skips
+                .iter()
+                .find(|entry| entry["qualname"] == qualname)
+                .unwrap_or_else(|| panic!("no skip recorded for {qualname}: {report}"))
+        };
+
+        let alpha = skip_for("alpha");
+        assert_eq!(alpha["source"], base_path.display().to_string());
+        assert_eq!(alpha["rule_index"], 1);
+        let beta = skip_for("beta");
+        assert_eq!(beta["source"], overlay_path.display().to_string());
+        assert_eq!(beta["rule_index"], 0);
+    });
+}
+
+#[test]
+fn recorder_package_code_is_never_traced() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+        let mut tracer = RuntimeTracer::new(
+            "test.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        // The imported recorder package, standing in for an install.
+        let package_dir = tmp.path().join("site").join("codetracer_python_recorder");
+        fs::create_dir_all(&package_dir).expect("create package dir");
+        let package_init = package_dir.join("__init__.py");
+        fs::write(&package_init, "").expect("write package init");
+        let helper_path = package_dir.join("session.py");
+        std::fs::write(
+            &helper_path,
+            "def stop(snapshot):\n    snapshot()\n    return 'stopped'\n",
+        )
+        .expect("write helper");
+        // A user checkout that merely sits under a directory with the
+        // package's name is still user code.
+        let checkout_dir = tmp
+            .path()
+            .join("src")
+            .join("codetracer_python_recorder")
+            .join("examples");
+        fs::create_dir_all(&checkout_dir).expect("create checkout dir");
+        let script_path = checkout_dir.join("script.py");
+        let script = format!(
+            "{PRELUDE}\nimport runpy\nhelper = runpy.run_path(r\"{}\")\nresult = helper[\"stop\"](snapshot)\nsnapshot()\n",
+            helper_path.display()
+        );
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let modules = py
+            .import("sys")
+            .and_then(|sys| sys.getattr("modules"))
+            .expect("sys.modules");
+        let previous = modules.get_item("codetracer_python_recorder").ok();
+        let package = PyModule::new(py, "codetracer_python_recorder").expect("create module");
+        package
+            .setattr("__file__", package_init.to_string_lossy().as_ref())
+            .expect("set __file__");
+        modules
+            .set_item("codetracer_python_recorder", package)
+            .expect("register package");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute script calling recorder helper");
+        }
+        match previous {
+            Some(previous) => modules
+                .set_item("codetracer_python_recorder", previous)
+                .expect("restore package"),
+            None => modules
+                .del_item("codetracer_python_recorder")
+                .expect("unregister package"),
+        }
+
+        let traced: Vec<&Path> = tracer
+            .path_stats
+            .top_paths(usize::MAX)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert!(
+            !traced.contains(&helper_path.as_path()),
+            "recorder helper was traced: {traced:?}"
+        );
+        assert!(
+            traced.contains(&script_path.as_path()),
+            "user script under a same-named directory was skipped: {traced:?}"
+        );
+    });
+}
+
+#[test]
+fn drop_filters_keep_call_return_pairs_balanced() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let drop_filter_path = install_drop_everything_filter(project_root);
+
+        let config = TraceFilterConfig::from_inline_and_paths(
+            &[("builtin-default", BUILTIN_TRACE_FILTER)],
+            &[drop_filter_path.clone()],
+        )
+        .expect("load filter chain");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let app_dir = project_root.join("app");
+        fs::create_dir_all(&app_dir).expect("create app dir");
+        let script_path = app_dir.join("classes.py");
+        let body = r#"
+def initializer(label):
+    start_call()
+    return emit_return(label.upper())
+
+class Alpha:
+    TOKEN = initializer("alpha")
+
+class Beta:
+    TOKEN = initializer("beta")
+
+class Gamma:
+    TOKEN = initializer("gamma")
+
+initializer("omega")
+"#;
+        let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
+        fs::write(&script_path, script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
+                project_root.display(),
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute classes script");
+        }
+
+        let mut call_count = 0usize;
+        let mut return_count = 0usize;
+        for event in tracer.writer.events() {
+            match event {
+                TraceLowLevelEvent::Call(_) => call_count += 1,
+                TraceLowLevelEvent::Return(_) => return_count += 1,
+                _ => {}
+            }
+        }
+        assert!(
+            call_count >= 4,
+            "expected at least four call events, saw {call_count}"
+        );
+        assert_eq!(
+            call_count, return_count,
+            "drop filters must keep call/return pairs balanced"
+        );
+    });
+}
+
+// FIXME(follow-up #254 phase 2): rewrite using `MetaDatReader` once the
+// crate exposes filter-provenance accessors.  The original assertion
+// read the trace_filter chain from the `trace_metadata.json` sidecar,
+// which the v3 CTFS rollout retired.  Filter provenance is still
+// recorded — it just lives inside `meta.dat` now (see TF-M7 spec § 7).
+#[ignore]
+#[test]
+fn trace_filter_metadata_includes_summary() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        let filters_dir = project_root.join(".codetracer");
+        fs::create_dir(&filters_dir).expect("create .codetracer");
+        let filter_path = filters_dir.join("filters.toml");
+        write_filter(
+            &filter_path,
+            r#"
+                [meta]
+                name = "redact"
+                version = 1
+
+                [scope]
+                default_exec = "trace"
+                default_value_action = "allow"
+
+                [[scope.rules]]
+                selector = "pkg:app.sec"
+                exec = "trace"
+                value_default = "allow"
+
+                [[scope.rules.value_patterns]]
+                selector = "arg:password"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "local:password"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "local:secret"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "global:shared_secret"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "ret:literal:app.sec.sensitive"
+                action = "redact"
+
+                [[scope.rules.value_patterns]]
+                selector = "local:internal"
+                action = "drop"
+                "#,
+        );
+        let config = TraceFilterConfig::from_paths(&[filter_path]).expect("load filter");
+        let engine = Arc::new(TraceFilterEngine::new(config));
+
+        let app_dir = project_root.join("app");
+        fs::create_dir_all(&app_dir).expect("create app dir");
+        let script_path = app_dir.join("sec.py");
+        let body = r#"
+shared_secret = "initial"
+
+def sensitive(password):
+    secret = "token"
+    internal = "hidden"
+    public = "visible"
+    globals()['shared_secret'] = password
+    snapshot()
+    emit_return(password)
+    return password
+
+sensitive("s3cr3t")
+"#;
+        let script = format!("{PRELUDE}\n{body}", PRELUDE = PRELUDE, body = body);
+        fs::write(&script_path, script).expect("write script");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+
+        let program = script_path.to_string_lossy().into_owned();
+        let mut tracer = RuntimeTracer::new(
+            &program,
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            Some(engine),
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\")",
+                project_root.display(),
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        // Filter chain provenance is now embedded in `meta.dat` inside
+        // the CTFS container (TF-M7 spec § 7).  This test is gated with
+        // `#[ignore]` above until the `MetaDatReader` exposes filter-
+        // provenance accessors; the original assertions read the
+        // retired `trace_metadata.json` sidecar.
+        let _ = outputs;
+    });
+}
+
+#[test]
+fn exclude_stdlib_skips_standard_library_frames() {
+    let body = r#"
+import json
+import os
+
+def encode_payload(value):
+    return json.dumps(value)
+
+def join_parts(*parts):
+    return os.path.join(*parts)
+
+encode_payload({"a": 1})
+join_parts("a", "b")
+"#;
+    let function_names = |policy: &RecorderPolicy| -> Vec<String> {
+        run_traced_script_events_with_policy(body, policy)
+            .into_iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Function(record) => Some(record.name),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let everything = function_names(&RecorderPolicy::default());
+    for stdlib in ["dumps", "join"] {
+        assert!(
+            everything.iter().any(|name| name == stdlib),
+            "{stdlib} should be traced without the flag: {everything:?}"
+        );
+    }
+
+    let policy = RecorderPolicy {
+        exclude_stdlib: true,
+        ..RecorderPolicy::default()
+    };
+    let user_only = function_names(&policy);
+    for user in ["encode_payload", "join_parts"] {
+        assert!(
+            user_only.iter().any(|name| name == user),
+            "{user} should still be traced: {user_only:?}"
+        );
+    }
+    for stdlib in ["dumps", "join"] {
+        assert!(
+            !user_only.iter().any(|name| name == stdlib),
+            "{stdlib} should be skipped: {user_only:?}"
+        );
+    }
+}
+
+#[test]
+fn skip_comprehensions_hides_comprehension_frames() {
+    let body = r#"
+def squares(n):
+    total = sum(x * x for x in range(n))
+    evens = [x for x in range(n) if x % 2 == 0]
+    return total, evens
+
+squares(4)
+"#;
+    let function_names = |policy: &RecorderPolicy| -> Vec<String> {
+        run_traced_script_events_with_policy(body, policy)
+            .into_iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Function(record) => Some(record.name),
+                _ => None,
+            })
+            .collect()
+    };
+    let is_comprehension = |name: &String| {
+        ["<genexpr>", "<listcomp>", "<setcomp>", "<dictcomp>"]
+            .iter()
+            .any(|form| name.ends_with(form))
+    };
+
+    let everything = function_names(&RecorderPolicy::default());
+    assert!(
+        everything.iter().any(is_comprehension),
+        "the generator expression has a frame of its own: {everything:?}"
+    );
+
+    let policy = RecorderPolicy {
+        skip_comprehensions: true,
+        ..RecorderPolicy::default()
+    };
+    let names = function_names(&policy);
+    assert!(
+        names.iter().any(|name| name == "squares"),
+        "the enclosing function is still traced: {names:?}"
+    );
+    assert!(
+        !names.iter().any(is_comprehension),
+        "comprehension frames are skipped: {names:?}"
+    );
+}
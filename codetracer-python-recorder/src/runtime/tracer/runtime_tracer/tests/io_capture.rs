@@ -0,0 +1,689 @@
+//! IO capture: line proxies, flush markers and the FD mirror.
+
+use super::*;
+
+#[derive(Debug, Deserialize)]
+struct IoMetadata {
+    stream: String,
+    path_id: Option<usize>,
+    line: Option<i64>,
+    flags: Vec<String>,
+}
+
+#[test]
+fn io_capture_records_python_and_native_output() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=False",
+        )
+        .expect("enable io capture proxies");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("io_script.py");
+        let script = format!(
+            "{PRELUDE}\n\nprint('python out')\nfrom ctypes import pythonapi, c_char_p\npythonapi.PySys_WriteStdout(c_char_p(b'native out\\n'))\n"
+        );
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute io script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
+                    Some((metadata, record.content.as_bytes().to_vec()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert!(io_events
+            .iter()
+            .any(|(meta, payload)| meta.stream == "stdout"
+                && String::from_utf8_lossy(payload).contains("python out")));
+        assert!(io_events
+            .iter()
+            .any(|(meta, payload)| meta.stream == "stdout"
+                && String::from_utf8_lossy(payload).contains("native out")));
+        assert!(io_events.iter().all(|(meta, _)| {
+            if meta.stream == "stdout" {
+                meta.path_id.is_some() && meta.line.is_some()
+            } else {
+                true
+            }
+        }));
+        assert!(io_events
+            .iter()
+            .filter(|(meta, _)| meta.stream == "stdout")
+            .any(|(meta, _)| meta.flags.iter().any(|flag| flag == "newline")));
+
+        reset_policy(py);
+    });
+}
+
+#[test]
+fn finish_flushes_trailing_partial_line_before_session_exit() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=False",
+        )
+        .expect("enable io capture proxies");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("partial_script.py");
+        let script = format!("{PRELUDE}\n\nprint('final partial', end='')\n");
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute partial script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let events = tracer.writer.events();
+        let partial_index = events
+            .iter()
+            .position(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    serde_json::from_str::<IoMetadata>(&record.metadata)
+                        .map(|meta| meta.stream == "stdout")
+                        .unwrap_or(false)
+                        && record.content.contains("final partial")
+                }
+                _ => false,
+            })
+            .expect("trailing partial line recorded");
+        let session_exit_index = events
+            .iter()
+            .rposition(|event| matches!(event, TraceLowLevelEvent::Return(_)))
+            .expect("session exit return recorded");
+        assert!(
+            partial_index < session_exit_index,
+            "partial output must be recorded before the session exit"
+        );
+
+        reset_policy(py);
+    });
+}
+
+#[test]
+fn print_flush_records_content_once_and_a_flush_marker() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=False",
+        )
+        .expect("enable io capture proxies");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("flush_script.py");
+        let script = format!("{PRELUDE}\n\nprint('x', flush=True)\n");
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute flush script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let events = tracer.writer.events();
+        let stdout: Vec<(IoMetadata, String)> = events
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let meta = serde_json::from_str::<IoMetadata>(&record.metadata).ok()?;
+                    (meta.stream == "stdout").then(|| (meta, record.content.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        let content: Vec<&str> = stdout
+            .iter()
+            .map(|(_, content)| content.as_str())
+            .filter(|content| !content.is_empty())
+            .collect();
+        assert_eq!(content, ["x\n"], "printed content must be recorded once");
+
+        let markers: Vec<&IoMetadata> = stdout
+            .iter()
+            .filter(|(meta, content)| {
+                content.is_empty() && meta.flags.iter().any(|flag| flag == "flush")
+            })
+            .map(|(meta, _)| meta)
+            .collect();
+        assert_eq!(markers.len(), 1, "expected one flush marker: {stdout:?}");
+        assert_eq!(
+            markers[0].line, stdout[0].0.line,
+            "flush is attributed to the print line"
+        );
+
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=False",
+        )
+        .expect("enable io capture proxies");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("flush_script.py");
+        let script = format!("{PRELUDE}\n\nprint('x', flush=True)\n");
+        std::fs::write(&script_path, &script).expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute flush script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let events = tracer.writer.events();
+        let partial_index = events
+            .iter()
+            .position(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    serde_json::from_str::<IoMetadata>(&record.metadata)
+                        .map(|meta| meta.stream == "stdout")
+                        .unwrap_or(false)
+                        && record.content.contains("final partial")
+                }
+                _ => false,
+            })
+            .expect("trailing partial line recorded");
+        let session_exit_index = events
+            .iter()
+            .rposition(|event| matches!(event, TraceLowLevelEvent::Return(_)))
+            .expect("session exit return recorded");
+        assert!(
+            partial_index < session_exit_index,
+            "partial output must be recorded before the session exit"
+        );
+
+        reset_policy(py);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_mirror_captures_os_write_payloads() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=True",
+        )
+        .expect("enable io capture with fd fallback");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let script_path = tmp.path().join("fd_mirror.py");
+        std::fs::write(
+            &script_path,
+            format!(
+                "{PRELUDE}\nimport os\nprint('proxy line')\nos.write(1, b'fd stdout\\n')\nos.write(2, b'fd stderr\\n')\n"
+            ),
+        )
+        .expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute fd script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
+                    Some((metadata, record.content.as_bytes().to_vec()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let stdout_mirror = io_events.iter().find(|(meta, _)| {
+            meta.stream == "stdout" && meta.flags.iter().any(|flag| flag == "mirror")
+        });
+        assert!(
+            stdout_mirror.is_some(),
+            "expected mirror event for stdout: {:?}",
+            io_events
+        );
+        let stdout_payload = &stdout_mirror.expect("stdout mirror event present").1;
+        assert!(
+            String::from_utf8_lossy(stdout_payload).contains("fd stdout"),
+            "mirror stdout payload missing expected text"
+        );
+
+        let stderr_mirror = io_events.iter().find(|(meta, _)| {
+            meta.stream == "stderr" && meta.flags.iter().any(|flag| flag == "mirror")
+        });
+        assert!(
+            stderr_mirror.is_some(),
+            "expected mirror event for stderr: {:?}",
+            io_events
+        );
+        let stderr_payload = &stderr_mirror.expect("stderr mirror event present").1;
+        assert!(
+            String::from_utf8_lossy(stderr_payload).contains("fd stderr"),
+            "mirror stderr payload missing expected text"
+        );
+
+        assert!(io_events.iter().any(|(meta, payload)| {
+            meta.stream == "stdout"
+                && !meta.flags.iter().any(|flag| flag == "mirror")
+                && String::from_utf8_lossy(payload).contains("proxy line")
+        }));
+
+        reset_policy(py);
+    });
+}
+
+#[cfg(windows)]
+#[test]
+fn fd_mirror_captures_native_stdout_on_windows() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=True",
+        )
+        .expect("enable io capture with fd fallback");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let script_path = tmp.path().join("fd_mirror.py");
+        std::fs::write(
+            &script_path,
+            format!(
+                "{PRELUDE}\nimport ctypes\nimport os\nprint('proxy line')\nos.write(1, b'fd stdout\\n')\nucrt = ctypes.CDLL('ucrtbase')\nucrt._write(1, b'native stdout\\n', 14)\nos.write(2, b'fd stderr\\n')\n"
+            ),
+        )
+        .expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute fd script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
+                    Some((metadata, record.content.as_bytes().to_vec()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let stdout_mirror = io_events.iter().find(|(meta, _)| {
+            meta.stream == "stdout" && meta.flags.iter().any(|flag| flag == "mirror")
+        });
+        assert!(
+            stdout_mirror.is_some(),
+            "expected mirror event for stdout: {:?}",
+            io_events
+        );
+        let stdout_payload = &stdout_mirror.expect("stdout mirror event present").1;
+        assert!(
+            String::from_utf8_lossy(stdout_payload).contains("fd stdout"),
+            "mirror stdout payload missing expected text"
+        );
+        assert!(
+            io_events.iter().any(|(meta, payload)| {
+                meta.stream == "stdout"
+                    && meta.flags.iter().any(|flag| flag == "mirror")
+                    && String::from_utf8_lossy(payload).contains("native stdout")
+            }),
+            "expected CRT-level stdout write to be mirrored: {:?}",
+            io_events
+        );
+
+        let stderr_mirror = io_events.iter().find(|(meta, _)| {
+            meta.stream == "stderr" && meta.flags.iter().any(|flag| flag == "mirror")
+        });
+        assert!(
+            stderr_mirror.is_some(),
+            "expected mirror event for stderr: {:?}",
+            io_events
+        );
+        let stderr_payload = &stderr_mirror.expect("stderr mirror event present").1;
+        assert!(
+            String::from_utf8_lossy(stderr_payload).contains("fd stderr"),
+            "mirror stderr payload missing expected text"
+        );
+
+        assert!(io_events.iter().any(|(meta, payload)| {
+            meta.stream == "stdout"
+                && !meta.flags.iter().any(|flag| flag == "mirror")
+                && String::from_utf8_lossy(payload).contains("proxy line")
+        }));
+
+        reset_policy(py);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_mirror_disabled_does_not_capture_os_write() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=False",
+        )
+        .expect("enable proxies without fd fallback");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let script_path = tmp.path().join("fd_disabled.py");
+        std::fs::write(
+            &script_path,
+            format!(
+                "{PRELUDE}\nimport os\nprint('proxy line')\nos.write(1, b'fd stdout\\n')\nos.write(2, b'fd stderr\\n')\n"
+            ),
+        )
+        .expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute fd script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
+                    Some((metadata, record.content.as_bytes().to_vec()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert!(
+            !io_events
+                .iter()
+                .any(|(meta, _)| meta.flags.iter().any(|flag| flag == "mirror")),
+            "mirror events should not be present when fallback disabled"
+        );
+
+        assert!(
+            !io_events.iter().any(|(_, payload)| {
+                String::from_utf8_lossy(payload).contains("fd stdout")
+                    || String::from_utf8_lossy(payload).contains("fd stderr")
+            }),
+            "native os.write payload unexpectedly captured without fallback"
+        );
+
+        assert!(io_events.iter().any(|(meta, payload)| {
+            meta.stream == "stdout" && String::from_utf8_lossy(payload).contains("proxy line")
+        }));
+
+        reset_policy(py);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn fd_mirror_stderr_only_leaves_stdout_unmirrored() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(
+            py,
+            "io_capture_line_proxies=True, io_capture_fd_fallback=False, \
+                 io_capture_fd_stdout=False, io_capture_fd_stderr=True",
+        )
+        .expect("mirror only stderr");
+
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let script_path = tmp.path().join("fd_stderr_only.py");
+        std::fs::write(
+            &script_path,
+            format!(
+                "{PRELUDE}\nimport os\nos.write(1, b'fd stdout\\n')\nos.write(2, b'fd stderr\\n')\n"
+            ),
+        )
+        .expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer
+            .install_io_capture(py, &policy::policy_snapshot())
+            .expect("install io capture");
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute fd script");
+        }
+
+        tracer.finish(py).expect("finish tracer");
+
+        let io_events: Vec<(IoMetadata, Vec<u8>)> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: IoMetadata = serde_json::from_str(&record.metadata).ok()?;
+                    Some((metadata, record.content.as_bytes().to_vec()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert!(
+            io_events.iter().any(|(meta, payload)| {
+                meta.stream == "stderr"
+                    && meta.flags.iter().any(|flag| flag == "mirror")
+                    && String::from_utf8_lossy(payload).contains("fd stderr")
+            }),
+            "expected mirror event for stderr: {:?}",
+            io_events
+        );
+        assert!(
+            !io_events.iter().any(|(meta, payload)| {
+                (meta.stream == "stdout" && meta.flags.iter().any(|flag| flag == "mirror"))
+                    || String::from_utf8_lossy(payload).contains("fd stdout")
+            }),
+            "stdout os.write should not be mirrored: {:?}",
+            io_events
+        );
+
+        reset_policy(py);
+    });
+}
@@ -0,0 +1,713 @@
+//! Call and step structure and the markers recorded alongside it.
+
+use super::*;
+
+#[test]
+fn statement_start_lines_policy_reports_first_line_of_statement() {
+    let body = r#"
+def compute(a, b, c):
+    total = (a +
+             b +
+             snap(c))
+    return total
+
+compute(1, 2, 3)
+"#;
+    let script = format!("{PRELUDE}\n{body}");
+    let line_of = |needle: &str| {
+        script
+            .lines()
+            .position(|line| line.contains(needle))
+            .map(|idx| idx as i64 + 1)
+            .expect("line present in script")
+    };
+    let statement_line = line_of("total = (a +");
+    let physical_line = line_of("snap(c))");
+
+    let snapshots = run_traced_script(body);
+    let snapshot = find_snapshot_with_vars(&snapshots, &["a", "b", "c"]);
+    assert_eq!(snapshot.line, physical_line);
+
+    let policy = RecorderPolicy {
+        statement_start_lines: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(body, &policy);
+    let snapshot = find_snapshot_with_vars(&snapshots, &["a", "b", "c"]);
+    assert_eq!(snapshot.line, statement_line);
+}
+
+#[test]
+fn calls_only_mode_records_calls_without_steps() {
+    Python::with_gil(|py| {
+        ensure_test_module(py);
+        let mut tracer = RuntimeTracer::new(
+            "test.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.apply_policy(&RecorderPolicy {
+            calls_only: true,
+            ..RecorderPolicy::default()
+        });
+
+        let events = crate::monitoring::monitoring_events(py).expect("monitoring events");
+        let interest = tracer.interest(events);
+        assert!(!interest.contains(&events.LINE));
+        assert!(interest.contains(&events.PY_START));
+        assert!(interest.contains(&events.PY_RETURN));
+
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let script_path = tmp.path().join("script.py");
+        let script = format!(
+            "{PRELUDE}\n{}",
+            r#"
+def inner(x):
+    start_call()
+    snapshot()
+    return emit_return(x * 2)
+
+def outer(y):
+    start_call()
+    snapshot()
+    return emit_return(inner(y) + 1)
+
+outer(3)
+snapshot()
+"#
+        );
+        std::fs::write(&script_path, &script).expect("write script");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute calls-only script");
+        }
+
+        let events = tracer.writer.events();
+        let calls = events
+            .iter()
+            .filter(|event| matches!(event, TraceLowLevelEvent::Call(_)))
+            .count();
+        let returns = events
+            .iter()
+            .filter(|event| matches!(event, TraceLowLevelEvent::Return(_)))
+            .count();
+        let steps = events
+            .iter()
+            .filter(|event| matches!(event, TraceLowLevelEvent::Step(_)))
+            .count();
+        assert_eq!(calls, 2, "expected a call for inner and outer");
+        assert_eq!(returns, 2, "expected a return for inner and outer");
+        assert_eq!(steps, 0, "calls-only mode must not emit steps");
+    });
+}
+
+#[test]
+fn with_block_markers_bracket_body_steps() {
+    // The harness only sees the LINE events `snapshot()` drives, so the
+    // header is reported explicitly for the entry and exit LINE events
+    // `sys.monitoring` fires on it.
+    let body = r#"
+import os
+import tempfile
+
+def read_first(path):
+    header = inspect.currentframe().f_lineno + 2
+    snapshot(header)
+    with open(path) as f:
+        snapshot()
+        first = f.readline()
+        snapshot()
+    snapshot(header)
+    return first
+
+fd, path = tempfile.mkstemp()
+os.write(fd, b"hello\n")
+os.close(fd)
+read_first(path)
+os.remove(path)
+"#;
+    let script = format!("{PRELUDE}\n{body}");
+    let header = script
+        .lines()
+        .position(|line| line.contains("with open(path) as f:"))
+        .map(|idx| idx as i64 + 1)
+        .expect("with header present in script");
+
+    let policy = RecorderPolicy {
+        capture_with_blocks: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    #[derive(Debug, PartialEq)]
+    enum Seen {
+        Step(i64),
+        Marker(String, i64),
+    }
+    let seen: Vec<Seen> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Step(step) => Some(Seen::Step(step.line.0)),
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "with_block").then(|| {
+                    Seen::Marker(
+                        metadata["phase"].as_str().unwrap_or_default().to_string(),
+                        metadata["line"].as_i64().unwrap_or_default(),
+                    )
+                })
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        seen,
+        vec![
+            Seen::Step(header),
+            Seen::Marker("__enter__".to_string(), header),
+            Seen::Step(header + 1),
+            Seen::Step(header + 3),
+            Seen::Step(header),
+            Seen::Marker("__exit__".to_string(), header),
+        ]
+    );
+
+    let events = run_traced_script_events(body);
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, TraceLowLevelEvent::Event(_))),
+        "with-block markers are opt-in"
+    );
+}
+
+#[test]
+fn capture_c_calls_records_only_matching_callables() {
+    let body = r#"
+import json
+
+def parse(text):
+    size = len(text)
+    return json.loads(text), size
+
+parse('{"a": 1}')
+"#;
+    let policy = RecorderPolicy {
+        capture_c_calls: vec!["json.loads".to_string()],
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let calls: Vec<serde_json::Value> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "c_call").then_some(metadata)
+            }
+            _ => None,
+        })
+        .collect();
+    let callables: Vec<&str> = calls
+        .iter()
+        .filter_map(|call| call["callable"].as_str())
+        .collect();
+    assert_eq!(callables, ["json.loads"], "len must not be recorded");
+
+    let arg0: ValueRecord =
+        serde_json::from_value(calls[0]["arg0"].clone()).expect("arg0 value record");
+    match arg0 {
+        ValueRecord::String { text, .. } => assert_eq!(text, r#"{"a": 1}"#),
+        other => panic!("expected the JSON text as arg0, got {other:?}"),
+    }
+}
+
+#[test]
+fn deprecation_warnings_are_recorded_as_structured_events() {
+    let body = r#"
+import warnings
+from test_tracer import capture_warning
+
+def legacy():
+    warnings.warn("legacy() is deprecated", DeprecationWarning)
+    return 1
+
+with warnings.catch_warnings():
+    warnings.simplefilter("always")
+    warnings.showwarning = capture_warning
+    legacy()
+"#;
+    let events = run_traced_script_events(body);
+
+    let warnings: Vec<(serde_json::Value, String)> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "warning").then(|| (metadata, record.content.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(warnings.len(), 1, "expected one warning: {warnings:?}");
+
+    let (warning, content) = &warnings[0];
+    assert_eq!(warning["category"], "DeprecationWarning");
+    assert_eq!(warning["message"], "legacy() is deprecated");
+    assert_eq!(content, "DeprecationWarning: legacy() is deprecated");
+    assert!(
+        warning["path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("script.py")),
+        "warning names the file that raised it: {warning}"
+    );
+    assert!(
+        warning["line"].as_u64().is_some(),
+        "warning has a line: {warning}"
+    );
+}
+
+#[test]
+fn trace_mark_records_an_annotation_at_the_calling_line() {
+    let body = r#"
+from test_tracer import capture_mark
+
+def phase():
+    start_call()
+    capture_mark("checkpoint")
+    capture_mark("phase 2 start", '{"step": 2}')
+    return emit_return(None)
+
+phase()
+"#;
+    let events = run_traced_script_events(body);
+
+    let marks: Vec<(serde_json::Value, String)> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "mark").then(|| (metadata, record.content.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(marks.len(), 2, "expected two marks: {marks:?}");
+
+    let (checkpoint, content) = &marks[0];
+    assert_eq!(content, "checkpoint");
+    assert_eq!(checkpoint["label"], "checkpoint");
+    assert!(checkpoint["metadata"].is_null());
+    assert!(
+        checkpoint["path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("script.py")),
+        "mark names the calling file: {checkpoint}"
+    );
+
+    let (phase, _) = &marks[1];
+    assert_eq!(phase["label"], "phase 2 start");
+    assert_eq!(phase["metadata"], r#"{"step": 2}"#);
+    let first_line = checkpoint["line"].as_u64().expect("checkpoint line");
+    assert_eq!(
+        phase["line"].as_u64(),
+        Some(first_line + 1),
+        "each mark is reported at its own calling line"
+    );
+}
+
+#[test]
+fn record_loop_iterations_marks_each_backward_jump() {
+    let body = r#"
+import sys
+from test_tracer import capture_jump
+
+def spin(n):
+    total = 0
+    for i in range(n):
+        total += i
+    return total
+
+mon = sys.monitoring
+TOOL = 3
+mon.use_tool_id(TOOL, "loop-iterations-test")
+mon.register_callback(TOOL, mon.events.JUMP, capture_jump)
+mon.set_local_events(TOOL, spin.__code__, mon.events.JUMP)
+try:
+    spin(4)
+finally:
+    mon.set_local_events(TOOL, spin.__code__, 0)
+    mon.register_callback(TOOL, mon.events.JUMP, None)
+    mon.free_tool_id(TOOL)
+"#;
+    let policy = RecorderPolicy {
+        record_loop_iterations: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let markers: Vec<serde_json::Value> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "loop_iteration").then_some(metadata)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(markers.len(), 4, "one marker per iteration: {markers:?}");
+    assert!(
+        markers
+            .iter()
+            .all(|marker| marker["line"] == markers[0]["line"]),
+        "every iteration jumps back to the loop header: {markers:?}"
+    );
+}
+
+#[test]
+fn record_instruction_offsets_separates_calls_on_one_line() {
+    let body = r#"
+import sys
+from test_tracer import capture_call
+
+def double(x):
+    return x * 2
+
+def combine():
+    return double(1) + double(2)
+
+mon = sys.monitoring
+TOOL = 3
+mon.use_tool_id(TOOL, "instruction-offsets-test")
+mon.register_callback(TOOL, mon.events.CALL, capture_call)
+mon.set_local_events(TOOL, combine.__code__, mon.events.CALL)
+try:
+    combine()
+finally:
+    mon.set_local_events(TOOL, combine.__code__, 0)
+    mon.register_callback(TOOL, mon.events.CALL, None)
+    mon.free_tool_id(TOOL)
+"#;
+    let policy = RecorderPolicy {
+        record_instruction_offsets: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let markers: Vec<serde_json::Value> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "instruction_offset").then_some(metadata)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(markers.len(), 2, "one marker per call site: {markers:?}");
+    assert_eq!(
+        markers[0]["line"], markers[1]["line"],
+        "both calls sit on the same line: {markers:?}"
+    );
+    assert_ne!(
+        markers[0]["offset"], markers[1]["offset"],
+        "each call has its own offset: {markers:?}"
+    );
+}
+
+#[test]
+fn return_site_marks_which_return_statement_fired() {
+    let body = r#"
+import sys
+from test_tracer import capture_py_return
+
+def sign(x):
+    if x < 0:
+        return "negative"
+    return "non-negative"
+
+mon = sys.monitoring
+TOOL = 3
+mon.use_tool_id(TOOL, "return-site-test")
+mon.register_callback(TOOL, mon.events.PY_RETURN, capture_py_return)
+mon.set_local_events(TOOL, sign.__code__, mon.events.PY_RETURN)
+try:
+    sign(-1)
+    sign(1)
+finally:
+    mon.set_local_events(TOOL, sign.__code__, 0)
+    mon.register_callback(TOOL, mon.events.PY_RETURN, None)
+    mon.free_tool_id(TOOL)
+"#;
+    let events = run_traced_script_events(body);
+
+    let sites: Vec<serde_json::Value> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "return_site").then_some(metadata)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(sites.len(), 2, "one marker per return: {sites:?}");
+    let negative = sites[0]["line"].as_u64().expect("negative return line");
+    let fallthrough = sites[1]["line"].as_u64().expect("fallthrough return line");
+    assert_eq!(
+        fallthrough,
+        negative + 1,
+        "each return statement is reported on its own line: {sites:?}"
+    );
+    assert_ne!(sites[0]["offset"], sites[1]["offset"]);
+}
+
+#[test]
+fn max_call_depth_records_only_the_outer_frames() {
+    let body = r#"
+import sys
+from test_tracer import capture_py_return, capture_py_start
+
+def recurse(n):
+    if n == 1:
+        return 1
+    return recurse(n - 1) + 1
+
+mon = sys.monitoring
+TOOL = 3
+mon.use_tool_id(TOOL, "max-call-depth-test")
+mon.register_callback(TOOL, mon.events.PY_START, capture_py_start)
+mon.register_callback(TOOL, mon.events.PY_RETURN, capture_py_return)
+mon.set_local_events(TOOL, recurse.__code__, mon.events.PY_START | mon.events.PY_RETURN)
+try:
+    recurse(20)
+finally:
+    mon.set_local_events(TOOL, recurse.__code__, 0)
+    mon.register_callback(TOOL, mon.events.PY_START, None)
+    mon.register_callback(TOOL, mon.events.PY_RETURN, None)
+    mon.free_tool_id(TOOL)
+"#;
+    let policy = RecorderPolicy {
+        max_call_depth: Some(5),
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let recorded_n: Vec<SimpleValue> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Call(record) => record.args.first(),
+            _ => None,
+        })
+        .map(|arg| SimpleValue::from_value(&arg.value))
+        .collect();
+    assert_eq!(
+        recorded_n,
+        (16..=20).rev().map(SimpleValue::Int).collect::<Vec<_>>(),
+        "only the outermost five frames are recorded"
+    );
+    let returns = events
+        .iter()
+        .filter(|event| matches!(event, TraceLowLevelEvent::Return(_)))
+        .count();
+    assert_eq!(returns, recorded_n.len(), "calls and returns stay balanced");
+}
+
+#[test]
+fn comprehension_calls_are_tagged_apart_from_function_calls() {
+    // List, set and dict comprehensions are inlined since Python 3.12,
+    // so a generator expression is the comprehension that still runs in
+    // a frame of its own.
+    let body = r#"
+import sys
+from test_tracer import capture_py_start
+
+def helper(x):
+    return x
+
+gen = (helper(x) for x in range(2))
+mon = sys.monitoring
+TOOL = 3
+mon.use_tool_id(TOOL, "comprehension-test")
+mon.register_callback(TOOL, mon.events.PY_START, capture_py_start)
+mon.set_local_events(TOOL, gen.gi_code, mon.events.PY_START)
+mon.set_local_events(TOOL, helper.__code__, mon.events.PY_START)
+try:
+    squares = list(gen)
+finally:
+    mon.set_local_events(TOOL, gen.gi_code, 0)
+    mon.set_local_events(TOOL, helper.__code__, 0)
+    mon.register_callback(TOOL, mon.events.PY_START, None)
+    mon.free_tool_id(TOOL)
+"#;
+    let events = run_traced_script_events(body);
+
+    let function_names: Vec<&str> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Function(record) => Some(record.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut last_call: Option<&str> = None;
+    let mut tagged = Vec::new();
+    let mut untagged = Vec::new();
+    for event in &events {
+        match event {
+            TraceLowLevelEvent::Call(record) => {
+                if let Some(name) = last_call.replace(function_names[record.function_id.0]) {
+                    untagged.push(name);
+                }
+            }
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value =
+                    serde_json::from_str(&record.metadata).unwrap_or_default();
+                if metadata["kind"] == "comprehension" {
+                    assert_eq!(metadata["form"], "genexpr");
+                    tagged.extend(last_call.take());
+                }
+            }
+            _ => {}
+        }
+    }
+    untagged.extend(last_call);
+
+    assert_eq!(tagged.len(), 1, "tagged calls: {tagged:?}");
+    assert!(tagged[0].contains("<genexpr>"), "tagged calls: {tagged:?}");
+    assert_eq!(untagged.len(), 2, "untagged calls: {untagged:?}");
+    assert!(
+        untagged.iter().all(|name| name.contains("helper")),
+        "helper calls are plain function calls: {untagged:?}"
+    );
+}
+
+#[test]
+fn generator_resumes_carry_the_generator_id_of_its_start() {
+    let body = r#"
+from test_tracer import capture_py_resume
+
+def resume_call():
+    frame = inspect.currentframe().f_back
+    capture_py_resume(frame.f_code, frame.f_lasti)
+
+def counter():
+    start_call()
+    yield emit_yield(1)
+    resume_call()
+    yield emit_yield(2)
+    resume_call()
+    return emit_return(3)
+
+gen = counter()
+next(gen)
+next(gen)
+other = counter()
+next(other)
+"#;
+    let events = run_traced_script_events(body);
+
+    let markers: Vec<(String, u64)> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                if metadata["kind"] != "generator" {
+                    return None;
+                }
+                Some((
+                    metadata["phase"].as_str()?.to_string(),
+                    metadata["generator_id"].as_u64()?,
+                ))
+            }
+            _ => None,
+        })
+        .collect();
+    let phases: Vec<&str> = markers.iter().map(|(phase, _)| phase.as_str()).collect();
+    assert_eq!(
+        phases,
+        ["start", "yield", "resume", "yield", "start", "yield"],
+        "markers: {markers:?}"
+    );
+    let first = markers[0].1;
+    assert!(
+        markers[..4].iter().all(|(_, id)| *id == first),
+        "both next() calls on one generator share its id: {markers:?}"
+    );
+    assert_ne!(
+        markers[4].1, first,
+        "a second generator gets its own id: {markers:?}"
+    );
+}
+
+#[test]
+fn call_timestamps_bracket_a_sleeping_call() {
+    let body = r#"
+import time
+
+def nap():
+    start_call()
+    time.sleep(0.01)
+    return emit_return(1)
+
+nap()
+"#;
+    let policy = RecorderPolicy {
+        call_timestamps: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let stamps: Vec<(String, u64)> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "call_timestamp").then(|| {
+                    (
+                        metadata["phase"].as_str().unwrap_or_default().to_string(),
+                        metadata["unix_ns"].as_u64().unwrap_or_default(),
+                    )
+                })
+            }
+            _ => None,
+        })
+        .collect();
+
+    let call = stamps
+        .iter()
+        .find(|(phase, _)| phase == "call")
+        .map(|(_, ns)| *ns)
+        .expect("call timestamp recorded");
+    let ret = stamps
+        .iter()
+        .rev()
+        .find(|(phase, _)| phase == "return")
+        .map(|(_, ns)| *ns)
+        .expect("return timestamp recorded");
+    assert!(call > 0, "timestamps are absolute: {stamps:?}");
+    assert!(ret > call, "return must follow the call: {stamps:?}");
+    assert!(
+        ret - call >= 10_000_000,
+        "the sleep should separate call and return: {stamps:?}"
+    );
+
+    let events = run_traced_script_events(body);
+    assert!(
+        !events
+            .iter()
+            .any(|event| matches!(event, TraceLowLevelEvent::Event(_))),
+        "call timestamps are opt-in"
+    );
+}
@@ -0,0 +1,826 @@
+//! Session lifecycle: begin/finish records, policies and summaries.
+
+use super::*;
+
+#[test]
+fn path_summary_counts_events_per_file() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        ensure_test_module(py);
+        let mut tracer = RuntimeTracer::new(
+            "test.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+
+        let tmp = tempfile::tempdir().expect("create temp dir");
+        let helper_path = tmp.path().join("path_stats_helper.py");
+        std::fs::write(
+            &helper_path,
+            "def work(snapshot):\n    snapshot()\n    snapshot()\n    snapshot()\n",
+        )
+        .expect("write helper");
+        let script_path = tmp.path().join("script.py");
+        let script = format!(
+            "{PRELUDE}\nimport runpy\nhelper = runpy.run_path(r\"{}\")\nsnapshot()\nhelper[\"work\"](snapshot)\nsnapshot()\n",
+            helper_path.display()
+        );
+        std::fs::write(&script_path, &script).expect("write script");
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute two-file script");
+        }
+        tracer.finish(py).expect("finish tracer");
+
+        let summaries = written_trace_log_events(&outputs, "path_summary");
+        assert_eq!(summaries.len(), 1, "expected one summary: {summaries:?}");
+        let summary = &summaries[0];
+        assert_eq!(summary["distinct_paths"], serde_json::json!(2));
+        let count_for = |path: &Path| {
+            summary["top_paths"]
+                .as_array()
+                .and_then(|entries| {
+                    entries
+                        .iter()
+                        .find(|entry| entry["path"] == path.display().to_string())
+                })
+                .and_then(|entry| entry["events"].as_u64())
+        };
+        assert_eq!(count_for(&helper_path), Some(3), "summary: {summary}");
+        assert_eq!(count_for(&script_path), Some(2), "summary: {summary}");
+        // The busiest file is listed first.
+        assert_eq!(
+            summary["top_paths"][0]["path"],
+            serde_json::json!(helper_path.display().to_string())
+        );
+    });
+}
+
+#[test]
+fn begin_records_interpreter_details() {
+    Python::with_gil(|py| {
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        let mut tracer = RuntimeTracer::new(
+            "program.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        assert!(tracer.lifecycle.interpreter().is_none());
+        tracer.begin(&outputs, 1).expect("begin tracer");
+
+        let info = tracer
+            .lifecycle
+            .interpreter()
+            .expect("interpreter details captured at begin");
+        let sys = py.import("sys").expect("import sys");
+        let expected: String = sys
+            .getattr("version")
+            .and_then(|v| v.extract())
+            .expect("sys.version");
+        assert_eq!(info.version, expected);
+        assert!(!info.implementation.is_empty());
+        assert!(!info.platform.is_empty());
+
+        let recorded = tracer
+            .writer
+            .events()
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: serde_json::Value =
+                        serde_json::from_str(&record.metadata).ok()?;
+                    (metadata["kind"] == "interpreter").then_some(metadata)
+                }
+                _ => None,
+            })
+            .expect("interpreter event recorded");
+        assert_eq!(recorded["version"], serde_json::json!(expected));
+        assert_eq!(
+            recorded["implementation"],
+            serde_json::json!(info.implementation)
+        );
+        assert_eq!(recorded["platform"], serde_json::json!(info.platform));
+    });
+}
+
+#[test]
+fn begin_records_command_line_and_working_directory() {
+    Python::with_gil(|py| {
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        let mut tracer = RuntimeTracer::new(
+            "program.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.apply_policy(&RecorderPolicy {
+            redact_argv: vec!["token".to_string()],
+            ..RecorderPolicy::default()
+        });
+
+        let sys = py.import("sys").expect("import sys");
+        let original = sys.getattr("argv").expect("argv").unbind();
+        sys.setattr("argv", ["program.py", "--mode", "fast", "--token=abc"])
+            .expect("set argv");
+        let began = tracer.begin(&outputs, 1);
+        sys.setattr("argv", original.bind(py))
+            .expect("restore argv");
+        began.expect("begin tracer");
+
+        let cwd: String = py
+            .import("os")
+            .and_then(|os| os.call_method0("getcwd"))
+            .and_then(|cwd| cwd.extract())
+            .expect("os.getcwd");
+        let expected_argv = ["program.py", "--mode", "fast", "--token=<redacted>"];
+        let command_line = tracer
+            .lifecycle
+            .command_line()
+            .expect("command line captured at begin");
+        assert_eq!(command_line.argv, expected_argv);
+        assert_eq!(command_line.cwd, cwd);
+
+        let recorded = tracer
+            .writer
+            .events()
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: serde_json::Value =
+                        serde_json::from_str(&record.metadata).ok()?;
+                    (metadata["kind"] == "command_line").then_some(metadata)
+                }
+                _ => None,
+            })
+            .expect("command_line event recorded");
+        assert_eq!(recorded["argv"], serde_json::json!(expected_argv));
+        assert_eq!(recorded["cwd"], serde_json::json!(cwd));
+    });
+}
+
+#[test]
+fn entry_module_records_main_execution_via_run_path() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let project_root = project.path();
+        fs::write(project_root.join("helper.py"), "VALUE = 1\n").expect("write helper");
+        let script_path = project_root.join("entry.py");
+        fs::write(&script_path, "import helper\nresult = helper.VALUE + 1\n")
+            .expect("write script");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        let program = script_path.to_string_lossy().into_owned();
+        let mut tracer = RuntimeTracer::new(
+            &program,
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        assert!(tracer.lifecycle.entry_module().is_none());
+
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            let run_code = format!(
+                "import runpy, sys\nsys.path.insert(0, r\"{}\")\nrunpy.run_path(r\"{}\", run_name=\"__main__\")\nsys.modules.pop(\"helper\", None)",
+                project_root.display(),
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute script");
+        }
+
+        let entry = tracer
+            .lifecycle
+            .entry_module()
+            .cloned()
+            .expect("entry module recorded");
+        tracer.finish(py).expect("finish tracer");
+
+        assert_eq!(entry.name, "__main__");
+        assert!(entry.ran_as_main, "run_path with __main__ is a main run");
+
+        let recorded: Vec<serde_json::Value> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: serde_json::Value =
+                        serde_json::from_str(&record.metadata).ok()?;
+                    (metadata["kind"] == "entry_module").then_some(metadata)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            recorded,
+            vec![serde_json::json!({
+                "kind": "entry_module",
+                "name": "__main__",
+                "ran_as_main": true,
+            })],
+            "only the first module frame is written, not the helper import"
+        );
+    });
+}
+
+#[test]
+fn begin_flags_missing_activation_path() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        let missing = outputs_dir.path().join("no_such_entry.py");
+        let mut tracer = RuntimeTracer::new(
+            "program.py",
+            &[],
+            TraceEventsFileFormat::Json,
+            Some(missing.as_path()),
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("missing path only warns");
+
+        assert!(
+            tracer.lifecycle.activation_path_missing(),
+            "missing activation path should be flagged in metadata"
+        );
+        tracer.finish(py).expect("finish tracer");
+
+        let recorded = tracer
+            .writer
+            .events()
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: serde_json::Value =
+                        serde_json::from_str(&record.metadata).ok()?;
+                    (metadata["kind"] == "activation_path_missing").then_some(metadata)
+                }
+                _ => None,
+            })
+            .expect("activation_path_missing event recorded");
+        assert_eq!(
+            recorded["path"],
+            serde_json::json!(missing.to_string_lossy())
+        );
+    });
+}
+
+#[test]
+fn trace_size_bytes_grows_across_flushes() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        ensure_test_module(py);
+
+        let project = tempfile::tempdir().expect("project dir");
+        let script_path = project.path().join("work.py");
+        let script = format!("{PRELUDE}\ndef work(n):\n    snapshot()\n    return n\n\nwork(1)\n");
+        fs::write(&script_path, script).expect("write script");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+        let program = script_path.to_string_lossy().into_owned();
+        let mut tracer = RuntimeTracer::new(
+            &program,
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        assert_eq!(tracer.trace_size_bytes(), 0);
+
+        let run_code = format!(
+            "import runpy\nrunpy.run_path(r\"{}\")",
+            script_path.display()
+        );
+        let run_code_c = CString::new(run_code).expect("script contains nul byte");
+        let run_and_flush = |tracer: &mut RuntimeTracer| {
+            {
+                let _guard = ScopedTracer::new(tracer);
+                py.run(run_code_c.as_c_str(), None, None)
+                    .expect("execute script");
+            }
+            Tracer::flush(tracer, py).expect("flush tracer");
+            tracer.trace_size_bytes()
+        };
+
+        let first = run_and_flush(&mut tracer);
+        assert!(first > 0, "flushed events file should not be empty");
+        let second = run_and_flush(&mut tracer);
+        assert!(
+            second > first,
+            "size should grow after more events: {first} -> {second}"
+        );
+
+        tracer.finish(py).expect("finish tracer");
+    });
+}
+
+#[test]
+fn finish_emits_toplevel_return_with_exit_code() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+
+        let script_dir = tempfile::tempdir().expect("script dir");
+        let program_path = script_dir.path().join("program.py");
+        std::fs::write(&program_path, "print('hi')\n").expect("write program");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+
+        let mut tracer = RuntimeTracer::new(
+            program_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer.record_exit_status(Some(7));
+
+        tracer.finish(py).expect("finish tracer");
+
+        let mut exit_value: Option<ValueRecord> = None;
+        for event in tracer.writer.events() {
+            if let TraceLowLevelEvent::Return(record) = event {
+                exit_value = Some(record.return_value.clone());
+            }
+        }
+
+        let exit_value = exit_value.expect("expected toplevel return value");
+        match exit_value {
+            ValueRecord::Int { i, .. } => assert_eq!(i, 7),
+            other => panic!("expected integer exit value, got {other:?}"),
+        }
+    });
+}
+
+#[test]
+fn finish_enforces_require_trace_policy() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(py, "require_trace=True")
+            .expect("enable require_trace policy");
+
+        let script_dir = tempfile::tempdir().expect("script dir");
+        let program_path = script_dir.path().join("program.py");
+        std::fs::write(&program_path, "print('hi')\n").expect("write program");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+
+        let mut tracer = RuntimeTracer::new(
+            program_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+
+        let err = tracer
+            .finish(py)
+            .expect_err("finish should error when require_trace true");
+        let message = err.to_string();
+        assert!(
+            message.contains("ERR_TRACE_MISSING"),
+            "expected trace missing error, got {message}"
+        );
+
+        reset_policy(py);
+    });
+}
+
+#[test]
+fn finish_removes_partial_outputs_when_policy_forbids_keep() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+
+        let script_dir = tempfile::tempdir().expect("script dir");
+        let program_path = script_dir.path().join("program.py");
+        std::fs::write(&program_path, "print('hi')\n").expect("write program");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+
+        let mut tracer = RuntimeTracer::new(
+            program_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer.mark_failure();
+
+        tracer.finish(py).expect("finish after failure");
+
+        assert!(!outputs.events().exists(), "expected events file removed");
+    });
+}
+
+#[test]
+fn finish_keeps_partial_outputs_when_policy_allows() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        policy::test_support::configure_policy_with(py, "keep_partial_trace=True")
+            .expect("enable keep_partial policy");
+
+        let script_dir = tempfile::tempdir().expect("script dir");
+        let program_path = script_dir.path().join("program.py");
+        std::fs::write(&program_path, "print('hi')\n").expect("write program");
+
+        let outputs_dir = tempfile::tempdir().expect("outputs dir");
+        let outputs = TraceOutputPaths::new(outputs_dir.path(), TraceEventsFileFormat::Json);
+
+        let mut tracer = RuntimeTracer::new(
+            program_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        tracer.mark_failure();
+
+        tracer.finish(py).expect("finish after failure");
+
+        assert!(outputs.events().exists(), "expected events file retained");
+
+        reset_policy(py);
+    });
+}
+
+#[test]
+fn capture_env_changes_records_variables_set_and_removed_mid_run() {
+    let body = r#"
+import os
+
+def probe(value):
+    start_call()
+    return emit_return(value)
+
+probe(1)
+os.environ["CODETRACER_ENV_CHANGE_TEST"] = "set-mid-run"
+probe(2)
+del os.environ["CODETRACER_ENV_CHANGE_TEST"]
+probe(3)
+"#;
+    let policy = RecorderPolicy {
+        capture_env_changes: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    // Other tests may touch unrelated variables concurrently, so only
+    // the mutations of the variable this script owns are compared.
+    let name = "CODETRACER_ENV_CHANGE_TEST";
+    let mutations: Vec<String> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "env_changed").then_some(metadata)
+            }
+            _ => None,
+        })
+        .flat_map(|metadata| {
+            let set = metadata["set"][name]
+                .as_str()
+                .map(|value| format!("set {value}"));
+            let removed = metadata["removed"]
+                .as_array()
+                .is_some_and(|names| names.iter().any(|removed| removed == name))
+                .then(|| "removed".to_string());
+            set.into_iter().chain(removed)
+        })
+        .collect();
+    assert_eq!(mutations, ["set set-mid-run", "removed"]);
+}
+
+#[test]
+fn capture_env_changes_redacts_matching_values() {
+    let body = r#"
+import os
+
+def probe(value):
+    start_call()
+    return emit_return(value)
+
+probe(1)
+os.environ["CODETRACER_ENV_REDACT_TOKEN"] = "secret-value"
+os.environ["CODETRACER_ENV_REDACT_PLAIN"] = "visible"
+probe(2)
+del os.environ["CODETRACER_ENV_REDACT_TOKEN"]
+del os.environ["CODETRACER_ENV_REDACT_PLAIN"]
+"#;
+    let policy = RecorderPolicy {
+        capture_env_changes: true,
+        redact_argv: vec!["token".to_string()],
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let changed = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "env_changed").then_some(metadata)
+            }
+            _ => None,
+        })
+        .find(|metadata| !metadata["set"]["CODETRACER_ENV_REDACT_PLAIN"].is_null())
+        .expect("env_changed event for the new variables");
+    assert_eq!(
+        changed["set"]["CODETRACER_ENV_REDACT_TOKEN"],
+        serde_json::json!("<redacted>")
+    );
+    assert_eq!(
+        changed["set"]["CODETRACER_ENV_REDACT_PLAIN"],
+        serde_json::json!("visible")
+    );
+}
+
+#[test]
+fn threads_spawned_mid_session_are_traced_with_thread_switch_markers() {
+    let body = r#"
+import threading
+
+def work(n):
+    return n * 2
+
+def main():
+    worker = threading.Thread(target=work, args=(21,), name="worker-thread")
+    worker.start()
+    worker.join()
+    return work(1)
+
+main()
+"#;
+    let events = run_traced_script_events(body);
+
+    let function_names: Vec<&str> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Function(record) => Some(record.name.as_str()),
+            _ => None,
+        })
+        .collect();
+    // Follow the markers to attribute each `work` call to its thread;
+    // events before the first marker belong to the session's own thread.
+    let mut thread = "<session thread>".to_string();
+    let mut switches = Vec::new();
+    let mut work_calls = Vec::new();
+    for event in &events {
+        match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value =
+                    serde_json::from_str(&record.metadata).unwrap_or_default();
+                if metadata["kind"] == "thread_switch" {
+                    thread = metadata["name"].as_str().unwrap_or("<unnamed>").to_string();
+                    switches.push((thread.clone(), metadata["first"] == true));
+                }
+            }
+            TraceLowLevelEvent::Call(record) if function_names[record.function_id.0] == "work" => {
+                work_calls.push(thread.clone());
+            }
+            _ => {}
+        }
+    }
+
+    assert!(
+        switches.contains(&("worker-thread".to_string(), true)),
+        "the new thread is announced on its first event: {switches:?}"
+    );
+    // The session thread's name depends on how the test harness entered
+    // Python, so it is taken from the marker that switches back to it.
+    let (session_thread, _) = switches
+        .iter()
+        .find(|(_, first)| !first)
+        .expect("returning to the session thread is marked too");
+    assert_ne!(session_thread, "worker-thread");
+    assert_eq!(work_calls, ["worker-thread", session_thread.as_str()]);
+}
+
+#[test]
+fn finish_records_the_session_duration() {
+    Python::with_gil(|py| {
+        reset_policy(py);
+        ensure_test_module(py);
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let script_path = tmp.path().join("slow.py");
+        std::fs::write(
+            &script_path,
+            format!(
+                "{PRELUDE}\nimport time\n\ndef slow():\n    time.sleep(0.01)\n    return 1\n\nslow()\n"
+            ),
+        )
+        .expect("write script");
+
+        let mut tracer = RuntimeTracer::new(
+            script_path.to_string_lossy().as_ref(),
+            &[],
+            TraceEventsFileFormat::Json,
+            None,
+            None,
+            false,
+        );
+        let outputs = TraceOutputPaths::new(tmp.path(), TraceEventsFileFormat::Json);
+        tracer.begin(&outputs, 1).expect("begin tracer");
+        {
+            let _guard = ScopedTracer::new(&mut tracer);
+            LAST_OUTCOME.with(|cell| cell.set(None));
+            let run_code = format!(
+                "import runpy\nrunpy.run_path(r\"{}\")",
+                script_path.display()
+            );
+            let run_code_c = CString::new(run_code).expect("script contains nul byte");
+            py.run(run_code_c.as_c_str(), None, None)
+                .expect("execute slow script");
+        }
+        tracer.finish(py).expect("finish tracer");
+
+        let durations: Vec<serde_json::Value> = tracer
+            .writer
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                TraceLowLevelEvent::Event(record) => {
+                    let metadata: serde_json::Value =
+                        serde_json::from_str(&record.metadata).ok()?;
+                    (metadata["kind"] == "session_duration").then_some(metadata)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(durations.len(), 1, "expected one duration: {durations:?}");
+        let duration_ms = durations[0]["duration_ms"]
+            .as_u64()
+            .expect("duration_ms is a count");
+        assert!(
+            duration_ms >= 10,
+            "a 10ms sleep should take at least 10ms, got {duration_ms}"
+        );
+
+        let events = tracer.writer.events();
+        let session_exit_index = events
+            .iter()
+            .rposition(|event| matches!(event, TraceLowLevelEvent::Return(_)))
+            .expect("session exit return recorded");
+        for kind in ["path_summary", "filter_summary", "session_duration"] {
+            let index = events
+                .iter()
+                .position(|event| match event {
+                    TraceLowLevelEvent::Event(record) => {
+                        serde_json::from_str::<serde_json::Value>(&record.metadata)
+                            .is_ok_and(|metadata| metadata["kind"] == kind)
+                    }
+                    _ => false,
+                })
+                .unwrap_or_else(|| panic!("{kind} recorded"));
+            assert!(
+                index < session_exit_index,
+                "{kind} must be recorded before the session exit"
+            );
+        }
+    });
+}
+
+#[test]
+fn trace_schema_covers_emitted_event_and_value_kinds() {
+    let body = r#"
+class Box:
+    pass
+
+def describe(flag, count, name, items, pair, thing):
+    start_call()
+    nothing = None
+    alias = count
+    snapshot()
+    return emit_return((name, items))
+
+describe(True, 3, "box", [1, 2], (4, 5), Box())
+"#;
+    let events = run_traced_script_events(body);
+
+    fn value_kinds(value: &serde_json::Value, kinds: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                if let (Some(kind), Some(_)) = (
+                    map.get("kind").and_then(|kind| kind.as_str()),
+                    map.get("type_id"),
+                ) {
+                    kinds.push(kind.to_string());
+                }
+                map.values().for_each(|nested| value_kinds(nested, kinds));
+            }
+            serde_json::Value::Array(items) => {
+                items.iter().for_each(|nested| value_kinds(nested, kinds))
+            }
+            _ => {}
+        }
+    }
+
+    let mut seen_values = Vec::new();
+    for event in &events {
+        let json = serde_json::to_value(event).expect("serialize event");
+        let object = json.as_object().expect("events are externally tagged");
+        let kind = object.keys().next().expect("event tag");
+        assert!(
+            crate::trace_schema::EVENT_KINDS.contains(&kind.as_str()),
+            "schema lacks event kind {kind}"
+        );
+        value_kinds(&json, &mut seen_values);
+    }
+    for kind in &seen_values {
+        assert!(
+            crate::trace_schema::VALUE_KINDS.contains(&kind.as_str()),
+            "schema lacks value kind {kind}"
+        );
+    }
+    for kind in ["Bool", "Int", "String", "Sequence", "Tuple", "Raw", "None"] {
+        assert!(
+            seen_values.iter().any(|seen| seen == kind),
+            "trace should exercise {kind}: {seen_values:?}"
+        );
+    }
+}
+
+#[test]
+fn deterministic_mode_repeats_identical_events() {
+    let body = r#"
+class Token:
+    pass
+
+def work():
+    start_call()
+    token = Token()
+    marker = object()
+    tags = {"beta", "alpha", "gamma"}
+    table = {"b": 2, "a": 1}
+    snapshot()
+    return emit_return(len(tags))
+
+work()
+"#;
+    let tmp = tempfile::tempdir().expect("create temp dir");
+    let script_path = tmp.path().join("script.py");
+    fs::write(&script_path, format!("{PRELUDE}\n{body}")).expect("write script");
+    let policy = RecorderPolicy {
+        deterministic: true,
+        call_timestamps: true,
+        ..RecorderPolicy::default()
+    };
+
+    let first = run_traced_file_events_with_policy(&script_path, &policy);
+    let second = run_traced_file_events_with_policy(&script_path, &policy);
+
+    assert_eq!(format!("{first:?}"), format!("{second:?}"));
+    let rendered = format!("{first:?}");
+    assert!(
+        rendered.contains("0xADDR"),
+        "object reprs should have their address normalised: {rendered}"
+    );
+    assert!(
+        rendered.contains("{'alpha', 'beta', 'gamma'}"),
+        "set elements should be sorted: {rendered}"
+    );
+    assert!(
+        !rendered.contains("call_timestamp"),
+        "deterministic mode must not emit timestamps: {rendered}"
+    );
+}
@@ -0,0 +1,1524 @@
+//! Value capture: locals, globals, exceptions and value policies.
+
+use super::*;
+
+#[test]
+fn captures_simple_function_locals() {
+    let snapshots = run_traced_script(
+        r#"
+def simple_function(x):
+    snapshot()
+    a = 1
+    snapshot()
+    b = a + x
+    snapshot()
+    return a, b
+
+simple_function(5)
+"#,
+    );
+
+    assert_var(&snapshots[0], "x", SimpleValue::Int(5));
+    assert!(!snapshots[0].vars.contains_key("a"));
+    assert_var(&snapshots[1], "a", SimpleValue::Int(1));
+    assert_var(&snapshots[2], "b", SimpleValue::Int(6));
+}
+
+#[test]
+fn sort_dict_keys_policy_orders_dict_entries() {
+    let policy = RecorderPolicy {
+        sort_dict_keys: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(
+        r#"
+def build():
+    ordered = {"b": 2, "c": 3, "a": 1}
+    mixed = {2: "two", "x": "ex"}
+    snapshot()
+
+build()
+"#,
+        &policy,
+    );
+
+    let entry = |key: SimpleValue, value: SimpleValue| SimpleValue::Tuple(vec![key, value]);
+    let snapshot = find_snapshot_with_vars(&snapshots, &["ordered", "mixed"]);
+    assert_var(
+        snapshot,
+        "ordered",
+        SimpleValue::Sequence(vec![
+            entry(SimpleValue::String("a".into()), SimpleValue::Int(1)),
+            entry(SimpleValue::String("b".into()), SimpleValue::Int(2)),
+            entry(SimpleValue::String("c".into()), SimpleValue::Int(3)),
+        ]),
+    );
+    // `2 < "x"` raises, so the mixed dict keeps insertion order.
+    assert_var(
+        snapshot,
+        "mixed",
+        SimpleValue::Sequence(vec![
+            entry(SimpleValue::Int(2), SimpleValue::String("two".into())),
+            entry(
+                SimpleValue::String("x".into()),
+                SimpleValue::String("ex".into()),
+            ),
+        ]),
+    );
+}
+
+#[test]
+fn captures_closure_variables() {
+    let snapshots = run_traced_script(
+        r#"
+def outer_func(x):
+    snapshot()
+    y = 1
+    snapshot()
+    def inner_func(z):
+        nonlocal y
+        snapshot()
+        w = x + y + z
+        snapshot()
+        y = w
+        snapshot()
+        return w
+    total = inner_func(5)
+    snapshot()
+    return y, total
+
+result = outer_func(2)
+"#,
+    );
+
+    let inner_entry = find_snapshot_with_vars(&snapshots, &["x", "y", "z"]);
+    assert_var(inner_entry, "x", SimpleValue::Int(2));
+    assert_var(inner_entry, "y", SimpleValue::Int(1));
+
+    let w_snapshot = find_snapshot_with_vars(&snapshots, &["w", "x", "y", "z"]);
+    assert_var(w_snapshot, "w", SimpleValue::Int(8));
+
+    let outer_after = find_snapshot_with_vars(&snapshots, &["total", "y"]);
+    assert_var(outer_after, "total", SimpleValue::Int(8));
+    assert_var(outer_after, "y", SimpleValue::Int(8));
+}
+
+#[test]
+fn captures_globals() {
+    let snapshots = run_traced_script(
+        r#"
+GLOBAL_VAL = 10
+counter = 0
+snapshot()
+
+def global_test():
+    snapshot()
+    local_copy = GLOBAL_VAL
+    snapshot()
+    global counter
+    counter += 1
+    snapshot()
+    return local_copy, counter
+
+before = counter
+snapshot()
+result = global_test()
+snapshot()
+after = counter
+snapshot()
+"#,
+    );
+
+    let access_global = find_snapshot_with_vars(&snapshots, &["local_copy", "GLOBAL_VAL"]);
+    assert_var(access_global, "GLOBAL_VAL", SimpleValue::Int(10));
+    assert_var(access_global, "local_copy", SimpleValue::Int(10));
+
+    let last_counter = snapshots
+        .iter()
+        .rev()
+        .find(|snap| snap.vars.contains_key("counter"))
+        .expect("Expected at least one counter snapshot");
+    assert_var(last_counter, "counter", SimpleValue::Int(1));
+}
+
+#[test]
+fn captures_class_scope() {
+    let snapshots = run_traced_script(
+        r#"
+CONSTANT = 42
+snapshot()
+
+class MetaCounter(type):
+    count = 0
+    snapshot()
+    def __init__(cls, name, bases, attrs):
+        snapshot()
+        MetaCounter.count += 1
+        super().__init__(name, bases, attrs)
+
+class Sample(metaclass=MetaCounter):
+    snapshot()
+    a = 10
+    snapshot()
+    b = a + 5
+    snapshot()
+    print(a, b, CONSTANT)
+    snapshot()
+    def method(self):
+        snapshot()
+        return self.a + self.b
+
+instance = Sample()
+snapshot()
+instances = MetaCounter.count
+snapshot()
+_ = instance.method()
+snapshot()
+"#,
+    );
+
+    let meta_init = find_snapshot_with_vars(&snapshots, &["cls", "name", "attrs"]);
+    assert_var(meta_init, "name", SimpleValue::String("Sample".to_string()));
+
+    let class_body = find_snapshot_with_vars(&snapshots, &["a", "b"]);
+    assert_var(class_body, "a", SimpleValue::Int(10));
+    assert_var(class_body, "b", SimpleValue::Int(15));
+
+    let method_snapshot = find_snapshot_with_vars(&snapshots, &["self"]);
+    assert!(method_snapshot.vars.contains_key("self"));
+}
+
+#[test]
+fn captures_lambda_and_comprehensions() {
+    let snapshots = run_traced_script(
+        r#"
+factor = 2
+snapshot()
+double = lambda y: snap(y * factor)
+snapshot()
+lambda_value = double(5)
+snapshot()
+squares = [snap(n ** 2) for n in range(3)]
+snapshot()
+scaled_set = {snap(n * factor) for n in range(3)}
+snapshot()
+mapping = {n: snap(n * factor) for n in range(3)}
+snapshot()
+gen_exp = (snap(n * factor) for n in range(3))
+snapshot()
+result_list = list(gen_exp)
+snapshot()
+"#,
+    );
+
+    let lambda_snapshot = find_snapshot_with_vars(&snapshots, &["y", "factor"]);
+    assert_var(lambda_snapshot, "y", SimpleValue::Int(5));
+    assert_var(lambda_snapshot, "factor", SimpleValue::Int(2));
+
+    let list_comp = find_snapshot_with_vars(&snapshots, &["n", "factor"]);
+    assert!(matches!(list_comp.vars.get("n"), Some(SimpleValue::Int(_))));
+
+    let result_snapshot = find_snapshot_with_vars(&snapshots, &["result_list"]);
+    assert!(matches!(
+        result_snapshot.vars.get("result_list"),
+        Some(SimpleValue::Sequence(_))
+    ));
+}
+
+#[test]
+fn captures_generators_and_coroutines() {
+    let snapshots = run_traced_script(
+        r#"
+import asyncio
+snapshot()
+
+
+def counter_gen(n):
+    snapshot()
+    total = 0
+    for i in range(n):
+        total += i
+        snapshot()
+        yield total
+    snapshot()
+    return total
+
+async def async_sum(data):
+    snapshot()
+    total = 0
+    for x in data:
+        total += x
+        snapshot()
+        await asyncio.sleep(0)
+    snapshot()
+    return total
+
+gen = counter_gen(3)
+gen_results = list(gen)
+snapshot()
+coroutine_result = asyncio.run(async_sum([1, 2, 3]))
+snapshot()
+"#,
+    );
+
+    let generator_step = find_snapshot_with_vars(&snapshots, &["i", "total"]);
+    assert!(matches!(
+        generator_step.vars.get("i"),
+        Some(SimpleValue::Int(_))
+    ));
+
+    let coroutine_steps: Vec<&Snapshot> = snapshots
+        .iter()
+        .filter(|snap| snap.vars.contains_key("x"))
+        .collect();
+    assert!(!coroutine_steps.is_empty());
+    let final_coroutine_step = coroutine_steps.last().expect("at least one coroutine step");
+    assert_var(final_coroutine_step, "total", SimpleValue::Int(6));
+
+    let coroutine_result_snapshot = find_snapshot_with_vars(&snapshots, &["coroutine_result"]);
+    assert!(coroutine_result_snapshot
+        .vars
+        .contains_key("coroutine_result"));
+}
+
+#[test]
+fn records_generator_final_return_separately_from_yields() {
+    let events = run_traced_script_events(
+        r#"
+def counter_gen(n):
+    total = 0
+    for i in range(n):
+        total += i
+        yield emit_yield(total)
+    return emit_return(total)
+
+gen_results = list(counter_gen(3))
+"#,
+    );
+
+    let returns: Vec<SimpleValue> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Return(record) => {
+                Some(SimpleValue::from_value(&record.return_value))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        returns,
+        vec![
+            SimpleValue::Int(0),
+            SimpleValue::Int(1),
+            SimpleValue::Int(3),
+            SimpleValue::Int(3),
+        ],
+        "expected three yields followed by the final return"
+    );
+
+    let mut names: Vec<String> = Vec::new();
+    let mut final_values: Vec<(usize, SimpleValue)> = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        match event {
+            TraceLowLevelEvent::VariableName(name) => names.push(name.clone()),
+            TraceLowLevelEvent::Value(FullValueRecord { variable_id, value })
+                if names.get(variable_id.0).map(String::as_str) == Some(GENERATOR_RETURN_LABEL) =>
+            {
+                final_values.push((index, SimpleValue::from_value(value)));
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(
+        final_values.len(),
+        1,
+        "only the generator's final return should carry the marker"
+    );
+    let (marker_index, marker_value) = &final_values[0];
+    assert_eq!(marker_value, &SimpleValue::Int(3));
+    let last_return_index = events
+        .iter()
+        .rposition(|event| matches!(event, TraceLowLevelEvent::Return(_)))
+        .expect("final return record");
+    assert!(
+        *marker_index < last_return_index,
+        "marker must precede the return record it describes"
+    );
+}
+
+#[test]
+fn captures_exception_and_with_blocks() {
+    let snapshots = run_traced_script(
+        r#"
+import io
+__file__ = "test_script.py"
+
+def exception_and_with_demo(x):
+    snapshot()
+    try:
+        inv = 10 / x
+        snapshot()
+    except ZeroDivisionError as e:
+        snapshot()
+        error_msg = f"Error: {e}"
+        snapshot()
+    else:
+        snapshot()
+        inv += 1
+        snapshot()
+    finally:
+        snapshot()
+        final_flag = True
+        snapshot()
+    with io.StringIO("dummy line") as f:
+        snapshot()
+        first_line = f.readline()
+        snapshot()
+    snapshot()
+    return locals()
+
+result1 = exception_and_with_demo(0)
+snapshot()
+result2 = exception_and_with_demo(5)
+snapshot()
+"#,
+    );
+
+    let except_snapshot = find_snapshot_with_vars(&snapshots, &["e", "error_msg"]);
+    assert!(matches!(
+        except_snapshot.vars.get("error_msg"),
+        Some(SimpleValue::String(_))
+    ));
+
+    let finally_snapshot = find_snapshot_with_vars(&snapshots, &["final_flag"]);
+    assert_var(finally_snapshot, "final_flag", SimpleValue::Bool(true));
+
+    let with_snapshot = find_snapshot_with_vars(&snapshots, &["f", "first_line"]);
+    assert!(with_snapshot.vars.contains_key("first_line"));
+}
+
+#[test]
+fn captures_exception_class_and_args() {
+    let snapshots = run_traced_script(
+        r#"
+def fail():
+    try:
+        raise ValueError("bad value", 42)
+    except ValueError as e:
+        snapshot()
+
+fail()
+"#,
+    );
+
+    let except_snapshot = find_snapshot_with_vars(&snapshots, &["e"]);
+    assert_var(
+        except_snapshot,
+        "e",
+        SimpleValue::Sequence(vec![
+            SimpleValue::Tuple(vec![
+                SimpleValue::String("__class__".into()),
+                SimpleValue::String("ValueError".into()),
+            ]),
+            SimpleValue::Tuple(vec![
+                SimpleValue::String("args".into()),
+                SimpleValue::Tuple(vec![
+                    SimpleValue::String("bad value".into()),
+                    SimpleValue::Int(42),
+                ]),
+            ]),
+        ]),
+    );
+}
+
+#[test]
+fn captures_exception_cause_and_context_chain() {
+    let snapshots = run_traced_script(
+        r#"
+def fail():
+    try:
+        try:
+            raise KeyError("missing")
+        except KeyError as inner:
+            raise ValueError("bad") from inner
+    except ValueError as e:
+        explicit = e
+    try:
+        try:
+            raise KeyError("lookup")
+        except KeyError:
+            raise RuntimeError("during")
+    except RuntimeError as e:
+        implicit = e
+    snapshot()
+
+fail()
+"#,
+    );
+
+    let exception = |class: &str, arg: &str, links: Vec<(&str, SimpleValue)>| {
+        let mut entries = vec![
+            SimpleValue::Tuple(vec![
+                SimpleValue::String("__class__".into()),
+                SimpleValue::String(class.into()),
+            ]),
+            SimpleValue::Tuple(vec![
+                SimpleValue::String("args".into()),
+                SimpleValue::Tuple(vec![SimpleValue::String(arg.into())]),
+            ]),
+        ];
+        for (label, linked) in links {
+            entries.push(SimpleValue::Tuple(vec![
+                SimpleValue::String(label.into()),
+                linked,
+            ]));
+        }
+        SimpleValue::Sequence(entries)
+    };
+
+    let chained = find_snapshot_with_vars(&snapshots, &["explicit", "implicit"]);
+    // `raise ... from` suppresses the implicit context, so only the cause is linked.
+    assert_var(
+        chained,
+        "explicit",
+        exception(
+            "ValueError",
+            "bad",
+            vec![("__cause__", exception("KeyError", "missing", vec![]))],
+        ),
+    );
+    assert_var(
+        chained,
+        "implicit",
+        exception(
+            "RuntimeError",
+            "during",
+            vec![("__context__", exception("KeyError", "lookup", vec![]))],
+        ),
+    );
+}
+
+#[test]
+fn deeply_nested_exception_chain_stays_within_the_depth_limit() {
+    let snapshots = run_traced_script(
+        r#"
+def fail():
+    def chain(depth):
+        error = ValueError(f"link {depth}")
+        if depth:
+            error.__cause__ = chain(depth - 1)
+        return error
+
+    deep = chain(8)
+    for _ in range(26):
+        deep = [deep]
+    snapshot()
+
+fail()
+"#,
+    );
+
+    fn nesting(value: &SimpleValue) -> usize {
+        match value {
+            SimpleValue::Tuple(items) | SimpleValue::Sequence(items) => {
+                1 + items.iter().map(nesting).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+    fn entries(value: &SimpleValue) -> &[SimpleValue] {
+        match value {
+            SimpleValue::Sequence(items) => items,
+            other => panic!("expected a sequence, got {other:?}"),
+        }
+    }
+
+    let snapshot = find_snapshot_with_vars(&snapshots, &["deep"]);
+    let deep = &snapshot.vars["deep"];
+    assert!(
+        nesting(deep) <= MAX_STREAMING_DEPTH,
+        "value nests deeper than the streaming limit: {deep:?}"
+    );
+
+    // The exception sits 26 lists down; only the first link fits below it.
+    let mut exception = deep;
+    for _ in 0..26 {
+        exception = &entries(exception)[0];
+    }
+    let exception = entries(exception);
+    assert_eq!(
+        exception.len(),
+        3,
+        "one chained link is kept: {exception:?}"
+    );
+    let SimpleValue::Tuple(link) = &exception[2] else {
+        panic!("expected a labelled link: {:?}", exception[2]);
+    };
+    assert_eq!(link[0], SimpleValue::String("__cause__".into()));
+    let linked = entries(&link[1]);
+    assert_eq!(
+        linked.len(),
+        2,
+        "the next link is past the limit: {linked:?}"
+    );
+    assert_eq!(
+        linked[1],
+        SimpleValue::Tuple(vec![
+            SimpleValue::String("args".into()),
+            SimpleValue::Raw("<depth limit>".into()),
+        ])
+    );
+}
+
+#[test]
+fn captures_decorators() {
+    let snapshots = run_traced_script(
+        r#"
+setting = "Hello"
+snapshot()
+
+
+def my_decorator(func):
+    snapshot()
+    def wrapper(*args, **kwargs):
+        snapshot()
+        return func(*args, **kwargs)
+    return wrapper
+
+@my_decorator
+def greet(name):
+    snapshot()
+    message = f"Hi, {name}"
+    snapshot()
+    return message
+
+output = greet("World")
+snapshot()
+"#,
+    );
+
+    let decorator_snapshot = find_snapshot_with_vars(&snapshots, &["func", "setting"]);
+    assert!(decorator_snapshot.vars.contains_key("func"));
+
+    let wrapper_snapshot = find_snapshot_with_vars(&snapshots, &["args", "kwargs", "setting"]);
+    assert!(wrapper_snapshot.vars.contains_key("args"));
+
+    let greet_snapshot = find_snapshot_with_vars(&snapshots, &["name", "message"]);
+    assert_var(
+        greet_snapshot,
+        "name",
+        SimpleValue::String("World".to_string()),
+    );
+}
+
+#[test]
+fn captures_dynamic_execution() {
+    let snapshots = run_traced_script(
+        r#"
+expr_code = "dynamic_var = 99"
+snapshot()
+exec(expr_code)
+snapshot()
+check = dynamic_var + 1
+snapshot()
+
+def eval_test():
+    snapshot()
+    value = 10
+    formula = "value * 2"
+    snapshot()
+    result = eval(formula)
+    snapshot()
+    return result
+
+out = eval_test()
+snapshot()
+"#,
+    );
+
+    let exec_snapshot = find_snapshot_with_vars(&snapshots, &["dynamic_var"]);
+    assert_var(exec_snapshot, "dynamic_var", SimpleValue::Int(99));
+
+    let eval_snapshot = find_snapshot_with_vars(&snapshots, &["value", "formula"]);
+    assert_var(eval_snapshot, "value", SimpleValue::Int(10));
+}
+
+#[test]
+fn captures_imports() {
+    let snapshots = run_traced_script(
+        r#"
+import math
+snapshot()
+
+def import_test():
+    snapshot()
+    import os
+    snapshot()
+    constant = math.pi
+    snapshot()
+    cwd = os.getcwd()
+    snapshot()
+    return constant, cwd
+
+val, path = import_test()
+snapshot()
+"#,
+    );
+
+    let global_import = find_snapshot_with_vars(&snapshots, &["math"]);
+    assert!(matches!(
+        global_import.vars.get("math"),
+        Some(SimpleValue::Raw(_))
+    ));
+
+    let local_import = find_snapshot_with_vars(&snapshots, &["os", "constant"]);
+    assert!(local_import.vars.contains_key("os"));
+}
+
+const SLOTTED_CLASS: &str = r#"
+class Point:
+    __slots__ = ("x", "__secret", "unset")
+
+    def __init__(self, x):
+        self.x = x
+        self.__secret = 7
+
+    @property
+    def doubled(self):
+        return self.x * 2
+
+    def show(self):
+        snapshot()
+
+Point(3).show()
+"#;
+
+fn slot_entry(name: &str, value: i64) -> SimpleValue {
+    SimpleValue::Tuple(vec![
+        SimpleValue::String(name.into()),
+        SimpleValue::Int(value),
+    ])
+}
+
+#[test]
+fn capture_slots_policy_records_slot_attributes() {
+    let policy = RecorderPolicy {
+        capture_slots: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(SLOTTED_CLASS, &policy);
+
+    // Unset slots are skipped and the property getter is not invoked.
+    let method = find_snapshot_with_vars(&snapshots, &["self"]);
+    assert_var(
+        method,
+        "self",
+        SimpleValue::Sequence(vec![slot_entry("x", 3), slot_entry("__secret", 7)]),
+    );
+}
+
+#[test]
+fn capture_slots_policy_records_inherited_slots_of_a_local() {
+    let body = r#"
+class Base:
+    __slots__ = ("x", "y")
+
+class Child(Base):
+    __slots__ = ("z",)
+
+def build():
+    child = Child()
+    child.x = 1
+    child.z = 3
+    snapshot()
+
+build()
+"#;
+    let policy = RecorderPolicy {
+        capture_slots: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(body, &policy);
+    let step = find_snapshot_with_vars(&snapshots, &["child"]);
+    assert_var(
+        step,
+        "child",
+        SimpleValue::Sequence(vec![slot_entry("z", 3), slot_entry("x", 1)]),
+    );
+
+    let snapshots = run_traced_script(body);
+    let step = find_snapshot_with_vars(&snapshots, &["child"]);
+    assert!(
+        matches!(step.vars.get("child"), Some(SimpleValue::Raw(_))),
+        "slots stay opt-in: {:?}",
+        step.vars.get("child")
+    );
+}
+
+#[test]
+fn opaque_modules_policy_records_type_name_without_str() {
+    let body = r#"
+class DataFrame:
+    __module__ = "pandas.core.frame"
+
+    def __str__(self):
+        raise RuntimeError("str() must not run for opaque modules")
+
+class Lookalike:
+    __module__ = "pandasx"
+
+    def __str__(self):
+        return "lookalike"
+
+def inspect_values():
+    frame = DataFrame()
+    other = Lookalike()
+    snapshot()
+
+inspect_values()
+"#;
+    let policy = RecorderPolicy {
+        opaque_modules: vec!["pandas".to_string()],
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(body, &policy);
+    let step = find_snapshot_with_vars(&snapshots, &["frame", "other"]);
+    assert_var(
+        step,
+        "frame",
+        SimpleValue::Raw("pandas.core.frame.DataFrame".to_string()),
+    );
+    assert_var(step, "other", SimpleValue::Raw("lookalike".to_string()));
+}
+
+#[test]
+fn capture_properties_policy_evaluates_property_getters() {
+    let policy = RecorderPolicy {
+        capture_slots: true,
+        capture_properties: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(SLOTTED_CLASS, &policy);
+
+    let method = find_snapshot_with_vars(&snapshots, &["self"]);
+    assert_var(
+        method,
+        "self",
+        SimpleValue::Sequence(vec![
+            slot_entry("x", 3),
+            slot_entry("__secret", 7),
+            slot_entry("doubled", 6),
+        ]),
+    );
+}
+
+#[test]
+fn summarize_modules_policy_records_module_name() {
+    let policy = RecorderPolicy {
+        summarize_modules: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(
+        r#"
+import math
+snapshot()
+"#,
+        &policy,
+    );
+
+    let global_import = find_snapshot_with_vars(&snapshots, &["math"]);
+    let Some(SimpleValue::Sequence(entries)) = global_import.vars.get("math") else {
+        panic!(
+            "expected module summary, got {:?}",
+            global_import.vars.get("math")
+        );
+    };
+    assert_eq!(
+        entries.first(),
+        Some(&SimpleValue::Tuple(vec![
+            SimpleValue::String("__name__".into()),
+            SimpleValue::String("math".into()),
+        ]))
+    );
+    let Some(SimpleValue::Tuple(file_entry)) = entries.get(1) else {
+        panic!("expected __file__ entry, got {entries:?}");
+    };
+    assert_eq!(
+        file_entry.first(),
+        Some(&SimpleValue::String("__file__".into()))
+    );
+}
+
+#[test]
+fn builtins_not_recorded() {
+    let snapshots = run_traced_script(
+        r#"
+def builtins_test(seq):
+    snapshot()
+    n = len(seq)
+    snapshot()
+    m = max(seq)
+    snapshot()
+    return n, m
+
+result = builtins_test([5, 3, 7])
+snapshot()
+"#,
+    );
+
+    let len_snapshot = find_snapshot_with_vars(&snapshots, &["n"]);
+    assert_var(len_snapshot, "n", SimpleValue::Int(3));
+    assert_no_variable(&snapshots, "len");
+}
+
+#[test]
+fn global_shadowing_builtin_is_recorded() {
+    let snapshots = run_traced_script(
+        r#"
+list = [1, 2]
+
+def reads_shadowed_global():
+    snapshot()
+    size = len(list)
+    snapshot()
+    return size
+
+reads_shadowed_global()
+"#,
+    );
+
+    // `list` is not a local of the function, so it can only come from
+    // the globals pass; a builtin-named global must not be filtered.
+    let inside = find_snapshot_with_vars(&snapshots, &["size", "list"]);
+    assert_var(
+        inside,
+        "list",
+        SimpleValue::Sequence(vec![SimpleValue::Int(1), SimpleValue::Int(2)]),
+    );
+    assert_var(inside, "size", SimpleValue::Int(2));
+}
+
+#[test]
+fn callables_are_recorded_by_qualified_name() {
+    let body = r#"
+def greet(name):
+    return name
+
+class Greeter:
+    def hello(self):
+        return "hello"
+
+fn_ref = greet
+method_ref = Greeter().hello
+builtin_ref = len
+snapshot()
+"#;
+    let snapshots = run_traced_script(body);
+    let snapshot = find_snapshot_with_vars(&snapshots, &["fn_ref", "method_ref", "builtin_ref"]);
+    // `runpy.run_path` runs the script as module `<run_path>`.
+    assert_var(
+        snapshot,
+        "fn_ref",
+        SimpleValue::Raw("<run_path>.greet".to_string()),
+    );
+    assert_var(
+        snapshot,
+        "method_ref",
+        SimpleValue::Raw("<run_path>.Greeter.hello".to_string()),
+    );
+    assert_var(
+        snapshot,
+        "builtin_ref",
+        SimpleValue::Raw("builtins.len".to_string()),
+    );
+}
+
+#[test]
+fn raw_values_carry_class_qualname() {
+    let body = r#"
+class Outer:
+    class Widget:
+        def __str__(self):
+            return "widget"
+
+w = Outer.Widget()
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let names: Vec<&String> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::VariableName(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    let types: Vec<&str> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Type(record) => Some(record.lang_type.as_str()),
+            _ => None,
+        })
+        .collect();
+    let (text, type_id) = events
+        .iter()
+        .find_map(|event| match event {
+            TraceLowLevelEvent::Value(FullValueRecord {
+                variable_id,
+                value: ValueRecord::Raw { r, type_id },
+            }) if names.get(variable_id.0).map(|n| n.as_str()) == Some("w") => {
+                Some((r.clone(), *type_id))
+            }
+            _ => None,
+        })
+        .expect("raw value recorded for w");
+    assert_eq!(text, "widget");
+    assert_eq!(types.get(type_id.0).copied(), Some("Outer.Widget"));
+}
+
+#[test]
+fn ints_beyond_i64_keep_their_digits_under_an_int_type() {
+    let body = r#"
+x = 2**100
+y = -(2**70)
+snapshot()
+"#;
+    let events = run_traced_script_events(body);
+    let names: Vec<&String> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::VariableName(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    let types: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Type(record) => Some(record),
+            _ => None,
+        })
+        .collect();
+    let raw_value = |variable: &str| {
+        events
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Value(FullValueRecord {
+                    variable_id,
+                    value: ValueRecord::Raw { r, type_id },
+                }) if names.get(variable_id.0).map(|n| n.as_str()) == Some(variable) => {
+                    Some((r.clone(), *type_id))
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("digits recorded for {variable}"))
+    };
+
+    for (variable, digits) in [
+        ("x", "1267650600228229401496703205376"),
+        ("y", "-1180591620717411303424"),
+    ] {
+        let (text, type_id) = raw_value(variable);
+        assert_eq!(text, digits);
+        let record = types.get(type_id.0).expect("type record for big int");
+        assert_eq!(record.lang_type, BIG_INT_TYPE_NAME);
+        assert!(matches!(record.kind, codetracer_trace_types::TypeKind::Int));
+    }
+}
+
+#[test]
+fn raw_values_are_truncated_to_max_length() {
+    let body = r#"
+class Huge:
+    def __str__(self):
+        return "x" * 1_000_000
+
+h = Huge()
+snapshot()
+"#;
+    let snapshots = run_traced_script(body);
+    let snapshot = find_snapshot_with_vars(&snapshots, &["h"]);
+    let expected = format!("{}…", "x".repeat(MAX_RAW_CHARS));
+    assert_var(snapshot, "h", SimpleValue::Raw(expected));
+}
+
+#[test]
+fn unrepr_fallback_policy_selects_recorded_text() {
+    let body = r#"
+class Stubborn:
+    def __str__(self):
+        raise RuntimeError("no str")
+
+    def __repr__(self):
+        return "Stubborn()"
+
+s = Stubborn()
+snapshot()
+"#;
+    let cases = [
+        (
+            UnreprFallback::Sentinel,
+            SimpleValue::Raw("<unrepr>".to_string()),
+        ),
+        (
+            UnreprFallback::Repr,
+            SimpleValue::Raw("Stubborn()".to_string()),
+        ),
+        (
+            UnreprFallback::TypeName,
+            SimpleValue::Raw("Stubborn".to_string()),
+        ),
+    ];
+    for (fallback, expected) in cases {
+        let policy = RecorderPolicy {
+            unrepr_fallback: fallback,
+            ..RecorderPolicy::default()
+        };
+        let snapshots = run_traced_script_with_policy(body, &policy);
+        let snapshot = find_snapshot_with_vars(&snapshots, &["s"]);
+        assert_var(snapshot, "s", expected);
+    }
+}
+
+#[test]
+fn expand_varargs_policy_records_indexed_args() {
+    let body = r#"
+def f(*args):
+    start_call()
+    return len(args)
+
+f(1, "two", None)
+"#;
+    let call_args = |policy: &RecorderPolicy| -> Vec<(String, SimpleValue)> {
+        let events = run_traced_script_events_with_policy(body, policy);
+        let record = events
+            .iter()
+            .find_map(|event| match event {
+                TraceLowLevelEvent::Call(record) => Some(record),
+                _ => None,
+            })
+            .expect("expected a call record for f");
+        record
+            .args
+            .iter()
+            .map(|arg| {
+                (
+                    variable_name_for(&events, arg.variable_id).unwrap_or_default(),
+                    SimpleValue::from_value(&arg.value),
+                )
+            })
+            .collect()
+    };
+
+    let tuple_args = call_args(&RecorderPolicy::default());
+    assert_eq!(tuple_args.len(), 1, "args: {tuple_args:?}");
+    assert_eq!(tuple_args[0].0, "args");
+
+    let policy = RecorderPolicy {
+        expand_varargs: true,
+        ..RecorderPolicy::default()
+    };
+    assert_eq!(
+        call_args(&policy),
+        vec![
+            ("args[0]".to_string(), SimpleValue::Int(1)),
+            (
+                "args[1]".to_string(),
+                SimpleValue::String("two".to_string())
+            ),
+            ("args[2]".to_string(), SimpleValue::None),
+        ]
+    );
+}
+
+#[test]
+fn capture_contextvars_records_context_at_function_entry() {
+    let body = r#"
+import contextvars
+
+request_id = contextvars.ContextVar("request_id")
+request_id.set("req-42")
+
+def handle():
+    start_call()
+    return emit_return(None)
+
+handle()
+"#;
+    let policy = RecorderPolicy {
+        capture_contextvars: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+    let record = events
+        .iter()
+        .find_map(|event| match event {
+            TraceLowLevelEvent::Call(record) => Some(record),
+            _ => None,
+        })
+        .expect("expected a call record for handle");
+    let context = record
+        .args
+        .iter()
+        .find(|arg| {
+            variable_name_for(&events, arg.variable_id).as_deref() == Some(CONTEXTVARS_LABEL)
+        })
+        .map(|arg| SimpleValue::from_value(&arg.value))
+        .expect("contextvars should be attached to the call record");
+    let SimpleValue::Sequence(entries) = context else {
+        panic!("contextvars should be a sequence of pairs: {context:?}");
+    };
+    assert!(
+        entries.contains(&SimpleValue::Tuple(vec![
+            SimpleValue::String("request_id".to_string()),
+            SimpleValue::String("req-42".to_string()),
+        ])),
+        "request_id missing from {entries:?}"
+    );
+}
+
+#[test]
+fn deleted_parameter_is_recorded_as_unavailable() {
+    let body = r#"
+def f(a, b):
+    del b
+    start_call()
+    return emit_return(a)
+
+f(1, 2)
+"#;
+    let events = run_traced_script_events(body);
+    let record = events
+        .iter()
+        .find_map(|event| match event {
+            TraceLowLevelEvent::Call(record) => Some(record),
+            _ => None,
+        })
+        .expect("expected a call record for f");
+    let arg_value = |name: &str| {
+        record
+            .args
+            .iter()
+            .find(|arg| variable_name_for(&events, arg.variable_id).as_deref() == Some(name))
+            .map(|arg| SimpleValue::from_value(&arg.value))
+    };
+    assert_eq!(arg_value("a"), Some(SimpleValue::Int(1)));
+    assert_eq!(
+        arg_value("b"),
+        Some(SimpleValue::Raw("<unavailable>".to_string()))
+    );
+}
+
+#[test]
+fn capture_object_ids_reports_shared_identity_for_aliases() {
+    let body = r#"
+def alias():
+    items = [1, 2, 3]
+    same = items
+    other = list(items)
+    count = 3
+    snapshot()
+
+alias()
+"#;
+    let policy = RecorderPolicy {
+        capture_object_ids: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+    let objects = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "object_ids").then(|| metadata["objects"].clone())
+            }
+            _ => None,
+        })
+        .find(|objects| objects.get("other").is_some())
+        .expect("object_ids marker for the step after `other` is bound");
+
+    assert_eq!(objects["items"]["id"], objects["same"]["id"]);
+    assert_ne!(objects["items"]["id"], objects["other"]["id"]);
+    assert!(objects["items"]["refcount"].as_u64().unwrap_or_default() >= 2);
+    assert!(objects.get("count").is_none(), "primitives are skipped");
+}
+
+#[test]
+fn capture_object_ids_is_off_by_default() {
+    let body = r#"
+def alias():
+    items = [1]
+    same = items
+    snapshot()
+
+alias()
+"#;
+    let events = run_traced_script_events(body);
+    assert!(!events.iter().any(|event| matches!(
+        event,
+        TraceLowLevelEvent::Event(record) if record.metadata.contains("object_ids")
+    )));
+}
+
+#[test]
+fn args_and_returns_only_skips_line_locals() {
+    let body = r#"
+def scale(value, factor):
+    start_call()
+    doubled = value * factor
+    snapshot()
+    return emit_return(doubled + 1)
+
+scale(4, 2)
+"#;
+    let policy = RecorderPolicy {
+        args_and_returns_only: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let record = events
+        .iter()
+        .find_map(|event| match event {
+            TraceLowLevelEvent::Call(record) => Some(record),
+            _ => None,
+        })
+        .expect("expected a call record for scale");
+    let args: Vec<(String, SimpleValue)> = record
+        .args
+        .iter()
+        .map(|arg| {
+            (
+                variable_name_for(&events, arg.variable_id).unwrap_or_default(),
+                SimpleValue::from_value(&arg.value),
+            )
+        })
+        .collect();
+    assert_eq!(
+        args,
+        vec![
+            ("value".to_string(), SimpleValue::Int(4)),
+            ("factor".to_string(), SimpleValue::Int(2)),
+        ]
+    );
+
+    let returns: Vec<SimpleValue> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Return(record) => {
+                Some(SimpleValue::from_value(&record.return_value))
+            }
+            _ => None,
+        })
+        .collect();
+    assert!(
+        returns.contains(&SimpleValue::Int(9)),
+        "returns: {returns:?}"
+    );
+
+    let snapshots = collect_snapshots(&events);
+    assert!(!snapshots.is_empty(), "steps are still recorded");
+    assert_no_variable(&snapshots, "doubled");
+    assert!(
+        snapshots.iter().all(|snap| snap.vars.is_empty()),
+        "no per-line values expected: {snapshots:?}"
+    );
+}
+
+#[test]
+fn flag_type_changes_marks_int_to_str_rebinding() {
+    let body = r#"
+def rebind():
+    x = 1
+    snapshot()
+    x = "one"
+    snapshot()
+    y = 2
+    snapshot()
+    return x
+
+rebind()
+"#;
+    let policy = RecorderPolicy {
+        flag_type_changes: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let changes: Vec<serde_json::Value> = events
+        .iter()
+        .filter_map(|event| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "type_changed").then_some(metadata)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![serde_json::json!({
+            "kind": "type_changed",
+            "name": "x",
+            "from": "int",
+            "to": "str",
+        })]
+    );
+}
+
+#[test]
+fn delta_locals_records_only_changed_variables_after_entry() {
+    let body = r#"
+def accumulate(n):
+    label = "sum"
+    total = 0
+    snapshot()
+    while total < n:
+        total += 10
+        snapshot()
+    return total
+
+accumulate(30)
+"#;
+    let policy = RecorderPolicy {
+        delta_locals: true,
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(body, &policy);
+
+    let entry = find_snapshot_with_vars(&snapshots, &["n", "label", "total"]);
+    assert_var(entry, "total", SimpleValue::Int(0));
+
+    let later: Vec<&Snapshot> = snapshots
+        .iter()
+        .skip_while(|snap| !std::ptr::eq(*snap, entry))
+        .skip(1)
+        .collect();
+    assert_eq!(later.len(), 3, "expected one snapshot per iteration");
+    for (snap, expected) in later.iter().zip([10, 20, 30]) {
+        let names: Vec<&str> = snap.vars.keys().map(String::as_str).collect();
+        assert_eq!(names, ["total"], "only the changed local is recorded");
+        assert_var(snap, "total", SimpleValue::Int(expected));
+    }
+}
+
+#[test]
+fn redact_values_over_replaces_large_values_with_a_size_marker() {
+    let body = r#"
+def hold():
+    blob = "x" * 10240
+    small = "kept"
+    snapshot()
+
+hold()
+"#;
+    let policy = RecorderPolicy {
+        redact_values_over: Some(1024),
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(body, &policy);
+
+    let snap = find_snapshot_with_vars(&snapshots, &["blob", "small"]);
+    assert_var(snap, "small", SimpleValue::String("kept".to_string()));
+    match &snap.vars["blob"] {
+        SimpleValue::Raw(marker) => {
+            let size: usize = marker
+                .strip_prefix("<large-value ")
+                .and_then(|rest| rest.strip_suffix(" bytes>"))
+                .and_then(|digits| digits.parse().ok())
+                .unwrap_or_else(|| panic!("unexpected marker {marker:?}"));
+            assert!(size > 10240, "marker reports the encoded size: {marker}");
+        }
+        other => panic!("expected a size marker for blob, got {other:?}"),
+    }
+}
+
+#[test]
+fn mark_default_args_flags_unpassed_parameters() {
+    let body = r#"
+import sys
+from test_tracer import capture_py_start
+
+def connect(host, port=80, *, timeout=30):
+    return host
+
+mon = sys.monitoring
+TOOL = 3
+mon.use_tool_id(TOOL, "default-args-test")
+mon.register_callback(TOOL, mon.events.PY_START, capture_py_start)
+mon.set_local_events(TOOL, connect.__code__, mon.events.PY_START)
+try:
+    connect("a")
+    connect("b", 8080, timeout=5)
+finally:
+    mon.set_local_events(TOOL, connect.__code__, 0)
+    mon.register_callback(TOOL, mon.events.PY_START, None)
+    mon.free_tool_id(TOOL)
+"#;
+    let policy = RecorderPolicy {
+        mark_default_args: true,
+        ..RecorderPolicy::default()
+    };
+    let events = run_traced_script_events_with_policy(body, &policy);
+
+    let call_indices: Vec<usize> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| matches!(event, TraceLowLevelEvent::Call(_)).then_some(index))
+        .collect();
+    assert_eq!(call_indices.len(), 2, "one call record per call");
+    let TraceLowLevelEvent::Call(first_call) = &events[call_indices[0]] else {
+        unreachable!("indices point at call records");
+    };
+    let timeout = first_call
+        .args
+        .iter()
+        .find(|arg| variable_name_for(&events, arg.variable_id).as_deref() == Some("timeout"))
+        .expect("defaulted kw-only argument is still captured");
+    assert_eq!(
+        SimpleValue::from_value(&timeout.value),
+        SimpleValue::Int(30)
+    );
+
+    let markers: Vec<(usize, serde_json::Value)> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| match event {
+            TraceLowLevelEvent::Event(record) => {
+                let metadata: serde_json::Value = serde_json::from_str(&record.metadata).ok()?;
+                (metadata["kind"] == "from_default").then_some((index, metadata))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        markers.len(),
+        1,
+        "only the call relying on defaults is marked: {markers:?}"
+    );
+    let (index, metadata) = &markers[0];
+    assert!(call_indices[0] < *index && *index < call_indices[1]);
+    assert_eq!(metadata["args"], serde_json::json!(["port", "timeout"]));
+}
+
+#[test]
+fn max_locals_per_step_caps_wide_frames_and_marks_omissions() {
+    let assignments: String = (0..500).map(|i| format!("    v{i} = {i}\n")).collect();
+    let body = format!("def wide():\n{assignments}    snapshot()\n\nwide()\n");
+    let policy = RecorderPolicy {
+        max_locals_per_step: Some(50),
+        ..RecorderPolicy::default()
+    };
+    let snapshots = run_traced_script_with_policy(&body, &policy);
+    let snapshot = find_snapshot_with_vars(&snapshots, &[OMITTED_LOCALS_LABEL]);
+
+    let recorded = snapshot
+        .vars
+        .keys()
+        .filter(|name| {
+            name.strip_prefix('v')
+                .is_some_and(|rest| rest.parse::<usize>().is_ok())
+        })
+        .count();
+    assert_eq!(recorded, 50, "expected exactly 50 locals under the cap");
+    assert_var(snapshot, OMITTED_LOCALS_LABEL, SimpleValue::Int(450));
+}
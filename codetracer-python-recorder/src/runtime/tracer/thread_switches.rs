@@ -0,0 +1,38 @@
+//! Which thread recorded the most recent events, so interleaved threads can
+//! be told apart in the event stream.
+
+use std::collections::HashSet;
+use std::thread::{self, ThreadId};
+
+#[derive(Debug, Default)]
+pub(crate) struct ThreadSwitches {
+    current: Option<ThreadId>,
+    seen: HashSet<ThreadId>,
+}
+
+/// The calling thread takes over from the thread that recorded last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ThreadSwitch {
+    /// No earlier event of the session came from this thread.
+    pub(crate) first: bool,
+}
+
+impl ThreadSwitches {
+    /// Note that the calling thread is about to record. Returns the switch to
+    /// mark when another thread recorded last; the first thread of a session
+    /// needs no marker, so single-threaded traces carry none.
+    pub(crate) fn enter_current(&mut self) -> Option<ThreadSwitch> {
+        let thread_id = thread::current().id();
+        let previous = self.current.replace(thread_id);
+        let first = self.seen.insert(thread_id);
+        match previous {
+            Some(previous) if previous != thread_id => Some(ThreadSwitch { first }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.current = None;
+        self.seen.clear();
+    }
+}
@@ -0,0 +1,34 @@
+//! Per-frame record of each local's type name, used to flag type changes.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub(crate) struct TypeChanges {
+    frames: HashMap<u64, HashMap<String, String>>,
+}
+
+impl TypeChanges {
+    /// Remember `type_name` for `name` in `frame` and return the previous
+    /// type name when it differs.
+    pub(crate) fn observe(&mut self, frame: u64, name: &str, type_name: &str) -> Option<String> {
+        let locals = self.frames.entry(frame).or_default();
+        match locals.get_mut(name) {
+            Some(previous) if previous == type_name => None,
+            Some(previous) => Some(std::mem::replace(previous, type_name.to_string())),
+            None => {
+                locals.insert(name.to_string(), type_name.to_string());
+                None
+            }
+        }
+    }
+
+    /// Drop the types recorded for `frame` so a frame later allocated at the
+    /// same address starts from scratch.
+    pub(crate) fn forget_frame(&mut self, frame: u64) {
+        self.frames.remove(&frame);
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.frames.clear();
+    }
+}
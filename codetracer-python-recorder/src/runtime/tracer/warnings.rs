@@ -0,0 +1,115 @@
+//! Replacement for `warnings.showwarning` that hands each warning to the
+//! installed tracer as a structured event instead of printing it to stderr.
+
+use crate::ffi;
+use crate::monitoring::warn_installed_tracer;
+use pyo3::prelude::*;
+use pyo3::types::PyAny;
+
+/// The fields of a warning as recorded in the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WarningFields {
+    pub(crate) category: String,
+    pub(crate) message: String,
+    pub(crate) path: String,
+    pub(crate) line: Option<i64>,
+}
+
+impl WarningFields {
+    /// Read the fields from the positional arguments of `showwarning`.
+    pub(crate) fn from_args(
+        message: &Bound<'_, PyAny>,
+        category: &Bound<'_, PyAny>,
+        filename: &Bound<'_, PyAny>,
+        lineno: &Bound<'_, PyAny>,
+    ) -> PyResult<Self> {
+        let category = match category.getattr("__name__") {
+            Ok(name) => name.extract()?,
+            Err(_) => category.str()?.to_string_lossy().into_owned(),
+        };
+        Ok(Self {
+            category,
+            message: message.str()?.to_string_lossy().into_owned(),
+            path: filename.str()?.to_string_lossy().into_owned(),
+            line: lineno.extract().ok(),
+        })
+    }
+}
+
+/// Callable installed as `warnings.showwarning` while a session runs.
+/// Warnings no tracer takes go to the hook it replaced.
+#[pyclass(module = "codetracer_python_recorder.runtime")]
+pub struct ShowWarningHook {
+    original: PyObject,
+}
+
+#[pymethods]
+impl ShowWarningHook {
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (message, category, filename, lineno, file=None, line=None))]
+    fn __call__(
+        &self,
+        py: Python<'_>,
+        message: Bound<'_, PyAny>,
+        category: Bound<'_, PyAny>,
+        filename: Bound<'_, PyAny>,
+        lineno: Bound<'_, PyAny>,
+        file: Option<Bound<'_, PyAny>>,
+        line: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        ffi::wrap_pyfunction("showwarning", || {
+            let fields = WarningFields::from_args(&message, &category, &filename, &lineno)?;
+            if warn_installed_tracer(
+                py,
+                &fields.category,
+                &fields.message,
+                &fields.path,
+                fields.line,
+            )? {
+                return Ok(());
+            }
+            self.original
+                .call1(py, (message, category, filename, lineno, file, line))?;
+            Ok(())
+        })
+    }
+}
+
+/// Owns the `warnings.showwarning` that was in place before the hook.
+#[derive(Default)]
+pub(crate) struct WarningsHook {
+    original: Option<PyObject>,
+}
+
+impl WarningsHook {
+    pub(crate) fn install(&mut self, py: Python<'_>) -> PyResult<()> {
+        if self.original.is_some() {
+            return Ok(());
+        }
+        let warnings = py.import("warnings")?;
+        let original = warnings.getattr("showwarning")?.unbind();
+        let hook = Py::new(
+            py,
+            ShowWarningHook {
+                original: original.clone_ref(py),
+            },
+        )?;
+        warnings.setattr("showwarning", hook)?;
+        self.original = Some(original);
+        Ok(())
+    }
+
+    /// Put the original `showwarning` back. Best-effort: the session is
+    /// ending, so a failure is only logged.
+    pub(crate) fn uninstall(&mut self, py: Python<'_>) {
+        let Some(original) = self.original.take() else {
+            return;
+        };
+        if let Err(err) = py
+            .import("warnings")
+            .and_then(|warnings| warnings.setattr("showwarning", original))
+        {
+            log::warn!("failed to restore warnings.showwarning: {err}");
+        }
+    }
+}
@@ -1,25 +1,35 @@
 //! Helpers for capturing call arguments and variable scope for tracing callbacks.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use pyo3::prelude::*;
-use pyo3::types::PyString;
+use pyo3::types::{PyDict, PyInt, PyString, PyTuple};
 
 use codetracer_trace_types::{FullValueRecord, TypeKind, ValueRecord};
 use codetracer_trace_writer_nim::trace_writer::TraceWriter;
-use recorder_errors::{usage, ErrorCode};
 
 use crate::code_object::CodeObjectWrapper;
-use crate::ffi;
 use crate::logging::record_dropped_event;
 use crate::runtime::frame_inspector::{capture_frame, FrameSnapshot};
-use crate::runtime::value_encoder::{encode_value, encode_value_streaming};
+use crate::runtime::value_encoder::{
+    encode_value, encode_value_streaming, redact_large_value, value_type_signature, CaptureEncoder,
+    EncodingOptions,
+};
 use crate::trace_filter::config::ValueAction;
 use crate::trace_filter::engine::{ValueKind, ValuePolicy};
-use codetracer_trace_writer_nim::StreamingValueEncoder;
 
 const REDACTED_SENTINEL: &str = "<redacted>";
 const DROPPED_SENTINEL: &str = "<dropped>";
+const UNAVAILABLE_SENTINEL: &str = "<unavailable>";
+
+/// Pseudo-variable name carrying the final value of a generator/coroutine.
+pub const GENERATOR_RETURN_LABEL: &str = "<generator-return>";
+
+/// Call argument name carrying the `contextvars` captured at function entry.
+pub const CONTEXTVARS_LABEL: &str = "<contextvars>";
+
+/// Pseudo-variable holding how many locals a capped snapshot left out.
+pub const OMITTED_LOCALS_LABEL: &str = "<omitted-locals>";
 
 const VALUE_KIND_COUNT: usize = 5;
 
@@ -64,6 +74,16 @@ fn dropped_value(writer: &mut dyn TraceWriter) -> ValueRecord {
     }
 }
 
+/// Stand-in for a declared parameter missing from the frame's locals, so the
+/// call record still lists every parameter of the signature.
+fn unavailable_value(writer: &mut dyn TraceWriter) -> ValueRecord {
+    let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, "Unavailable");
+    ValueRecord::Error {
+        msg: UNAVAILABLE_SENTINEL.to_string(),
+        type_id: ty,
+    }
+}
+
 fn record_redaction(kind: ValueKind, candidate: &str, telemetry: Option<&mut ValueFilterStats>) {
     if let Some(stats) = telemetry {
         stats.record_redaction(kind);
@@ -106,6 +126,7 @@ fn encode_with_policy<'py>(
     kind: ValueKind,
     candidate: &str,
     telemetry: Option<&mut ValueFilterStats>,
+    options: &EncodingOptions,
 ) -> Option<ValueRecord> {
     match policy.map(|p| p.decide(kind, candidate)) {
         Some(ValueAction::Redact) => {
@@ -116,7 +137,10 @@ fn encode_with_policy<'py>(
             record_drop(kind, candidate, telemetry);
             None
         }
-        _ => Some(encode_value(py, writer, value)),
+        _ => {
+            let record = encode_value(py, writer, value, options);
+            Some(redact_large_value(writer, record, options))
+        }
     }
 }
 
@@ -128,6 +152,7 @@ pub fn capture_call_arguments<'py>(
     code: &CodeObjectWrapper,
     policy: Option<&ValuePolicy>,
     mut telemetry: Option<&mut ValueFilterStats>,
+    options: &EncodingOptions,
 ) -> PyResult<Vec<FullValueRecord>> {
     let snapshot = capture_frame(py, code)?;
     let locals = snapshot.locals();
@@ -148,20 +173,14 @@ pub fn capture_call_arguments<'py>(
 
     let positional_take = std::cmp::min(argcount, varnames.len());
     for name in varnames.iter().take(positional_take) {
-        let value = locals.get_item(name)?.ok_or_else(|| {
-            ffi::map_recorder_error(usage!(
-                ErrorCode::MissingPositionalArgument,
-                "missing positional arg '{name}'"
-            ))
-        })?;
-        if let Some(encoded) = encode_with_policy(
+        if let Some(encoded) = encode_parameter(
             py,
             writer,
-            &value,
+            locals.get_item(name)?,
             policy,
-            ValueKind::Arg,
             name,
             telemetry.as_deref_mut(),
+            options,
         ) {
             args.push(TraceWriter::arg(writer, name, encoded));
         }
@@ -170,38 +189,52 @@ pub fn capture_call_arguments<'py>(
 
     if (flags & CO_VARARGS) != 0 && idx < varnames.len() {
         let name = &varnames[idx];
-        if let Some(value) = locals.get_item(name)? {
-            if let Some(encoded) = encode_with_policy(
-                py,
-                writer,
-                &value,
-                policy,
-                ValueKind::Arg,
-                name,
-                telemetry.as_deref_mut(),
-            ) {
-                args.push(TraceWriter::arg(writer, name, encoded));
+        let value = locals.get_item(name)?;
+        let expanded = match &value {
+            Some(value) if options.expand_varargs => value.downcast::<PyTuple>().ok(),
+            _ => None,
+        };
+        if let Some(items) = expanded {
+            // One entry per element so `arg:` selectors can target `args[1]`.
+            for (index, item) in items.iter().enumerate() {
+                let item_name = format!("{name}[{index}]");
+                if let Some(encoded) = encode_with_policy(
+                    py,
+                    writer,
+                    &item,
+                    policy,
+                    ValueKind::Arg,
+                    &item_name,
+                    telemetry.as_deref_mut(),
+                    options,
+                ) {
+                    args.push(TraceWriter::arg(writer, &item_name, encoded));
+                }
             }
+        } else if let Some(encoded) = encode_parameter(
+            py,
+            writer,
+            value,
+            policy,
+            name,
+            telemetry.as_deref_mut(),
+            options,
+        ) {
+            args.push(TraceWriter::arg(writer, name, encoded));
         }
         idx += 1;
     }
 
     let kwonly_take = std::cmp::min(kwonly, varnames.len().saturating_sub(idx));
     for name in varnames.iter().skip(idx).take(kwonly_take) {
-        let value = locals.get_item(name)?.ok_or_else(|| {
-            ffi::map_recorder_error(usage!(
-                ErrorCode::MissingKeywordArgument,
-                "missing kw-only arg '{name}'"
-            ))
-        })?;
-        if let Some(encoded) = encode_with_policy(
+        if let Some(encoded) = encode_parameter(
             py,
             writer,
-            &value,
+            locals.get_item(name)?,
             policy,
-            ValueKind::Arg,
             name,
             telemetry.as_deref_mut(),
+            options,
         ) {
             args.push(TraceWriter::arg(writer, name, encoded));
         }
@@ -210,24 +243,192 @@ pub fn capture_call_arguments<'py>(
 
     if (flags & CO_VARKEYWORDS) != 0 && idx < varnames.len() {
         let name = &varnames[idx];
-        if let Some(value) = locals.get_item(name)? {
-            if let Some(encoded) = encode_with_policy(
-                py,
-                writer,
-                &value,
-                policy,
-                ValueKind::Arg,
-                name,
-                telemetry.as_deref_mut(),
-            ) {
-                args.push(TraceWriter::arg(writer, name, encoded));
-            }
+        if let Some(encoded) = encode_parameter(
+            py,
+            writer,
+            locals.get_item(name)?,
+            policy,
+            name,
+            telemetry.as_deref_mut(),
+            options,
+        ) {
+            args.push(TraceWriter::arg(writer, name, encoded));
         }
     }
 
     Ok(args)
 }
 
+/// Encode parameter `name`, recording [`UNAVAILABLE_SENTINEL`] when it is no
+/// longer bound in the frame (deleted, or not yet bound).
+fn encode_parameter<'py>(
+    py: Python<'py>,
+    writer: &mut dyn TraceWriter,
+    value: Option<Bound<'py, PyAny>>,
+    policy: Option<&ValuePolicy>,
+    name: &str,
+    telemetry: Option<&mut ValueFilterStats>,
+    options: &EncodingOptions,
+) -> Option<ValueRecord> {
+    match value {
+        Some(value) => encode_with_policy(
+            py,
+            writer,
+            &value,
+            policy,
+            ValueKind::Arg,
+            name,
+            telemetry,
+            options,
+        ),
+        None => Some(unavailable_value(writer)),
+    }
+}
+
+/// Encode the entries of `contextvars.copy_context()` as one call argument
+/// named [`CONTEXTVARS_LABEL`], a sequence of `(name, value)` pairs. Each value
+/// passes through the value policy under its variable name, so redaction rules
+/// apply. Returns `None` when the context is empty.
+pub fn capture_context_vars<'py>(
+    py: Python<'py>,
+    writer: &mut dyn TraceWriter,
+    policy: Option<&ValuePolicy>,
+    mut telemetry: Option<&mut ValueFilterStats>,
+    options: &EncodingOptions,
+) -> PyResult<Option<FullValueRecord>> {
+    let context = py.import("contextvars")?.call_method0("copy_context")?;
+    let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, "ContextVars");
+    let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+    let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+    let mut elements = Vec::new();
+    for item in context.call_method0("items")?.try_iter()? {
+        let (var, value): (Bound<'py, PyAny>, Bound<'py, PyAny>) = item?.extract()?;
+        let name: String = var.getattr("name")?.extract()?;
+        if let Some(encoded) = encode_with_policy(
+            py,
+            writer,
+            &value,
+            policy,
+            ValueKind::Global,
+            &name,
+            telemetry.as_deref_mut(),
+            options,
+        ) {
+            elements.push(ValueRecord::Tuple {
+                elements: vec![
+                    ValueRecord::String {
+                        text: name,
+                        type_id: str_ty,
+                    },
+                    encoded,
+                ],
+                type_id: tuple_ty,
+            });
+        }
+    }
+    if elements.is_empty() {
+        return Ok(None);
+    }
+    let record = ValueRecord::Sequence {
+        elements,
+        is_slice: false,
+        type_id: seq_ty,
+    };
+    Ok(Some(TraceWriter::arg(writer, CONTEXTVARS_LABEL, record)))
+}
+
+/// Names of the parameters of the frame running `code` that still hold their
+/// default value, in declaration order.
+///
+/// The function object is found by walking `co_qualname` from the frame's
+/// globals, so functions defined inside another function resolve to an empty
+/// list. A value passed explicitly that is the default object itself (such as
+/// an explicit `None` for a `None` default) counts as defaulted.
+pub fn default_argument_names(py: Python<'_>, code: &CodeObjectWrapper) -> PyResult<Vec<String>> {
+    let snapshot = capture_frame(py, code)?;
+    let Some(function) = resolve_function(&snapshot, code.as_bound(py).as_any())? else {
+        return Ok(Vec::new());
+    };
+    let locals = snapshot.locals();
+    let code_bound = code.as_bound(py);
+    let argcount = code.arg_count(py)? as usize;
+    let kwonly: usize = code_bound.getattr("co_kwonlyargcount")?.extract()?;
+    let flags = code.flags(py)?;
+    let varnames: Vec<String> = code_bound.getattr("co_varnames")?.extract()?;
+
+    let holds_default = |name: &str, default: &Bound<'_, PyAny>| -> PyResult<bool> {
+        Ok(locals
+            .get_item(name)?
+            .is_some_and(|value| value.is(default)))
+    };
+
+    let mut names = Vec::new();
+    let defaults = function.getattr("__defaults__")?;
+    if let Ok(defaults) = defaults.downcast::<PyTuple>() {
+        let first = argcount.saturating_sub(defaults.len());
+        for (name, default) in varnames
+            .iter()
+            .take(argcount)
+            .skip(first)
+            .zip(defaults.iter())
+        {
+            if holds_default(name, &default)? {
+                names.push(name.clone());
+            }
+        }
+    }
+
+    const CO_VARARGS: u32 = 0x04;
+    let kwonly_start = argcount + usize::from(flags & CO_VARARGS != 0);
+    let kwdefaults = function.getattr("__kwdefaults__")?;
+    if let Ok(kwdefaults) = kwdefaults.downcast::<PyDict>() {
+        for name in varnames.iter().skip(kwonly_start).take(kwonly) {
+            if let Some(default) = kwdefaults.get_item(name)? {
+                if holds_default(name, &default)? {
+                    names.push(name.clone());
+                }
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Look up the function whose `__code__` is `code` by following its
+/// qualified name from the frame's globals.
+fn resolve_function<'py>(
+    snapshot: &FrameSnapshot<'py>,
+    code: &Bound<'py, PyAny>,
+) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let Some(globals) = snapshot.globals() else {
+        return Ok(None);
+    };
+    let qualname: String = code.getattr("co_qualname")?.extract()?;
+    if qualname.contains("<locals>") {
+        return Ok(None);
+    }
+    let mut parts = qualname.split('.');
+    let Some(mut current) = parts
+        .next()
+        .and_then(|head| globals.get_item(head).ok().flatten())
+    else {
+        return Ok(None);
+    };
+    for part in parts {
+        match current.getattr(part) {
+            Ok(next) => current = next,
+            Err(_) => return Ok(None),
+        }
+    }
+    // Class and static methods come back bound or wrapped; unwrap to the function.
+    if let Ok(inner) = current.getattr("__func__") {
+        current = inner;
+    }
+    match current.getattr("__code__") {
+        Ok(found) if found.is(code) => Ok(Some(current)),
+        _ => Ok(None),
+    }
+}
+
 /// Encode a single argument with the current value policy, producing a call argument record.
 pub fn encode_named_argument<'py>(
     py: Python<'py>,
@@ -236,6 +437,7 @@ pub fn encode_named_argument<'py>(
     name: &str,
     policy: Option<&ValuePolicy>,
     mut telemetry: Option<&mut ValueFilterStats>,
+    options: &EncodingOptions,
 ) -> Option<FullValueRecord> {
     encode_with_policy(
         py,
@@ -245,6 +447,7 @@ pub fn encode_named_argument<'py>(
         ValueKind::Arg,
         name,
         telemetry.as_deref_mut(),
+        options,
     )
     .map(|encoded| TraceWriter::arg(writer, name, encoded))
 }
@@ -259,7 +462,7 @@ pub fn encode_named_argument<'py>(
 fn encode_with_policy_streaming<'py>(
     py: Python<'py>,
     writer: &mut dyn TraceWriter,
-    encoder: &mut StreamingValueEncoder,
+    encoder: &mut CaptureEncoder,
     value: &Bound<'py, PyAny>,
     policy: Option<&ValuePolicy>,
     kind: ValueKind,
@@ -283,18 +486,31 @@ fn encode_with_policy_streaming<'py>(
     }
 }
 
+/// Encoded value of each variable as last recorded in one frame.
+pub type LastValues = HashMap<String, Vec<u8>>;
+
 /// Streaming variant of [`record_visible_scope`]. Encodes Python values
 /// directly to CBOR bytes and passes them to `register_variable_cbor`,
 /// avoiding intermediate `ValueRecord` tree allocations.
+///
+/// With `max_locals`, only the first that many locals are encoded and the
+/// number left out is recorded under [`OMITTED_LOCALS_LABEL`]. With
+/// `last_values`, variables whose encoding matches the previous snapshot
+/// of the frame are left out.
+#[allow(clippy::too_many_arguments)]
 pub fn record_visible_scope_streaming(
     py: Python<'_>,
     writer: &mut dyn TraceWriter,
-    encoder: &mut StreamingValueEncoder,
+    encoder: &mut CaptureEncoder,
     snapshot: &FrameSnapshot<'_>,
     recorded: &mut HashSet<String>,
     policy: Option<&ValuePolicy>,
     mut telemetry: Option<&mut ValueFilterStats>,
+    max_locals: Option<usize>,
+    mut last_values: Option<&mut LastValues>,
 ) {
+    let mut kept = 0usize;
+    let mut omitted = 0usize;
     for (key, value) in snapshot.locals().iter() {
         let name = match key.downcast::<PyString>() {
             Ok(pystr) => match pystr.to_str() {
@@ -303,6 +519,11 @@ pub fn record_visible_scope_streaming(
             },
             Err(_) => continue,
         };
+        if max_locals.is_some_and(|cap| kept >= cap) {
+            omitted += 1;
+            continue;
+        }
+        kept += 1;
         let cbor = encode_with_policy_streaming(
             py,
             writer,
@@ -314,11 +535,19 @@ pub fn record_visible_scope_streaming(
             telemetry.as_deref_mut(),
         );
         if let Some(cbor) = cbor {
-            TraceWriter::register_variable_cbor(writer, &name, &cbor);
+            if !unchanged(&mut last_values, &name, &cbor) {
+                TraceWriter::register_variable_cbor(writer, &name, &cbor);
+            }
             recorded.insert(name);
         }
     }
 
+    if omitted > 0 {
+        let count = PyInt::new(py, omitted);
+        let cbor = encode_value_streaming(py, writer, encoder, count.as_any());
+        TraceWriter::register_variable_cbor(writer, OMITTED_LOCALS_LABEL, &cbor);
+    }
+
     if snapshot.locals_is_globals() {
         return;
     }
@@ -346,26 +575,104 @@ pub fn record_visible_scope_streaming(
                 telemetry.as_deref_mut(),
             );
             if let Some(cbor) = cbor {
-                TraceWriter::register_variable_cbor(writer, name, &cbor);
+                if !unchanged(&mut last_values, name, &cbor) {
+                    TraceWriter::register_variable_cbor(writer, name, &cbor);
+                }
                 recorded.insert(name.to_owned());
             }
         }
     }
 }
 
+/// Remember `cbor` as the last value of `name` and report whether it matches
+/// the value recorded before. Always `false` without `last_values`.
+fn unchanged(last_values: &mut Option<&mut LastValues>, name: &str, cbor: &[u8]) -> bool {
+    let Some(last) = last_values.as_deref_mut() else {
+        return false;
+    };
+    if last
+        .get(name)
+        .is_some_and(|previous| previous.as_slice() == cbor)
+    {
+        return true;
+    }
+    last.insert(name.to_owned(), cbor.to_vec());
+    false
+}
+
 /// Streaming variant of [`record_return_value`]. Encodes the return value
 /// directly to CBOR bytes and passes them to `register_return_cbor`.
+#[allow(clippy::too_many_arguments)]
 pub fn record_return_value_streaming(
     py: Python<'_>,
     writer: &mut dyn TraceWriter,
-    encoder: &mut StreamingValueEncoder,
+    encoder: &mut CaptureEncoder,
     value: &Bound<'_, PyAny>,
     policy: Option<&ValuePolicy>,
-    mut telemetry: Option<&mut ValueFilterStats>,
+    telemetry: Option<&mut ValueFilterStats>,
     candidate: Option<&str>,
+    keep_dropped_type: bool,
 ) {
     let name = candidate.unwrap_or("<return>");
-    let cbor = encode_with_policy_streaming(
+    let cbor = encode_return_streaming(
+        py,
+        writer,
+        encoder,
+        value,
+        policy,
+        telemetry,
+        name,
+        keep_dropped_type,
+    );
+    TraceWriter::register_return_cbor(writer, &cbor);
+}
+
+/// Record the final value of a generator or coroutine frame.
+///
+/// A `return X` inside a generator surfaces to callers only as
+/// `StopIteration.value`, so a plain return record would look identical to
+/// the `<yield>` records emitted for every suspension. We first bind the
+/// value to the [`GENERATOR_RETURN_LABEL`] pseudo-variable and then close
+/// the call with the usual return record, letting consumers tell the final
+/// value apart from yielded ones while keeping call/return pairs balanced.
+pub fn record_generator_return_streaming(
+    py: Python<'_>,
+    writer: &mut dyn TraceWriter,
+    encoder: &mut CaptureEncoder,
+    value: &Bound<'_, PyAny>,
+    policy: Option<&ValuePolicy>,
+    telemetry: Option<&mut ValueFilterStats>,
+    keep_dropped_type: bool,
+) {
+    let cbor = encode_return_streaming(
+        py,
+        writer,
+        encoder,
+        value,
+        policy,
+        telemetry,
+        GENERATOR_RETURN_LABEL,
+        keep_dropped_type,
+    );
+    TraceWriter::register_variable_cbor(writer, GENERATOR_RETURN_LABEL, &cbor);
+    TraceWriter::register_return_cbor(writer, &cbor);
+}
+
+/// Encode a return value under `policy`. A dropped value is recorded as the
+/// `<dropped>` sentinel; with `keep_dropped_type` the sentinel carries the
+/// value's own type instead of the generic `Dropped` type.
+#[allow(clippy::too_many_arguments)]
+fn encode_return_streaming(
+    py: Python<'_>,
+    writer: &mut dyn TraceWriter,
+    encoder: &mut CaptureEncoder,
+    value: &Bound<'_, PyAny>,
+    policy: Option<&ValuePolicy>,
+    mut telemetry: Option<&mut ValueFilterStats>,
+    name: &str,
+    keep_dropped_type: bool,
+) -> Vec<u8> {
+    encode_with_policy_streaming(
         py,
         writer,
         encoder,
@@ -377,12 +684,16 @@ pub fn record_return_value_streaming(
     )
     .unwrap_or_else(|| {
         // Encode the dropped sentinel via the streaming encoder.
-        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, "Dropped");
+        let ty = if keep_dropped_type {
+            let (kind, type_name) = value_type_signature(value);
+            TraceWriter::ensure_type_id(writer, kind, &type_name)
+        } else {
+            TraceWriter::ensure_type_id(writer, TypeKind::Raw, "Dropped")
+        };
         encoder.reset();
         encoder.write_error(DROPPED_SENTINEL, ty);
         encoder.get_bytes_copy()
-    });
-    TraceWriter::register_return_cbor(writer, &cbor);
+    })
 }
 
 /// Record all visible variables from the provided frame snapshot into the writer.
@@ -413,6 +724,7 @@ pub fn record_visible_scope(
             ValueKind::Local,
             &name,
             telemetry.as_deref_mut(),
+            &EncodingOptions::default(),
         );
         if let Some(encoded) = encoded {
             TraceWriter::register_variable_with_full_value(writer, &name, encoded);
@@ -444,6 +756,7 @@ pub fn record_visible_scope(
                 ValueKind::Global,
                 name,
                 telemetry.as_deref_mut(),
+                &EncodingOptions::default(),
             );
             if let Some(encoded) = encoded {
                 TraceWriter::register_variable_with_full_value(writer, name, encoded);
@@ -474,6 +787,7 @@ pub fn record_return_value(
         ValueKind::Return,
         name,
         telemetry.as_deref_mut(),
+        &EncodingOptions::default(),
     )
     .unwrap_or_else(|| dropped_value(writer));
     TraceWriter::register_return(writer, encoded);
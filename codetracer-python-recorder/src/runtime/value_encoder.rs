@@ -12,17 +12,543 @@
 //!    using Python's `id()` (object identity). This is the M58 path.
 
 use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
 
 use codetracer_trace_types::{TypeKind, ValueRecord, NONE_VALUE};
 use codetracer_trace_writer_nim::trace_writer::TraceWriter;
 use codetracer_trace_writer_nim::StreamingValueEncoder;
+use pyo3::exceptions::PyBaseException;
 use pyo3::prelude::*;
-use pyo3::types::{PyAny, PyDict, PyList, PyTuple};
+use pyo3::sync::GILOnceCell;
+use pyo3::types::{
+    PyAny, PyBool, PyCFunction, PyDict, PyFloat, PyFrozenSet, PyFunction, PyInt, PyList, PyModule,
+    PySet, PySlice, PyString, PyTuple, PyType,
+};
+
+use crate::policy::{RecorderPolicy, UnreprFallback};
 
 /// Maximum recursion depth for streaming encoding. Protects against
 /// pathological nesting that would overflow the encoder's compound stack
 /// (which supports 32 levels) or the Rust call stack.
-const MAX_STREAMING_DEPTH: usize = 30;
+pub(crate) const MAX_STREAMING_DEPTH: usize = 30;
+
+/// Longest `str()` text, in characters, recorded for a `Raw` fallback. Longer
+/// texts are cut to this length and suffixed with `…`.
+pub(crate) const MAX_RAW_CHARS: usize = 4096;
+
+/// Longest `__cause__`/`__context__` chain recorded below an exception.
+/// Links past this depth are left out.
+const MAX_EXCEPTION_CHAIN_DEPTH: usize = 8;
+
+/// Session-level knobs that shape how values are encoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EncodingOptions {
+    /// Emit dict entries sorted by key instead of insertion order.
+    pub sort_dict_keys: bool,
+    /// Encode modules as a `__name__`/`__file__` summary instead of their repr.
+    pub summarize_modules: bool,
+    /// Encode instances with their `__slots__` attributes instead of their repr.
+    pub capture_slots: bool,
+    /// Also evaluate `@property` getters when encoding instances. Off by
+    /// default because getters can run arbitrary code with side effects.
+    pub capture_properties: bool,
+    /// What to record when a value's `str()` raises.
+    pub unrepr_fallback: UnreprFallback,
+    /// Record `*args` as `args[0]`, `args[1]`, ... instead of one tuple.
+    pub expand_varargs: bool,
+    /// Replace memory addresses in raw texts with `0xADDR` and render sets
+    /// in sorted order, so repeated runs encode identically.
+    pub deterministic: bool,
+    /// Module prefixes whose instances are recorded as their type name only,
+    /// without calling `str()` or reading attributes.
+    pub opaque_modules: Vec<String>,
+    /// Values whose encoding exceeds this many bytes are replaced by a
+    /// `<large-value N bytes>` marker instead of being recorded.
+    pub redact_values_over: Option<usize>,
+}
+
+impl EncodingOptions {
+    /// Derive the encoding options from the recorder policy.
+    pub fn from_policy(policy: &RecorderPolicy) -> Self {
+        Self {
+            sort_dict_keys: policy.sort_dict_keys || policy.deterministic,
+            summarize_modules: policy.summarize_modules,
+            capture_slots: policy.capture_slots,
+            capture_properties: policy.capture_properties,
+            unrepr_fallback: policy.unrepr_fallback,
+            expand_varargs: policy.expand_varargs,
+            deterministic: policy.deterministic,
+            opaque_modules: policy.opaque_modules.clone(),
+            redact_values_over: policy.redact_values_over,
+        }
+    }
+}
+
+/// Streaming encoder paired with the session's [`EncodingOptions`].
+///
+/// The tracer owns one instance and hands it to every streaming capture
+/// helper, so the options travel with the encoder instead of being threaded
+/// through each call separately.
+pub struct CaptureEncoder {
+    encoder: StreamingValueEncoder,
+    options: EncodingOptions,
+}
+
+impl CaptureEncoder {
+    pub fn new() -> Self {
+        Self {
+            encoder: StreamingValueEncoder::new(),
+            options: EncodingOptions::default(),
+        }
+    }
+
+    pub fn options(&self) -> &EncodingOptions {
+        &self.options
+    }
+
+    pub fn set_options(&mut self, options: EncodingOptions) {
+        self.options = options;
+    }
+}
+
+impl Default for CaptureEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for CaptureEncoder {
+    type Target = StreamingValueEncoder;
+
+    fn deref(&self) -> &Self::Target {
+        &self.encoder
+    }
+}
+
+impl DerefMut for CaptureEncoder {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.encoder
+    }
+}
+
+/// Return the `(key, value)` pairs of `dict` in encoding order.
+///
+/// Insertion order is the default. With `sort_keys`, entries are ordered with
+/// Python's own `sorted()` so mixed numeric keys compare naturally; keys that
+/// cannot be ordered against each other (e.g. `1` and `"a"`) make `sorted()`
+/// raise, in which case we keep insertion order rather than fail the capture.
+fn dict_entries<'py>(
+    py: Python<'py>,
+    dict: &Bound<'py, PyDict>,
+    sort_keys: bool,
+) -> Vec<(Bound<'py, PyAny>, Bound<'py, PyAny>)> {
+    if sort_keys {
+        if let Some(sorted) = sorted_dict_entries(py, dict) {
+            return sorted;
+        }
+    }
+    dict.iter().collect()
+}
+
+/// Identify a module by its `__name__` and `__file__` (absent for builtins).
+fn module_summary(module: &Bound<'_, PyModule>) -> Option<(String, Option<String>)> {
+    let name = module.name().ok()?.to_string_lossy().into_owned();
+    let file = module
+        .getattr("__file__")
+        .ok()
+        .and_then(|file| file.extract::<String>().ok());
+    Some((name, file))
+}
+
+/// Type name attached to `Raw` fallbacks so opaque objects stay identifiable
+/// by class. Falls back to the generic `Object` type if the class has no
+/// readable `__qualname__`.
+fn raw_type_name(value: &Bound<'_, PyAny>) -> String {
+    value
+        .get_type()
+        .qualname()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "Object".to_string())
+}
+
+/// `module.qualname` of `value`'s type when that module is one of the
+/// configured opaque prefixes (the module itself or a submodule).
+fn opaque_type_name(value: &Bound<'_, PyAny>, options: &EncodingOptions) -> Option<String> {
+    if options.opaque_modules.is_empty() {
+        return None;
+    }
+    let module: String = value
+        .get_type()
+        .getattr("__module__")
+        .ok()?
+        .extract()
+        .ok()?;
+    let opaque = options.opaque_modules.iter().any(|prefix| {
+        module
+            .strip_prefix(prefix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    });
+    opaque.then(|| format!("{module}.{}", raw_type_name(value)))
+}
+
+/// Type name of the marker that replaces values over `redact_values_over`.
+pub(crate) const LARGE_VALUE_TYPE_NAME: &str = "LargeValue";
+
+fn large_value_marker(size: usize) -> String {
+    format!("<large-value {size} bytes>")
+}
+
+/// Replace `record` with a `<large-value N bytes>` marker when its JSON
+/// encoding exceeds `redact_values_over`. Unlike truncation, none of the
+/// value's content is kept.
+pub(crate) fn redact_large_value(
+    writer: &mut dyn TraceWriter,
+    record: ValueRecord,
+    options: &EncodingOptions,
+) -> ValueRecord {
+    let Some(limit) = options.redact_values_over else {
+        return record;
+    };
+    let size = serde_json::to_vec(&record).map_or(0, |bytes| bytes.len());
+    if size <= limit {
+        return record;
+    }
+    ValueRecord::Error {
+        msg: large_value_marker(size),
+        type_id: TraceWriter::ensure_type_id(writer, TypeKind::Raw, LARGE_VALUE_TYPE_NAME),
+    }
+}
+
+/// Type name of ints outside the `i64` range. The value carries the exact
+/// decimal digits as raw text under an `Int`-kind type.
+pub(crate) const BIG_INT_TYPE_NAME: &str = "BigInt";
+
+/// Decimal digits of an exact `int` that does not fit in an `i64`. `None`
+/// for other values, and for ints past Python's digit conversion limit.
+fn big_int_digits(value: &Bound<'_, PyAny>) -> Option<String> {
+    if !value.is_exact_instance_of::<PyInt>() {
+        return None;
+    }
+    value.str().ok()?.to_str().ok().map(str::to_owned)
+}
+
+/// Type kind and name the encoders record for `value`, without encoding its
+/// contents. Instances outside the builtin scalars and containers report
+/// their class qualname as a `Raw` type.
+pub(crate) fn value_type_signature(value: &Bound<'_, PyAny>) -> (TypeKind, String) {
+    let (kind, name) = if value.is_none() {
+        (TypeKind::Raw, "NoneType")
+    } else if value.is_instance_of::<PyBool>() {
+        (TypeKind::Bool, "Bool")
+    } else if value.is_instance_of::<PyInt>() {
+        (TypeKind::Int, "Int")
+    } else if value.is_instance_of::<PyFloat>() {
+        (TypeKind::Float, "Float")
+    } else if value.is_instance_of::<PyString>() {
+        (TypeKind::String, "String")
+    } else if value.is_instance_of::<PyTuple>() {
+        (TypeKind::Tuple, "Tuple")
+    } else if value.is_instance_of::<PyList>() {
+        (TypeKind::Seq, "List")
+    } else if value.is_instance_of::<PyDict>() {
+        (TypeKind::Seq, "Dict")
+    } else {
+        return (TypeKind::Raw, raw_type_name(value));
+    };
+    (kind, name.to_string())
+}
+
+/// Text recorded for a `Raw` fallback: `str()` of `value`, or what
+/// `options.unrepr_fallback` prescribes when `str()` raises. `None` means the
+/// `<unrepr>` sentinel should be recorded.
+fn raw_text(value: &Bound<'_, PyAny>, options: &EncodingOptions) -> Option<String> {
+    let text = raw_text_unnormalised(value, options)?;
+    Some(if options.deterministic {
+        normalise_addresses(&text)
+    } else {
+        text
+    })
+}
+
+fn raw_text_unnormalised(value: &Bound<'_, PyAny>, options: &EncodingOptions) -> Option<String> {
+    if options.deterministic {
+        if let Some(text) = sorted_set_text(value) {
+            return Some(text);
+        }
+    }
+    if let Ok(text) = value.str().and_then(|text| capped_text(&text)) {
+        return Some(text);
+    }
+    match options.unrepr_fallback {
+        UnreprFallback::Sentinel => None,
+        UnreprFallback::Repr => value.repr().and_then(|text| capped_text(&text)).ok(),
+        UnreprFallback::TypeName => Some(raw_type_name(value)),
+    }
+}
+
+/// Render a non-empty `set` / `frozenset` like `str()` does, but with its
+/// elements in `sorted()` order instead of hash order (which changes with
+/// `PYTHONHASHSEED`). `None` when `value` is not a set or its elements cannot
+/// be ordered against each other.
+fn sorted_set_text(value: &Bound<'_, PyAny>) -> Option<String> {
+    let frozen = value.is_instance_of::<PyFrozenSet>();
+    if !frozen && !value.is_instance_of::<PySet>() {
+        return None;
+    }
+    if value.len().ok()? == 0 {
+        return None;
+    }
+    let sorted = value
+        .py()
+        .import("builtins")
+        .and_then(|builtins| builtins.getattr("sorted"))
+        .and_then(|sorted| sorted.call1((value,)))
+        .ok()?;
+    let mut items = Vec::new();
+    for item in sorted.try_iter().ok()? {
+        items.push(item.ok()?.repr().ok()?.to_string_lossy().into_owned());
+    }
+    let body = format!("{{{}}}", items.join(", "));
+    let text = if frozen {
+        format!("frozenset({body})")
+    } else {
+        body
+    };
+    let text = PyString::new(value.py(), &text);
+    capped_text(&text).ok()
+}
+
+/// Replace every hex literal (`0x` followed by hex digits, as in
+/// `<Foo object at 0x7f3a...>`) with `0xADDR`.
+fn normalise_addresses(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find("0x") {
+        let (head, tail) = rest.split_at(index);
+        out.push_str(head);
+        let digits = tail[2..]
+            .find(|c: char| !c.is_ascii_hexdigit())
+            .unwrap_or(tail.len() - 2);
+        if digits == 0 {
+            out.push_str("0x");
+        } else {
+            out.push_str("0xADDR");
+        }
+        rest = &tail[2 + digits..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Copy `text` into Rust, capped at [`MAX_RAW_CHARS`].
+///
+/// Oversized texts are sliced on the Python side so only the kept prefix is
+/// copied into Rust.
+fn capped_text(text: &Bound<'_, PyString>) -> PyResult<String> {
+    if text.len()? <= MAX_RAW_CHARS {
+        return Ok(text.to_string_lossy().into_owned());
+    }
+    let slice = PySlice::new(text.py(), 0, MAX_RAW_CHARS as isize, 1);
+    let prefix = text.get_item(slice)?;
+    let prefix = prefix.downcast::<PyString>()?;
+    let mut truncated = prefix.to_string_lossy().into_owned();
+    truncated.push('…');
+    Ok(truncated)
+}
+
+/// Name a function, builtin or bound method as `__module__.__qualname__`.
+/// Unlike their repr this carries no memory address, so it is stable across
+/// runs. Bound methods are named after their underlying function.
+pub(crate) fn callable_name(value: &Bound<'_, PyAny>) -> Option<String> {
+    static METHOD_TYPE: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    let function = if value.is_instance_of::<PyFunction>() || value.is_instance_of::<PyCFunction>()
+    {
+        value.clone()
+    } else {
+        let method_type = METHOD_TYPE.import(value.py(), "types", "MethodType").ok()?;
+        if !value.get_type().is(method_type) {
+            return None;
+        }
+        value.getattr("__func__").ok()?
+    };
+    let qualname: String = function.getattr("__qualname__").ok()?.extract().ok()?;
+    // Methods of builtin types (`[].append`) have no module.
+    match function
+        .getattr("__module__")
+        .ok()
+        .and_then(|module| module.extract::<String>().ok())
+    {
+        Some(module) => Some(format!("{module}.{qualname}")),
+        None => Some(qualname),
+    }
+}
+
+/// Identify an exception instance by its class `__qualname__` and `args`.
+fn exception_summary<'py>(value: &Bound<'py, PyAny>) -> Option<(String, Bound<'py, PyTuple>)> {
+    let exc = value.downcast::<PyBaseException>().ok()?;
+    let class_name = exc
+        .get_type()
+        .qualname()
+        .ok()?
+        .to_string_lossy()
+        .into_owned();
+    let args = exc.getattr("args").ok()?.downcast_into::<PyTuple>().ok()?;
+    Some((class_name, args))
+}
+
+/// Summarised exception plus the exceptions it is chained to.
+struct ExceptionRecord<'py> {
+    class_name: String,
+    args: Bound<'py, PyTuple>,
+    links: Vec<(&'static str, ExceptionRecord<'py>)>,
+}
+
+/// Summarise `value` and follow its `__cause__` and, unless suppressed by
+/// `raise ... from`, its `__context__`, up to `remaining` links deep. The
+/// links mirror the chain Python prints in a traceback.
+fn exception_record<'py>(
+    value: &Bound<'py, PyAny>,
+    remaining: usize,
+) -> Option<ExceptionRecord<'py>> {
+    let (class_name, args) = exception_summary(value)?;
+    let mut links = Vec::new();
+    if remaining > 0 {
+        let linked = |attr: &str| value.getattr(attr).ok().filter(|linked| !linked.is_none());
+        if let Some(cause) = linked("__cause__") {
+            if let Some(record) = exception_record(&cause, remaining - 1) {
+                links.push(("__cause__", record));
+            }
+        }
+        let suppressed = value
+            .getattr("__suppress_context__")
+            .and_then(|flag| flag.is_truthy())
+            .unwrap_or(false);
+        if !suppressed {
+            if let Some(context) = linked("__context__") {
+                if let Some(record) = exception_record(&context, remaining - 1) {
+                    links.push(("__context__", record));
+                }
+            }
+        }
+    }
+    Some(ExceptionRecord {
+        class_name,
+        args,
+        links,
+    })
+}
+
+/// Collect the slot and/or property attributes of an instance, walking the
+/// MRO so inherited slots are included. Returns the type name plus the
+/// `(attribute, value)` pairs, or `None` when nothing was captured so the
+/// caller falls back to the repr. Unset slots and raising getters are skipped.
+fn object_attributes<'py>(
+    value: &Bound<'py, PyAny>,
+    options: &EncodingOptions,
+) -> Option<(String, Vec<(String, Bound<'py, PyAny>)>)> {
+    if !options.capture_slots && !options.capture_properties {
+        return None;
+    }
+    let py = value.py();
+    let ty = value.get_type();
+    let property_type = py
+        .import("builtins")
+        .and_then(|builtins| builtins.getattr("property"))
+        .ok()?;
+    let mut names = HashSet::new();
+    let mut attributes = Vec::new();
+    for cls in ty.getattr("__mro__").ok()?.try_iter().ok()? {
+        let Ok(cls) = cls else { continue };
+        let Ok(namespace) = cls.getattr("__dict__") else {
+            continue;
+        };
+        if options.capture_slots {
+            if let Ok(slots) = namespace.get_item("__slots__") {
+                let class_name: String = cls
+                    .getattr("__name__")
+                    .and_then(|name| name.extract())
+                    .unwrap_or_default();
+                for slot in slot_names(&slots) {
+                    if slot == "__dict__" || slot == "__weakref__" || !names.insert(slot.clone()) {
+                        continue;
+                    }
+                    let attribute = mangle_private_name(&class_name, &slot);
+                    if let Ok(item) = value.getattr(attribute.as_str()) {
+                        attributes.push((slot, item));
+                    }
+                }
+            }
+        }
+        if options.capture_properties {
+            let Ok(items) = namespace
+                .call_method0("items")
+                .and_then(|items| items.try_iter())
+            else {
+                continue;
+            };
+            for item in items.flatten() {
+                let Ok((name, attr)) = item.extract::<(String, Bound<'py, PyAny>)>() else {
+                    continue;
+                };
+                if !attr.is_instance(&property_type).unwrap_or(false) || !names.insert(name.clone())
+                {
+                    continue;
+                }
+                if let Ok(item) = value.getattr(name.as_str()) {
+                    attributes.push((name, item));
+                }
+            }
+        }
+    }
+    if attributes.is_empty() {
+        return None;
+    }
+    let type_name = ty.name().ok()?.to_string_lossy().into_owned();
+    Some((type_name, attributes))
+}
+
+/// `__slots__` may be a single string or any iterable of strings.
+fn slot_names(slots: &Bound<'_, PyAny>) -> Vec<String> {
+    if let Ok(single) = slots.extract::<String>() {
+        return vec![single];
+    }
+    slots
+        .try_iter()
+        .map(|iter| {
+            iter.filter_map(|item| item.ok()?.extract::<String>().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Apply Python's private name mangling (`__x` in `Cls` is stored as
+/// `_Cls__x`) so private slots can be read back with `getattr`.
+fn mangle_private_name(class_name: &str, name: &str) -> String {
+    let stripped = class_name.trim_start_matches('_');
+    if name.starts_with("__") && !name.ends_with("__") && !stripped.is_empty() {
+        format!("_{stripped}{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+fn sorted_dict_entries<'py>(
+    py: Python<'py>,
+    dict: &Bound<'py, PyDict>,
+) -> Option<Vec<(Bound<'py, PyAny>, Bound<'py, PyAny>)>> {
+    let sorted = py
+        .import("builtins")
+        .and_then(|builtins| builtins.getattr("sorted"))
+        .and_then(|sorted| sorted.call1((dict.keys(),)))
+        .ok()?;
+    let mut entries = Vec::with_capacity(dict.len());
+    for key in sorted.try_iter().ok()? {
+        let key = key.ok()?;
+        let value = dict.get_item(&key).ok().flatten()?;
+        entries.push((key, value));
+    }
+    Some(entries)
+}
 
 /// Convert Python values into `ValueRecord` instances understood by
 /// `runtime_tracing`. Nested containers are encoded recursively and reuse the
@@ -34,6 +560,7 @@ pub fn encode_value<'py>(
     py: Python<'py>,
     writer: &mut dyn TraceWriter,
     value: &Bound<'py, PyAny>,
+    options: &EncodingOptions,
 ) -> ValueRecord {
     if value.is_none() {
         return NONE_VALUE;
@@ -49,6 +576,14 @@ pub fn encode_value<'py>(
         return ValueRecord::Int { i, type_id: ty };
     }
 
+    if let Some(digits) = big_int_digits(value) {
+        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Int, BIG_INT_TYPE_NAME);
+        return ValueRecord::Raw {
+            r: digits,
+            type_id: ty,
+        };
+    }
+
     if let Ok(s) = value.extract::<String>() {
         let ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
         return ValueRecord::String {
@@ -60,7 +595,7 @@ pub fn encode_value<'py>(
     if let Ok(tuple) = value.downcast::<PyTuple>() {
         let mut elements = Vec::with_capacity(tuple.len());
         for item in tuple.iter() {
-            elements.push(encode_value(py, writer, &item));
+            elements.push(encode_value(py, writer, &item, options));
         }
         let ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
         return ValueRecord::Tuple {
@@ -72,7 +607,7 @@ pub fn encode_value<'py>(
     if let Ok(list) = value.downcast::<PyList>() {
         let mut elements = Vec::with_capacity(list.len());
         for item in list.iter() {
-            elements.push(encode_value(py, writer, &item));
+            elements.push(encode_value(py, writer, &item, options));
         }
         let ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, "List");
         return ValueRecord::Sequence {
@@ -87,27 +622,21 @@ pub fn encode_value<'py>(
         let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
         let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
         let mut elements = Vec::with_capacity(dict.len());
-        for pair in dict.items().iter() {
-            if let Ok(pair_tuple) = pair.downcast::<PyTuple>() {
-                if pair_tuple.len() == 2 {
-                    let key = pair_tuple.get_item(0).unwrap();
-                    let value = pair_tuple.get_item(1).unwrap();
-                    let key_record = if let Ok(text) = key.extract::<String>() {
-                        ValueRecord::String {
-                            text,
-                            type_id: str_ty,
-                        }
-                    } else {
-                        encode_value(py, writer, &key)
-                    };
-                    let value_record = encode_value(py, writer, &value);
-                    let pair_record = ValueRecord::Tuple {
-                        elements: vec![key_record, value_record],
-                        type_id: tuple_ty,
-                    };
-                    elements.push(pair_record);
+        for (key, value) in dict_entries(py, dict, options.sort_dict_keys) {
+            let key_record = if let Ok(text) = key.extract::<String>() {
+                ValueRecord::String {
+                    text,
+                    type_id: str_ty,
                 }
-            }
+            } else {
+                encode_value(py, writer, &key, options)
+            };
+            let value_record = encode_value(py, writer, &value, options);
+            let pair_record = ValueRecord::Tuple {
+                elements: vec![key_record, value_record],
+                type_id: tuple_ty,
+            };
+            elements.push(pair_record);
         }
         return ValueRecord::Sequence {
             elements,
@@ -116,13 +645,85 @@ pub fn encode_value<'py>(
         };
     }
 
-    let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, "Object");
-    match value.str() {
-        Ok(text) => ValueRecord::Raw {
-            r: text.to_string_lossy().into_owned(),
+    if let Some(record) = exception_record(value, MAX_EXCEPTION_CHAIN_DEPTH) {
+        return encode_exception(py, writer, record, options);
+    }
+
+    if let Some(name) = callable_name(value) {
+        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, &raw_type_name(value));
+        return ValueRecord::Raw {
+            r: name,
+            type_id: ty,
+        };
+    }
+
+    if options.summarize_modules {
+        if let Some((name, file)) = value.downcast::<PyModule>().ok().and_then(module_summary) {
+            let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, "Module");
+            let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+            let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+            let string = |text: String| ValueRecord::String {
+                text,
+                type_id: str_ty,
+            };
+            let file_record = file.map(string).unwrap_or(NONE_VALUE);
+            let elements = vec![
+                ValueRecord::Tuple {
+                    elements: vec![string("__name__".to_string()), string(name)],
+                    type_id: tuple_ty,
+                },
+                ValueRecord::Tuple {
+                    elements: vec![string("__file__".to_string()), file_record],
+                    type_id: tuple_ty,
+                },
+            ];
+            return ValueRecord::Sequence {
+                elements,
+                is_slice: false,
+                type_id: seq_ty,
+            };
+        }
+    }
+
+    if let Some(name) = opaque_type_name(value, options) {
+        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, &raw_type_name(value));
+        return ValueRecord::Raw {
+            r: name,
+            type_id: ty,
+        };
+    }
+
+    if let Some((type_name, attributes)) = object_attributes(value, options) {
+        let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, &type_name);
+        let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+        let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+        let elements = attributes
+            .into_iter()
+            .map(|(name, item)| ValueRecord::Tuple {
+                elements: vec![
+                    ValueRecord::String {
+                        text: name,
+                        type_id: str_ty,
+                    },
+                    encode_value(py, writer, &item, options),
+                ],
+                type_id: tuple_ty,
+            })
+            .collect();
+        return ValueRecord::Sequence {
+            elements,
+            is_slice: false,
+            type_id: seq_ty,
+        };
+    }
+
+    let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, &raw_type_name(value));
+    match raw_text(value, options) {
+        Some(text) => ValueRecord::Raw {
+            r: text,
             type_id: ty,
         },
-        Err(_) => ValueRecord::Error {
+        None => ValueRecord::Error {
             msg: "<unrepr>".to_string(),
             type_id: ty,
         },
@@ -153,13 +754,105 @@ pub fn encode_value<'py>(
 pub fn encode_value_streaming<'py>(
     py: Python<'py>,
     writer: &mut dyn TraceWriter,
-    encoder: &mut StreamingValueEncoder,
+    encoder: &mut CaptureEncoder,
     value: &Bound<'py, PyAny>,
 ) -> Vec<u8> {
     let mut seen = HashSet::new();
     encoder.reset();
     encode_streaming_recursive(py, writer, encoder, value, &mut seen, 0);
-    encoder.get_bytes_copy()
+    let bytes = encoder.get_bytes_copy();
+    let limit = encoder.options().redact_values_over;
+    match limit {
+        Some(limit) if bytes.len() > limit => {
+            let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, LARGE_VALUE_TYPE_NAME);
+            encoder.reset();
+            encoder.write_error(&large_value_marker(bytes.len()), ty);
+            encoder.get_bytes_copy()
+        }
+        _ => bytes,
+    }
+}
+
+/// Encode an exception as `[("__class__", name), ("args", args)]` followed
+/// by a `("__cause__" | "__context__", exception)` entry per chained link.
+fn encode_exception(
+    py: Python<'_>,
+    writer: &mut dyn TraceWriter,
+    record: ExceptionRecord<'_>,
+    options: &EncodingOptions,
+) -> ValueRecord {
+    let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, "Exception");
+    let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+    let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+    let string = |text: String| ValueRecord::String {
+        text,
+        type_id: str_ty,
+    };
+    let mut elements = vec![
+        ValueRecord::Tuple {
+            elements: vec![string("__class__".to_string()), string(record.class_name)],
+            type_id: tuple_ty,
+        },
+        ValueRecord::Tuple {
+            elements: vec![
+                string("args".to_string()),
+                encode_value(py, writer, record.args.as_any(), options),
+            ],
+            type_id: tuple_ty,
+        },
+    ];
+    for (label, linked) in record.links {
+        elements.push(ValueRecord::Tuple {
+            elements: vec![
+                string(label.to_string()),
+                encode_exception(py, writer, linked, options),
+            ],
+            type_id: tuple_ty,
+        });
+    }
+    ValueRecord::Sequence {
+        elements,
+        is_slice: false,
+        type_id: seq_ty,
+    }
+}
+
+/// Streaming counterpart of [`encode_exception`].
+fn encode_exception_streaming<'py>(
+    py: Python<'py>,
+    writer: &mut dyn TraceWriter,
+    encoder: &mut CaptureEncoder,
+    record: ExceptionRecord<'py>,
+    seen: &mut HashSet<isize>,
+    depth: usize,
+) {
+    let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, "Exception");
+    let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+    let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+    // The record nests its labelled entries one level down and their values
+    // two levels down, so a linked exception sits at `depth + 2`. Follow the
+    // link only while that level is still within the limit.
+    let links = if depth + 2 < MAX_STREAMING_DEPTH {
+        record.links
+    } else {
+        Vec::new()
+    };
+    encoder.begin_sequence(seq_ty, 2 + links.len());
+    encoder.begin_tuple(tuple_ty, 2);
+    encoder.write_string("__class__", str_ty);
+    encoder.write_string(&record.class_name, str_ty);
+    encoder.end_compound();
+    encoder.begin_tuple(tuple_ty, 2);
+    encoder.write_string("args", str_ty);
+    encode_streaming_recursive(py, writer, encoder, record.args.as_any(), seen, depth + 2);
+    encoder.end_compound();
+    for (label, linked) in links {
+        encoder.begin_tuple(tuple_ty, 2);
+        encoder.write_string(label, str_ty);
+        encode_exception_streaming(py, writer, encoder, linked, seen, depth + 2);
+        encoder.end_compound();
+    }
+    encoder.end_compound();
 }
 
 /// Recursive streaming encoder. Walks the Python object graph and calls
@@ -168,7 +861,7 @@ pub fn encode_value_streaming<'py>(
 fn encode_streaming_recursive<'py>(
     py: Python<'py>,
     writer: &mut dyn TraceWriter,
-    encoder: &mut StreamingValueEncoder,
+    encoder: &mut CaptureEncoder,
     value: &Bound<'py, PyAny>,
     seen: &mut HashSet<isize>,
     depth: usize,
@@ -202,6 +895,12 @@ fn encode_streaming_recursive<'py>(
         return;
     }
 
+    if let Some(digits) = big_int_digits(value) {
+        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Int, BIG_INT_TYPE_NAME);
+        encoder.write_raw(&digits, ty);
+        return;
+    }
+
     // Try float extraction. Python floats are always f64.
     if let Ok(f) = value.extract::<f64>() {
         // Only accept if the value is actually a Python float, not an int
@@ -265,34 +964,100 @@ fn encode_streaming_recursive<'py>(
         // Encode dict as a sequence of (key, value) tuples, matching the
         // tree-based encoder's representation for backward compatibility.
         encoder.begin_sequence(seq_ty, dict.len());
-        for pair in dict.items().iter() {
-            if let Ok(pair_tuple) = pair.downcast::<PyTuple>() {
-                if pair_tuple.len() == 2 {
-                    let key = pair_tuple.get_item(0).unwrap();
-                    let val = pair_tuple.get_item(1).unwrap();
-
-                    encoder.begin_tuple(tuple_ty, 2);
-                    // Optimize string keys: extract directly without recursion.
-                    if let Ok(text) = key.extract::<String>() {
-                        encoder.write_string(&text, str_ty);
-                    } else {
-                        encode_streaming_recursive(py, writer, encoder, &key, seen, depth + 1);
-                    }
-                    encode_streaming_recursive(py, writer, encoder, &val, seen, depth + 1);
-                    encoder.end_compound();
-                }
+        let sort_keys = encoder.options().sort_dict_keys;
+        for (key, val) in dict_entries(py, dict, sort_keys) {
+            encoder.begin_tuple(tuple_ty, 2);
+            // Optimize string keys: extract directly without recursion.
+            if let Ok(text) = key.extract::<String>() {
+                encoder.write_string(&text, str_ty);
+            } else {
+                encode_streaming_recursive(py, writer, encoder, &key, seen, depth + 1);
+            }
+            encode_streaming_recursive(py, writer, encoder, &val, seen, depth + 1);
+            encoder.end_compound();
+        }
+        encoder.end_compound();
+        seen.remove(&obj_id);
+        return;
+    }
+
+    // Exceptions keep their class alongside their args so `except ... as e`
+    // locals stay distinguishable by type, and link the exceptions they
+    // were raised from or during.
+    if let Some(record) = exception_record(value, MAX_EXCEPTION_CHAIN_DEPTH) {
+        encode_exception_streaming(py, writer, encoder, record, seen, depth);
+        seen.remove(&obj_id);
+        return;
+    }
+
+    // Functions and bound methods are recorded by name; their repr embeds a
+    // memory address that changes from run to run.
+    if let Some(name) = callable_name(value) {
+        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, &raw_type_name(value));
+        encoder.write_raw(&name, ty);
+        seen.remove(&obj_id);
+        return;
+    }
+
+    // Modules are encoded like a two-entry dict so consumers can identify
+    // them without walking (or bloating the trace with) their namespace.
+    if encoder.options().summarize_modules {
+        if let Some((name, file)) = value.downcast::<PyModule>().ok().and_then(module_summary) {
+            let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, "Module");
+            let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+            let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+            let none_ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, "NoneType");
+            encoder.begin_sequence(seq_ty, 2);
+            encoder.begin_tuple(tuple_ty, 2);
+            encoder.write_string("__name__", str_ty);
+            encoder.write_string(&name, str_ty);
+            encoder.end_compound();
+            encoder.begin_tuple(tuple_ty, 2);
+            encoder.write_string("__file__", str_ty);
+            match file {
+                Some(file) => encoder.write_string(&file, str_ty),
+                None => encoder.write_none(none_ty),
             }
+            encoder.end_compound();
+            encoder.end_compound();
+            seen.remove(&obj_id);
+            return;
+        }
+    }
+
+    // Instances from opaque modules are recorded by type name alone, so
+    // their (possibly expensive) `str()` never runs.
+    if let Some(name) = opaque_type_name(value, encoder.options()) {
+        let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, &raw_type_name(value));
+        encoder.write_raw(&name, ty);
+        seen.remove(&obj_id);
+        return;
+    }
+
+    // Instances with slots/properties are encoded like a dict of their
+    // attributes, typed by the instance's class name.
+    if let Some((type_name, attributes)) = object_attributes(value, encoder.options()) {
+        let seq_ty = TraceWriter::ensure_type_id(writer, TypeKind::Seq, &type_name);
+        let tuple_ty = TraceWriter::ensure_type_id(writer, TypeKind::Tuple, "Tuple");
+        let str_ty = TraceWriter::ensure_type_id(writer, TypeKind::String, "String");
+        encoder.begin_sequence(seq_ty, attributes.len());
+        for (name, item) in attributes {
+            encoder.begin_tuple(tuple_ty, 2);
+            encoder.write_string(&name, str_ty);
+            encode_streaming_recursive(py, writer, encoder, &item, seen, depth + 1);
+            encoder.end_compound();
         }
         encoder.end_compound();
         seen.remove(&obj_id);
         return;
     }
 
-    // Fallback: use Python's str() representation as a Raw value.
-    let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, "Object");
-    match value.str() {
-        Ok(text) => encoder.write_raw(&text.to_string_lossy(), ty),
-        Err(_) => encoder.write_error("<unrepr>", ty),
+    // Fallback: use Python's str() representation as a Raw value, typed by
+    // the object's class so it can still be identified.
+    let ty = TraceWriter::ensure_type_id(writer, TypeKind::Raw, &raw_type_name(value));
+    match raw_text(value, encoder.options()) {
+        Some(text) => encoder.write_raw(&text, ty),
+        None => encoder.write_error("<unrepr>", ty),
     }
     seen.remove(&obj_id);
 }
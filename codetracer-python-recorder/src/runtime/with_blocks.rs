@@ -0,0 +1,183 @@
+//! Tracks `with` blocks so the tracer can mark context-manager boundaries.
+//!
+//! `__enter__`/`__exit__` of C-implemented context managers (files, locks)
+//! never surface as Python calls, so we infer the boundaries from `LINE`
+//! events instead. A `with` statement's header line fires once before
+//! `__enter__` runs and again when control leaves the body, because the
+//! compiler attributes the `__exit__` call to the header. Leaving the block
+//! any other way (`return`, `break`, an exception) lands on a line outside the
+//! block, or ends the frame.
+//!
+//! Block spans come from the file's AST, parsed once per file.
+
+use crate::runtime::statement_lines::parse_source;
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Line ranges of a single `with` / `async with` statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WithBlockSpan {
+    /// First line of the statement; identifies the block in emitted markers.
+    pub header: u32,
+    body_start: u32,
+    body_end: u32,
+}
+
+impl WithBlockSpan {
+    fn in_header(&self, line: u32) -> bool {
+        line >= self.header && line < self.body_start
+    }
+
+    fn in_body(&self, line: u32) -> bool {
+        line >= self.body_start && line <= self.body_end
+    }
+}
+
+/// Context-manager boundary inferred from a `LINE` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WithBlockEvent {
+    Enter(u32),
+    Exit(u32),
+}
+
+/// Every `with` statement of one source file.
+#[derive(Debug, Default)]
+pub struct WithBlockMap {
+    spans: Vec<WithBlockSpan>,
+}
+
+impl WithBlockMap {
+    fn span_for_header(&self, line: u32) -> Option<WithBlockSpan> {
+        self.spans.iter().copied().find(|span| span.in_header(line))
+    }
+}
+
+#[derive(Debug)]
+struct OpenBlock {
+    span: WithBlockSpan,
+    entered_body: bool,
+}
+
+/// Per-file span cache plus the blocks currently open in each frame.
+#[derive(Default)]
+pub struct WithBlocks {
+    cache: HashMap<String, Arc<WithBlockMap>>,
+    open: HashMap<u64, Vec<OpenBlock>>,
+}
+
+impl WithBlocks {
+    /// Look up or build the `with` spans for `filename`.
+    pub fn map_for(&mut self, py: Python<'_>, filename: &str) -> Arc<WithBlockMap> {
+        if let Some(existing) = self.cache.get(filename) {
+            return Arc::clone(existing);
+        }
+        let map = match build_map(py, filename) {
+            Ok(map) => map,
+            Err(err) => {
+                log::debug!(
+                    "[RuntimeTracer] with-block map unavailable for {}: {}",
+                    filename,
+                    err
+                );
+                WithBlockMap::default()
+            }
+        };
+        let map = Arc::new(map);
+        self.cache.insert(filename.to_string(), Arc::clone(&map));
+        map
+    }
+
+    /// Advance `frame` to `line` and return the blocks it left (innermost
+    /// first) followed by the block it entered, if any.
+    pub fn on_line(&mut self, frame: u64, line: u32, map: &WithBlockMap) -> Vec<WithBlockEvent> {
+        let mut events = Vec::new();
+        let stack = self.open.entry(frame).or_default();
+        let mut exited_here = None;
+        while let Some(top) = stack.last_mut() {
+            if top.span.in_header(line) {
+                if !top.entered_body {
+                    // Header spread over several lines; still entering.
+                    break;
+                }
+                // Back on the header after the body: `__exit__` runs now.
+                exited_here = Some(top.span.header);
+            } else if top.span.in_body(line) {
+                top.entered_body = true;
+                break;
+            }
+            let span = top.span;
+            stack.pop();
+            events.push(WithBlockEvent::Exit(span.header));
+        }
+
+        let already_open = stack.last().is_some_and(|top| top.span.in_header(line));
+        if !already_open {
+            if let Some(span) = map.span_for_header(line) {
+                if exited_here != Some(span.header) {
+                    stack.push(OpenBlock {
+                        span,
+                        entered_body: false,
+                    });
+                    events.push(WithBlockEvent::Enter(span.header));
+                }
+            }
+        }
+        if stack.is_empty() {
+            self.open.remove(&frame);
+        }
+        events
+    }
+
+    /// Close every block still open in `frame` (the frame returned or
+    /// unwound), innermost first.
+    pub fn close_frame(&mut self, frame: u64) -> Vec<WithBlockEvent> {
+        self.open
+            .remove(&frame)
+            .map(|stack| {
+                stack
+                    .iter()
+                    .rev()
+                    .map(|block| WithBlockEvent::Exit(block.span.header))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn has_open_blocks(&self) -> bool {
+        !self.open.is_empty()
+    }
+}
+
+fn build_map(py: Python<'_>, filename: &str) -> PyResult<WithBlockMap> {
+    let Some(tree) = parse_source(py, filename)? else {
+        return Ok(WithBlockMap::default());
+    };
+    let ast = py.import("ast")?;
+    let with_type = ast.getattr("With")?;
+    let async_with_type = ast.getattr("AsyncWith")?;
+
+    let mut spans = Vec::new();
+    for node in ast.call_method1("walk", (tree,))?.try_iter()? {
+        let node = node?;
+        if !node.is_instance(&with_type)? && !node.is_instance(&async_with_type)? {
+            continue;
+        }
+        let header: u32 = node.getattr("lineno")?.extract()?;
+        let body = node.getattr("body")?;
+        let body = body.downcast::<PyList>()?;
+        let body_start: u32 = body.get_item(0)?.getattr("lineno")?.extract()?;
+        let body_end = node
+            .getattr("end_lineno")?
+            .extract::<Option<u32>>()?
+            .unwrap_or(body_start);
+        spans.push(WithBlockSpan {
+            header,
+            // `with f: pass` keeps its body on the header line.
+            body_start: body_start.max(header + 1),
+            body_end,
+        });
+    }
+    Ok(WithBlockMap { spans })
+}
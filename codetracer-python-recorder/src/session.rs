@@ -1,33 +1,70 @@
 //! PyO3 entry points for starting and managing trace sessions.
 
 mod bootstrap;
+mod socket_target;
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use pyo3::prelude::*;
-use recorder_errors::{usage, ErrorCode};
+use pyo3::types::{PyDict, PyList, PyTuple};
+use recorder_errors::{enverr, usage, ErrorCode};
 
 use crate::ffi;
 use crate::logging::init_rust_logging_with_default;
 use crate::monitoring::{
-    flush_installed_tracer, install_tracer, uninstall_tracer, update_exit_status,
+    flush_installed_tracer, flush_installed_tracer_bytes, install_tracer, mark_installed_tracer,
+    set_disable_listener, set_paused, tool_id_owners, uninstall_tracer, update_exit_status,
+    RECORDER_TOOL_ID,
 };
 use crate::policy::policy_snapshot;
-use crate::runtime::{RuntimeTracer, TraceOutputPaths};
-use bootstrap::TraceSessionBootstrap;
+use crate::runtime::tracer::lifecycle::redact_argv;
+use crate::runtime::RuntimeTracer;
+use crate::trace_filter::engine::TraceFilterEngine;
+use bootstrap::{
+    resolve_on_existing, resolve_trace_format, OnExisting, QualnameGlobs, TraceSessionBootstrap,
+};
+use socket_target::SocketRelay;
 
 /// Global flag tracking whether tracing is active.
 static ACTIVE: AtomicBool = AtomicBool::new(false);
 
+/// Filter chain of the active session, kept for `trace_filter_summary`.
+static ACTIVE_FILTER: Mutex<Option<Arc<TraceFilterEngine>>> = Mutex::new(None);
+
+fn set_active_filter(engine: Option<Arc<TraceFilterEngine>>) {
+    let mut guard = ACTIVE_FILTER.lock().unwrap_or_else(|e| e.into_inner());
+    *guard = engine;
+}
+
+/// Relay forwarding the active session to its `socket://` target, if any.
+static SOCKET_RELAY: Mutex<Option<SocketRelay>> = Mutex::new(None);
+
+fn set_socket_relay(relay: Option<SocketRelay>) -> Option<SocketRelay> {
+    let mut guard = SOCKET_RELAY.lock().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, relay)
+}
+
 /// Start tracing using sys.monitoring and runtime_tracing writer.
-#[pyfunction(signature = (path, format, activation_path=None, trace_filter=None, test_framework=None))]
+///
+/// `only` and `exclude` are `module.qualname` globs compiled into an inline
+/// filter: with `only`, everything else is skipped; `exclude` always skips.
+/// `on_existing` (`overwrite`, `fail` or `append`) decides what happens to
+/// trace files an earlier session left in `path`.
+///
+/// A `path` of the form `socket://host:port` streams the binary format to
+/// that TCP address instead of writing a trace directory.
+#[pyfunction(signature = (path, format, activation_path=None, trace_filter=None, test_framework=None, only=None, exclude=None, on_existing=None))]
 pub fn start_tracing(
     path: &str,
     format: &str,
     activation_path: Option<&str>,
     trace_filter: Option<Vec<String>>,
     test_framework: Option<&str>,
+    only: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    on_existing: Option<&str>,
 ) -> PyResult<()> {
     ffi::wrap_pyfunction("start_tracing", || {
         // Ensure logging is ready before any tracer logs might be emitted.
@@ -40,37 +77,76 @@ pub fn start_tracing(
             )));
         }
 
+        let on_existing = match on_existing {
+            Some(value) => resolve_on_existing(value).map_err(ffi::map_recorder_error)?,
+            None => OnExisting::default(),
+        };
+        let socket = match socket_target::socket_address(path) {
+            Some(address) => {
+                let format = resolve_trace_format(format).map_err(ffi::map_recorder_error)?;
+                socket_target::ensure_socket_format(format).map_err(ffi::map_recorder_error)?;
+                let stream = socket_target::connect(address).map_err(ffi::map_recorder_error)?;
+                Some((stream, socket_target::spool_directory()))
+            }
+            None => None,
+        };
+        let trace_directory = match &socket {
+            Some((_, spool)) => spool.clone(),
+            None => PathBuf::from(path),
+        };
         let activation_path = activation_path.map(PathBuf::from);
         let filter_paths: Option<Vec<PathBuf>> =
             trace_filter.map(|items| items.into_iter().map(PathBuf::from).collect());
+        let globs = QualnameGlobs {
+            only: only.unwrap_or_default(),
+            exclude: exclude.unwrap_or_default(),
+        };
 
         Python::with_gil(|py| {
-            let bootstrap = TraceSessionBootstrap::prepare_with_framework(
+            let policy = policy_snapshot();
+            let bootstrap = TraceSessionBootstrap::prepare_with_globs(
                 py,
-                Path::new(path),
+                &trace_directory,
                 format,
                 activation_path.as_deref(),
                 filter_paths.as_ref().map(|paths| paths.as_slice()),
                 test_framework,
+                &globs,
+                &policy.always_trace,
             )
             .map_err(ffi::map_recorder_error)?;
 
-            let outputs = TraceOutputPaths::new(bootstrap.trace_directory(), bootstrap.format());
-            let policy = policy_snapshot();
+            let outputs = bootstrap
+                .prepare_outputs(on_existing)
+                .map_err(ffi::map_recorder_error)?;
 
             let mut tracer = RuntimeTracer::new(
                 bootstrap.program(),
-                bootstrap.args(),
+                &redact_argv(bootstrap.args(), &policy.redact_argv),
                 bootstrap.format(),
                 bootstrap.activation_path(),
                 bootstrap.trace_filter(),
                 policy.module_name_from_globals,
             );
+            tracer.apply_policy(&policy);
             tracer.begin(&outputs, 1)?;
             tracer.install_io_capture(py, &policy)?;
+            tracer.install_warnings_hook(py, &policy)?;
+            if let Some((stream, spool)) = socket {
+                // A dead socket would otherwise grow the spool until the
+                // session stops, so recording stops with the relay.
+                let relay = SocketRelay::spawn(stream, spool, outputs.events(), || {
+                    log::warn!("trace socket disconnected; recording stopped");
+                    set_paused(true);
+                })
+                .map_err(ffi::map_recorder_error)?;
+                set_socket_relay(Some(relay));
+            }
 
             // Install callbacks
+            set_paused(false);
             install_tracer(py, Box::new(tracer))?;
+            set_active_filter(bootstrap.trace_filter());
             ACTIVE.store(true, Ordering::SeqCst);
             Ok(())
         })
@@ -84,19 +160,183 @@ pub fn stop_tracing(exit_code: Option<i32>) -> PyResult<()> {
         Python::with_gil(|py| {
             update_exit_status(py, exit_code)?;
             // Uninstall triggers finish() on tracer implementation.
-            uninstall_tracer(py)?;
+            let uninstalled = uninstall_tracer(py);
+            let relayed = match set_socket_relay(None) {
+                Some(relay) => py.allow_threads(|| relay.finish()),
+                None => Ok(()),
+            };
+            uninstalled?;
+            set_active_filter(None);
+            set_paused(false);
             ACTIVE.store(false, Ordering::SeqCst);
-            Ok(())
+            relayed.map_err(ffi::map_recorder_error)
         })
     })
 }
 
+/// Trace a single call: start a session in `path`, invoke
+/// `func(*args, **kwargs)`, stop the session and return the result.
+///
+/// The session is finalised even when `func` raises; its exception is then
+/// re-raised in preference to any error from stopping.
+#[pyfunction(signature = (path, format, func, *args, **kwargs))]
+pub fn trace_call(
+    path: &str,
+    format: &str,
+    func: &Bound<'_, PyAny>,
+    args: &Bound<'_, PyTuple>,
+    kwargs: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    ffi::wrap_pyfunction("trace_call", || {
+        start_tracing(path, format, None, None, None, None, None, None)?;
+        let result = func.call(args, kwargs);
+        let stopped = stop_tracing(None);
+        let value = result?;
+        stopped?;
+        Ok(value.unbind())
+    })
+}
+
+/// Stop recording events without uninstalling: callbacks stay registered and
+/// the writer stays open until `resume_tracing` or `stop_tracing`.
+#[pyfunction]
+pub fn pause_tracing() -> PyResult<()> {
+    ffi::wrap_pyfunction("pause_tracing", || {
+        if ACTIVE.load(Ordering::SeqCst) {
+            set_paused(true);
+        }
+        Ok(())
+    })
+}
+
+/// Resume recording after `pause_tracing`. A `socket://` session whose peer
+/// disconnected stays stopped.
+#[pyfunction]
+pub fn resume_tracing() -> PyResult<()> {
+    ffi::wrap_pyfunction("resume_tracing", || {
+        let disconnected = SOCKET_RELAY
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(SocketRelay::is_disconnected);
+        if disconnected {
+            return Err(ffi::map_recorder_error(enverr!(
+                ErrorCode::Io,
+                "trace socket disconnected; recording cannot resume"
+            )));
+        }
+        set_paused(false);
+        Ok(())
+    })
+}
+
 /// Query whether tracing is currently active.
 #[pyfunction]
 pub fn is_tracing() -> PyResult<bool> {
     ffi::wrap_pyfunction("is_tracing", || Ok(ACTIVE.load(Ordering::SeqCst)))
 }
 
+/// Report whether the interpreter can host a trace session.
+///
+/// Returns `{"python_version", "monitoring_available", "free_tool_ids",
+/// "recorder_tool_id", "recorder_tool_id_owner", "tracing_active"}`.
+/// `recorder_tool_id_owner` names the tool holding the recorder's id, which
+/// is `"codetracer"` while a session runs and another tool's name when it
+/// would conflict.
+#[pyfunction]
+pub fn check_environment(py: Python<'_>) -> PyResult<PyObject> {
+    ffi::wrap_pyfunction("check_environment", || {
+        let version = py.version_info();
+        let owners = tool_id_owners(py)?;
+        let report = PyDict::new(py);
+        report.set_item(
+            "python_version",
+            format!("{}.{}.{}", version.major, version.minor, version.patch),
+        )?;
+        report.set_item("monitoring_available", owners.is_some())?;
+        let owners = owners.unwrap_or_default();
+        let free: Vec<u8> = owners
+            .iter()
+            .filter(|(_, owner)| owner.is_none())
+            .map(|(id, _)| *id)
+            .collect();
+        report.set_item("free_tool_ids", free)?;
+        report.set_item("recorder_tool_id", RECORDER_TOOL_ID)?;
+        let recorder_owner = owners
+            .into_iter()
+            .find(|(id, _)| *id == RECORDER_TOOL_ID)
+            .and_then(|(_, owner)| owner);
+        report.set_item("recorder_tool_id_owner", recorder_owner)?;
+        report.set_item("tracing_active", ACTIVE.load(Ordering::SeqCst))?;
+        Ok(report.into_any().unbind())
+    })
+}
+
+/// Register a callable invoked as `callback(code, message)` when the `disable`
+/// policy detaches the tracer after a recorder error. `None` clears it.
+#[pyfunction(signature = (callback=None))]
+pub fn set_disable_callback(callback: Option<Py<PyAny>>) -> PyResult<()> {
+    ffi::wrap_pyfunction("set_disable_callback", || {
+        set_disable_listener(callback);
+        Ok(())
+    })
+}
+
+/// Describe the filter chain loaded for the active session.
+///
+/// Returns `{"filters": [{"path", "sha256", "name", "version", "rules"}]}`
+/// with one entry per source in composition order, or `None` when no
+/// session is running.
+#[pyfunction]
+pub fn trace_filter_summary(py: Python<'_>) -> PyResult<Option<PyObject>> {
+    ffi::wrap_pyfunction("trace_filter_summary", || {
+        if !ACTIVE.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let engine = {
+            let guard = ACTIVE_FILTER.lock().unwrap_or_else(|e| e.into_inner());
+            guard.as_ref().map(Arc::clone)
+        };
+        let filters = PyList::empty(py);
+        if let Some(engine) = engine {
+            for entry in engine.summary().entries {
+                let item = PyDict::new(py);
+                item.set_item("path", entry.path.display().to_string())?;
+                item.set_item("sha256", entry.sha256.as_str())?;
+                match engine.sources().iter().find(|info| info.path == entry.path) {
+                    Some(info) => {
+                        item.set_item("name", info.name.as_str())?;
+                        item.set_item("version", info.version)?;
+                        item.set_item("rules", info.rule_count)?;
+                    }
+                    None => {
+                        item.set_item("name", py.None())?;
+                        item.set_item("version", py.None())?;
+                        item.set_item("rules", py.None())?;
+                    }
+                }
+                filters.append(item)?;
+            }
+        }
+        let summary = PyDict::new(py);
+        summary.set_item("filters", filters)?;
+        Ok(Some(summary.into_any().unbind()))
+    })
+}
+
+/// Attach an annotation named `label` to the current step of the active
+/// trace, with an optional free-form `metadata` string. Does nothing when no
+/// session is running or recording is paused.
+#[pyfunction(signature = (label, metadata=None))]
+pub fn trace_mark(py: Python<'_>, label: &str, metadata: Option<&str>) -> PyResult<()> {
+    ffi::wrap_pyfunction("trace_mark", || {
+        if !ACTIVE.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        mark_installed_tracer(py, label, metadata)
+    })
+}
+
 /// Flush buffered trace data (best-effort, non-streaming formats only).
 #[pyfunction]
 pub fn flush_tracing() -> PyResult<()> {
@@ -104,3 +344,13 @@ pub fn flush_tracing() -> PyResult<()> {
         Python::with_gil(|py| flush_installed_tracer(py))
     })
 }
+
+/// Flush like `flush_tracing` and return the on-disk size of the events file
+/// in bytes (0 when no session is running). Streaming formats only grow the
+/// file as the writer drains its buffers, so the value may lag behind.
+#[pyfunction]
+pub fn flush_tracing_bytes() -> PyResult<u64> {
+    ffi::wrap_pyfunction("flush_tracing_bytes", || {
+        Python::with_gil(|py| flush_installed_tracer_bytes(py))
+    })
+}
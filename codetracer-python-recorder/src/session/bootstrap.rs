@@ -12,11 +12,18 @@ use codetracer_trace_writer_nim::TraceEventsFileFormat;
 use pyo3::prelude::*;
 
 use crate::errors::Result;
+use crate::runtime::TraceOutputPaths;
 use crate::trace_filter::engine::TraceFilterEngine;
-use filesystem::{ensure_trace_directory, resolve_trace_format};
-use filters::load_trace_filter_with_framework;
+use filesystem::{ensure_trace_directory, is_ndjson_format, prepare_trace_outputs};
+use filters::load_trace_filter_with_globs;
 use metadata::collect_program_metadata;
 
+/// Parse a user-provided trace format name.
+pub use filesystem::resolve_trace_format;
+/// Handling of trace files left in the directory by an earlier session.
+pub use filesystem::{resolve_on_existing, OnExisting};
+/// `only`/`exclude` globs that narrow tracing without a filter file.
+pub use filters::QualnameGlobs;
 /// Basic metadata about the currently running Python program.
 pub use metadata::ProgramMetadata;
 
@@ -25,6 +32,7 @@ pub use metadata::ProgramMetadata;
 pub struct TraceSessionBootstrap {
     trace_directory: PathBuf,
     format: TraceEventsFileFormat,
+    ndjson: bool,
     activation_path: Option<PathBuf>,
     metadata: ProgramMetadata,
     trace_filter: Option<Arc<TraceFilterEngine>>,
@@ -35,6 +43,7 @@ impl fmt::Debug for TraceSessionBootstrap {
         f.debug_struct("TraceSessionBootstrap")
             .field("trace_directory", &self.trace_directory)
             .field("format", &self.format)
+            .field("ndjson", &self.ndjson)
             .field("activation_path", &self.activation_path)
             .field("metadata", &self.metadata)
             .field("trace_filter", &self.trace_filter.is_some())
@@ -70,18 +79,46 @@ impl TraceSessionBootstrap {
         activation_path: Option<&Path>,
         explicit_trace_filters: Option<&[PathBuf]>,
         test_framework: Option<&str>,
+    ) -> Result<Self> {
+        Self::prepare_with_globs(
+            py,
+            trace_directory,
+            format,
+            activation_path,
+            explicit_trace_filters,
+            test_framework,
+            &QualnameGlobs::default(),
+            &[],
+        )
+    }
+
+    /// Prepare a tracing session whose filter chain also honours
+    /// `only`/`exclude` qualname globs and the `always_trace` module list.
+    pub fn prepare_with_globs(
+        py: Python<'_>,
+        trace_directory: &Path,
+        format: &str,
+        activation_path: Option<&Path>,
+        explicit_trace_filters: Option<&[PathBuf]>,
+        test_framework: Option<&str>,
+        globs: &QualnameGlobs,
+        always_trace: &[String],
     ) -> Result<Self> {
         ensure_trace_directory(trace_directory)?;
+        let ndjson = is_ndjson_format(format);
         let format = resolve_trace_format(format)?;
         let metadata = collect_program_metadata(py)?;
-        let trace_filter = load_trace_filter_with_framework(
+        let trace_filter = load_trace_filter_with_globs(
             explicit_trace_filters,
             &metadata.program,
             test_framework,
+            globs,
+            always_trace,
         )?;
         Ok(Self {
             trace_directory: trace_directory.to_path_buf(),
             format,
+            ndjson,
             activation_path: activation_path.map(|p| p.to_path_buf()),
             metadata,
             trace_filter,
@@ -92,6 +129,12 @@ impl TraceSessionBootstrap {
         &self.trace_directory
     }
 
+    /// Output files for this session, after applying `on_existing` to any
+    /// trace files already in the trace directory.
+    pub fn prepare_outputs(&self, on_existing: OnExisting) -> Result<TraceOutputPaths> {
+        prepare_trace_outputs(&self.trace_directory, self.format, self.ndjson, on_existing)
+    }
+
     pub fn format(&self) -> TraceEventsFileFormat {
         self.format
     }
@@ -1,11 +1,26 @@
 use std::env;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
 use codetracer_trace_writer_nim::TraceEventsFileFormat;
 use recorder_errors::{enverr, usage, ErrorCode};
 
 use crate::errors::Result;
+use crate::runtime::TraceOutputPaths;
+
+/// What `start` does when the trace directory already holds trace files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnExisting {
+    /// Delete the earlier trace files before recording.
+    #[default]
+    Overwrite,
+    /// Refuse to start.
+    Fail,
+    /// Keep the earlier files and record into the next numbered segment
+    /// (`trace.1.json`, `trace.2.json`, ...).
+    Append,
+}
 
 /// Ensure the requested trace directory exists and is writable.
 pub fn ensure_trace_directory(path: &Path) -> Result<()> {
@@ -13,11 +28,13 @@ pub fn ensure_trace_directory(path: &Path) -> Result<()> {
         if !path.is_dir() {
             return Err(usage!(
                 ErrorCode::TraceDirectoryConflict,
-                "trace path exists and is not a directory"
+                "trace path '{}' exists and is not a directory; pass a directory \
+                 path, which is created when missing and receives the trace files",
+                path.display()
             )
             .with_context("path", path.display().to_string()));
         }
-        return Ok(());
+        return ensure_directory_writable(path);
     }
 
     fs::create_dir_all(path).map_err(|e| {
@@ -30,10 +47,36 @@ pub fn ensure_trace_directory(path: &Path) -> Result<()> {
     })
 }
 
+/// Probe an existing trace directory so permission problems surface at
+/// `start` with the offending path instead of when the writer opens its files.
+fn ensure_directory_writable(path: &Path) -> Result<()> {
+    let probe = path.join(".codetracer-write-probe");
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(_) => {
+            // Best effort: a leftover probe only trips `AlreadyExists` below.
+            let _ = fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok(()),
+        Err(err) => Err(enverr!(
+            ErrorCode::OutputDirectoryUnwritable,
+            "trace directory is not writable"
+        )
+        .with_context("path", path.display().to_string())
+        .with_context("io", err.to_string())),
+    }
+}
+
 /// Convert a user-provided format string into the runtime representation.
 pub fn resolve_trace_format(value: &str) -> Result<TraceEventsFileFormat> {
     match value.to_ascii_lowercase().as_str() {
-        "json" => Ok(TraceEventsFileFormat::Json),
+        // NDJSON records through the JSON writer; the output paths lay the
+        // events out one per line.
+        "json" | "ndjson" => Ok(TraceEventsFileFormat::Json),
         // Default binary format uses CBOR + Zstandard compression.
         "binary" | "bin" => Ok(TraceEventsFileFormat::Binary),
         // Legacy Cap'n Proto binary format.
@@ -41,12 +84,76 @@ pub fn resolve_trace_format(value: &str) -> Result<TraceEventsFileFormat> {
         "ctfs" => Ok(TraceEventsFileFormat::Ctfs),
         other => Err(usage!(
             ErrorCode::UnsupportedFormat,
-            "unsupported trace format '{}'. Expected one of: json, binary, binaryv0, ctfs",
+            "unsupported trace format '{}'. Expected one of: json, ndjson, binary, binaryv0, ctfs",
+            other
+        )),
+    }
+}
+
+/// Whether a user-provided format string selects the NDJSON layout.
+pub fn is_ndjson_format(value: &str) -> bool {
+    value.eq_ignore_ascii_case("ndjson")
+}
+
+/// Convert a user-provided `on_existing` string into [`OnExisting`].
+pub fn resolve_on_existing(value: &str) -> Result<OnExisting> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "overwrite" => Ok(OnExisting::Overwrite),
+        "fail" => Ok(OnExisting::Fail),
+        "append" => Ok(OnExisting::Append),
+        other => Err(usage!(
+            ErrorCode::InvalidPolicyValue,
+            "invalid on_existing value '{}' (expected 'overwrite', 'fail' or 'append')",
             other
         )),
     }
 }
 
+/// Choose the output files for a session in `root`, applying `on_existing`
+/// to trace files left behind by an earlier session.
+pub fn prepare_trace_outputs(
+    root: &Path,
+    format: TraceEventsFileFormat,
+    ndjson: bool,
+    on_existing: OnExisting,
+) -> Result<TraceOutputPaths> {
+    let layout = |paths: TraceOutputPaths| if ndjson { paths.into_ndjson() } else { paths };
+    let outputs = layout(TraceOutputPaths::new(root, format));
+    match on_existing {
+        OnExisting::Overwrite => {
+            for path in outputs.existing_files() {
+                fs::remove_file(path).map_err(|err| {
+                    enverr!(ErrorCode::Io, "failed to remove existing trace file")
+                        .with_context("path", path.display().to_string())
+                        .with_context("io", err.to_string())
+                })?;
+            }
+            Ok(outputs)
+        }
+        OnExisting::Fail => match outputs.existing_files().first() {
+            Some(path) => Err(usage!(
+                ErrorCode::TraceDirectoryConflict,
+                "trace directory already contains trace files"
+            )
+            .with_context("path", path.display().to_string())),
+            None => Ok(outputs),
+        },
+        OnExisting::Append => {
+            if outputs.existing_files().is_empty() {
+                return Ok(outputs);
+            }
+            let mut index = 1;
+            while !layout(TraceOutputPaths::segment(root, format, index))
+                .existing_files()
+                .is_empty()
+            {
+                index += 1;
+            }
+            Ok(layout(TraceOutputPaths::segment(root, format, index)))
+        }
+    }
+}
+
 pub fn resolve_program_directory(program: &str) -> Result<PathBuf> {
     let trimmed = program.trim();
     if trimmed.is_empty() || trimmed == "<unknown>" {
@@ -102,6 +209,37 @@ mod tests {
         std::fs::write(&file_path, b"stub").expect("write stub file");
         let err = ensure_trace_directory(&file_path).expect_err("should reject file path");
         assert_eq!(err.code, ErrorCode::TraceDirectoryConflict);
+        assert!(
+            err.message().contains(&file_path.display().to_string()),
+            "message should name the file: {}",
+            err.message()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_unwritable_existing_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = tempdir().expect("tempdir");
+        let target = tmp.path().join("locked");
+        std::fs::create_dir(&target).expect("create locked dir");
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o555))
+            .expect("lock directory");
+        // Privileged users bypass mode bits; nothing to assert there.
+        let writable = std::fs::File::create(target.join("probe")).is_ok();
+
+        let result = ensure_trace_directory(&target);
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755))
+            .expect("unlock directory");
+        if writable {
+            return;
+        }
+
+        let err = result.expect_err("should reject unwritable directory");
+        assert_eq!(err.code, ErrorCode::OutputDirectoryUnwritable);
+        assert_eq!(err.context.get("path"), Some(&target.display().to_string()));
+        assert!(err.context.contains_key("io"));
     }
 
     #[test]
@@ -122,6 +260,12 @@ mod tests {
             resolve_trace_format("ctfs").expect("ctfs format"),
             TraceEventsFileFormat::Ctfs
         ));
+        assert!(matches!(
+            resolve_trace_format("ndjson").expect("ndjson format"),
+            TraceEventsFileFormat::Json
+        ));
+        assert!(is_ndjson_format("NDJSON"));
+        assert!(!is_ndjson_format("json"));
     }
 
     #[test]
@@ -129,4 +273,100 @@ mod tests {
         let err = resolve_trace_format("yaml").expect_err("should reject yaml");
         assert_eq!(err.code, ErrorCode::UnsupportedFormat);
     }
+
+    #[test]
+    fn resolves_on_existing_modes() {
+        assert_eq!(
+            resolve_on_existing("Overwrite").expect("overwrite"),
+            OnExisting::Overwrite
+        );
+        assert_eq!(resolve_on_existing("fail").expect("fail"), OnExisting::Fail);
+        assert_eq!(
+            resolve_on_existing("append").expect("append"),
+            OnExisting::Append
+        );
+        let err = resolve_on_existing("merge").expect_err("should reject merge");
+        assert_eq!(err.code, ErrorCode::InvalidPolicyValue);
+    }
+
+    #[test]
+    fn on_existing_fail_rejects_earlier_trace() {
+        let tmp = tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("trace.json"), b"[]").expect("write trace");
+
+        let err = prepare_trace_outputs(
+            tmp.path(),
+            TraceEventsFileFormat::Json,
+            false,
+            OnExisting::Fail,
+        )
+        .expect_err("existing trace should fail");
+        assert_eq!(err.code, ErrorCode::TraceDirectoryConflict);
+        assert_eq!(
+            err.context.get("path"),
+            Some(&tmp.path().join("trace.json").display().to_string())
+        );
+    }
+
+    #[test]
+    fn on_existing_overwrite_removes_earlier_files() {
+        let tmp = tempdir().expect("tempdir");
+        std::fs::write(tmp.path().join("trace.json"), b"[]").expect("write trace");
+        std::fs::write(tmp.path().join("functions.json"), b"{}").expect("write functions");
+        std::fs::write(tmp.path().join("notes.txt"), b"keep").expect("write unrelated");
+
+        let outputs = prepare_trace_outputs(
+            tmp.path(),
+            TraceEventsFileFormat::Json,
+            false,
+            OnExisting::Overwrite,
+        )
+        .expect("overwrite");
+        assert_eq!(outputs.events(), tmp.path().join("trace.json"));
+        assert!(outputs.existing_files().is_empty());
+        assert!(
+            tmp.path().join("notes.txt").exists(),
+            "unrelated files must survive"
+        );
+    }
+
+    #[test]
+    fn on_existing_append_uses_next_free_segment() {
+        let tmp = tempdir().expect("tempdir");
+        let format = TraceEventsFileFormat::Json;
+
+        let first =
+            prepare_trace_outputs(tmp.path(), format, false, OnExisting::Append).expect("first");
+        assert_eq!(first.events(), tmp.path().join("trace.json"));
+        std::fs::write(first.events(), b"[]").expect("write first");
+
+        let second =
+            prepare_trace_outputs(tmp.path(), format, false, OnExisting::Append).expect("second");
+        assert_eq!(second.events(), tmp.path().join("trace.1.json"));
+        assert_eq!(second.functions(), tmp.path().join("functions.1.json"));
+        std::fs::write(second.events(), b"[]").expect("write second");
+
+        let third =
+            prepare_trace_outputs(tmp.path(), format, false, OnExisting::Append).expect("third");
+        assert_eq!(third.events(), tmp.path().join("trace.2.json"));
+        assert!(
+            tmp.path().join("trace.json").exists(),
+            "append must keep the earlier trace"
+        );
+    }
+
+    #[test]
+    fn ndjson_outputs_use_ndjson_events_file() {
+        let tmp = tempdir().expect("tempdir");
+        let format = TraceEventsFileFormat::Json;
+
+        let first =
+            prepare_trace_outputs(tmp.path(), format, true, OnExisting::Append).expect("first");
+        assert_eq!(first.events(), tmp.path().join("trace.ndjson"));
+        std::fs::write(first.events(), b"").expect("write first");
+
+        let second =
+            prepare_trace_outputs(tmp.path(), format, true, OnExisting::Append).expect("second");
+        assert_eq!(second.events(), tmp.path().join("trace.1.ndjson"));
+    }
 }
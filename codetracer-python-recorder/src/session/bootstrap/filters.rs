@@ -4,15 +4,12 @@ use std::sync::Arc;
 use crate::errors::Result;
 use crate::trace_filter::config::TraceFilterConfig;
 use crate::trace_filter::convert_filter_error;
+use crate::trace_filter::discovery::{BUILTIN_FILTER_LABEL, BUILTIN_TRACE_FILTER, FILTER_DIR};
 use crate::trace_filter::engine::TraceFilterEngine;
 
 use super::filesystem::resolve_program_directory;
 
-const TRACE_FILTER_DIR: &str = ".codetracer";
 const TRACE_FILTER_FILE: &str = "trace-filter.toml";
-const BUILTIN_FILTER_LABEL: &str = "builtin-default";
-const BUILTIN_TRACE_FILTER: &str =
-    include_str!("../../../resources/trace_filters/builtin_default.toml");
 
 // Framework-specific builtin filters
 const BUILTIN_PYTEST_FILTER_LABEL: &str = "builtin-pytest";
@@ -23,6 +20,84 @@ const BUILTIN_UNITTEST_FILTER_LABEL: &str = "builtin-unittest";
 const BUILTIN_UNITTEST_FILTER: &str =
     include_str!("../../../resources/trace_filters/builtin_unittest.toml");
 
+const QUALNAME_GLOBS_FILTER_LABEL: &str = "start-tracing-globs";
+const ALWAYS_TRACE_FILTER_LABEL: &str = "always-trace";
+
+/// `only`/`exclude` globs passed to `start_tracing`, matched against
+/// `module.qualname` through `obj:glob:` selectors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QualnameGlobs {
+    pub only: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl QualnameGlobs {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Render the globs as an inline filter. With `only` patterns a leading
+    /// catch-all `skip` rule hides everything else, so the restriction holds
+    /// even when a later filter file resets the scope default; `exclude`
+    /// rules come last and win over `only`.
+    fn to_filter_toml(&self) -> String {
+        let mut toml = format!(
+            "[meta]\nname = \"{QUALNAME_GLOBS_FILTER_LABEL}\"\nversion = 1\n\n\
+             [scope]\ndefault_exec = \"trace\"\ndefault_value_action = \"allow\"\n"
+        );
+        let catch_all = String::from("*");
+        let rules = (!self.only.is_empty())
+            .then_some((&catch_all, "skip"))
+            .into_iter()
+            .chain(self.only.iter().map(|pattern| (pattern, "trace")))
+            .chain(self.exclude.iter().map(|pattern| (pattern, "skip")));
+        for (pattern, exec) in rules {
+            toml.push_str(&format!(
+                "\n[[scope.rules]]\nselector = {}\nexec = \"{exec}\"\n",
+                toml_basic_string(&format!("obj:glob:{pattern}"))
+            ));
+        }
+        toml
+    }
+}
+
+/// Render the `always_trace` policy as a filter tracing each module and its
+/// submodules. It is composed behind the builtin default so the builtin
+/// value redaction still applies to the forced scopes.
+fn always_trace_filter_toml(modules: &[String]) -> String {
+    let mut toml = format!(
+        "[meta]\nname = \"{ALWAYS_TRACE_FILTER_LABEL}\"\nversion = 1\n\n\
+         [scope]\ndefault_exec = \"trace\"\ndefault_value_action = \"allow\"\n"
+    );
+    for module in modules {
+        for selector in [
+            format!("pkg:literal:{module}"),
+            format!("pkg:glob:{module}.*"),
+        ] {
+            toml.push_str(&format!(
+                "\n[[scope.rules]]\nselector = {}\nexec = \"trace\"\n",
+                toml_basic_string(&selector)
+            ));
+        }
+    }
+    toml
+}
+
+fn toml_basic_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04X}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 pub fn load_trace_filter(
     explicit: Option<&[PathBuf]>,
     program: &str,
@@ -34,6 +109,24 @@ pub fn load_trace_filter_with_framework(
     explicit: Option<&[PathBuf]>,
     program: &str,
     test_framework: Option<&str>,
+) -> Result<Option<Arc<TraceFilterEngine>>> {
+    load_trace_filter_with_globs(
+        explicit,
+        program,
+        test_framework,
+        &QualnameGlobs::default(),
+        &[],
+    )
+}
+
+/// Build the filter chain. Modules listed in `always_trace` are traced even
+/// when a filter in the chain would skip them.
+pub fn load_trace_filter_with_globs(
+    explicit: Option<&[PathBuf]>,
+    program: &str,
+    test_framework: Option<&str>,
+    globs: &QualnameGlobs,
+    always_trace: &[String],
 ) -> Result<Option<Arc<TraceFilterEngine>>> {
     let mut chain: Vec<PathBuf> = Vec::new();
 
@@ -67,16 +160,34 @@ pub fn load_trace_filter_with_framework(
         }
     }
 
+    let globs_filter = (!globs.is_empty()).then(|| globs.to_filter_toml());
+    if let Some(toml) = globs_filter.as_deref() {
+        inline_filters.push((QUALNAME_GLOBS_FILTER_LABEL, toml));
+    }
+
     let config = TraceFilterConfig::from_inline_and_paths(&inline_filters, &chain)
         .map_err(convert_filter_error)?;
-    Ok(Some(Arc::new(TraceFilterEngine::new(config))))
+    let mut engine = TraceFilterEngine::new(config);
+    if !always_trace.is_empty() {
+        let toml = always_trace_filter_toml(always_trace);
+        let always_config = TraceFilterConfig::from_inline_and_paths(
+            &[
+                (BUILTIN_FILTER_LABEL, BUILTIN_TRACE_FILTER),
+                (ALWAYS_TRACE_FILTER_LABEL, toml.as_str()),
+            ],
+            &[],
+        )
+        .map_err(convert_filter_error)?;
+        engine = engine.with_always_trace(always_config);
+    }
+    Ok(Some(Arc::new(engine)))
 }
 
 fn discover_default_trace_filter(program: &str) -> Result<Option<PathBuf>> {
     let start_dir = resolve_program_directory(program)?;
     let mut current: Option<&Path> = Some(start_dir.as_path());
     while let Some(dir) = current {
-        let candidate = dir.join(TRACE_FILTER_DIR).join(TRACE_FILTER_FILE);
+        let candidate = dir.join(FILTER_DIR).join(TRACE_FILTER_FILE);
         if matches!(std::fs::metadata(&candidate), Ok(metadata) if metadata.is_file()) {
             return Ok(Some(candidate));
         }
@@ -93,7 +204,7 @@ pub mod tests {
     use tempfile::tempdir;
 
     pub fn write_default_filter(root: &Path) -> PathBuf {
-        let filters_dir = root.join(TRACE_FILTER_DIR);
+        let filters_dir = root.join(FILTER_DIR);
         fs::create_dir_all(&filters_dir).expect("create filter dir");
         let filter_path = filters_dir.join(TRACE_FILTER_FILE);
         fs::write(
@@ -294,4 +405,119 @@ pub mod tests {
         assert!(paths.contains(&PathBuf::from("<inline:builtin-default>")));
         assert_eq!(paths.len(), 1, "Should only have builtin-default filter");
     }
+
+    #[test]
+    fn load_trace_filter_with_globs_appends_inline_filter() {
+        let temp = tempdir().expect("tempdir");
+        let root = temp.path();
+        let script = write_app(root);
+        let globs = QualnameGlobs {
+            only: vec!["app.worker.*".to_string()],
+            exclude: vec!["app.worker.\"quoted\"".to_string()],
+        };
+
+        let engine =
+            load_trace_filter_with_globs(None, script.to_str().expect("utf8"), None, &globs, &[])
+                .expect("load")
+                .expect("engine");
+
+        let paths: Vec<PathBuf> = engine
+            .summary()
+            .entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("<inline:builtin-default>"),
+                PathBuf::from("<inline:start-tracing-globs>"),
+            ]
+        );
+        let rules = engine
+            .sources()
+            .iter()
+            .find(|info| info.name == "start-tracing-globs")
+            .map(|info| info.rule_count);
+        assert_eq!(
+            rules,
+            Some(3),
+            "catch-all skip, one only and one exclude rule"
+        );
+    }
+
+    #[test]
+    fn always_trace_wins_over_skip_everything_filter() {
+        use crate::code_object::CodeObjectWrapper;
+        use crate::trace_filter::engine::ExecDecision;
+        use pyo3::prelude::*;
+        use pyo3::types::PyCode;
+        use std::ffi::CString;
+
+        let temp = tempdir().expect("tempdir");
+        let root = temp.path();
+        let filters_dir = root.join(FILTER_DIR);
+        fs::create_dir_all(&filters_dir).expect("create filter dir");
+        fs::write(
+            filters_dir.join(TRACE_FILTER_FILE),
+            r#"
+            [meta]
+            name = "skip-everything"
+            version = 1
+
+            [scope]
+            default_exec = "skip"
+            default_value_action = "allow"
+
+            [[scope.rules]]
+            selector = "pkg:glob:*"
+            exec = "skip"
+            "#,
+        )
+        .expect("write filter");
+        let app_dir = root.join("app");
+        fs::create_dir_all(&app_dir).expect("create app dir");
+        fs::write(app_dir.join("__init__.py"), "").expect("write __init__");
+        let script = app_dir.join("main.py");
+        fs::write(&script, "print('run')\n").expect("write script");
+
+        let engine = load_trace_filter_with_globs(
+            None,
+            script.to_str().expect("utf8"),
+            None,
+            &QualnameGlobs::default(),
+            &["app.critical".to_string()],
+        )
+        .expect("load")
+        .expect("engine");
+
+        Python::with_gil(|py| {
+            let exec_for = |module: &str| {
+                let path = app_dir.join(format!("{}.py", module.trim_start_matches("app.")));
+                let source = "def handler():\n    return 1\n";
+                fs::write(&path, source).expect("write module");
+                let code_c = CString::new(source).expect("source");
+                let file_c = CString::new(path.to_str().expect("utf8")).expect("path");
+                let module_c = CString::new(module).expect("module");
+                let loaded = PyModule::from_code(
+                    py,
+                    code_c.as_c_str(),
+                    file_c.as_c_str(),
+                    module_c.as_c_str(),
+                )
+                .expect("load module");
+                let code = loaded
+                    .getattr("handler")
+                    .and_then(|func| func.getattr("__code__"))
+                    .expect("__code__")
+                    .downcast_into::<PyCode>()
+                    .expect("PyCode");
+                let wrapper = CodeObjectWrapper::new(py, &code);
+                engine.resolve(py, &wrapper, None).expect("resolve").exec()
+            };
+
+            assert_eq!(exec_for("app.critical"), ExecDecision::Trace);
+            assert_eq!(exec_for("app.other"), ExecDecision::Skip);
+        });
+    }
 }
@@ -0,0 +1,329 @@
+//! `socket://host:port` trace targets.
+//!
+//! The trace writer only knows how to write files, so a socket session records
+//! the streaming binary format into a private temporary directory and a relay
+//! thread tails the events file onto the TCP connection as the writer drains
+//! its buffers.
+//!
+//! The relay relies on the binary writer only ever appending to the events
+//! file: bytes already forwarded are never re-read. A file that shrinks below
+//! the forwarded length is reported as an error; an in-place rewrite of the
+//! same length cannot be detected.
+//!
+//! When forwarding fails, for example because the peer disconnected, the relay
+//! stops recording through its `on_disconnect` hook so the spool cannot grow
+//! without bound, keeps the temporary directory with everything recorded so
+//! far, and reports the failure from [`SocketRelay::finish`].
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use codetracer_trace_writer_nim::TraceEventsFileFormat;
+use recorder_errors::{enverr, usage, ErrorCode};
+use uuid::Uuid;
+
+use crate::errors::Result;
+
+/// Scheme prefix selecting a socket target instead of a trace directory.
+pub const SOCKET_SCHEME: &str = "socket://";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Return the `host:port` part of `path` when it names a socket target.
+pub fn socket_address(path: &str) -> Option<&str> {
+    path.strip_prefix(SOCKET_SCHEME)
+}
+
+/// Socket targets carry the streaming binary format only.
+pub fn ensure_socket_format(format: TraceEventsFileFormat) -> Result<()> {
+    match format {
+        TraceEventsFileFormat::Binary => Ok(()),
+        _ => Err(usage!(
+            ErrorCode::UnsupportedFormat,
+            "socket targets stream the binary format; pass format='binary'"
+        )),
+    }
+}
+
+/// Connect to `address`, trying each resolved address in turn.
+pub fn connect(address: &str) -> Result<TcpStream> {
+    let connect_error = |err: io::Error| {
+        enverr!(ErrorCode::Io, "failed to connect to trace socket")
+            .with_context("address", address.to_string())
+            .with_context("io", err.to_string())
+    };
+    let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, "no addresses resolved");
+    for candidate in address.to_socket_addrs().map_err(connect_error)? {
+        match TcpStream::connect_timeout(&candidate, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(connect_error(last_error))
+}
+
+/// Private directory the writer records into while the relay forwards it.
+pub fn spool_directory() -> PathBuf {
+    std::env::temp_dir().join(format!("codetracer-socket-{}", Uuid::new_v4()))
+}
+
+/// Background thread forwarding the events file to a connected socket.
+#[derive(Debug)]
+pub struct SocketRelay {
+    spool: PathBuf,
+    done: Arc<AtomicBool>,
+    disconnected: Arc<AtomicBool>,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+impl SocketRelay {
+    /// Start forwarding `events` (inside `spool`) to `stream`. `on_disconnect`
+    /// runs on the relay thread once if forwarding fails before `finish`.
+    pub fn spawn(
+        stream: TcpStream,
+        spool: PathBuf,
+        events: &Path,
+        on_disconnect: impl FnOnce() + Send + 'static,
+    ) -> Result<Self> {
+        let file = File::open(events).map_err(|err| {
+            enverr!(ErrorCode::Io, "failed to open trace events for streaming")
+                .with_context("path", events.display().to_string())
+                .with_context("io", err.to_string())
+        })?;
+        let done = Arc::new(AtomicBool::new(false));
+        let disconnected = Arc::new(AtomicBool::new(false));
+        let thread_done = Arc::clone(&done);
+        let thread_disconnected = Arc::clone(&disconnected);
+        let handle = thread::Builder::new()
+            .name("codetracer-socket-relay".to_string())
+            .spawn(move || {
+                let forwarded = forward(file, stream, &thread_done);
+                if forwarded.is_err() {
+                    thread_disconnected.store(true, Ordering::Release);
+                    on_disconnect();
+                }
+                forwarded
+            })
+            .map_err(|err| {
+                enverr!(ErrorCode::Io, "failed to start trace socket relay")
+                    .with_context("io", err.to_string())
+            })?;
+        Ok(Self {
+            spool,
+            done,
+            disconnected,
+            handle,
+        })
+    }
+
+    /// Whether forwarding has failed and recording was stopped.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::Acquire)
+    }
+
+    /// Forward whatever the finished writer left in the events file, close the
+    /// connection and remove the spool directory. When forwarding failed the
+    /// directory is kept and the error names it.
+    pub fn finish(self) -> Result<()> {
+        self.done.store(true, Ordering::Release);
+        let outcome = self
+            .handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("trace socket relay panicked")));
+        match outcome {
+            Ok(()) => {
+                if let Err(err) = fs::remove_dir_all(&self.spool) {
+                    log::debug!(
+                        "failed to remove socket spool {}: {err}",
+                        self.spool.display()
+                    );
+                }
+                Ok(())
+            }
+            Err(err) => Err(enverr!(
+                ErrorCode::Io,
+                "trace socket disconnected; recording stopped and the trace so far was kept"
+            )
+            .with_context("spool", self.spool.display().to_string())
+            .with_context("io", err.to_string())),
+        }
+    }
+}
+
+/// Copy `file` to `stream` as it grows until `done` is set and the file is
+/// drained. Fails if the file shrinks below what was already forwarded.
+fn forward(mut file: File, mut stream: TcpStream, done: &AtomicBool) -> io::Result<()> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut forwarded: u64 = 0;
+    loop {
+        // Check before reading so bytes written just before `done` was set
+        // are still forwarded.
+        let finishing = done.load(Ordering::Acquire);
+        let read = file.read(&mut buf)?;
+        if read > 0 {
+            stream.write_all(&buf[..read])?;
+            forwarded += read as u64;
+            continue;
+        }
+        if file.metadata()?.len() < forwarded {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "trace events file shrank below the bytes already streamed",
+            ));
+        }
+        if finishing {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    stream.flush()?;
+    stream.shutdown(Shutdown::Write)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn socket_address_strips_scheme() {
+        assert_eq!(
+            socket_address("socket://127.0.0.1:9000"),
+            Some("127.0.0.1:9000")
+        );
+        assert_eq!(socket_address("/tmp/trace"), None);
+    }
+
+    #[test]
+    fn connect_reports_unreachable_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("local addr").to_string();
+        drop(listener);
+
+        let err = connect(&address).expect_err("nothing listens on the port");
+        assert_eq!(err.code, ErrorCode::Io);
+        assert_eq!(err.context.get("address"), Some(&address));
+    }
+
+    #[test]
+    fn relay_forwards_bytes_appended_while_running() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("local addr").to_string();
+        let receiver = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().expect("accept");
+            let mut received = Vec::new();
+            conn.read_to_end(&mut received).expect("read stream");
+            received
+        });
+
+        let spool = spool_directory();
+        fs::create_dir_all(&spool).expect("create spool");
+        let events = spool.join("trace.bin");
+        fs::write(&events, b"head-").expect("write events");
+        let relay = SocketRelay::spawn(
+            connect(&address).expect("connect"),
+            spool.clone(),
+            &events,
+            || panic!("relay must not disconnect"),
+        )
+        .expect("spawn relay");
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&events)
+            .and_then(|mut file| file.write_all(b"tail"))
+            .expect("append events");
+        relay.finish().expect("clean finish");
+
+        assert_eq!(receiver.join().expect("receiver thread"), b"head-tail");
+        assert!(!spool.exists(), "spool removed after a clean finish");
+    }
+
+    #[test]
+    fn relay_stops_recording_and_reports_a_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("local addr").to_string();
+        let receiver = thread::spawn(move || drop(listener.accept().expect("accept")));
+
+        let spool = spool_directory();
+        fs::create_dir_all(&spool).expect("create spool");
+        let events = spool.join("trace.bin");
+        fs::write(&events, b"").expect("write events");
+        let stopped = Arc::new(AtomicBool::new(false));
+        let hook_stopped = Arc::clone(&stopped);
+        let relay = SocketRelay::spawn(
+            connect(&address).expect("connect"),
+            spool.clone(),
+            &events,
+            move || hook_stopped.store(true, Ordering::Release),
+        )
+        .expect("spawn relay");
+        receiver.join().expect("receiver thread");
+
+        // Writes to a closed peer fail once the reset arrives.
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&events)
+            .expect("open events");
+        for _ in 0..500 {
+            if relay.is_disconnected() {
+                break;
+            }
+            file.write_all(&[0u8; 4096]).expect("append events");
+            thread::sleep(POLL_INTERVAL);
+        }
+        assert!(relay.is_disconnected(), "relay noticed the disconnect");
+        assert!(stopped.load(Ordering::Acquire), "recording was stopped");
+
+        let err = relay.finish().expect_err("disconnect is reported");
+        assert_eq!(err.code, ErrorCode::Io);
+        assert_eq!(err.context.get("spool"), Some(&spool.display().to_string()));
+        assert!(spool.exists(), "spool kept after a disconnect");
+        fs::remove_dir_all(&spool).expect("remove spool");
+    }
+
+    #[test]
+    fn relay_rejects_an_events_file_that_shrinks() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+        let address = listener.local_addr().expect("local addr").to_string();
+        let (sent, received) = std::sync::mpsc::channel();
+        let receiver = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().expect("accept");
+            let mut head = [0u8; 9];
+            conn.read_exact(&mut head).expect("read head");
+            sent.send(()).expect("signal head");
+            let mut rest = Vec::new();
+            let _ = conn.read_to_end(&mut rest);
+        });
+
+        let spool = spool_directory();
+        fs::create_dir_all(&spool).expect("create spool");
+        let events = spool.join("trace.bin");
+        fs::write(&events, b"head-tail").expect("write events");
+        let relay = SocketRelay::spawn(
+            connect(&address).expect("connect"),
+            spool.clone(),
+            &events,
+            || {},
+        )
+        .expect("spawn relay");
+        received.recv().expect("head forwarded");
+        fs::write(&events, b"head").expect("rewrite events");
+
+        let err = relay.finish().expect_err("shrinking file is an error");
+        assert!(
+            err.context
+                .get("io")
+                .is_some_and(|io| io.contains("shrank")),
+            "unexpected error: {err:?}"
+        );
+        receiver.join().expect("receiver thread");
+        fs::remove_dir_all(&spool).expect("remove spool");
+    }
+}
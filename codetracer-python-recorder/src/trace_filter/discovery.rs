@@ -0,0 +1,161 @@
+//! Deterministic discovery of every filter file under a project root.
+//!
+//! `start_tracing` only composes the nearest `.codetracer/trace-filter.toml`
+//! above the traced program. Tools that want the whole project's filter set
+//! (and tests that need to reproduce it) use [`discover_filter_files`] and
+//! [`load_filter_tree`] instead.
+//!
+//! ## Merge order
+//!
+//! 1. The builtin default filter.
+//! 2. Each `.codetracer` directory in the tree, shallowest first; directories
+//!    at the same depth are ordered by their path relative to the root.
+//! 3. Within a directory, its `*.toml` files ordered by file name.
+//!
+//! Later sources win when rules overlap, so deeper (more specific) project
+//! directories override the ones above them. Other hidden directories are not
+//! searched and symlinked directories are not followed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::trace_filter::config::TraceFilterConfig;
+use crate::trace_filter::convert_filter_error;
+use recorder_errors::{enverr, ErrorCode, RecorderResult};
+
+/// Directory holding project filter files.
+pub const FILTER_DIR: &str = ".codetracer";
+/// Label of the builtin default filter at the head of every chain.
+pub const BUILTIN_FILTER_LABEL: &str = "builtin-default";
+/// Source of the builtin default filter.
+pub const BUILTIN_TRACE_FILTER: &str =
+    include_str!("../../resources/trace_filters/builtin_default.toml");
+
+/// List the `*.toml` files of every `.codetracer` directory under `root`, in
+/// merge order (see the module docs).
+pub fn discover_filter_files(root: &Path) -> RecorderResult<Vec<PathBuf>> {
+    let mut filter_dirs = Vec::new();
+    collect_filter_dirs(root, root, 0, &mut filter_dirs)?;
+    filter_dirs.sort();
+
+    let mut files = Vec::new();
+    for (_, _, dir) in filter_dirs {
+        let mut toml_files: Vec<PathBuf> = read_dir(&dir)?
+            .into_iter()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml") && path.is_file())
+            .collect();
+        toml_files.sort();
+        files.extend(toml_files);
+    }
+    Ok(files)
+}
+
+/// Compose the builtin default filter with every filter file under `root`.
+pub fn load_filter_tree(root: &Path) -> RecorderResult<TraceFilterConfig> {
+    let files = discover_filter_files(root)?;
+    TraceFilterConfig::from_inline_and_paths(
+        &[(BUILTIN_FILTER_LABEL, BUILTIN_TRACE_FILTER)],
+        &files,
+    )
+    .map_err(convert_filter_error)
+}
+
+/// Record `(depth, relative path, path)` for each `.codetracer` directory
+/// below `dir`.
+fn collect_filter_dirs(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    found: &mut Vec<(usize, PathBuf, PathBuf)>,
+) -> RecorderResult<()> {
+    for path in read_dir(dir)? {
+        let Ok(file_type) = fs::symlink_metadata(&path).map(|meta| meta.file_type()) else {
+            continue;
+        };
+        if !file_type.is_dir() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if name == FILTER_DIR {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            found.push((depth, relative, path));
+        } else if !name.starts_with('.') {
+            collect_filter_dirs(root, &path, depth + 1, found)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_dir(dir: &Path) -> RecorderResult<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(|err| {
+        enverr!(ErrorCode::Io, "failed to scan for trace filters")
+            .with_context("path", dir.display().to_string())
+            .with_context("io", err.to_string())
+    })?;
+    Ok(entries.flatten().map(|entry| entry.path()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write_filter(dir: &Path, file: &str, name: &str) {
+        fs::create_dir_all(dir).expect("create filter dir");
+        fs::write(
+            dir.join(file),
+            format!(
+                "[meta]\nname = \"{name}\"\nversion = 1\n\n\
+                 [scope]\ndefault_exec = \"trace\"\ndefault_value_action = \"allow\"\n"
+            ),
+        )
+        .expect("write filter");
+    }
+
+    #[test]
+    fn nested_filter_dirs_merge_shallowest_first_then_by_name() {
+        let tmp = tempdir().expect("tempdir");
+        let root = tmp.path();
+        write_filter(&root.join("pkg/sub/.codetracer"), "a.toml", "pkg-sub");
+        write_filter(&root.join("b/.codetracer"), "x.toml", "b");
+        write_filter(&root.join(".codetracer"), "z.toml", "root-z");
+        write_filter(&root.join(".codetracer"), "a.toml", "root-a");
+        write_filter(&root.join("a/.codetracer"), "x.toml", "a");
+        write_filter(&root.join(".git/.codetracer"), "x.toml", "hidden");
+        fs::write(root.join(".codetracer/notes.txt"), "not a filter").expect("write notes");
+
+        let config = load_filter_tree(root).expect("load filter tree");
+        let names: Vec<&str> = config
+            .sources()
+            .iter()
+            .map(|source| source.meta.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                BUILTIN_FILTER_LABEL,
+                "root-a",
+                "root-z",
+                "a",
+                "b",
+                "pkg-sub"
+            ]
+        );
+
+        let again = discover_filter_files(root).expect("discover again");
+        assert_eq!(again.len(), 5);
+        assert_eq!(again, discover_filter_files(root).expect("stable order"));
+    }
+
+    #[test]
+    fn tree_without_filters_loads_builtin_default_only() {
+        let tmp = tempdir().expect("tempdir");
+        fs::create_dir_all(tmp.path().join("src")).expect("create src");
+
+        let config = load_filter_tree(tmp.path()).expect("load filter tree");
+        assert_eq!(config.sources().len(), 1);
+        assert_eq!(config.sources()[0].meta.name, BUILTIN_FILTER_LABEL);
+    }
+}
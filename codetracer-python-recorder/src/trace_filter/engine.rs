@@ -80,9 +80,10 @@ extern "C" {
     ) -> std::ffi::c_int;
 }
 use recorder_errors::{target, ErrorCode, RecorderResult};
+use std::collections::HashMap;
 use std::ffi::c_void;
-use std::path::Path;
-use std::sync::atomic::{AtomicIsize, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicIsize, AtomicU64, Ordering};
 use std::sync::Arc;
 
 /// Global `co_extra` slot index, lazily acquired the first time a
@@ -169,18 +170,99 @@ pub struct TraceFilterEngine {
     /// Slot index returned by `_PyEval_RequestCodeExtraIndex`. A negative
     /// value disables caching (logged as a warning at construction time).
     code_extra_index: isize,
+    /// `[meta]` header and rule count of each source, in chain order.
+    sources: Vec<FilterSourceInfo>,
+    /// Source id and source-local index of each chain-wide rule index.
+    rule_origins: Vec<(usize, usize)>,
+    /// Highest-precedence override consulted when the chain skips a scope.
+    always_trace: Option<AlwaysTrace>,
+    /// `resolve` calls answered from `co_extra`.
+    cache_hits: AtomicU64,
+    /// `resolve` calls that had to run the classifier.
+    cache_misses: AtomicU64,
+}
+
+/// Hit and miss counts of the `co_extra` resolution cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Classifier whose last source lists scopes that must be traced. Only
+/// matches on that source's rules count, so any filters composed in front
+/// of it (e.g. the builtin default) merely contribute value policies.
+struct AlwaysTrace {
+    classifier: Classifier,
+    first_rule: usize,
+}
+
+/// Descriptive metadata for one filter source in the loaded chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterSourceInfo {
+    pub path: PathBuf,
+    pub name: String,
+    pub version: u32,
+    pub rule_count: usize,
 }
 
 impl TraceFilterEngine {
     /// Construct the engine from a fully resolved configuration.
     pub fn new(config: TraceFilterConfig) -> Self {
+        let sources = config
+            .sources()
+            .iter()
+            .enumerate()
+            .map(|(index, source)| FilterSourceInfo {
+                path: source.path.clone(),
+                name: source.meta.name.clone(),
+                version: source.meta.version,
+                rule_count: config
+                    .rules()
+                    .iter()
+                    .filter(|rule| rule.source_id == index)
+                    .count(),
+            })
+            .collect();
+        let mut per_source: HashMap<usize, usize> = HashMap::new();
+        let rule_origins = config
+            .rules()
+            .iter()
+            .map(|rule| {
+                let local = per_source.entry(rule.source_id).or_insert(0);
+                let origin = (rule.source_id, *local);
+                *local += 1;
+                origin
+            })
+            .collect();
         let classifier = Classifier::new(config);
         Python::with_gil(|py| Self {
             classifier: Arc::new(classifier),
             code_extra_index: ensure_code_extra_index(py),
+            sources,
+            rule_origins,
+            always_trace: None,
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
         })
     }
 
+    /// Force `exec = trace` for scopes the chain would skip whenever they
+    /// match a rule from the last source of `config`.
+    pub fn with_always_trace(mut self, config: TraceFilterConfig) -> Self {
+        let last_source = config.sources().len().saturating_sub(1);
+        let first_rule = config
+            .rules()
+            .iter()
+            .position(|rule| rule.source_id == last_source)
+            .unwrap_or(config.rules().len());
+        self.always_trace = Some(AlwaysTrace {
+            classifier: Classifier::new(config),
+            first_rule,
+        });
+        self
+    }
+
     /// Resolve the scope decision for `code`, reusing the cached result
     /// stashed in `co_extra` when available.
     ///
@@ -206,6 +288,7 @@ impl TraceFilterEngine {
                     // without taking ownership of the leaked pointer.
                     let raw = slot as *const ScopeResolution;
                     Arc::increment_strong_count(raw);
+                    self.cache_hits.fetch_add(1, Ordering::Relaxed);
                     return Ok(Arc::from_raw(raw));
                 }
                 // If rc != 0 CPython has already cleared the error
@@ -214,6 +297,7 @@ impl TraceFilterEngine {
         }
 
         // Cache miss (or caching disabled): classify and (try to) store.
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
         let resolution = Arc::new(self.classify(py, code, module_hint)?);
 
         if self.code_extra_index >= 0 {
@@ -244,48 +328,43 @@ impl TraceFilterEngine {
         Ok(resolution)
     }
 
+    /// Hit and miss counts of `resolve` since construction or the last
+    /// [`Self::reset_cache_stats`].
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset_cache_stats(&self) {
+        self.cache_hits.store(0, Ordering::Relaxed);
+        self.cache_misses.store(0, Ordering::Relaxed);
+    }
+
     fn classify(
         &self,
         py: Python<'_>,
         code: &CodeObjectWrapper,
         module_hint: Option<&str>,
     ) -> RecorderResult<ScopeResolution> {
-        // Resolve the filename + qualname from the code object once;
-        // these strings are then borrowed into the ScopeQuery without
-        // further allocation.
-        let filename = code
-            .filename(py)
-            .map_err(|err| py_attr_error("co_filename", err))?;
-        let qualname = code
-            .qualname(py)
-            .map_err(|err| py_attr_error("co_qualname", err))?;
-
-        // Build the initial ScopeQuery and run a first classification pass.
-        // The classifier itself does best-effort module-name derivation from
-        // the filename. If it still couldn't produce a module name we try
-        // the `__init__.py`-walking fallback (Python-specific) before
-        // running a second classification pass.
-        let mut query = ScopeQuery::new(filename).with_qualname(qualname);
-        if let Some(hint) = module_hint {
-            query = query.with_module_hint(hint);
+        let resolution = classify_with(&self.classifier, py, code, module_hint)?;
+        if resolution.exec() != ExecDecision::Skip {
+            return Ok(resolution);
         }
-        let resolution = self.classifier.classify(&query);
-
-        // If the classifier saw no usable module name, try the package
-        // discovery fallback from the recorder's module_identity helpers.
-        if resolution.module_name().is_none() {
-            if let Some(absolute) = resolution.absolute_path() {
-                if let Some(derived) = module_name_from_packages(Path::new(absolute)) {
-                    if is_valid_module_name(&derived) {
-                        let query = ScopeQuery::new(filename)
-                            .with_qualname(qualname)
-                            .with_module_hint(&derived);
-                        return Ok(self.classifier.classify(&query));
-                    }
-                }
-            }
+        let Some(always) = self.always_trace.as_ref() else {
+            return Ok(resolution);
+        };
+        // Reuse the module name the chain derived: the override config has
+        // no filter file to anchor project-relative module names.
+        let hint = resolution.module_name().or(module_hint);
+        let forced = classify_with(&always.classifier, py, code, hint)?;
+        let forced_by_rule = forced
+            .matched_rule_index()
+            .is_some_and(|index| index >= always.first_rule);
+        if forced.exec() == ExecDecision::Trace && forced_by_rule {
+            return Ok(forced);
         }
-
         Ok(resolution)
     }
 
@@ -294,6 +373,19 @@ impl TraceFilterEngine {
         self.classifier.summary()
     }
 
+    /// Return the `[meta]` name, version and rule count of each source.
+    pub fn sources(&self) -> &[FilterSourceInfo] {
+        &self.sources
+    }
+
+    /// Map a chain-wide rule index (as reported by
+    /// [`ScopeResolution::matched_rule_index`]) to the source that declared
+    /// the rule and the rule's index within that source.
+    pub fn rule_origin(&self, rule_index: usize) -> Option<(&FilterSourceInfo, usize)> {
+        let (source_id, local) = *self.rule_origins.get(rule_index)?;
+        self.sources.get(source_id).map(|source| (source, local))
+    }
+
     /// Borrow the underlying compiled classifier.  Useful for unit tests
     /// that bypass the Python code-object layer.
     pub fn classifier(&self) -> &Classifier {
@@ -301,6 +393,53 @@ impl TraceFilterEngine {
     }
 }
 
+/// Run `classifier` over `code`, retrying with a package-derived module
+/// name when the classifier could not infer one from the filename.
+fn classify_with(
+    classifier: &Classifier,
+    py: Python<'_>,
+    code: &CodeObjectWrapper,
+    module_hint: Option<&str>,
+) -> RecorderResult<ScopeResolution> {
+    // Resolve the filename + qualname from the code object once;
+    // these strings are then borrowed into the ScopeQuery without
+    // further allocation.
+    let filename = code
+        .filename(py)
+        .map_err(|err| py_attr_error("co_filename", err))?;
+    let qualname = code
+        .qualname(py)
+        .map_err(|err| py_attr_error("co_qualname", err))?;
+
+    // Build the initial ScopeQuery and run a first classification pass.
+    // The classifier itself does best-effort module-name derivation from
+    // the filename. If it still couldn't produce a module name we try
+    // the `__init__.py`-walking fallback (Python-specific) before
+    // running a second classification pass.
+    let mut query = ScopeQuery::new(filename).with_qualname(qualname);
+    if let Some(hint) = module_hint {
+        query = query.with_module_hint(hint);
+    }
+    let resolution = classifier.classify(&query);
+
+    // If the classifier saw no usable module name, try the package
+    // discovery fallback from the recorder's module_identity helpers.
+    if resolution.module_name().is_none() {
+        if let Some(absolute) = resolution.absolute_path() {
+            if let Some(derived) = module_name_from_packages(Path::new(absolute)) {
+                if is_valid_module_name(&derived) {
+                    let query = ScopeQuery::new(filename)
+                        .with_qualname(qualname)
+                        .with_module_hint(&derived);
+                    return Ok(classifier.classify(&query));
+                }
+            }
+        }
+    }
+
+    Ok(resolution)
+}
+
 /// Adapter so callers in the recorder that historically reached for
 /// `crate::trace_filter::engine::TraceFilterEngine` continue to receive
 /// the `RecorderResult` flavour of errors.
@@ -475,6 +614,7 @@ mod tests {
             // Second resolve should observe the cached value (co_extra hit).
             let second = engine.resolve(py, &wrapper, None)?;
             assert_eq!(second.exec(), ExecDecision::Trace);
+            assert_eq!(engine.cache_stats(), CacheStats { hits: 1, misses: 1 });
 
             // The slot is reachable through CPython directly.
             let index = current_code_extra_index().expect("co_extra index allocated");
@@ -528,6 +668,50 @@ mod tests {
         })
     }
 
+    #[test]
+    fn always_trace_overrides_skip_everything_filter() -> RecorderResult<()> {
+        let (config, file_path) = filter_with_pkg_rule(
+            r#"
+            [scope]
+            default_exec = "skip"
+            default_value_action = "allow"
+            "#,
+        )?;
+        let always = r#"
+            [meta]
+            name = "always-trace"
+            version = 1
+
+            [scope]
+            default_exec = "skip"
+            default_value_action = "allow"
+
+            [[scope.rules]]
+            selector = "pkg:glob:app.*"
+            exec = "trace"
+        "#;
+        let always_config = TraceFilterConfig::from_inline_and_paths(&[("always", always)], &[])
+            .map_err(convert_error)?;
+
+        Python::with_gil(|py| -> RecorderResult<()> {
+            let module = load_module(py, "app.foo", &file_path, "def foo():\n    return 1\n")?;
+            let code_obj = get_code(&module, "foo")?;
+            let wrapper = CodeObjectWrapper::new(py, &code_obj);
+
+            let plain = TraceFilterEngine::new(config.clone());
+            assert_eq!(
+                plain.classify(py, &wrapper, None)?.exec(),
+                ExecDecision::Skip
+            );
+
+            let engine = TraceFilterEngine::new(config).with_always_trace(always_config);
+            let resolution = engine.classify(py, &wrapper, None)?;
+            assert_eq!(resolution.exec(), ExecDecision::Trace);
+            assert_eq!(resolution.module_name(), Some("app.foo"));
+            Ok(())
+        })
+    }
+
     #[test]
     fn inline_pkg_rule_uses_sys_modules_fallback() -> RecorderResult<()> {
         let inline = r#"
@@ -14,7 +14,10 @@
 //!    hot path).
 //! 3. Adapts the crate's `FilterError` to the recorder's `RecorderError`
 //!    facade so callers continue to see `recorder_errors::RecorderResult`.
+//! 4. Discovers the filter files of a whole project tree in a fixed merge
+//!    order ([`discovery`]).
 
+pub mod discovery;
 pub mod engine;
 
 /// Re-export of the shared crate's selector grammar.  Existing call sites
@@ -0,0 +1,209 @@
+//! JSON Schema for the events file written with `format="json"`.
+//!
+//! The events file is an array of externally tagged `TraceLowLevelEvent`
+//! objects (`{"Step": {...}}`), and values are `ValueRecord` objects tagged by
+//! `kind`. The schema only lists the variants this recorder emits, so
+//! [`EVENT_KINDS`] and [`VALUE_KINDS`] must grow whenever the tracer starts
+//! writing a new one; `runtime_tracer` tests check real traces against them.
+//! Program metadata and the path table live in the binary CTFS `meta.dat`
+//! block and are not described here.
+
+use pyo3::prelude::*;
+use serde_json::{json, Map, Value};
+
+/// Schema dialect declared by [`trace_schema_json`].
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// `TraceLowLevelEvent` variants written by the recorder.
+pub const EVENT_KINDS: &[&str] = &[
+    "Path",
+    "VariableName",
+    "Type",
+    "Value",
+    "Function",
+    "Step",
+    "Call",
+    "Return",
+    "Event",
+    "BindVariable",
+    "Assignment",
+];
+
+/// `ValueRecord` kinds produced by the value encoder.
+pub const VALUE_KINDS: &[&str] = &[
+    "None", "Bool", "Int", "String", "Tuple", "Sequence", "Raw", "Error",
+];
+
+/// Build the JSON Schema describing the events file.
+pub fn trace_schema_json() -> Value {
+    let events: Vec<Value> = EVENT_KINDS
+        .iter()
+        .map(|kind| {
+            json!({
+                "type": "object",
+                "required": [kind],
+                "additionalProperties": false,
+                "properties": { (kind.to_string()): event_payload(kind) },
+            })
+        })
+        .collect();
+    let values: Vec<Value> = VALUE_KINDS.iter().copied().map(value_variant).collect();
+
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "CodeTracer trace events",
+        "type": "array",
+        "items": { "$ref": "#/$defs/TraceLowLevelEvent" },
+        "$defs": {
+            "TraceLowLevelEvent": { "oneOf": events },
+            "ValueRecord": { "oneOf": values },
+        },
+    })
+}
+
+/// Python entry point returning the schema as a JSON string.
+#[pyfunction(name = "trace_schema")]
+pub fn py_trace_schema() -> PyResult<String> {
+    serde_json::to_string_pretty(&trace_schema_json())
+        .map_err(|err| pyo3::exceptions::PyRuntimeError::new_err(err.to_string()))
+}
+
+fn id_schema() -> Value {
+    json!({ "type": "integer", "minimum": 0 })
+}
+
+fn value_ref() -> Value {
+    json!({ "$ref": "#/$defs/ValueRecord" })
+}
+
+fn object(required: &[&str], properties: Value) -> Value {
+    json!({
+        "type": "object",
+        "required": required,
+        "properties": properties,
+    })
+}
+
+fn event_payload(kind: &str) -> Value {
+    match kind {
+        "Path" | "VariableName" => json!({ "type": "string" }),
+        "Type" => object(
+            &["kind", "lang_type"],
+            json!({
+                "kind": { "type": "integer" },
+                "lang_type": { "type": "string" },
+                "specific_info": { "type": "object" },
+            }),
+        ),
+        "Value" => object(
+            &["variable_id", "value"],
+            json!({ "variable_id": id_schema(), "value": value_ref() }),
+        ),
+        "Function" => object(
+            &["path_id", "line", "name"],
+            json!({
+                "path_id": id_schema(),
+                "line": { "type": "integer" },
+                "name": { "type": "string" },
+            }),
+        ),
+        "Step" => object(
+            &["path_id", "line"],
+            json!({ "path_id": id_schema(), "line": { "type": "integer" } }),
+        ),
+        "Call" => object(
+            &["function_id", "args"],
+            json!({
+                "function_id": id_schema(),
+                "args": {
+                    "type": "array",
+                    "items": object(
+                        &["variable_id", "value"],
+                        json!({ "variable_id": id_schema(), "value": value_ref() }),
+                    ),
+                },
+            }),
+        ),
+        "Return" => object(&["return_value"], json!({ "return_value": value_ref() })),
+        "Event" => object(
+            &["kind", "metadata", "content"],
+            json!({
+                "kind": { "type": "integer" },
+                "metadata": {
+                    "type": "string",
+                    "description": "JSON text; trace-log events carry a `kind` field",
+                },
+                "content": { "type": "string" },
+            }),
+        ),
+        "BindVariable" => object(
+            &["variable_id", "place"],
+            json!({ "variable_id": id_schema(), "place": {} }),
+        ),
+        "Assignment" => object(
+            &["to", "pass_by", "from"],
+            json!({ "to": id_schema(), "pass_by": {}, "from": {} }),
+        ),
+        _ => json!({}),
+    }
+}
+
+fn value_variant(kind: &str) -> Value {
+    let mut properties = Map::new();
+    properties.insert("kind".to_string(), json!({ "const": kind }));
+    properties.insert("type_id".to_string(), id_schema());
+    let mut required = vec!["kind", "type_id"];
+    let payload: Vec<(&str, Value)> = match kind {
+        "Bool" => vec![("b", json!({ "type": "boolean" }))],
+        "Int" => vec![("i", json!({ "type": "integer" }))],
+        "String" => vec![("text", json!({ "type": "string" }))],
+        "Tuple" => vec![("elements", json!({ "type": "array", "items": value_ref() }))],
+        "Sequence" => vec![
+            ("elements", json!({ "type": "array", "items": value_ref() })),
+            ("is_slice", json!({ "type": "boolean" })),
+        ],
+        "Raw" => vec![("r", json!({ "type": "string" }))],
+        "Error" => vec![("msg", json!({ "type": "string" }))],
+        _ => Vec::new(),
+    };
+    for (field, schema) in payload {
+        properties.insert(field.to_string(), schema);
+        required.push(field);
+    }
+    json!({
+        "type": "object",
+        "required": required,
+        "properties": properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_is_valid_json_listing_known_kinds() {
+        let text = py_trace_schema().expect("render schema");
+        let schema: Value = serde_json::from_str(&text).expect("schema parses as JSON");
+        assert_eq!(schema["$schema"], SCHEMA_DIALECT);
+
+        let event_kinds: Vec<&str> = schema["$defs"]["TraceLowLevelEvent"]["oneOf"]
+            .as_array()
+            .expect("event variants")
+            .iter()
+            .filter_map(|variant| variant["required"][0].as_str())
+            .collect();
+        assert_eq!(event_kinds, EVENT_KINDS);
+        for kind in ["Step", "Call", "Return", "Value", "Event"] {
+            assert!(event_kinds.contains(&kind), "missing {kind}");
+        }
+
+        let value_kinds: Vec<&str> = schema["$defs"]["ValueRecord"]["oneOf"]
+            .as_array()
+            .expect("value variants")
+            .iter()
+            .filter_map(|variant| variant["properties"]["kind"]["const"].as_str())
+            .collect();
+        assert_eq!(value_kinds, VALUE_KINDS);
+    }
+}
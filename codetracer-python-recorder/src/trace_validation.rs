@@ -0,0 +1,181 @@
+//! Self-check for finished JSON traces.
+//!
+//! [`validate_events`] walks an events array and reports, as readable
+//! strings, the structural problems a replay would trip over:
+//!
+//! - a `Return` without an open `Call`, or calls still open at the end;
+//! - a `Value` or call argument naming a variable id no earlier
+//!   `VariableName` event introduced;
+//! - a `Step` or `Function` naming a path id no earlier `Path` event
+//!   introduced.
+//!
+//! An empty list means the trace passed. Only the JSON and NDJSON events
+//! files can be read back here; binary and CTFS traces need their own readers.
+
+use std::path::Path;
+
+use codetracer_trace_writer_nim::TraceEventsFileFormat;
+use pyo3::prelude::*;
+use recorder_errors::{enverr, usage, ErrorCode, RecorderResult};
+use serde_json::Value;
+
+use crate::ffi;
+use crate::runtime::TraceOutputPaths;
+
+/// Check a decoded events array and describe every problem found.
+pub fn validate_events(events: &[Value]) -> Vec<String> {
+    let mut issues = Vec::new();
+    let mut variables = 0u64;
+    let mut paths = 0u64;
+    let mut depth = 0usize;
+
+    for (index, event) in events.iter().enumerate() {
+        let Some((kind, payload)) = event.as_object().and_then(|object| object.iter().next())
+        else {
+            issues.push(format!("event {index}: not a tagged event object"));
+            continue;
+        };
+        match kind.as_str() {
+            "VariableName" => variables += 1,
+            "Path" => paths += 1,
+            "Call" => {
+                depth += 1;
+                for arg in payload["args"].as_array().into_iter().flatten() {
+                    check_variable(&mut issues, index, arg["variable_id"].as_u64(), variables);
+                }
+            }
+            "Return" => match depth.checked_sub(1) {
+                Some(remaining) => depth = remaining,
+                None => issues.push(format!("event {index}: Return without a matching Call")),
+            },
+            "Value" => check_variable(
+                &mut issues,
+                index,
+                payload["variable_id"].as_u64(),
+                variables,
+            ),
+            "Step" | "Function" => match payload["path_id"].as_u64() {
+                Some(id) if id < paths => {}
+                Some(id) => issues.push(format!(
+                    "event {index}: path id {id} has no Path (only {paths} defined)"
+                )),
+                None => issues.push(format!("event {index}: missing path_id")),
+            },
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        issues.push(format!("{depth} Call event(s) without a matching Return"));
+    }
+    issues
+}
+
+fn check_variable(issues: &mut Vec<String>, index: usize, id: Option<u64>, known: u64) {
+    match id {
+        Some(id) if id < known => {}
+        Some(id) => issues.push(format!(
+            "event {index}: variable id {id} has no VariableName (only {known} defined)"
+        )),
+        None => issues.push(format!("event {index}: missing variable_id")),
+    }
+}
+
+/// Validate the JSON or NDJSON events file of the trace recorded in `dir`.
+pub fn validate_trace_dir(dir: &Path) -> RecorderResult<Vec<String>> {
+    let json = TraceOutputPaths::new(dir, TraceEventsFileFormat::Json);
+    let ndjson = json.clone().into_ndjson();
+    match [json.events(), ndjson.events()]
+        .into_iter()
+        .find(|events| events.is_file())
+    {
+        Some(events) => validate_events_file(events),
+        None => Err(usage!(
+            ErrorCode::TraceMissing,
+            "no JSON trace to validate; record with format='json' or format='ndjson'"
+        )
+        .with_context("path", json.events().display().to_string())),
+    }
+}
+
+/// Validate a JSON events file, or an NDJSON one when the extension is
+/// `.ndjson`. Content that does not decode is reported as an issue rather
+/// than an error.
+pub fn validate_events_file(path: &Path) -> RecorderResult<Vec<String>> {
+    let raw = std::fs::read(path).map_err(|err| {
+        enverr!(ErrorCode::Io, "failed to read trace events")
+            .with_context("path", path.display().to_string())
+            .with_context("io", err.to_string())
+    })?;
+    if path.extension().is_some_and(|ext| ext == "ndjson") {
+        let mut issues = Vec::new();
+        let mut events = Vec::new();
+        for (index, line) in String::from_utf8_lossy(&raw).lines().enumerate() {
+            match serde_json::from_str::<Value>(line) {
+                Ok(event) => events.push(event),
+                Err(err) => issues.push(format!("line {}: not valid JSON: {err}", index + 1)),
+            }
+        }
+        issues.extend(validate_events(&events));
+        return Ok(issues);
+    }
+    match serde_json::from_slice::<Value>(&raw) {
+        Ok(Value::Array(events)) => Ok(validate_events(&events)),
+        Ok(_) => Ok(vec!["events file is not a JSON array".to_string()]),
+        Err(err) => Ok(vec![format!("events file is not valid JSON: {err}")]),
+    }
+}
+
+/// Python entry point: the issues found in the JSON trace under `path`.
+#[pyfunction(name = "validate_trace")]
+pub fn py_validate_trace(path: &str) -> PyResult<Vec<String>> {
+    ffi::wrap_pyfunction("validate_trace", || {
+        validate_trace_dir(Path::new(path)).map_err(ffi::map_recorder_error)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn good_events() -> Vec<Value> {
+        vec![
+            json!({"Path": "program.py"}),
+            json!({"Function": {"path_id": 0, "line": 1, "name": "<toplevel>"}}),
+            json!({"Call": {"function_id": 0, "args": []}}),
+            json!({"Step": {"path_id": 0, "line": 1}}),
+            json!({"VariableName": "x"}),
+            json!({"Value": {"variable_id": 0, "value": {"kind": "Int", "type_id": 0, "i": 1}}}),
+            json!({"Return": {"return_value": {"kind": "None", "type_id": 0}}}),
+        ]
+    }
+
+    #[test]
+    fn balanced_trace_has_no_issues() {
+        assert!(validate_events(&good_events()).is_empty());
+    }
+
+    #[test]
+    fn corrupted_events_are_reported() {
+        let mut events = good_events();
+        events.push(json!({"Return": {"return_value": {"kind": "None", "type_id": 0}}}));
+        events.push(json!({"Value": {"variable_id": 5, "value": {"kind": "None", "type_id": 0}}}));
+        events.push(json!({"Step": {"path_id": 3, "line": 2}}));
+
+        let issues = validate_events(&events);
+        assert_eq!(issues.len(), 3, "{issues:?}");
+        assert!(issues[0].contains("Return without a matching Call"));
+        assert!(issues[1].contains("variable id 5"));
+        assert!(issues[2].contains("path id 3"));
+
+        let unclosed = validate_events(&good_events()[..4]);
+        assert_eq!(unclosed, ["1 Call event(s) without a matching Return"]);
+    }
+
+    #[test]
+    fn missing_json_trace_is_a_usage_error() {
+        let tmp = tempfile::tempdir().expect("tempdir");
+        let err = validate_trace_dir(tmp.path()).expect_err("no trace.json");
+        assert_eq!(err.code, ErrorCode::TraceMissing);
+    }
+}
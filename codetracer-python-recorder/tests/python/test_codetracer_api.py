@@ -1,8 +1,13 @@
+import json
 import os
+import signal
+import socket
 import subprocess
 import sys
 import tempfile
+import threading
 import unittest
+import warnings
 from pathlib import Path
 
 import codetracer_python_recorder as codetracer
@@ -64,6 +69,43 @@ class TracingApiTests(unittest.TestCase):
             out = subprocess.check_output([sys.executable, "-c", script], env=env)
             self.assertEqual(out.decode(), "True")
 
+    def test_stop_on_exit_finalizes_trace_without_explicit_stop(self) -> None:
+        script = (
+            "import sys\n"
+            "import codetracer_python_recorder as codetracer\n"
+            "codetracer.start(sys.argv[1], stop_on_exit=True)\n"
+            "total = sum(range(5))\n"
+        )
+        with tempfile.TemporaryDirectory() as tmpdir:
+            trace_dir = Path(tmpdir)
+            env = os.environ.copy()
+            env.pop("CODETRACER_TRACE", None)
+            subprocess.run(
+                [sys.executable, "-c", script, str(trace_dir)], env=env, check=True
+            )
+            ct_files = [path for path in trace_dir.glob("*.ct") if path.stat().st_size > 0]
+            self.assertTrue(
+                ct_files,
+                f"expected atexit hook to finalise a .ct file; contents: {list(trace_dir.iterdir())}",
+            )
+
+    @unittest.skipIf(sys.platform == "win32", "SIGTERM delivery is POSIX-specific")
+    def test_stop_on_exit_finalizes_trace_on_sigterm(self) -> None:
+        script = (
+            "import os, signal, sys\n"
+            "import codetracer_python_recorder as codetracer\n"
+            "codetracer.start(sys.argv[1], stop_on_exit=True)\n"
+            "os.kill(os.getpid(), signal.SIGTERM)\n"
+        )
+        with tempfile.TemporaryDirectory() as tmpdir:
+            trace_dir = Path(tmpdir)
+            env = os.environ.copy()
+            env.pop("CODETRACER_TRACE", None)
+            result = subprocess.run([sys.executable, "-c", script, str(trace_dir)], env=env)
+            self.assertEqual(result.returncode, -signal.SIGTERM)
+            ct_files = [path for path in trace_dir.glob("*.ct") if path.stat().st_size > 0]
+            self.assertTrue(ct_files, f"contents: {list(trace_dir.iterdir())}")
+
     def test_start_rejects_unsupported_format(self) -> None:
         with tempfile.TemporaryDirectory() as tmpdir:
             with self.assertRaises(ValueError):
@@ -78,6 +120,224 @@ class TracingApiTests(unittest.TestCase):
                 codetracer.start(file_path)
         self.assertFalse(codetracer.is_tracing())
 
+    def test_trace_filter_summary_lists_loaded_filters(self) -> None:
+        self.assertIsNone(codetracer.trace_filter_summary())
+        with tempfile.TemporaryDirectory() as tmpdir:
+            root = Path(tmpdir)
+            base = root / "base.toml"
+            base.write_text(
+                "[meta]\n"
+                'name = "base"\n'
+                "version = 1\n\n"
+                "[scope]\n"
+                'default_exec = "trace"\n'
+                'default_value_action = "allow"\n\n'
+                "[[scope.rules]]\n"
+                'selector = "pkg:app"\n'
+                'exec = "trace"\n'
+            )
+            extra = root / "extra.toml"
+            extra.write_text(
+                "[meta]\n"
+                'name = "extra"\n'
+                "version = 2\n\n"
+                "[scope]\n"
+                'default_exec = "trace"\n'
+                'default_value_action = "allow"\n\n'
+                "[[scope.rules]]\n"
+                'selector = "pkg:app.secrets"\n'
+                'value_default = "redact"\n\n'
+                "[[scope.rules]]\n"
+                'selector = "pkg:app.vendor"\n'
+                'exec = "skip"\n'
+            )
+            trace_dir = root / "trace"
+            trace_dir.mkdir()
+            codetracer.start(trace_dir, trace_filter=[base, extra])
+            try:
+                summary = codetracer.trace_filter_summary()
+            finally:
+                codetracer.stop()
+
+        self.assertIsNotNone(summary)
+        entries = {entry["name"]: entry for entry in summary["filters"]}
+        self.assertIn("builtin-default", entries)
+        self.assertEqual(Path(entries["base"]["path"]).name, "base.toml")
+        self.assertEqual(entries["base"]["version"], 1)
+        self.assertEqual(entries["base"]["rules"], 1)
+        self.assertEqual(Path(entries["extra"]["path"]).name, "extra.toml")
+        self.assertEqual(entries["extra"]["version"], 2)
+        self.assertEqual(entries["extra"]["rules"], 2)
+        for entry in (entries["base"], entries["extra"]):
+            self.assertEqual(len(entry["sha256"]), 64)
+        names = [entry["name"] for entry in summary["filters"]]
+        self.assertLess(names.index("base"), names.index("extra"))
+        self.assertIsNone(codetracer.trace_filter_summary())
+
+    def test_check_environment_reports_monitoring_and_inactive_recorder(self) -> None:
+        report = codetracer.check_environment()
+
+        expected_version = ".".join(str(part) for part in sys.version_info[:3])
+        self.assertEqual(report["python_version"], expected_version)
+        self.assertEqual(report["monitoring_available"], sys.version_info >= (3, 12))
+        self.assertFalse(report["tracing_active"])
+        self.assertEqual(report["recorder_tool_id"], 5)
+        self.assertIsNone(report["recorder_tool_id_owner"])
+        self.assertIn(report["recorder_tool_id"], report["free_tool_ids"])
+
+    def test_check_environment_reports_active_recorder(self) -> None:
+        with tempfile.TemporaryDirectory() as tmpdir:
+            codetracer.start(Path(tmpdir))
+            try:
+                report = codetracer.check_environment()
+            finally:
+                codetracer.stop()
+
+        self.assertTrue(report["tracing_active"])
+        self.assertEqual(report["recorder_tool_id_owner"], "codetracer")
+        self.assertNotIn(report["recorder_tool_id"], report["free_tool_ids"])
+
+    def test_trace_schema_describes_known_event_kinds(self) -> None:
+        schema = json.loads(codetracer.trace_schema())
+
+        self.assertEqual(schema["type"], "array")
+        event_kinds = {
+            variant["required"][0] for variant in schema["$defs"]["TraceLowLevelEvent"]["oneOf"]
+        }
+        self.assertTrue({"Path", "Function", "Call", "Step", "Value", "Return"} <= event_kinds)
+        value_kinds = {
+            variant["properties"]["kind"]["const"]
+            for variant in schema["$defs"]["ValueRecord"]["oneOf"]
+        }
+        self.assertTrue({"None", "Int", "String", "Sequence", "Raw"} <= value_kinds)
+
+    def test_validate_trace_checks_recorded_json_trace(self) -> None:
+        with tempfile.TemporaryDirectory() as tmpdir:
+            trace_dir = Path(tmpdir)
+            codetracer.start(trace_dir, format="json")
+            sum(range(3))
+            codetracer.stop()
+            self.assertEqual(codetracer.validate_trace(str(trace_dir)), [])
+
+            events_file = trace_dir / "trace.json"
+            events = json.loads(events_file.read_text())
+            events.append({"Return": {"return_value": {"kind": "None", "type_id": 0}}})
+            events_file.write_text(json.dumps(events))
+            issues = codetracer.validate_trace(str(trace_dir))
+
+        self.assertEqual(len(issues), 1)
+        self.assertIn("Return without a matching Call", issues[0])
+
+    def test_trace_mark_records_an_annotation_event(self) -> None:
+        codetracer.trace_mark("ignored")  # no session: a no-op
+        with tempfile.TemporaryDirectory() as tmpdir:
+            trace_dir = Path(tmpdir)
+            codetracer.start(trace_dir, format="json")
+            codetracer.trace_mark("checkpoint", "phase 2")
+            codetracer.stop()
+            events = json.loads((trace_dir / "trace.json").read_text())
+
+        marks = [
+            json.loads(event["Event"]["metadata"])
+            for event in events
+            if "Event" in event and '"mark"' in event["Event"]["metadata"]
+        ]
+        self.assertEqual(len(marks), 1)
+        self.assertEqual(marks[0]["label"], "checkpoint")
+        self.assertEqual(marks[0]["metadata"], "phase 2")
+        self.assertEqual(marks[0]["path"], __file__)
+
+    def test_capture_warnings_records_structured_warning_events(self) -> None:
+        original = warnings.showwarning
+        codetracer.configure_policy(capture_warnings=True)
+        try:
+            with tempfile.TemporaryDirectory() as tmpdir:
+                trace_dir = Path(tmpdir)
+                codetracer.start(trace_dir, format="json")
+                with warnings.catch_warnings():
+                    warnings.simplefilter("always")
+                    warnings.warn("old API", DeprecationWarning)
+                codetracer.stop()
+                events = json.loads((trace_dir / "trace.json").read_text())
+        finally:
+            codetracer.configure_policy(capture_warnings=False)
+
+        self.assertIs(warnings.showwarning, original)
+        recorded = [
+            json.loads(event["Event"]["metadata"])
+            for event in events
+            if "Event" in event and '"warning"' in event["Event"]["metadata"]
+        ]
+        self.assertEqual(len(recorded), 1)
+        self.assertEqual(recorded[0]["category"], "DeprecationWarning")
+        self.assertEqual(recorded[0]["message"], "old API")
+        self.assertEqual(recorded[0]["path"], __file__)
+        self.assertIsInstance(recorded[0]["line"], int)
+
+    def test_ndjson_format_writes_one_event_per_line(self) -> None:
+        def work() -> int:
+            return sum(range(3))
+
+        with tempfile.TemporaryDirectory() as tmpdir:
+            trace_dir = Path(tmpdir)
+            session = codetracer.start(trace_dir, format=codetracer.TRACE_NDJSON)
+            work()
+            session.flush()
+            events_file = trace_dir / "trace.ndjson"
+            flushed = events_file.read_text().splitlines()
+            self.assertTrue(flushed, "flush should append the events recorded so far")
+            work()
+            codetracer.stop()
+
+            lines = events_file.read_text().splitlines()
+            self.assertFalse((trace_dir / "trace.json").exists())
+            self.assertEqual(codetracer.validate_trace(str(trace_dir)), [])
+
+        self.assertGreaterEqual(len(lines), len(flushed))
+        for line in lines:
+            event = json.loads(line)
+            self.assertIsInstance(event, dict)
+            self.assertEqual(len(event), 1, f"event should carry one variant tag: {line}")
+
+    def test_start_streams_binary_trace_to_socket_listener(self) -> None:
+        listener = socket.create_server(("127.0.0.1", 0))
+        port = listener.getsockname()[1]
+        received = bytearray()
+
+        def receive() -> None:
+            conn, _ = listener.accept()
+            with conn:
+                while chunk := conn.recv(65536):
+                    received.extend(chunk)
+
+        receiver = threading.Thread(target=receive)
+        receiver.start()
+        try:
+            session = codetracer.start(f"socket://127.0.0.1:{port}", format="binary")
+            self.assertEqual(session.path, f"socket://127.0.0.1:{port}")
+
+            def work(n: int) -> int:
+                return sum(range(n))
+
+            self.assertEqual(work(4), 6)
+            codetracer.stop()
+            receiver.join(timeout=10)
+        finally:
+            listener.close()
+
+        self.assertFalse(receiver.is_alive())
+        self.assertGreater(len(received), 0)
+
+    def test_start_reports_unreachable_socket_target(self) -> None:
+        with socket.create_server(("127.0.0.1", 0)) as listener:
+            port = listener.getsockname()[1]
+
+        with self.assertRaises(codetracer.EnvironmentError) as ctx:
+            codetracer.start(f"socket://127.0.0.1:{port}", format="binary")
+        self.assertEqual(ctx.exception.code, "ERR_IO")
+        self.assertIn("failed to connect to trace socket", str(ctx.exception))
+        self.assertFalse(codetracer.is_tracing())
+
 
 if __name__ == "__main__":
     unittest.main()
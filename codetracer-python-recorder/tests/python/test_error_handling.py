@@ -48,6 +48,55 @@ def test_start_tracing_raises_environment_error(tmp_path: Path) -> None:
         locked_dir.chmod(stat.S_IRWXU)
 
 
+@pytest.mark.skipif(os.name == "nt", reason="posix permissions required")
+def test_start_tracing_reports_unwritable_output_directory(tmp_path: Path) -> None:
+    trace_dir = tmp_path / "readonly-trace"
+    trace_dir.mkdir()
+    trace_dir.chmod(stat.S_IRUSR | stat.S_IXUSR)
+
+    try:
+        with pytest.raises(codetracer.EnvironmentError) as excinfo:
+            codetracer.start(trace_dir)
+        assert excinfo.value.code == "ERR_OUTPUT_DIR"
+        assert str(trace_dir) in str(excinfo.value)
+    finally:
+        trace_dir.chmod(stat.S_IRWXU)
+
+
+def test_start_on_existing_fail_rejects_earlier_trace(tmp_path: Path) -> None:
+    codetracer.start(tmp_path, format="json")
+    codetracer.stop()
+
+    with pytest.raises(codetracer.UsageError) as excinfo:
+        codetracer.start(tmp_path, format="json", on_existing="fail")
+    assert excinfo.value.code == "ERR_TRACE_DIR_CONFLICT"
+    assert not codetracer.is_tracing()
+
+
+def test_start_on_existing_overwrite_replaces_earlier_trace(tmp_path: Path) -> None:
+    stale = tmp_path / "trace.json"
+    stale.write_text("stale", encoding="utf-8")
+    (tmp_path / "coverage.json").write_text("stale", encoding="utf-8")
+
+    codetracer.start(tmp_path, format="json")
+    codetracer.stop()
+
+    assert stale.read_text(encoding="utf-8") != "stale"
+    assert not (tmp_path / "coverage.json").exists()
+
+
+def test_start_on_existing_append_keeps_earlier_trace(tmp_path: Path) -> None:
+    codetracer.start(tmp_path, format="json", on_existing="append")
+    codetracer.stop()
+    first = (tmp_path / "trace.json").read_bytes()
+
+    codetracer.start(tmp_path, format="json", on_existing="append")
+    codetracer.stop()
+
+    assert (tmp_path / "trace.json").read_bytes() == first
+    assert (tmp_path / "trace.1.json").exists()
+
+
 TARGET_ERROR_SCRIPT = """
 import os
 import sys
@@ -192,3 +192,31 @@ def test_default_exit_payload_uses_placeholder(tmp_path: Path) -> None:
         "expected exactly one '<exit>' String placeholder among the recorded "
         f"return values, got {returns!r}"
     )
+
+
+def test_stop_exit_code_becomes_toplevel_return(tmp_path: Path) -> None:
+    trace_dir = tmp_path / "trace"
+    trace_dir.mkdir()
+
+    # A wrapper that knows the real process status passes it to ``stop``;
+    # it replaces the ``<exit>`` placeholder as the session-exit return.
+    script = (
+        "import json\n"
+        "from pathlib import Path\n"
+        "import codetracer_python_recorder as codetracer\n"
+        f"trace_dir = Path({json.dumps(str(trace_dir))!s})\n"
+        "session = codetracer.start(trace_dir)\n"
+        "session.stop(exit_code=2)\n"
+    )
+    subprocess.run([sys.executable, "-c", script], check=True)
+
+    returns = _all_return_values(trace_dir)
+    exit_values = [
+        value for value in returns if value.get("kind") == "Int" and value.get("i") == 2
+    ]
+    assert len(exit_values) == 1, (
+        f"expected exit code 2 as the session-exit return, got {returns!r}"
+    )
+    assert not any(
+        value.get("kind") == "String" and value.get("text") == "<exit>" for value in returns
+    ), "an explicit exit code must replace the '<exit>' placeholder"
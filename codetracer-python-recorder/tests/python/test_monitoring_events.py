@@ -15,6 +15,10 @@ them still fails the test loudly.
 """
 from __future__ import annotations
 
+import json
+import runpy
+import subprocess
+import sys
 from pathlib import Path
 from typing import Any, Dict, List
 
@@ -22,7 +26,7 @@ import pytest
 
 import codetracer_python_recorder as codetracer
 
-from .support.ctfs import ParsedCtfsTrace, parse_ctfs_trace, record_script
+from .support.ctfs import ParsedCtfsTrace, find_ct_file, parse_ctfs_trace, record_script
 from .support import ensure_trace_dir
 
 
@@ -204,6 +208,397 @@ def test_module_name_follows_globals_policy(tmp_path: Path) -> None:
     assert any(name == "<__main__>" for name in names), f"expected <__main__> in {names}"
 
 
+def test_line_coverage_counts_loop_body_hits(tmp_path: Path) -> None:
+    script = tmp_path / "script_coverage.py"
+    script.write_text(
+        "total = 0\n"
+        "for i in range(5):\n"
+        "    total += i\n"
+        "print(total)\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    codetracer.configure_policy(line_coverage=True)
+    try:
+        record_script(out_dir, script)
+    finally:
+        codetracer.configure_policy(line_coverage=False)
+
+    report = json.loads((out_dir / "coverage.json").read_text(encoding="utf-8"))
+    lines = report["files"][str(script)]
+    assert lines["1"] == 1
+    assert lines["3"] == 5, f"loop body hits: {lines}"
+    assert lines["4"] == 1
+
+
+def test_function_table_maps_ids_to_qualname_and_file(tmp_path: Path) -> None:
+    script = tmp_path / "script_functions.py"
+    script.write_text(
+        "def helper(x):\n"
+        "    return x + 1\n"
+        "\n"
+        "class Greeter:\n"
+        "    def hello(self):\n"
+        "        return helper(1)\n"
+        "\n"
+        "Greeter().hello()\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    trace_ct = record_script(out_dir, script)
+
+    report = json.loads((out_dir / "functions.json").read_text(encoding="utf-8"))
+    entries = {entry["name"]: entry for entry in report["functions"]}
+    assert entries["helper"]["path"] == str(script)
+    assert entries["helper"]["line"] == 1
+    assert entries["Greeter.hello"]["path"] == str(script)
+    assert entries["Greeter.hello"]["line"] == 5
+
+    parsed = parse_ctfs_trace(trace_ct)
+    for entry in report["functions"]:
+        assert parsed.functions[entry["id"]]["name"] == entry["name"]
+
+
+def test_function_table_records_params_and_docstring(tmp_path: Path) -> None:
+    script = tmp_path / "script_signature.py"
+    script.write_text(
+        "def greet(name, *rest, loud=False, **extra):\n"
+        '    """Say hello to someone.\n'
+        "\n"
+        '    Longer explanation that is not recorded.\n'
+        '    """\n'
+        "    return name\n"
+        "\n"
+        "def plain(x):\n"
+        "    return x\n"
+        "\n"
+        "greet('ada', loud=True)\n"
+        "plain(1)\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    record_script(out_dir, script)
+
+    report = json.loads((out_dir / "functions.json").read_text(encoding="utf-8"))
+    entries = {entry["name"]: entry for entry in report["functions"]}
+    assert entries["greet"]["params"] == ["name", "*rest", "loud", "**extra"]
+    assert entries["greet"]["doc"] == "Say hello to someone."
+    assert entries["plain"]["params"] == ["x"]
+    assert entries["plain"]["doc"] is None
+
+
+def test_function_table_has_no_doc_for_module_and_class_bodies(tmp_path: Path) -> None:
+    script = tmp_path / "script_bodies.py"
+    script.write_text(
+        'GREETING = "not a module docstring"\n'
+        "\n"
+        "class Widget:\n"
+        '    label = "not a class docstring"\n'
+        "\n"
+        "    def describe(self):\n"
+        '        """Describe the widget."""\n'
+        "        return self.label\n"
+        "\n"
+        "Widget().describe()\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    record_script(out_dir, script)
+
+    report = json.loads((out_dir / "functions.json").read_text(encoding="utf-8"))
+    entries = {entry["name"]: entry for entry in report["functions"]}
+    assert entries["Widget.describe"]["doc"] == "Describe the widget."
+    assert entries["Widget"]["doc"] is None
+    module = [entry for entry in report["functions"] if entry["line"] == 1]
+    assert len(module) == 1
+    assert module[0]["doc"] is None
+
+
+def test_function_table_records_declared_annotations(tmp_path: Path) -> None:
+    script = tmp_path / "script_annotations.py"
+    script.write_text(
+        "def total(prices: list[float], *, rate: float = 0.2) -> float:\n"
+        "    subtotal: float = sum(prices)\n"
+        "    count: int\n"
+        "    count = len(prices)\n"
+        "    def nested() -> None:\n"
+        "        hidden: str = 'x'\n"
+        "    return subtotal * (1 + rate) / max(count, 1)\n"
+        "\n"
+        "total([1.0, 2.0])\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    codetracer.configure_policy(capture_annotations=True)
+    try:
+        record_script(out_dir, script)
+    finally:
+        codetracer.configure_policy(capture_annotations=False)
+
+    report = json.loads((out_dir / "functions.json").read_text(encoding="utf-8"))
+    entries = {entry["name"]: entry for entry in report["functions"]}
+    assert entries["total"]["annotations"] == {
+        "prices": "list[float]",
+        "rate": "float",
+        "return": "float",
+        "subtotal": "float",
+        "count": "int",
+    }
+
+
+def test_function_table_counts_calls_per_function(tmp_path: Path) -> None:
+    script = tmp_path / "script_call_counts.py"
+    script.write_text(
+        "def hot(x):\n"
+        "    return x\n"
+        "\n"
+        "def warm(x):\n"
+        "    return x\n"
+        "\n"
+        "for i in range(10):\n"
+        "    hot(i)\n"
+        "for i in range(3):\n"
+        "    warm(i)\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    record_script(out_dir, script)
+
+    report = json.loads((out_dir / "functions.json").read_text(encoding="utf-8"))
+    counts = {entry["name"]: entry["calls"] for entry in report["call_counts"]}
+    assert counts["hot"] == 10
+    assert counts["warm"] == 3
+    names = [entry["name"] for entry in report["call_counts"]]
+    assert names.index("hot") < names.index("warm")
+
+
+def test_function_table_counts_a_generator_once(tmp_path: Path) -> None:
+    script = tmp_path / "script_generator_count.py"
+    script.write_text(
+        "def numbers():\n"
+        "    yield 1\n"
+        "    yield 2\n"
+        "    yield 3\n"
+        "\n"
+        "gen = numbers()\n"
+        "next(gen)\n"
+        "next(gen)\n"
+        "try:\n"
+        "    gen.throw(ValueError('stop'))\n"
+        "except ValueError:\n"
+        "    pass\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    record_script(out_dir, script)
+
+    report = json.loads((out_dir / "functions.json").read_text(encoding="utf-8"))
+    counts = {entry["name"]: entry["calls"] for entry in report["call_counts"]}
+    assert counts["numbers"] == 1
+
+
+def test_only_glob_traces_matching_functions(tmp_path: Path) -> None:
+    package = tmp_path / "app"
+    package.mkdir()
+    (package / "__init__.py").write_text("", encoding="utf-8")
+    (package / "worker.py").write_text(
+        "def run(n):\n    return n * 2\n", encoding="utf-8"
+    )
+    (package / "other.py").write_text(
+        "def idle(n):\n    return n + 1\n", encoding="utf-8"
+    )
+    script = tmp_path / "script_only.py"
+    script.write_text(
+        "from app.other import idle\n"
+        "from app.worker import run\n"
+        "print(run(idle(1)))\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    sys.path.insert(0, str(tmp_path))
+    try:
+        codetracer.start(out_dir, only="app.worker.*")
+        try:
+            runpy.run_path(str(script), run_name="__main__")
+        finally:
+            codetracer.flush()
+            codetracer.stop()
+    finally:
+        sys.path.remove(str(tmp_path))
+        for name in ("app", "app.worker", "app.other"):
+            sys.modules.pop(name, None)
+
+    parsed = parse_ctfs_trace(find_ct_file(out_dir))
+    called = {parsed.functions[fid]["name"] for fid in parsed.calls}
+    assert "run" in called, f"expected app.worker.run to be traced: {called}"
+    assert "idle" not in called, f"app.other.idle must be skipped: {called}"
+    assert "<__main__>" not in called, f"the script itself must be skipped: {called}"
+
+
+def test_trace_call_traces_one_call_and_returns_its_result(tmp_path: Path) -> None:
+    module = tmp_path / "script_trace_call.py"
+    module.write_text(
+        "def double(n, *, offset=0):\n    return n * 2 + offset\n",
+        encoding="utf-8",
+    )
+    double = runpy.run_path(str(module))["double"]
+
+    out_dir = ensure_trace_dir(tmp_path)
+    result = codetracer.trace_call(str(out_dir), "ctfs", double, 20, offset=2)
+
+    assert result == 42
+    assert not codetracer.is_tracing()
+    parsed = parse_ctfs_trace(find_ct_file(out_dir))
+    called = {parsed.functions[fid]["name"] for fid in parsed.calls}
+    assert "double" in called, f"expected the traced call to be recorded: {called}"
+
+
+def test_trace_call_stops_tracing_and_reraises(tmp_path: Path) -> None:
+    def explode() -> None:
+        raise ValueError("boom")
+
+    out_dir = ensure_trace_dir(tmp_path)
+    with pytest.raises(ValueError, match="boom"):
+        codetracer.trace_call(str(out_dir), "ctfs", explode)
+
+    assert not codetracer.is_tracing()
+    find_ct_file(out_dir)
+
+
+def test_pause_skips_events_until_resume(tmp_path: Path) -> None:
+    script = tmp_path / "script_pause.py"
+    script.write_text(
+        "import codetracer_python_recorder as codetracer\n"
+        "\n"
+        "def before(n):\n    return n + 1\n\n"
+        "def hidden(n):\n    return n * 2\n\n"
+        "def after(n):\n    return n - 1\n\n"
+        "x = before(1)\n"
+        "codetracer.pause()\n"
+        "y = hidden(x)\n"
+        "codetracer.resume()\n"
+        "z = after(y)\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    codetracer.start(out_dir)
+    try:
+        runpy.run_path(str(script), run_name="__main__")
+        assert codetracer.is_tracing(), "pausing must keep the session active"
+    finally:
+        codetracer.flush()
+        codetracer.stop()
+
+    parsed = parse_ctfs_trace(find_ct_file(out_dir))
+    called = {parsed.functions[fid]["name"] for fid in parsed.calls}
+    assert "before" in called, f"code before pause() must be traced: {called}"
+    assert "after" in called, f"code after resume() must be traced: {called}"
+    assert "hidden" not in called, f"paused region must not record events: {called}"
+
+    script_path_id = parsed.paths.index(str(script))
+    hidden_line = script.read_text().splitlines().index("    return n * 2") + 1
+    seen_lines = {ln for pid, ln in parsed.steps if pid == script_path_id}
+    assert hidden_line not in seen_lines
+
+
+def test_activation_triggers_for_relative_script_path(
+    tmp_path: Path, monkeypatch: pytest.MonkeyPatch
+) -> None:
+    script = tmp_path / "script_relative.py"
+    script.write_text(
+        "def compute(x):\n    return x + 1\n\nprint(compute(1))\n",
+        encoding="utf-8",
+    )
+
+    out_dir = ensure_trace_dir(tmp_path)
+    monkeypatch.chdir(tmp_path)
+    relative = Path("script_relative.py")
+    # Run and activate through the relative path, so the code objects carry
+    # a relative ``co_filename`` while the recorder holds an absolute one.
+    trace_ct = record_script(out_dir, relative, start_on_enter=relative)
+
+    parsed = parse_ctfs_trace(trace_ct)
+    called = {parsed.functions[fid]["name"] for fid in parsed.calls}
+    assert "compute" in called, f"activation never triggered: {called}"
+
+
+def test_str_calling_traced_code_does_not_deadlock(tmp_path: Path) -> None:
+    script = tmp_path / "script_reentrant_str.py"
+    script.write_text(
+        "def describe(value):\n"
+        "    return f'Widget({value})'\n"
+        "\n"
+        "class Widget:\n"
+        "    def __init__(self, value):\n"
+        "        self.value = value\n"
+        "\n"
+        "    def __str__(self):\n"
+        "        return describe(self.value)\n"
+        "\n"
+        "def use(widget):\n"
+        "    return widget.value\n"
+        "\n"
+        "w = Widget(3)\n"
+        "print(use(w))\n",
+        encoding="utf-8",
+    )
+    out_dir = ensure_trace_dir(tmp_path)
+
+    # Encoding `widget` runs `Widget.__str__`, which calls traced Python code
+    # while the recorder is inside a monitoring callback. Run out of process
+    # so a deadlock fails the test through the timeout instead of hanging.
+    result = subprocess.run(
+        [
+            sys.executable,
+            "-m",
+            "codetracer_python_recorder",
+            "--out-dir",
+            str(out_dir),
+            str(script),
+        ],
+        capture_output=True,
+        text=True,
+        timeout=120,
+        check=False,
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert result.stdout.strip().endswith("3")
+
+
+def test_module_frame_records_call_and_return(tmp_path: Path) -> None:
+    script = tmp_path / "script_toplevel.py"
+    script.write_text("VALUE = 1\nprint(VALUE)\n", encoding="utf-8")
+
+    out_dir = ensure_trace_dir(tmp_path)
+    trace_ct = record_script(out_dir, script)
+
+    parsed = parse_ctfs_trace(trace_ct)
+    module_name = "<__main__>"
+    module_fids = [fid for fid, fn in enumerate(parsed.functions) if fn["name"] == module_name]
+    assert module_fids, f"expected {module_name} in {[f['name'] for f in parsed.functions]}"
+
+    # The <module> frame must open with a PY_START call edge and close with
+    # its PY_RETURN, like any other frame in the call tree.
+    kinds = [
+        event["kind"]
+        for event in parsed.events
+        if (event.get("kind") == "call_entry" and int(event["function_id"]) in module_fids)
+        or (event.get("kind") == "call_exit" and event.get("function") == module_name)
+    ]
+    assert kinds[:1] == ["call_entry"], f"module frame call not recorded: {kinds}"
+    assert "call_exit" in kinds, f"module frame return not recorded: {kinds}"
+
+
 def test_all_argument_kinds_recorded_on_py_start(tmp_path: Path) -> None:
     # Arrange: write a script with a function using all Python argument kinds.
     code = (
@@ -467,6 +862,9 @@ def test_coroutine_send_and_throw_events_capture_resume_and_exception(tmp_path:
             return value.get("text", "")
         if value.get("kind") == "Raw":
             return value.get("r", "")
+        if value.get("kind") in ("Sequence", "Tuple"):
+            # Exceptions are recorded as their class name plus args.
+            return " ".join(decode_text(item) for item in value.get("elements", []))
         return ""
 
     exception_calls = [
@@ -94,6 +94,56 @@ def test_cli_abort_policy_propagates_internal_error(tmp_path: Path) -> None:
     assert "test-injected failure" in result.stderr
 
 
+def test_cli_disable_policy_detaches_when_function_id_fails(tmp_path: Path) -> None:
+    script = tmp_path / "app.py"
+    script.write_text("def f():\n    return 1\n\nprint(f())\n")
+    trace_dir = tmp_path / "trace"
+
+    env = os.environ.copy()
+    env["CODETRACER_TEST_INJECT_FAILURE"] = "function-id"
+
+    result = _run_cli(
+        script,
+        "--out-dir",
+        str(trace_dir),
+        "--on-recorder-error",
+        "disable",
+        env=env,
+    )
+
+    _assert_injection_support(result)
+
+    # The call that could not be named is reported instead of being dropped,
+    # so the tracer detaches rather than writing an unbalanced trace.
+    assert result.returncode == 0, result.stderr
+    assert "1" in result.stdout
+    assert "test-injected failure at function_id" in result.stderr
+    assert not (trace_dir / "trace.ct").exists()
+
+
+def test_cli_abort_policy_propagates_function_id_failure(tmp_path: Path) -> None:
+    script = tmp_path / "app.py"
+    script.write_text("def f():\n    return 1\n\nprint(f())\n")
+    trace_dir = tmp_path / "trace"
+
+    env = os.environ.copy()
+    env["CODETRACER_TEST_INJECT_FAILURE"] = "function-id"
+
+    result = _run_cli(
+        script,
+        "--out-dir",
+        str(trace_dir),
+        "--on-recorder-error",
+        "abort",
+        env=env,
+    )
+
+    _assert_injection_support(result)
+
+    assert result.returncode != 0
+    assert "test-injected failure at function_id" in result.stderr
+
+
 @pytest.mark.skipif(not hasattr(os, "symlink"), reason="symlinks required")
 def test_cli_require_trace_fails_when_no_events_recorded(tmp_path: Path) -> None:
     script = tmp_path / "real_script.py"
@@ -164,3 +214,40 @@ def test_cli_json_errors_emits_trailer(tmp_path: Path) -> None:
     assert trailer["message"].startswith("test-injected failure")
     assert trailer["run_id"]
     assert trailer["trace_id"]
+
+
+def test_disable_callback_receives_error_code(tmp_path: Path) -> None:
+    trace_dir = tmp_path / "trace"
+    script = tmp_path / "driver.py"
+    script.write_text(
+        "import json\n"
+        "import codetracer_python_recorder as recorder\n"
+        "\n"
+        "seen = []\n"
+        "\n"
+        "def on_disable(code, message):\n"
+        "    # Calling back into the recorder must not deadlock.\n"
+        "    seen.append({'code': code, 'message': message, 'tracing': recorder.is_tracing()})\n"
+        "\n"
+        "recorder.set_disable_callback(on_disable)\n"
+        f"recorder.start({str(trace_dir)!r}, policy={{'on_recorder_error': 'disable'}})\n"
+        "value = 1\n"
+        "value += 1\n"
+        "recorder.stop()\n"
+        "print(json.dumps(seen))\n"
+    )
+
+    env = os.environ.copy()
+    env["CODETRACER_TEST_INJECT_FAILURE"] = "line"
+
+    result = subprocess.run(
+        [sys.executable, str(script)], capture_output=True, text=True, env=env, check=False
+    )
+
+    _assert_injection_support(result)
+
+    assert result.returncode == 0, result.stderr
+    seen = json.loads(result.stdout.strip().splitlines()[-1])
+    assert len(seen) == 1
+    assert seen[0]["code"] == "ERR_TRACE_INCOMPLETE"
+    assert "test-injected failure" in seen[0]["message"]
@@ -35,6 +35,9 @@ def test_auto_start_resolves_filter_chain(tmp_path: Path, monkeypatch: pytest.Mo
         activation: str | None,
         filters: list[str] | None,
         test_framework: str | None = None,
+        only: list[str] | None = None,
+        exclude: list[str] | None = None,
+        on_existing: str | None = None,
     ) -> None:
         state["active"] = True
         captured_filters.append(filters)
@@ -184,6 +184,233 @@ def test_parse_args_controls_io_capture(tmp_path: Path) -> None:
     }
 
 
+def test_parse_args_enables_calls_only(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--calls-only", str(script)])
+
+    assert config.policy_overrides == {"calls_only": True}
+
+
+def test_parse_args_enables_sort_dict_keys(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--sort-dict-keys", str(script)])
+
+    assert config.policy_overrides == {"sort_dict_keys": True}
+
+
+def test_parse_args_enables_summarize_modules(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--summarize-modules", str(script)])
+
+    assert config.policy_overrides == {"summarize_modules": True}
+
+
+def test_parse_args_enables_slot_and_property_capture(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-slots", "--capture-properties", str(script)])
+
+    assert config.policy_overrides == {"capture_slots": True, "capture_properties": True}
+
+
+def test_parse_args_enables_statement_start_lines(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--statement-start-lines", str(script)])
+
+    assert config.policy_overrides == {"statement_start_lines": True}
+
+
+def test_parse_args_enables_capture_with_blocks(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-with-blocks", str(script)])
+
+    assert config.policy_overrides == {"capture_with_blocks": True}
+
+
+def test_parse_args_sets_unrepr_fallback(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--unrepr-fallback", "repr", str(script)])
+
+    assert config.policy_overrides == {"unrepr_fallback": "repr"}
+
+
+def test_parse_args_enables_expand_varargs(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--expand-varargs", str(script)])
+
+    assert config.policy_overrides == {"expand_varargs": True}
+
+
+def test_parse_args_enables_line_coverage(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--line-coverage", str(script)])
+
+    assert config.policy_overrides == {"line_coverage": True}
+
+
+def test_parse_args_enables_call_timestamps(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--call-timestamps", str(script)])
+
+    assert config.policy_overrides == {"call_timestamps": True}
+
+
+def test_parse_args_enables_args_and_returns_only(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--args-and-returns-only", str(script)])
+
+    assert config.policy_overrides == {"args_and_returns_only": True}
+
+
+def test_parse_args_collects_always_trace_modules(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(
+        ["--always-trace", "app.critical", "--always-trace", "app.billing", str(script)]
+    )
+
+    assert config.policy_overrides == {"always_trace": ["app.critical", "app.billing"]}
+
+
+def test_parse_args_enables_flag_type_changes(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--flag-type-changes", str(script)])
+
+    assert config.policy_overrides == {"flag_type_changes": True}
+
+
+def test_parse_args_enables_record_loop_iterations(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--record-loop-iterations", str(script)])
+
+    assert config.policy_overrides == {"record_loop_iterations": True}
+
+
+def test_parse_args_enables_deterministic(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--deterministic", str(script)])
+
+    assert config.policy_overrides == {"deterministic": True}
+
+
+def test_parse_args_sets_max_locals_per_step(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--max-locals-per-step", "50", str(script)])
+
+    assert config.policy_overrides == {"max_locals_per_step": 50}
+
+
+def test_parse_args_enables_capture_contextvars(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-contextvars", str(script)])
+
+    assert config.policy_overrides == {"capture_contextvars": True}
+
+
+def test_parse_args_enables_log_filter_decisions(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--log-filter-decisions", str(script)])
+
+    assert config.policy_overrides == {"log_filter_decisions": True}
+
+
+def test_parse_args_enables_pretty_json(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--pretty-json", str(script)])
+
+    assert config.policy_overrides == {"pretty_json": True}
+
+
+def test_parse_args_enables_keep_dropped_return_type(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--keep-dropped-return-type", str(script)])
+
+    assert config.policy_overrides == {"keep_dropped_return_type": True}
+
+
+def test_parse_args_enables_capture_annotations(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-annotations", str(script)])
+
+    assert config.policy_overrides == {"capture_annotations": True}
+
+
+def test_parse_args_collects_redact_argv_patterns(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--redact-argv", "token", "--redact-argv", "password", str(script)])
+
+    assert config.policy_overrides == {"redact_argv": ["token", "password"]}
+
+
+def test_parse_args_enables_capture_object_ids(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-object-ids", str(script)])
+
+    assert config.policy_overrides == {"capture_object_ids": True}
+
+
+def test_parse_args_enables_record_instruction_offsets(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--record-instruction-offsets", str(script)])
+
+    assert config.policy_overrides == {"record_instruction_offsets": True}
+
+
+def test_parse_args_collects_opaque_modules(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--opaque-module", "pandas", "--opaque-module", "torch", str(script)])
+
+    assert config.policy_overrides == {"opaque_modules": ["pandas", "torch"]}
+
+
 def test_parse_args_collects_trace_filters(tmp_path: Path) -> None:
     script = tmp_path / "app.py"
     _write_script(script)
@@ -224,6 +451,25 @@ def test_parse_args_enables_io_capture_fd_mirroring(tmp_path: Path) -> None:
     }
 
 
+def test_parse_args_enables_io_capture_fd_mirroring_for_stderr_only(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(
+        [
+            "--io-capture",
+            "proxies+fd-stderr",
+            str(script),
+        ]
+    )
+
+    assert config.policy_overrides == {
+        "io_capture_line_proxies": True,
+        "io_capture_fd_fallback": False,
+        "io_capture_fd_stderr": True,
+    }
+
+
 def test_parse_args_enables_module_name_from_globals(tmp_path: Path) -> None:
     script = tmp_path / "entry.py"
     _write_script(script)
@@ -254,3 +500,95 @@ def test_parse_args_disables_module_name_from_globals(tmp_path: Path) -> None:
     assert config.policy_overrides == {
         "module_name_from_globals": False,
     }
+
+
+def test_parse_args_enables_validate_trace(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--validate-trace", str(script)])
+
+    assert config.policy_overrides == {"validate_trace": True}
+
+
+def test_parse_args_sets_max_call_depth(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--max-call-depth", "5", str(script)])
+
+    assert config.policy_overrides == {"max_call_depth": 5}
+
+
+def test_parse_args_enables_mark_default_args(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--mark-default-args", str(script)])
+
+    assert config.policy_overrides == {"mark_default_args": True}
+
+
+def test_parse_args_enables_delta_locals(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--delta-locals", str(script)])
+
+    assert config.policy_overrides == {"delta_locals": True}
+
+
+def test_parse_args_enables_capture_env_changes(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-env-changes", str(script)])
+
+    assert config.policy_overrides == {"capture_env_changes": True}
+
+
+def test_parse_args_enables_exclude_stdlib(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--exclude-stdlib", str(script)])
+
+    assert config.policy_overrides == {"exclude_stdlib": True}
+
+
+def test_parse_args_collects_capture_c_call_globs(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(
+        ["--capture-c-call", "json.loads", "--capture-c-call", "requests.*", str(script)]
+    )
+
+    assert config.policy_overrides == {"capture_c_calls": ["json.loads", "requests.*"]}
+
+
+def test_parse_args_enables_skip_comprehensions(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--skip-comprehensions", str(script)])
+
+    assert config.policy_overrides == {"skip_comprehensions": True}
+
+
+def test_parse_args_sets_redact_values_over(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--redact-values-over", "1024", str(script)])
+
+    assert config.policy_overrides == {"redact_values_over": 1024}
+
+
+def test_parse_args_enables_capture_warnings(tmp_path: Path) -> None:
+    script = tmp_path / "entry.py"
+    _write_script(script)
+
+    config = _parse_args(["--capture-warnings", str(script)])
+
+    assert config.policy_overrides == {"capture_warnings": True}
@@ -20,6 +20,42 @@ def reset_policy() -> None:
         json_errors=False,
         module_name_from_globals=True,
         propagate_script_exit=False,
+        calls_only=False,
+        sort_dict_keys=False,
+        summarize_modules=False,
+        capture_slots=False,
+        capture_properties=False,
+        statement_start_lines=False,
+        capture_with_blocks=False,
+        unrepr_fallback="sentinel",
+        expand_varargs=False,
+        line_coverage=False,
+        call_timestamps=False,
+        args_and_returns_only=False,
+        always_trace=[],
+        flag_type_changes=False,
+        record_loop_iterations=False,
+        deterministic=False,
+        max_locals_per_step=0,
+        capture_contextvars=False,
+        log_filter_decisions=False,
+        pretty_json=False,
+        keep_dropped_return_type=False,
+        capture_annotations=False,
+        redact_argv=[],
+        capture_object_ids=False,
+        record_instruction_offsets=False,
+        opaque_modules=[],
+        validate_trace=False,
+        max_call_depth=0,
+        mark_default_args=False,
+        delta_locals=False,
+        capture_env_changes=False,
+        exclude_stdlib=False,
+        capture_c_calls=[],
+        skip_comprehensions=False,
+        redact_values_over=0,
+        capture_warnings=False,
     )
     yield
     codetracer.configure_policy(
@@ -31,6 +67,42 @@ def reset_policy() -> None:
         json_errors=False,
         module_name_from_globals=True,
         propagate_script_exit=False,
+        calls_only=False,
+        sort_dict_keys=False,
+        summarize_modules=False,
+        capture_slots=False,
+        capture_properties=False,
+        statement_start_lines=False,
+        capture_with_blocks=False,
+        unrepr_fallback="sentinel",
+        expand_varargs=False,
+        line_coverage=False,
+        call_timestamps=False,
+        args_and_returns_only=False,
+        always_trace=[],
+        flag_type_changes=False,
+        record_loop_iterations=False,
+        deterministic=False,
+        max_locals_per_step=0,
+        capture_contextvars=False,
+        log_filter_decisions=False,
+        pretty_json=False,
+        keep_dropped_return_type=False,
+        capture_annotations=False,
+        redact_argv=[],
+        capture_object_ids=False,
+        record_instruction_offsets=False,
+        opaque_modules=[],
+        validate_trace=False,
+        max_call_depth=0,
+        mark_default_args=False,
+        delta_locals=False,
+        capture_env_changes=False,
+        exclude_stdlib=False,
+        capture_c_calls=[],
+        skip_comprehensions=False,
+        redact_values_over=0,
+        capture_warnings=False,
     )
 
 
@@ -82,6 +154,31 @@ def test_policy_env_configuration(monkeypatch: pytest.MonkeyPatch, tmp_path: Pat
     assert snapshot["propagate_script_exit"] is True
 
 
+def test_configure_policy_sets_a_single_field_by_keyword() -> None:
+    codetracer.configure_policy(require_trace=True)
+
+    snapshot = codetracer.policy_snapshot()
+    assert snapshot["require_trace"] is True
+    assert snapshot["on_recorder_error"] == "abort"
+    assert snapshot["keep_partial_trace"] is False
+
+
+def test_configure_policy_rejects_positional_arguments() -> None:
+    with pytest.raises(TypeError):
+        codetracer.configure_policy("disable")  # type: ignore[misc]
+
+
+def test_configure_policy_rejects_unknown_keywords() -> None:
+    with pytest.raises(TypeError, match="no_such_field"):
+        codetracer.configure_policy(no_such_field=True)  # type: ignore[call-arg]
+
+
+def test_configure_policy_sets_always_trace_modules() -> None:
+    codetracer.configure_policy(always_trace=["app.critical"])
+
+    assert codetracer.policy_snapshot()["always_trace"] == ["app.critical"]
+
+
 def test_clearing_log_configuration(tmp_path: Path) -> None:
     codetracer.configure_policy(log_level="debug", log_file=str(tmp_path / "log.txt"))
     codetracer.configure_policy(log_level="", log_file="")
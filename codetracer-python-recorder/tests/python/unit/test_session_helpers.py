@@ -52,8 +52,9 @@ def test_validate_trace_path_expands_user(tmp_path: Path, monkeypatch: pytest.Mo
 def test_validate_trace_path_rejects_file(tmp_path: Path) -> None:
     file_path = tmp_path / "trace.bin"
     file_path.write_text("stub")
-    with pytest.raises(ValueError):
+    with pytest.raises(ValueError) as excinfo:
         session._validate_trace_path(file_path)
+    assert str(file_path) in str(excinfo.value)
 
 
 def test_normalize_activation_path_handles_none() -> None:
@@ -110,6 +111,18 @@ def test_trace_session_flush_noop_when_inactive(monkeypatch: pytest.MonkeyPatch)
     assert flushed == []
 
 
+def test_pause_noop_when_inactive(monkeypatch: pytest.MonkeyPatch) -> None:
+    monkeypatch.setattr(session, "_is_tracing_backend", lambda: False)
+    paused = []
+
+    def fake_pause() -> None:
+        paused.append(True)
+
+    monkeypatch.setattr(session, "_pause_backend", fake_pause)
+    session.pause()
+    assert paused == []
+
+
 def test_trace_context_manager_starts_and_stops(monkeypatch: pytest.MonkeyPatch, tmp_path: Path) -> None:
     calls = {"start": [], "stop": []}
 
@@ -121,6 +134,9 @@ def test_trace_context_manager_starts_and_stops(monkeypatch: pytest.MonkeyPatch,
         activation: str | None,
         filters: list[str] | None,
         test_framework: str | None = None,
+        only: list[str] | None = None,
+        exclude: list[str] | None = None,
+        on_existing: str | None = None,
     ) -> None:
         trace_state["active"] = True
         calls["start"].append((Path(path), fmt, activation, filters))
@@ -157,6 +173,52 @@ def test_stop_forwards_exit_code(monkeypatch: pytest.MonkeyPatch) -> None:
     assert session._active_session is None
 
 
+def test_start_forwards_qualname_globs(monkeypatch: pytest.MonkeyPatch, tmp_path: Path) -> None:
+    captured: list[tuple[list[str] | None, list[str] | None]] = []
+
+    def fake_start(
+        path: str,
+        fmt: str,
+        activation: str | None,
+        filters: list[str] | None,
+        test_framework: str | None = None,
+        only: list[str] | None = None,
+        exclude: list[str] | None = None,
+        on_existing: str | None = None,
+    ) -> None:
+        captured.append((only, exclude))
+
+    monkeypatch.setattr(session, "_start_backend", fake_start)
+    monkeypatch.setattr(session, "_is_tracing_backend", lambda: False)
+
+    session.start(tmp_path, only="app.worker.*", exclude=["app.worker.noisy", " app.tmp "])
+
+    assert captured == [(["app.worker.*"], ["app.worker.noisy", "app.tmp"])]
+
+
+def test_start_forwards_on_existing(monkeypatch: pytest.MonkeyPatch, tmp_path: Path) -> None:
+    captured: list[str | None] = []
+
+    def fake_start(*args, **kwargs) -> None:
+        captured.append(args[7])
+
+    monkeypatch.setattr(session, "_start_backend", fake_start)
+    monkeypatch.setattr(session, "_is_tracing_backend", lambda: False)
+
+    session.start(tmp_path)
+    session.start(tmp_path, on_existing="append")
+
+    assert captured == ["overwrite", "append"]
+
+
+def test_normalize_qualname_globs_rejects_empty_pattern() -> None:
+    assert session._normalize_qualname_globs(None, "only") is None
+    with pytest.raises(ValueError):
+        session._normalize_qualname_globs(["app.*", "  "], "only")
+    with pytest.raises(TypeError):
+        session._normalize_qualname_globs([1], "exclude")  # type: ignore[list-item]
+
+
 def test_normalize_trace_filter_handles_none() -> None:
     assert session._normalize_trace_filter(None) is None
 
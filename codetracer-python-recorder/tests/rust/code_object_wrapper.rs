@@ -21,6 +21,34 @@ fn wrapper_basic_attributes() {
     });
 }
 
+#[test]
+fn wrapper_memoizes_code_attributes() {
+    Python::with_gil(|py| {
+        let src = CString::new("\n\nclass C:\n    def k(self):\n        return 1\n").unwrap();
+        let filename = CString::new("memo.py").unwrap();
+        let module = CString::new("m4").unwrap();
+        let m = PyModule::from_code(py, src.as_c_str(), filename.as_c_str(), module.as_c_str())
+            .unwrap();
+        let func = m.getattr("C").unwrap().getattr("k").unwrap();
+        let code: Bound<'_, PyCode> = func.getattr("__code__").unwrap().downcast_into().unwrap();
+        let wrapper = CodeObjectWrapper::new(py, &code);
+
+        let qualname = wrapper.qualname(py).unwrap();
+        let filename = wrapper.filename(py).unwrap();
+        assert_eq!(qualname, "C.k");
+        assert_eq!(filename, "memo.py");
+        assert_eq!(wrapper.first_line(py).unwrap(), 4);
+
+        // Repeated lookups return the cached strings rather than fresh
+        // extractions from the code object.
+        for _ in 0..3 {
+            assert!(std::ptr::eq(wrapper.qualname(py).unwrap(), qualname));
+            assert!(std::ptr::eq(wrapper.filename(py).unwrap(), filename));
+            assert_eq!(wrapper.first_line(py).unwrap(), 4);
+        }
+    });
+}
+
 #[test]
 fn wrapper_line_for_offset() {
     Python::with_gil(|py| {